@@ -2,6 +2,7 @@ mod client;
 mod error;
 mod filter;
 mod keypair;
+mod nip19;
 mod note;
 mod profile;
 mod pubkey;
@@ -16,7 +17,7 @@ pub use nostr::SecretKey;
 pub use note::{Note, NoteId};
 pub use profile::Profile;
 pub use pubkey::{Pubkey, PubkeyRef};
-pub use relay::message::{RelayEvent, RelayMessage};
+pub use relay::message::{event_note_id, RelayEvent, RelayMessage};
 pub use relay::pool::{PoolEvent, PoolRelay, RelayPool};
 pub use relay::subs_debug::{OwnedRelayEvent, RelayLogEvent, SubsDebug, TransferStats};
 pub use relay::{Relay, RelayStatus};
@@ -0,0 +1,115 @@
+//! The TLV flavour of NIP-19 entities (`nprofile`, `nevent`), which carry
+//! relay hints along with the id.
+
+const TLV_SPECIAL: u8 = 0;
+const TLV_RELAY: u8 = 1;
+const TLV_AUTHOR: u8 = 2;
+
+#[derive(Default)]
+struct Tlv(Vec<u8>);
+
+impl Tlv {
+    fn push(&mut self, kind: u8, value: &[u8]) {
+        // values longer than this can't be encoded, skip them
+        let Ok(len) = u8::try_from(value.len()) else {
+            return;
+        };
+
+        self.0.push(kind);
+        self.0.push(len);
+        self.0.extend_from_slice(value);
+    }
+
+    fn relays(mut self, relays: &[String]) -> Self {
+        for relay in relays {
+            self.push(TLV_RELAY, relay.as_bytes());
+        }
+        self
+    }
+
+    fn encode(&self, hrp: bech32::Hrp) -> Option<String> {
+        bech32::encode::<bech32::Bech32>(hrp, &self.0).ok()
+    }
+}
+
+static HRP_NPROFILE: bech32::Hrp = bech32::Hrp::parse_unchecked("nprofile");
+pub(crate) static HRP_NEVENT: bech32::Hrp = bech32::Hrp::parse_unchecked("nevent");
+
+pub(crate) fn nprofile(pubkey: &[u8; 32], relays: &[String]) -> Option<String> {
+    let mut tlv = Tlv::default();
+    tlv.push(TLV_SPECIAL, pubkey);
+    tlv.relays(relays).encode(HRP_NPROFILE)
+}
+
+pub(crate) fn nevent(
+    id: &[u8; 32],
+    relays: &[String],
+    author: Option<&[u8; 32]>,
+) -> Option<String> {
+    let mut tlv = Tlv::default();
+    tlv.push(TLV_SPECIAL, id);
+    let mut tlv = tlv.relays(relays);
+    if let Some(author) = author {
+        tlv.push(TLV_AUTHOR, author);
+    }
+    tlv.encode(HRP_NEVENT)
+}
+
+/// The note id in an `nevent`'s TLV data
+pub(crate) fn nevent_id(data: &[u8]) -> Option<[u8; 32]> {
+    let mut rest = data;
+    while let [kind, len, tail @ ..] = rest {
+        let len = *len as usize;
+        let value = tail.get(..len)?;
+        if *kind == TLV_SPECIAL {
+            return value.try_into().ok();
+        }
+        rest = &tail[len..];
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nprofile_matches_nip19() {
+        let pubkey: [u8; 32] =
+            hex::decode("3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let relays = vec![
+            "wss://r.x.com".to_owned(),
+            "wss://djbas.sadkb.com".to_owned(),
+        ];
+
+        assert_eq!(
+            nprofile(&pubkey, &relays).unwrap(),
+            "nprofile1qqsrhuxx8l9ex335q7he0f09aej04zpazpl0ne2cgukyawd24mayt8gpp4mhxue69uhhytnc9e3k7mgpz4mhxue69uhkg6nzv9ejuumpv34kytnrdaksjlyr9p"
+        );
+    }
+
+    #[test]
+    fn test_nevent_tlv() {
+        let id = [1; 32];
+        let author = [2; 32];
+        let relays = vec!["wss://relay.damus.io".to_owned()];
+
+        let nevent = nevent(&id, &relays, Some(&author)).unwrap();
+        let (hrp, data) = bech32::decode(&nevent).unwrap();
+        assert_eq!(hrp, HRP_NEVENT);
+
+        let mut expected = vec![TLV_SPECIAL, 32];
+        expected.extend_from_slice(&id);
+        expected.extend_from_slice(&[TLV_RELAY, 20]);
+        expected.extend_from_slice(b"wss://relay.damus.io");
+        expected.extend_from_slice(&[TLV_AUTHOR, 32]);
+        expected.extend_from_slice(&author);
+        assert_eq!(data, expected);
+        assert_eq!(nevent_id(&data), Some(id));
+        assert_eq!(nevent_id(&data[..20]), None);
+    }
+}
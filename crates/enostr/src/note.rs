@@ -36,6 +36,23 @@ impl NoteId {
     pub fn to_bech(&self) -> Option<String> {
         bech32::encode::<bech32::Bech32>(HRP_NOTE, &self.0).ok()
     }
+
+    /// From a NIP-19 `note` or `nevent`
+    pub fn from_bech(s: &str) -> Option<Self> {
+        let (hrp, data) = bech32::decode(s).ok()?;
+        if hrp == HRP_NOTE {
+            Some(NoteId(data.try_into().ok()?))
+        } else if hrp == crate::nip19::HRP_NEVENT {
+            crate::nip19::nevent_id(&data).map(NoteId)
+        } else {
+            None
+        }
+    }
+
+    /// NIP-19 `nevent` with relays where the note can be found
+    pub fn to_nevent(&self, relays: &[String], author: Option<&Pubkey>) -> Option<String> {
+        crate::nip19::nevent(&self.0, relays, author.map(|pk| pk.bytes()))
+    }
 }
 
 /// Event is the struct used to represent a Nostr event
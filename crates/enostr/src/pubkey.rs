@@ -118,6 +118,11 @@ impl Pubkey {
     pub fn to_bech(&self) -> Option<String> {
         bech32::encode::<bech32::Bech32>(HRP_NPUB, &self.0).ok()
     }
+
+    /// NIP-19 `nprofile` with relays where the profile can be found
+    pub fn to_nprofile(&self, relays: &[String]) -> Option<String> {
+        crate::nip19::nprofile(&self.0, relays)
+    }
 }
 
 impl fmt::Display for Pubkey {
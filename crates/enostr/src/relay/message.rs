@@ -1,4 +1,4 @@
-use crate::{Error, Result};
+use crate::{Error, NoteId, Result};
 use ewebsock::{WsEvent, WsMessage};
 
 #[derive(Debug, Eq, PartialEq)]
@@ -8,6 +8,22 @@ pub struct CommandResult<'a> {
     message: &'a str,
 }
 
+impl CommandResult<'_> {
+    /// Hex id of the event the relay is answering about
+    pub fn event_id(&self) -> &str {
+        self.event_id
+    }
+
+    /// Did the relay accept it?
+    pub fn is_accepted(&self) -> bool {
+        self.status
+    }
+
+    pub fn message(&self) -> &str {
+        self.message
+    }
+}
+
 pub fn calculate_command_result_size(result: &CommandResult) -> usize {
     std::mem::size_of_val(result) + result.event_id.len() + result.message.len()
 }
@@ -156,10 +172,50 @@ impl<'a> RelayMessage<'a> {
     }
 }
 
+/// The id of the note in a raw `EVENT` message. This only scans for the
+/// `id` field instead of parsing the whole note, nostrdb does that when it
+/// ingests it.
+pub fn event_note_id(msg: &str) -> Option<NoteId> {
+    let bytes = msg.as_bytes();
+    let mut from = 0;
+
+    while let Some(pos) = msg[from..].find("\"id\"") {
+        let key = from + pos;
+        from = key + 4;
+
+        // an escaped quote means we're inside a string, eg. the content
+        if key > 0 && bytes[key - 1] == b'\\' {
+            continue;
+        }
+
+        let rest = msg[from..].trim_start();
+        let Some(rest) = rest.strip_prefix(':') else {
+            continue;
+        };
+        let Some(rest) = rest.trim_start().strip_prefix('"') else {
+            continue;
+        };
+
+        return rest.get(..64).and_then(|hex| NoteId::from_hex(hex).ok());
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_event_note_id() {
+        let id = "b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0";
+        let msg =
+            format!(r#"["EVENT","sub",{{"content":"\"id\":\"nope\"","kind":1, "id" : "{id}"}}]"#);
+
+        assert_eq!(event_note_id(&msg), NoteId::from_hex(id).ok());
+        assert_eq!(event_note_id(r#"["EOSE","sub"]"#), None);
+    }
+
     #[test]
     fn test_handle_various_messages() -> Result<()> {
         let tests = vec![
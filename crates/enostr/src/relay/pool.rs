@@ -45,6 +45,10 @@ pub struct WebsocketRelay {
     pub last_ping: Instant,
     pub last_connect_attempt: Instant,
     pub retry_connect_after: Duration,
+
+    /// Only gets what we send straight to it with [`RelayPool::send_to`],
+    /// not what we send or subscribe to on the whole pool
+    pub exclusive: bool,
 }
 
 impl PoolRelay {
@@ -78,6 +82,13 @@ impl PoolRelay {
         }
     }
 
+    pub fn is_exclusive(&self) -> bool {
+        match self {
+            Self::Websocket(wsr) => wsr.exclusive,
+            Self::Multicast(_mcr) => false,
+        }
+    }
+
     pub fn send(&mut self, msg: &ClientMessage) -> Result<()> {
         match self {
             Self::Websocket(wsr) => {
@@ -115,6 +126,7 @@ impl WebsocketRelay {
             last_ping: Instant::now(),
             last_connect_attempt: Instant::now(),
             retry_connect_after: Self::initial_reconnect_duration(),
+            exclusive: false,
         }
     }
 
@@ -180,8 +192,27 @@ impl RelayPool {
             .collect()
     }
 
+    /// Whether the relay only gets what we send straight to it
+    pub fn is_exclusive(&self, url: &str) -> bool {
+        self.relays
+            .iter()
+            .any(|relay| relay.url() == url && relay.is_exclusive())
+    }
+
+    /// Mark which relays only get what we send straight to them
+    pub fn set_exclusive(&mut self, urls: &BTreeSet<String>) {
+        for relay in &mut self.relays {
+            if let PoolRelay::Websocket(wsr) = relay {
+                wsr.exclusive = urls.contains(wsr.relay.url.as_str());
+            }
+        }
+    }
+
     pub fn send(&mut self, cmd: &ClientMessage) {
         for relay in &mut self.relays {
+            if relay.is_exclusive() {
+                continue;
+            }
             if let Some(debug) = &mut self.debug {
                 debug.send_cmd(relay.url().to_owned(), cmd);
             }
@@ -191,6 +222,7 @@ impl RelayPool {
         }
     }
 
+    /// Close the subscription on every relay, exclusive ones included
     pub fn unsubscribe(&mut self, subid: String) {
         for relay in &mut self.relays {
             let cmd = ClientMessage::close(subid.clone());
@@ -209,6 +241,9 @@ impl RelayPool {
 
     pub fn subscribe(&mut self, subid: String, filter: Vec<Filter>) {
         for relay in &mut self.relays {
+            if relay.is_exclusive() {
+                continue;
+            }
             if let Some(debug) = &mut self.debug {
                 debug.send_cmd(
                     relay.url().to_owned(),
@@ -1,12 +1,14 @@
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::{
-    AccountStorage, MuteFun, Muted, RelaySpec, SingleUnkIdAction, UnknownIds, UserAccount,
+    lists::KIND_BOOKMARKS, AccountStorage, BookmarkContext, Bookmarks, MuteFun, Muted, RelaySpec,
+    SingleUnkIdAction, UnknownIds, UserAccount,
 };
-use enostr::{ClientMessage, FilledKeypair, Keypair, Pubkey, RelayPool};
-use nostrdb::{Filter, Ndb, Note, NoteBuilder, NoteKey, Subscription, Transaction};
+use enostr::{ClientMessage, FilledKeypair, Keypair, Pubkey, RelayPool, RelayStatus, SecretKey};
+use nostrdb::{Filter, IngestMetadata, Ndb, Note, NoteBuilder, NoteKey, Subscription, Transaction};
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
 use url::Url;
 use uuid::Uuid;
 
@@ -132,7 +134,11 @@ impl AccountRelayData {
         }
     }
 
-    fn harvest_nip65_relays(ndb: &Ndb, txn: &Transaction, nks: &[NoteKey]) -> Vec<RelaySpec> {
+    pub(crate) fn harvest_nip65_relays(
+        ndb: &Ndb,
+        txn: &Transaction,
+        nks: &[NoteKey],
+    ) -> Vec<RelaySpec> {
         let mut relays = Vec::new();
         for nk in nks.iter() {
             if let Ok(note) = ndb.get_note_by_key(txn, *nk) {
@@ -184,76 +190,155 @@ impl AccountRelayData {
     }
 }
 
-pub struct AccountMutedData {
+/// A replaceable list an account publishes, like its mutes. We watch for
+/// new versions of it in nostrdb, and ask relays for it while the account
+/// is selected.
+struct AccountList {
+    /// What the list is, for logging
+    name: &'static str,
     filter: Filter,
     subid: Option<String>,
     sub: Option<Subscription>,
-    muted: Arc<Muted>,
 }
 
-impl AccountMutedData {
-    pub fn new(ndb: &Ndb, pubkey: &[u8; 32]) -> Self {
-        // Construct a filter for the user's NIP-51 muted list
+impl AccountList {
+    fn new(name: &'static str, pubkey: &[u8; 32], kind: u64) -> Self {
         let filter = Filter::new()
             .authors([pubkey])
-            .kinds([10000])
+            .kinds([kind])
             .limit(1)
             .build();
 
-        // Query the ndb immediately to see if the user's muted list is already there
-        let txn = Transaction::new(ndb).expect("transaction");
-        let lim = filter.limit().unwrap_or(crate::filter::default_limit()) as i32;
-        let nks = ndb
-            .query(&txn, &[filter.clone()], lim)
-            .expect("query user muted results")
-            .iter()
-            .map(|qr| qr.note_key)
-            .collect::<Vec<NoteKey>>();
-        let muted = Self::harvest_nip51_muted(ndb, &txn, &nks);
-        debug!("pubkey {}: initial muted {:?}", hex::encode(pubkey), muted);
-
-        AccountMutedData {
+        AccountList {
+            name,
             filter,
             subid: None,
             sub: None,
-            muted: Arc::new(muted),
         }
     }
 
+    fn is_active(&self) -> bool {
+        self.subid.is_some()
+    }
+
+    /// Read the versions of the list we already have in nostrdb
+    fn query<T>(
+        &self,
+        ndb: &Ndb,
+        harvest: impl FnOnce(&Transaction, &[NoteKey]) -> T,
+    ) -> Option<T> {
+        let txn = match Transaction::new(ndb) {
+            Ok(txn) => txn,
+            Err(err) => {
+                error!("could not query {} list: {err}", self.name);
+                return None;
+            }
+        };
+
+        let lim = self
+            .filter
+            .limit()
+            .unwrap_or(crate::filter::default_limit()) as i32;
+        let nks = match ndb.query(&txn, &[self.filter.clone()], lim) {
+            Ok(results) => results
+                .iter()
+                .map(|qr| qr.note_key)
+                .collect::<Vec<NoteKey>>(),
+            Err(err) => {
+                error!("could not query {} list: {err}", self.name);
+                return None;
+            }
+        };
+
+        Some(harvest(&txn, &nks))
+    }
+
+    /// Read new versions of the list that arrived since we last looked
+    fn poll<T>(&self, ndb: &Ndb, harvest: impl FnOnce(&Transaction, &[NoteKey]) -> T) -> Option<T> {
+        let nks = ndb.poll_for_notes(self.sub?, 1);
+        if nks.is_empty() {
+            return None;
+        }
+
+        let txn = match Transaction::new(ndb) {
+            Ok(txn) => txn,
+            Err(err) => {
+                error!("could not read new {} list: {err}", self.name);
+                return None;
+            }
+        };
+
+        Some(harvest(&txn, &nks))
+    }
+
     // make this account the current selected account
-    pub fn activate(&mut self, ndb: &Ndb, pool: &mut RelayPool) {
-        debug!("activating muted sub {}", self.filter.json().unwrap());
-        assert_eq!(self.subid, None, "subid already exists");
-        assert_eq!(self.sub, None, "sub already exists");
+    fn activate(&mut self, ndb: &Ndb, pool: &mut RelayPool) {
+        if self.is_active() {
+            error!("{} list is already active", self.name);
+            return;
+        }
+        debug!("activating {} sub {:?}", self.name, self.filter.json());
 
         // local subscription
-        let sub = ndb
-            .subscribe(&[self.filter.clone()])
-            .expect("ndb muted subscription");
+        match ndb.subscribe(&[self.filter.clone()]) {
+            Ok(sub) => self.sub = Some(sub),
+            Err(err) => error!("could not subscribe to {} list: {err}", self.name),
+        }
 
         // remote subscription
         let subid = Uuid::new_v4().to_string();
         pool.subscribe(subid.clone(), vec![self.filter.clone()]);
-
-        self.sub = Some(sub);
         self.subid = Some(subid);
     }
 
     // this account is no longer the selected account
-    pub fn deactivate(&mut self, ndb: &mut Ndb, pool: &mut RelayPool) {
-        debug!("deactivating muted sub {}", self.filter.json().unwrap());
-        assert_ne!(self.subid, None, "subid doesn't exist");
-        assert_ne!(self.sub, None, "sub doesn't exist");
+    fn deactivate(&mut self, ndb: &mut Ndb, pool: &mut RelayPool) {
+        debug!("deactivating {} sub {:?}", self.name, self.filter.json());
 
         // remote subscription
-        pool.unsubscribe(self.subid.as_ref().unwrap().clone());
+        if let Some(subid) = self.subid.take() {
+            pool.unsubscribe(subid);
+        }
 
         // local subscription
-        ndb.unsubscribe(self.sub.unwrap())
-            .expect("ndb muted unsubscribe");
+        if let Some(sub) = self.sub.take() {
+            if let Err(err) = ndb.unsubscribe(sub) {
+                error!("could not unsubscribe from {} list: {err}", self.name);
+            }
+        }
+    }
 
-        self.sub = None;
-        self.subid = None;
+    /// Ask a relay that just connected for the list
+    fn send_initial_filter(&self, pool: &mut RelayPool, relay_url: &str) {
+        if let Some(subid) = &self.subid {
+            pool.send_to(
+                &ClientMessage::req(subid.clone(), vec![self.filter.clone()]),
+                relay_url,
+            );
+        }
+    }
+}
+
+pub struct AccountMutedData {
+    list: AccountList,
+    muted: Arc<Muted>,
+}
+
+impl AccountMutedData {
+    pub fn new(ndb: &Ndb, pubkey: &[u8; 32]) -> Self {
+        // the user's NIP-51 muted list
+        let list = AccountList::new("muted", pubkey, 10000);
+
+        // see if the user's muted list is already there
+        let muted = list
+            .query(ndb, |txn, nks| Self::harvest_nip51_muted(ndb, txn, nks))
+            .unwrap_or_default();
+        debug!("pubkey {}: initial muted {:?}", hex::encode(pubkey), muted);
+
+        AccountMutedData {
+            list,
+            muted: Arc::new(muted),
+        }
     }
 
     fn harvest_nip51_muted(ndb: &Ndb, txn: &Transaction, nks: &[NoteKey]) -> Muted {
@@ -298,9 +383,296 @@ impl AccountMutedData {
     }
 }
 
+pub struct AccountMediaServerData {
+    list: AccountList,
+    servers: Vec<String>, // advertised via kind 10063, most preferred first
+}
+
+impl AccountMediaServerData {
+    pub fn new(ndb: &Ndb, pubkey: &[u8; 32]) -> Self {
+        // the user's blossom server list
+        let list = AccountList::new("media server", pubkey, 10063);
+
+        // see if the user's server list is already there
+        let servers = list
+            .query(ndb, |txn, nks| Self::harvest_media_servers(ndb, txn, nks))
+            .unwrap_or_default();
+        debug!(
+            "pubkey {}: initial media servers {:?}",
+            hex::encode(pubkey),
+            servers
+        );
+
+        AccountMediaServerData { list, servers }
+    }
+
+    fn harvest_media_servers(ndb: &Ndb, txn: &Transaction, nks: &[NoteKey]) -> Vec<String> {
+        let mut servers = Vec::new();
+        for nk in nks.iter() {
+            if let Ok(note) = ndb.get_note_by_key(txn, *nk) {
+                for tag in note.tags() {
+                    if tag.get(0).and_then(|t| t.variant().str()) != Some("server") {
+                        continue;
+                    }
+
+                    if let Some(url) = tag.get(1).and_then(|f| f.variant().str()) {
+                        if Url::parse(url).is_ok() && !servers.iter().any(|s| s == url) {
+                            servers.push(url.to_owned());
+                        }
+                    }
+                }
+            }
+        }
+        servers
+    }
+}
+
+/// A bookmark list we saved, on its way to the relays
+struct OutgoingList {
+    id: [u8; 32],
+    raw_msg: String,
+
+    /// Connected relays we sent it to
+    sent_to: BTreeSet<String>,
+
+    /// Relays that answered with an OK, we don't send it to them again
+    answered_by: BTreeSet<String>,
+}
+
+pub struct AccountBookmarksData {
+    list: AccountList,
+    seckey: Option<SecretKey>,
+
+    /// The newest list we got from relays or nostrdb, without our changes
+    remote: Bookmarks,
+
+    /// What we show: `remote` with our pending changes on top
+    bookmarks: Arc<Bookmarks>,
+
+    /// A relay finished sending us the list, so publishing won't
+    /// overwrite one we haven't seen
+    loaded: bool,
+
+    /// Changes the relays don't have yet. They stay on top of whatever
+    /// list arrives until we publish them.
+    pending: Vec<([u8; 32], BookmarkContext)>,
+
+    /// Lists we signed ourselves, so we don't take them for new ones
+    signed: HashSet<[u8; 32]>,
+
+    /// A new list arrived under our pending changes, sign it again
+    dirty: bool,
+
+    /// The last list we published, until every relay has it
+    outgoing: Option<OutgoingList>,
+}
+
+impl AccountBookmarksData {
+    pub fn new(ndb: &Ndb, pubkey: &[u8; 32], seckey: Option<&SecretKey>) -> Self {
+        // the user's NIP-51 bookmark list
+        let list = AccountList::new("bookmarks", pubkey, KIND_BOOKMARKS as u64);
+        let signed = HashSet::new();
+
+        // see if the user's bookmarks are already there
+        let remote = list
+            .query(ndb, |txn, nks| {
+                Self::harvest_bookmarks(ndb, txn, nks, seckey, &signed)
+            })
+            .flatten()
+            .unwrap_or_default();
+
+        AccountBookmarksData {
+            list,
+            seckey: seckey.cloned(),
+            bookmarks: Arc::new(remote.clone()),
+            remote,
+            loaded: false,
+            pending: Vec::new(),
+            signed,
+            dirty: false,
+            outgoing: None,
+        }
+    }
+
+    fn apply(bookmarks: &mut Bookmarks, note_id: &[u8; 32], context: &BookmarkContext) -> bool {
+        match context {
+            BookmarkContext::Public => bookmarks.add(*note_id, false),
+            BookmarkContext::Private => bookmarks.add(*note_id, true),
+            BookmarkContext::Remove => bookmarks.remove(note_id),
+        }
+    }
+
+    /// Add or remove a bookmark. It shows and is stored locally right
+    /// away, and goes to the relays once we've seen their list.
+    fn bookmark(
+        &mut self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        note_id: &[u8; 32],
+        context: &BookmarkContext,
+    ) {
+        if self.seckey.is_none() {
+            error!("can't bookmark without a secret key");
+            return;
+        }
+
+        let mut bookmarks = (*self.bookmarks).clone();
+        if !Self::apply(&mut bookmarks, note_id, context) {
+            return;
+        }
+
+        self.pending.push((*note_id, context.clone()));
+        self.bookmarks = Arc::new(bookmarks);
+        if !self.loaded {
+            info!("bookmarks haven't loaded yet, publishing the change later");
+        }
+        self.save(ndb, pool);
+    }
+
+    /// Sign the list we show and store it locally. Once a relay has sent
+    /// us its list, publish it too.
+    fn save(&mut self, ndb: &Ndb, pool: &mut RelayPool) {
+        self.dirty = false;
+        let Some(seckey) = &self.seckey else {
+            return;
+        };
+
+        let mut bookmarks = (*self.bookmarks).clone();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let Some(note) = bookmarks.to_note(seckey, now) else {
+            error!("could not build bookmark list");
+            return;
+        };
+        let json = match note.json() {
+            Ok(json) => json,
+            Err(err) => {
+                error!("could not build bookmark list: {err}");
+                return;
+            }
+        };
+
+        let raw_msg = format!("[\"EVENT\",{json}]");
+        self.signed.insert(*note.id());
+        if let Err(err) = ndb.process_event_with(&raw_msg, IngestMetadata::new().client(true)) {
+            error!("could not process bookmark list: {err}");
+        }
+        self.bookmarks = Arc::new(bookmarks);
+
+        if !self.loaded {
+            return;
+        }
+
+        self.remote = (*self.bookmarks).clone();
+        self.pending.clear();
+        self.outgoing = Some(OutgoingList {
+            id: *note.id(),
+            raw_msg,
+            sent_to: BTreeSet::new(),
+            answered_by: BTreeSet::new(),
+        });
+        self.publish(pool);
+    }
+
+    /// Take a newer list from relays or another client, and put our
+    /// pending changes back on top of it
+    fn poll(&mut self, ndb: &Ndb) -> bool {
+        let seckey = self.seckey.as_ref();
+        let signed = &self.signed;
+        let newer = self
+            .list
+            .poll(ndb, |txn, nks| {
+                Self::harvest_bookmarks(ndb, txn, nks, seckey, signed)
+            })
+            .flatten()
+            .filter(|b| b.created_at() > self.remote.created_at());
+        let Some(remote) = newer else {
+            return false;
+        };
+
+        let mut bookmarks = remote.clone();
+        for (note_id, context) in &self.pending {
+            Self::apply(&mut bookmarks, note_id, context);
+        }
+        self.remote = remote;
+        self.bookmarks = Arc::new(bookmarks);
+        self.dirty |= !self.pending.is_empty();
+        true
+    }
+
+    /// Send the list we saved to connected relays that don't have it yet,
+    /// again after they reconnect
+    fn publish(&mut self, pool: &mut RelayPool) {
+        let Some(outgoing) = &mut self.outgoing else {
+            return;
+        };
+
+        let connected: BTreeSet<String> = pool
+            .relays
+            .iter()
+            .filter(|relay| matches!(relay.status(), RelayStatus::Connected))
+            .filter(|relay| !relay.is_exclusive())
+            .map(|relay| relay.url().to_owned())
+            .collect();
+        outgoing.sent_to.retain(|url| connected.contains(url));
+
+        let msg = ClientMessage::raw(outgoing.raw_msg.clone());
+        for url in connected {
+            if !outgoing.answered_by.contains(&url) && outgoing.sent_to.insert(url.clone()) {
+                pool.send_to(&msg, &url);
+            }
+        }
+    }
+
+    fn eose_received(&mut self, subid: &str) -> bool {
+        if self.list.subid.as_deref() != Some(subid) {
+            return false;
+        }
+
+        self.loaded = true;
+        // publish what we changed before the list loaded
+        self.dirty |= !self.pending.is_empty();
+        true
+    }
+
+    fn ok_received(&mut self, relay: &str, event_id: &str, accepted: bool) {
+        let Some(outgoing) = &mut self.outgoing else {
+            return;
+        };
+
+        if hex::encode(outgoing.id) != event_id {
+            return;
+        }
+
+        if !accepted {
+            warn!("{relay} rejected our bookmark list");
+        }
+        outgoing.answered_by.insert(relay.to_owned());
+    }
+
+    /// The newest list among `nks` that we didn't sign, if any
+    fn harvest_bookmarks(
+        ndb: &Ndb,
+        txn: &Transaction,
+        nks: &[NoteKey],
+        seckey: Option<&SecretKey>,
+        signed: &HashSet<[u8; 32]>,
+    ) -> Option<Bookmarks> {
+        nks.iter()
+            .filter_map(|nk| ndb.get_note_by_key(txn, *nk).ok())
+            .filter(|note| !signed.contains(note.id()))
+            .max_by_key(|note| note.created_at())
+            .map(|note| Bookmarks::from_note(&note, seckey))
+    }
+}
+
 pub struct AccountData {
     relay: AccountRelayData,
     muted: AccountMutedData,
+    media_servers: AccountMediaServerData,
+    bookmarks: AccountBookmarksData,
 }
 
 /// The interface for managing the user's accounts.
@@ -312,6 +684,8 @@ pub struct Accounts {
     account_data: BTreeMap<[u8; 32], AccountData>,
     forced_relays: BTreeSet<RelaySpec>,
     bootstrap_relays: BTreeSet<RelaySpec>,
+    /// Relays columns need on top of the account's, like relay feeds
+    column_relays: BTreeSet<RelaySpec>,
     needs_relay_config: bool,
 }
 
@@ -358,6 +732,7 @@ impl Accounts {
             account_data,
             forced_relays,
             bootstrap_relays,
+            column_relays: BTreeSet::new(),
             needs_relay_config: true,
         }
     }
@@ -423,6 +798,20 @@ impl Accounts {
         self.needs_relay_config = true;
     }
 
+    /// Stay connected to these relays as long as a column needs them,
+    /// even if they aren't in the account's relay list
+    pub fn set_column_relays(&mut self, relays: &BTreeSet<String>) {
+        let relays: BTreeSet<RelaySpec> = relays
+            .iter()
+            .map(|u| RelaySpec::new(AccountRelayData::canonicalize_url(u), false, false))
+            .collect();
+
+        if relays != self.column_relays {
+            self.column_relays = relays;
+            self.needs_relay_config = true;
+        }
+    }
+
     fn contains_account(&self, pubkey: &[u8; 32]) -> Option<ContainsAccount> {
         for (index, account) in self.accounts.iter().enumerate() {
             let has_pubkey = account.key.pubkey.bytes() == pubkey;
@@ -592,7 +981,59 @@ impl Accounts {
         Box::new(|_: &Note, _: &[u8; 32]| false)
     }
 
+    /// The media servers the selected account uploads to, from its kind
+    /// 10063 list. Empty if they haven't published one.
+    pub fn selected_media_servers(&self) -> &[String] {
+        self.selected_account_pubkey_bytes()
+            .and_then(|pk| self.account_data.get(pk))
+            .map(|data| data.media_servers.servers.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The selected account's bookmarks, shared so they can be shown
+    /// alongside a mutable borrow of the accounts
+    pub fn bookmarks(&self) -> Arc<Bookmarks> {
+        self.selected_account_pubkey_bytes()
+            .and_then(|pk| self.account_data.get(pk))
+            .map(|data| Arc::clone(&data.bookmarks.bookmarks))
+            .unwrap_or_default()
+    }
+
+    /// Bookmark a note for the selected account, or remove it
+    pub fn bookmark(
+        &mut self,
+        ndb: &Ndb,
+        pool: &mut RelayPool,
+        note_id: &[u8; 32],
+        context: &BookmarkContext,
+    ) {
+        match self.get_selected_account_data() {
+            Some(data) => data.bookmarks.bookmark(ndb, pool, note_id, context),
+            None => error!("no account is currently selected."),
+        }
+    }
+
+    /// A relay finished sending one of our account subscriptions. Returns
+    /// false if it isn't one of ours.
+    pub fn eose_received(&mut self, subid: &str) -> bool {
+        self.account_data
+            .values_mut()
+            .any(|data| data.bookmarks.eose_received(subid))
+    }
+
+    /// A relay answered a note we sent
+    pub fn ok_received(&mut self, relay: &str, event_id: &str, accepted: bool) {
+        for data in self.account_data.values_mut() {
+            data.bookmarks.ok_received(relay, event_id, accepted);
+        }
+    }
+
     pub fn send_initial_filters(&mut self, pool: &mut RelayPool, relay_url: &str) {
+        if pool.is_exclusive(relay_url) {
+            // only there for a column
+            return;
+        }
+
         for data in self.account_data.values() {
             // send the active account's relay list subscription
             if let Some(relay_subid) = &data.relay.subid {
@@ -601,13 +1042,11 @@ impl Accounts {
                     relay_url,
                 );
             }
-            // send the active account's muted subscription
-            if let Some(muted_subid) = &data.muted.subid {
-                pool.send_to(
-                    &ClientMessage::req(muted_subid.clone(), vec![data.muted.filter.clone()]),
-                    relay_url,
-                );
-            }
+            // send the active account's muted, media server and bookmarks
+            // subscriptions
+            data.muted.list.send_initial_filter(pool, relay_url);
+            data.media_servers.list.send_initial_filter(pool, relay_url);
+            data.bookmarks.list.send_initial_filter(pool, relay_url);
         }
     }
 
@@ -632,10 +1071,18 @@ impl Accounts {
     fn handle_added_account(&mut self, ndb: &Ndb, pubkey: &[u8; 32]) {
         debug!("handle_added_account {}", hex::encode(pubkey));
 
+        let seckey = self
+            .accounts
+            .iter()
+            .find(|acc| acc.key.pubkey.bytes() == pubkey)
+            .and_then(|acc| acc.key.secret_key.as_ref());
+
         // Create the user account data
         let new_account_data = AccountData {
             relay: AccountRelayData::new(ndb, pubkey),
             muted: AccountMutedData::new(ndb, pubkey),
+            media_servers: AccountMediaServerData::new(ndb, pubkey),
+            bookmarks: AccountBookmarksData::new(ndb, pubkey, seckey),
         };
         self.account_data.insert(*pubkey, new_account_data);
     }
@@ -663,15 +1110,27 @@ impl Accounts {
                     changed = true;
                 }
             }
-            if let Some(sub) = data.muted.sub {
-                let nks = ndb.poll_for_notes(sub, 1);
-                if !nks.is_empty() {
-                    let txn = Transaction::new(ndb).expect("txn");
-                    let muted = AccountMutedData::harvest_nip51_muted(ndb, &txn, &nks);
-                    debug!("pubkey {}: updated muted {:?}", hex::encode(pubkey), muted);
-                    data.muted.muted = Arc::new(muted);
-                    changed = true;
-                }
+            let muted = data.muted.list.poll(ndb, |txn, nks| {
+                AccountMutedData::harvest_nip51_muted(ndb, txn, nks)
+            });
+            if let Some(muted) = muted {
+                debug!("pubkey {}: updated muted {:?}", hex::encode(pubkey), muted);
+                data.muted.muted = Arc::new(muted);
+                changed = true;
+            }
+            let servers = data.media_servers.list.poll(ndb, |txn, nks| {
+                AccountMediaServerData::harvest_media_servers(ndb, txn, nks)
+            });
+            if let Some(servers) = servers {
+                debug!(
+                    "pubkey {}: updated media servers {:?}",
+                    hex::encode(pubkey),
+                    servers
+                );
+                data.media_servers.servers = servers;
+            }
+            if data.bookmarks.poll(ndb) {
+                debug!("pubkey {}: updated bookmarks", hex::encode(pubkey));
             }
         }
         changed
@@ -704,6 +1163,15 @@ impl Accounts {
             desired_relays = self.bootstrap_relays.clone();
         }
 
+        // Columns can browse relays we don't otherwise use. Those only
+        // get the column's own subscription.
+        let exclusive: BTreeSet<String> = self
+            .column_relays
+            .difference(&desired_relays)
+            .map(|r| r.url.clone())
+            .collect();
+        desired_relays.extend(self.column_relays.iter().cloned());
+
         debug!("current relays: {:?}", pool.urls());
         debug!("desired relays: {:?}", desired_relays);
 
@@ -728,6 +1196,7 @@ impl Accounts {
             debug!("removing unwanted relays: {:?}", sub);
             pool.remove_urls(&sub.iter().map(|r| r.url.clone()).collect());
         }
+        pool.set_exclusive(&exclusive);
 
         debug!("current relays: {:?}", pool.urls());
     }
@@ -753,9 +1222,17 @@ impl Accounts {
                         // this account has relay subs, deactivate them
                         data.relay.deactivate(ndb, pool);
                     }
-                    if data.muted.sub.is_some() {
+                    if data.muted.list.is_active() {
                         // this account has muted subs, deactivate them
-                        data.muted.deactivate(ndb, pool);
+                        data.muted.list.deactivate(ndb, pool);
+                    }
+                    if data.media_servers.list.is_active() {
+                        // this account has media server subs, deactivate them
+                        data.media_servers.list.deactivate(ndb, pool);
+                    }
+                    if data.bookmarks.list.is_active() {
+                        // this account has bookmark subs, deactivate them
+                        data.bookmarks.list.deactivate(ndb, pool);
                     }
                 }
             }
@@ -787,10 +1264,25 @@ impl Accounts {
                 // the currently selected account doesn't have relay subs, activate them
                 data.relay.activate(ndb, pool);
             }
-            if data.muted.sub.is_none() {
+            if !data.muted.list.is_active() {
                 // the currently selected account doesn't have muted subs, activate them
-                data.muted.activate(ndb, pool);
+                data.muted.list.activate(ndb, pool);
+            }
+            if !data.media_servers.list.is_active() {
+                // the currently selected account doesn't have media server subs, activate them
+                data.media_servers.list.activate(ndb, pool);
+            }
+            if !data.bookmarks.list.is_active() {
+                // the currently selected account doesn't have bookmark subs, activate them
+                data.bookmarks.list.activate(ndb, pool);
+            }
+
+            // publish changes made before the list loaded or under a newer
+            // list, and send it to relays that don't have it yet
+            if data.bookmarks.dirty {
+                data.bookmarks.save(ndb, pool);
             }
+            data.bookmarks.publish(pool);
         }
     }
 
@@ -1,9 +1,11 @@
 use crate::persist::{AppSizeHandler, ZoomHandler};
+use crate::poll::Polls;
 use crate::wallet::GlobalWallet;
 use crate::zaps::Zaps;
 use crate::{
     frame_history::FrameHistory, AccountStorage, Accounts, AppContext, Args, DataPath,
-    DataPathType, Directory, Images, NoteCache, RelayDebugView, ThemeHandler, UnknownIds,
+    DataPathType, Directory, Images, Nip05Cache, NoteCache, RelayDebugView, ThemeHandler,
+    UnknownIds,
 };
 use egui::ThemePreference;
 use egui_winit::clipboard::Clipboard;
@@ -37,6 +39,8 @@ pub struct Notedeck {
     unrecognized_args: BTreeSet<String>,
     clipboard: Clipboard,
     zaps: Zaps,
+    nip05: Nip05Cache,
+    polls: Polls,
     frame_history: FrameHistory,
 }
 
@@ -91,6 +95,10 @@ impl eframe::App for Notedeck {
         self.zaps
             .process(&mut self.accounts, &mut self.global_wallet, &self.ndb);
 
+        self.nip05.handle_io();
+        self.polls.update(&mut self.ndb);
+        self.img_cache.handle_io();
+
         render_notedeck(self, ctx);
 
         self.zoom.try_save_zoom_factor(ctx);
@@ -197,7 +205,7 @@ impl Notedeck {
             }
         }
 
-        let img_cache = Images::new(img_cache_dir);
+        let img_cache = Images::new(img_cache_dir, parsed_args.cache_limits);
         let note_cache = NoteCache::default();
         let unknown_ids = UnknownIds::default();
         let zoom = ZoomHandler::new(&path);
@@ -214,6 +222,10 @@ impl Notedeck {
 
         let global_wallet = GlobalWallet::new(&path);
         let zaps = Zaps::default();
+        let nip05 = Nip05Cache::new(
+            ctx,
+            path.path(DataPathType::Cache).join(Nip05Cache::rel_path()),
+        );
 
         Self {
             ndb,
@@ -233,6 +245,8 @@ impl Notedeck {
             frame_history: FrameHistory::default(),
             clipboard: Clipboard::new(None),
             zaps,
+            nip05,
+            polls: Polls::default(),
         }
     }
 
@@ -255,6 +269,8 @@ impl Notedeck {
             theme: &mut self.theme,
             clipboard: &mut self.clipboard,
             zaps: &mut self.zaps,
+            nip05: &mut self.nip05,
+            polls: &mut self.polls,
             frame_history: &mut self.frame_history,
         }
     }
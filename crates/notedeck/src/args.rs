@@ -3,6 +3,8 @@ use std::collections::BTreeSet;
 use enostr::{Keypair, Pubkey, SecretKey};
 use tracing::error;
 
+use crate::CacheLimits;
+
 pub struct Args {
     pub relays: Vec<String>,
     pub is_mobile: Option<bool>,
@@ -17,6 +19,7 @@ pub struct Args {
     pub use_keystore: bool,
     pub dbpath: Option<String>,
     pub datapath: Option<String>,
+    pub cache_limits: CacheLimits,
 }
 
 impl Args {
@@ -34,6 +37,7 @@ impl Args {
             use_keystore: true,
             dbpath: None,
             datapath: None,
+            cache_limits: CacheLimits::default(),
         };
 
         let mut i = 0;
@@ -112,6 +116,28 @@ impl Args {
                     continue;
                 };
                 res.relays.push(relay.clone());
+            } else if arg == "--img-cache-size" {
+                i += 1;
+                if let Some(mb) = parse_number(arg, args.get(i)) {
+                    res.cache_limits.img.disk_bytes = mb * 1024 * 1024;
+                }
+            } else if arg == "--gif-cache-size" {
+                i += 1;
+                if let Some(mb) = parse_number(arg, args.get(i)) {
+                    res.cache_limits.gif.disk_bytes = mb * 1024 * 1024;
+                }
+            } else if arg == "--texture-cache-size" {
+                i += 1;
+                if let Some(mb) = parse_number(arg, args.get(i)) {
+                    let bytes = (mb * 1024 * 1024) as usize;
+                    res.cache_limits.img.texture_bytes = bytes;
+                    res.cache_limits.gif.texture_bytes = bytes;
+                }
+            } else if arg == "--url-cache-entries" {
+                i += 1;
+                if let Some(entries) = parse_number(arg, args.get(i)) {
+                    res.cache_limits.url_entries = entries as usize;
+                }
             } else if arg == "--no-keystore" {
                 res.use_keystore = false;
             } else if arg == "--relay-debug" {
@@ -126,3 +152,18 @@ impl Args {
         (res, unrecognized_args)
     }
 }
+
+fn parse_number(arg: &str, value: Option<&String>) -> Option<u64> {
+    let Some(value) = value else {
+        error!("{arg} argument missing?");
+        return None;
+    };
+
+    match value.parse() {
+        Ok(n) => Some(n),
+        Err(e) => {
+            error!("failed to parse {arg} argument {value}: {e}");
+            None
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! [BlurHash](https://blurha.sh), a short string describing a blurry version
+//! of an image that we can show while the real one loads.
+
+use std::f32::consts::PI;
+
+const BASE83: &[u8; 83] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+fn decode83(s: &str) -> Option<u32> {
+    s.bytes().try_fold(0u32, |value, c| {
+        let digit = BASE83.iter().position(|&b| b == c)? as u32;
+        value.checked_mul(83)?.checked_add(digit)
+    })
+}
+
+fn encode83(value: u32, length: u32, out: &mut String) {
+    for i in 1..=length {
+        let digit = (value / 83u32.pow(length - i)) % 83;
+        out.push(BASE83[digit as usize] as char);
+    }
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let v = value as f32 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let v = value.clamp(0.0, 1.0);
+    if v <= 0.0031308 {
+        (v * 12.92 * 255.0 + 0.5) as u8
+    } else {
+        ((1.055 * v.powf(1.0 / 2.4) - 0.055) * 255.0 + 0.5) as u8
+    }
+}
+
+fn sign_pow(value: f32, exp: f32) -> f32 {
+    value.abs().powf(exp).copysign(value)
+}
+
+/// Is this something we can decode?
+pub fn is_valid(hash: &str) -> bool {
+    components(hash).is_some()
+}
+
+fn components(hash: &str) -> Option<(usize, usize)> {
+    if hash.len() < 6 || !hash.is_ascii() {
+        return None;
+    }
+
+    let size_flag = decode83(&hash[0..1])? as usize;
+    let (num_x, num_y) = (size_flag % 9 + 1, size_flag / 9 + 1);
+
+    (hash.len() == 4 + 2 * num_x * num_y).then_some((num_x, num_y))
+}
+
+/// Decode `hash` into `width` x `height` rgba pixels
+pub fn decode(hash: &str, width: usize, height: usize) -> Option<Vec<u8>> {
+    let (num_x, num_y) = components(hash)?;
+
+    let max_value = (decode83(&hash[1..2])? as f32 + 1.0) / 166.0;
+
+    let mut colors = Vec::with_capacity(num_x * num_y);
+    let dc = decode83(&hash[2..6])?;
+    colors.push([
+        srgb_to_linear((dc >> 16) as u8),
+        srgb_to_linear((dc >> 8) as u8),
+        srgb_to_linear(dc as u8),
+    ]);
+
+    for i in 1..num_x * num_y {
+        let ac = decode83(&hash[4 + i * 2..6 + i * 2])?;
+        let quant = |v: u32| sign_pow((v as f32 - 9.0) / 9.0, 2.0) * max_value;
+        colors.push([quant(ac / (19 * 19)), quant((ac / 19) % 19), quant(ac % 19)]);
+    }
+
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let mut rgb = [0.0f32; 3];
+            for j in 0..num_y {
+                let basis_y = (PI * y as f32 * j as f32 / height as f32).cos();
+                for i in 0..num_x {
+                    let basis = (PI * x as f32 * i as f32 / width as f32).cos() * basis_y;
+                    let color = colors[i + j * num_x];
+                    rgb[0] += color[0] * basis;
+                    rgb[1] += color[1] * basis;
+                    rgb[2] += color[2] * basis;
+                }
+            }
+
+            pixels.extend_from_slice(&[
+                linear_to_srgb(rgb[0]),
+                linear_to_srgb(rgb[1]),
+                linear_to_srgb(rgb[2]),
+                255,
+            ]);
+        }
+    }
+
+    Some(pixels)
+}
+
+/// Describe `width` x `height` rgba pixels with `num_x` x `num_y`
+/// components (each 1 to 9). Small images encode just as well as big ones
+/// and are much faster, so scale it down first.
+pub fn encode(num_x: u32, num_y: u32, width: usize, height: usize, rgba: &[u8]) -> String {
+    let num_x = num_x.clamp(1, 9) as usize;
+    let num_y = num_y.clamp(1, 9) as usize;
+
+    let mut factors = Vec::with_capacity(num_x * num_y);
+    for j in 0..num_y {
+        for i in 0..num_x {
+            let normalisation = if i == 0 && j == 0 { 1.0 } else { 2.0 };
+            let mut rgb = [0.0f32; 3];
+            for y in 0..height {
+                let basis_y = (PI * j as f32 * y as f32 / height as f32).cos();
+                for x in 0..width {
+                    let basis =
+                        normalisation * (PI * i as f32 * x as f32 / width as f32).cos() * basis_y;
+                    let pixel = &rgba[(y * width + x) * 4..];
+                    rgb[0] += basis * srgb_to_linear(pixel[0]);
+                    rgb[1] += basis * srgb_to_linear(pixel[1]);
+                    rgb[2] += basis * srgb_to_linear(pixel[2]);
+                }
+            }
+
+            let scale = 1.0 / (width * height) as f32;
+            factors.push(rgb.map(|c| c * scale));
+        }
+    }
+
+    let mut hash = String::with_capacity(4 + 2 * factors.len());
+    encode83(((num_x - 1) + (num_y - 1) * 9) as u32, 1, &mut hash);
+
+    let (dc, ac) = factors.split_first().expect("at least one component");
+    let max_value = if ac.is_empty() {
+        encode83(0, 1, &mut hash);
+        1.0
+    } else {
+        let actual_max = ac.iter().flatten().fold(0.0f32, |m, c| m.max(c.abs()));
+        let quantised = ((actual_max * 166.0 - 0.5).floor()).clamp(0.0, 82.0) as u32;
+        encode83(quantised, 1, &mut hash);
+        (quantised as f32 + 1.0) / 166.0
+    };
+
+    let dc_value = ((linear_to_srgb(dc[0]) as u32) << 16)
+        + ((linear_to_srgb(dc[1]) as u32) << 8)
+        + linear_to_srgb(dc[2]) as u32;
+    encode83(dc_value, 4, &mut hash);
+
+    for component in ac {
+        let quant = |c: f32| ((sign_pow(c / max_value, 0.5) * 9.0 + 9.5).floor()).clamp(0.0, 18.0);
+        let value =
+            quant(component[0]) * 19.0 * 19.0 + quant(component[1]) * 19.0 + quant(component[2]);
+        encode83(value as u32, 2, &mut hash);
+    }
+
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blurhash_round_trip() {
+        // left half red, right half blue
+        let (w, h) = (16, 8);
+        let rgba: Vec<u8> = (0..w * h)
+            .flat_map(|i| {
+                if i % w < w / 2 {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 0, 255, 255]
+                }
+            })
+            .collect();
+
+        let hash = encode(4, 3, w, h, &rgba);
+        assert_eq!(hash.len(), 4 + 2 * 4 * 3);
+        assert!(is_valid(&hash));
+
+        let pixels = decode(&hash, w, h).unwrap();
+        assert_eq!(pixels.len(), w * h * 4);
+
+        let left = &pixels[(h / 2 * w) * 4..][..4];
+        let right = &pixels[(h / 2 * w + w - 1) * 4..][..4];
+        assert!(left[0] > left[2], "left is red {left:?}");
+        assert!(right[2] > right[0], "right is blue {right:?}");
+    }
+
+    #[test]
+    fn test_decode_known_hash() {
+        let hash = "LEHV6nWB2yk8pyo0adR*.7kCMdnj";
+        assert!(is_valid(hash));
+        assert_eq!(decode(hash, 4, 3).unwrap().len(), 4 * 3 * 4);
+
+        assert!(!is_valid("LEHV6nWB2yk8"));
+        assert!(decode("not a blurhash!", 4, 3).is_none());
+    }
+}
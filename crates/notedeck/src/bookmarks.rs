@@ -0,0 +1,318 @@
+//! The account's [NIP-51](https://github.com/nostr-protocol/nips/blob/master/51.md)
+//! bookmark list, kind 10003. Public bookmarks are `e` tags, private ones
+//! are the same tags encrypted to ourselves in the content.
+
+use crate::lists::KIND_BOOKMARKS;
+use enostr::SecretKey;
+use nostr::nips::{nip04, nip44};
+use nostr::Keys;
+use nostrdb::{Note, NoteBuilder, Tag};
+use tracing::error;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Bookmark {
+    pub id: [u8; 32],
+    pub relay: Option<String>,
+    pub private: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Bookmarks {
+    /// In the order they were saved, oldest first
+    entries: Vec<Bookmark>,
+
+    /// Tags we don't show (hashtags, articles, urls), kept so that
+    /// saving the list doesn't drop them
+    public_other: Vec<Vec<String>>,
+    private_other: Vec<Vec<String>>,
+
+    /// Private content we couldn't decrypt, kept as is so that we don't
+    /// overwrite it
+    locked: Option<String>,
+
+    created_at: u64,
+}
+
+impl Bookmarks {
+    /// Read a kind 10003 list. Private bookmarks are only available with
+    /// the author's secret key.
+    pub fn from_note(note: &Note, seckey: Option<&SecretKey>) -> Self {
+        let mut bookmarks = Bookmarks {
+            created_at: note.created_at(),
+            ..Default::default()
+        };
+
+        let public = note.tags().into_iter().map(|tag| {
+            (0..tag.count())
+                .filter_map(|i| tag_value(&tag, i))
+                .collect::<Vec<String>>()
+        });
+        bookmarks.harvest(public, false);
+
+        let content = note.content();
+        if content.is_empty() {
+            return bookmarks;
+        }
+
+        match seckey.and_then(|sk| decrypt_tags(sk, content)) {
+            Some(private) => bookmarks.harvest(private.into_iter(), true),
+            None => bookmarks.locked = Some(content.to_owned()),
+        }
+
+        bookmarks
+    }
+
+    fn harvest(&mut self, tags: impl Iterator<Item = Vec<String>>, private: bool) {
+        for tag in tags {
+            let id = (tag.len() >= 2 && tag[0] == "e")
+                .then(|| hex::decode(&tag[1]).ok())
+                .flatten()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok());
+
+            match id {
+                Some(id) if !self.contains(&id) => self.entries.push(Bookmark {
+                    id,
+                    relay: tag.get(2).filter(|r| !r.is_empty()).cloned(),
+                    private,
+                }),
+                Some(_) => {}
+                None if private => self.private_other.push(tag),
+                None => self.public_other.push(tag),
+            }
+        }
+    }
+
+    pub fn contains(&self, id: &[u8; 32]) -> bool {
+        self.entries.iter().any(|b| &b.id == id)
+    }
+
+    /// Whether we could read the private part, so we can add to it
+    pub fn is_unlocked(&self) -> bool {
+        self.locked.is_none()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// When the list we read was made
+    pub fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    /// The most recently saved first
+    pub fn newest_first(&self) -> impl Iterator<Item = &Bookmark> {
+        self.entries.iter().rev()
+    }
+
+    /// Returns false if the note was already bookmarked, or if it's
+    /// private and we can't read the private part of the list
+    pub fn add(&mut self, id: [u8; 32], private: bool) -> bool {
+        if self.contains(&id) || (private && !self.is_unlocked()) {
+            return false;
+        }
+
+        self.entries.push(Bookmark {
+            id,
+            relay: None,
+            private,
+        });
+        true
+    }
+
+    pub fn remove(&mut self, id: &[u8; 32]) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|b| &b.id != id);
+        self.entries.len() != before
+    }
+
+    /// Sign the list as a new version. It's always newer than the one we
+    /// read, even when edited twice within a second.
+    pub fn to_note(&mut self, seckey: &SecretKey, now: u64) -> Option<Note<'static>> {
+        let (public, private) = self.tags();
+
+        let content = if private.is_empty() {
+            self.locked.clone().unwrap_or_default()
+        } else {
+            encrypt_tags(seckey, &private)?
+        };
+
+        let created_at = now.max(self.created_at + 1);
+        let mut builder = NoteBuilder::new()
+            .kind(KIND_BOOKMARKS)
+            .content(&content)
+            .created_at(created_at);
+
+        for tag in &public {
+            builder = builder.start_tag();
+            for value in tag {
+                builder = builder.tag_str(value);
+            }
+        }
+
+        let note = builder.sign(&seckey.to_secret_bytes()).build()?;
+        self.created_at = created_at;
+        Some(note)
+    }
+
+    fn tags(&self) -> (Vec<Vec<String>>, Vec<Vec<String>>) {
+        let mut public = self.public_other.clone();
+        let mut private = self.private_other.clone();
+
+        for bookmark in &self.entries {
+            let mut tag = vec!["e".to_owned(), hex::encode(bookmark.id)];
+            if let Some(relay) = &bookmark.relay {
+                tag.push(relay.clone());
+            }
+
+            if bookmark.private {
+                private.push(tag);
+            } else {
+                public.push(tag);
+            }
+        }
+
+        (public, private)
+    }
+}
+
+/// nostrdb packs 64 character hex values as ids, which `get_str` doesn't
+/// return
+fn tag_value(tag: &Tag, index: u16) -> Option<String> {
+    match tag.get_id(index) {
+        Some(id) => Some(hex::encode(id)),
+        None => tag.get_str(index).map(str::to_owned),
+    }
+}
+
+/// Private tags are encrypted to ourselves. Older clients used NIP-04,
+/// which we can still read but no longer write.
+fn decrypt_tags(seckey: &SecretKey, content: &str) -> Option<Vec<Vec<String>>> {
+    let pubkey = Keys::new(seckey.clone()).public_key();
+
+    let json = if content.contains("?iv=") {
+        nip04::decrypt(seckey, &pubkey, content).ok()?
+    } else {
+        nip44::decrypt(seckey, &pubkey, content).ok()?
+    };
+
+    match serde_json::from_str(&json) {
+        Ok(tags) => Some(tags),
+        Err(err) => {
+            error!("invalid private bookmarks: {err}");
+            None
+        }
+    }
+}
+
+fn encrypt_tags(seckey: &SecretKey, tags: &[Vec<String>]) -> Option<String> {
+    let pubkey = Keys::new(seckey.clone()).public_key();
+    let json = serde_json::to_string(tags).ok()?;
+
+    match nip44::encrypt(seckey, &pubkey, json, nip44::Version::V2) {
+        Ok(content) => Some(content),
+        Err(err) => {
+            error!("could not encrypt private bookmarks: {err}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enostr::FullKeypair;
+    use nostrdb::{Config, Filter, IngestMetadata, Ndb, Transaction};
+    use tempfile::TempDir;
+
+    const BOOKMARKS: &str = r#"{"id":"e568c18aae241d35bea56f5f7049c7832778e3c770fc9f8e2b029cdbfe688664","pubkey":"94bdd61bdc97db14423926d663d33c6ab6dd5e59d38ba26bf14ffb8b40f00a18","created_at":1743191138,"kind":10003,"tags":[["t","nostr"],["e","0101010101010101010101010101010101010101010101010101010101010101","wss://relay.damus.io"],["e","0202020202020202020202020202020202020202020202020202020202020202"]],"content":"","sig":"bf7d7e5003ac548f22cf43fb21befcf22075cb4781d1a46653a32890d221c573af7fd1158a290d44276f6040430a6ea2cb625e6acccc997e189a01e6799f0b10"}"#;
+
+    fn seckey() -> SecretKey {
+        FullKeypair::generate().secret_key
+    }
+
+    #[test]
+    fn test_private_bookmarks_round_trip() {
+        let sk = seckey();
+        let mut bookmarks = Bookmarks::default();
+        assert!(bookmarks.add([1; 32], false));
+        assert!(bookmarks.add([2; 32], true));
+        assert!(!bookmarks.add([1; 32], true));
+
+        let note = bookmarks.to_note(&sk, 100).unwrap();
+        assert_eq!(note.kind(), KIND_BOOKMARKS);
+        assert!(!note.content().contains(&hex::encode([2; 32])));
+
+        let read = Bookmarks::from_note(&note, Some(&sk));
+        let saved: Vec<(&[u8; 32], bool)> =
+            read.newest_first().map(|b| (&b.id, b.private)).collect();
+        assert_eq!(saved, vec![(&[2; 32], true), (&[1; 32], false)]);
+
+        // without the key only the public part is readable, and we keep
+        // the private part as is
+        let mut public = Bookmarks::from_note(&note, None);
+        assert!(!public.is_unlocked());
+        assert!(public.contains(&[1; 32]));
+        assert!(!public.contains(&[2; 32]));
+        assert!(!public.add([3; 32], true));
+
+        let resaved = public.to_note(&sk, 100).unwrap();
+        assert_eq!(resaved.content(), note.content());
+        assert!(resaved.created_at() > note.created_at());
+    }
+
+    #[test]
+    fn test_keeps_other_tags() {
+        let sk = seckey();
+        let note = NoteBuilder::new()
+            .kind(KIND_BOOKMARKS)
+            .content("")
+            .start_tag()
+            .tag_str("t")
+            .tag_str("nostr")
+            .start_tag()
+            .tag_str("e")
+            .tag_str(&hex::encode([1; 32]))
+            .tag_str("wss://relay.damus.io")
+            .sign(&sk.to_secret_bytes())
+            .build()
+            .unwrap();
+
+        let mut bookmarks = Bookmarks::from_note(&note, Some(&sk));
+        assert!(bookmarks.remove(&[1; 32]));
+        assert!(bookmarks.is_empty());
+
+        let saved = bookmarks.to_note(&sk, 0).unwrap();
+        let tags: Vec<Option<&str>> = saved.tags().into_iter().map(|t| t.get_str(0)).collect();
+        assert_eq!(tags, vec![Some("t")]);
+    }
+
+    #[tokio::test]
+    async fn test_reads_ids_from_ndb() {
+        let tmp_dir = TempDir::new().unwrap();
+        let ndb = Ndb::new(tmp_dir.path().to_str().unwrap(), &Config::new()).unwrap();
+
+        let filter = Filter::new().kinds([KIND_BOOKMARKS as u64]).build();
+        let sub_id = ndb.subscribe(&[filter]).unwrap();
+        let ev = format!(r#"["EVENT", "random_string", {BOOKMARKS}]"#);
+        assert!(ndb.process_event_with(&ev, IngestMetadata::new()).is_ok());
+
+        let note_key = ndb.wait_for_notes(sub_id, 1).await.unwrap()[0];
+        let txn = Transaction::new(&ndb).unwrap();
+        let note = ndb.get_note_by_key(&txn, note_key).unwrap();
+
+        let bookmarks = Bookmarks::from_note(&note, None);
+        let saved: Vec<(&[u8; 32], Option<&str>)> = bookmarks
+            .newest_first()
+            .map(|b| (&b.id, b.relay.as_deref()))
+            .collect();
+        assert_eq!(
+            saved,
+            vec![(&[2; 32], None), (&[1; 32], Some("wss://relay.damus.io"))]
+        );
+        assert_eq!(
+            bookmarks.public_other,
+            vec![vec!["t".to_owned(), "nostr".to_owned()]]
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
-    frame_history::FrameHistory, wallet::GlobalWallet, zaps::Zaps, Accounts, Args, DataPath,
-    Images, NoteCache, ThemeHandler, UnknownIds,
+    frame_history::FrameHistory, poll::Polls, wallet::GlobalWallet, zaps::Zaps, Accounts, Args,
+    DataPath, Images, Nip05Cache, NoteCache, ThemeHandler, UnknownIds,
 };
 use egui_winit::clipboard::Clipboard;
 
@@ -22,5 +22,7 @@ pub struct AppContext<'a> {
     pub theme: &'a mut ThemeHandler,
     pub clipboard: &'a mut Clipboard,
     pub zaps: &'a mut Zaps,
+    pub nip05: &'a mut Nip05Cache,
+    pub polls: &'a mut Polls,
     pub frame_history: &'a mut FrameHistory,
 }
@@ -1,5 +1,6 @@
 use crate::error::{Error, FilterError};
 use crate::note::NoteRef;
+use crate::poll::KIND_POLL;
 use nostrdb::{Filter, FilterBuilder, Note, Subscription};
 use std::collections::HashMap;
 use tracing::{debug, warn};
@@ -167,7 +168,7 @@ pub struct FilteredTags {
 
 impl FilteredTags {
     pub fn into_follow_filter(self) -> Vec<Filter> {
-        self.into_filter([1], default_limit())
+        self.into_filter([1, KIND_POLL as u64], default_limit())
     }
 
     // TODO: make this more general
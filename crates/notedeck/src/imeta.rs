@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use nostrdb::Note;
+
+/// What a note's `imeta` tags (NIP-92) tell us about one of its urls, so
+/// we can lay it out before it has loaded
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Imeta {
+    pub dimensions: Option<(u32, u32)>,
+    pub blurhash: Option<String>,
+    pub thumbhash: Option<String>,
+    pub mime_type: Option<String>,
+}
+
+impl Imeta {
+    /// Width over height, if we know it
+    pub fn aspect_ratio(&self) -> Option<f32> {
+        if let Some((w, h)) = self.dimensions.filter(|(w, h)| *w > 0 && *h > 0) {
+            return Some(w as f32 / h as f32);
+        }
+
+        self.thumbhash
+            .as_deref()
+            .and_then(crate::thumbhash::from_base64)
+            .and_then(|hash| crate::thumbhash::aspect_ratio(&hash))
+    }
+}
+
+/// The `imeta` entries of a note, by url
+pub fn note_imeta(note: &Note) -> HashMap<String, Imeta> {
+    let mut imetas = HashMap::new();
+
+    for tag in note.tags() {
+        if tag.get_str(0) != Some("imeta") {
+            continue;
+        }
+
+        let mut url = None;
+        let mut imeta = Imeta::default();
+        for i in 1..tag.count() {
+            let Some((key, value)) = tag.get_str(i).and_then(|entry| entry.split_once(' ')) else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key {
+                "url" => url = Some(value.to_owned()),
+                "dim" => {
+                    imeta.dimensions = value
+                        .split_once('x')
+                        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                }
+                "blurhash" if crate::blurhash::is_valid(value) => {
+                    imeta.blurhash = Some(value.to_owned())
+                }
+                "thumbhash" => imeta.thumbhash = Some(value.to_owned()),
+                "m" => imeta.mime_type = Some(value.to_owned()),
+                _ => {}
+            }
+        }
+
+        if let Some(url) = url {
+            imetas.insert(url, imeta);
+        }
+    }
+
+    imetas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostrdb::NoteBuilder;
+
+    #[test]
+    fn test_note_imeta() {
+        let note = NoteBuilder::new()
+            .content("https://example.com/a.jpg https://example.com/b.png")
+            .start_tag()
+            .tag_str("imeta")
+            .tag_str("url https://example.com/a.jpg")
+            .tag_str("m image/jpeg")
+            .tag_str("dim 1200x800")
+            .tag_str("blurhash LEHV6nWB2yk8pyo0adR*.7kCMdnj")
+            .start_tag()
+            .tag_str("imeta")
+            .tag_str("url https://example.com/b.png")
+            .tag_str("dim bogus")
+            .tag_str("thumbhash 1QcSHQRnh493V4dIh4eXh1h4kJUI")
+            .sign(&[1; 32])
+            .build()
+            .unwrap();
+
+        let imetas = note_imeta(&note);
+        assert_eq!(imetas.len(), 2);
+
+        let a = &imetas["https://example.com/a.jpg"];
+        assert_eq!(a.dimensions, Some((1200, 800)));
+        assert_eq!(a.mime_type.as_deref(), Some("image/jpeg"));
+        assert_eq!(a.aspect_ratio(), Some(1.5));
+        assert!(a.blurhash.is_some());
+
+        let b = &imetas["https://example.com/b.png"];
+        assert_eq!(b.dimensions, None);
+        assert!(b.aspect_ratio().is_some_and(|r| r < 1.0));
+    }
+}
@@ -15,11 +15,28 @@ use hex::ToHex;
 use sha2::Digest;
 use std::path;
 use std::path::PathBuf;
-use tracing::warn;
+use tracing::{info, warn};
+
+/// Media that hasn't been drawn for this long is considered off-screen
+const OFF_SCREEN_AFTER: Duration = Duration::from_secs(2);
+
+/// How often we check the texture budget
+const TEXTURE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often we check the disk budget
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+const MB: u64 = 1024 * 1024;
+
+/// Placeholders are tiny, but there's one per image we've seen
+const MAX_PLACEHOLDERS: usize = 2000;
 
 pub type MediaCacheValue = Promise<Result<TexturedImage>>;
 pub type MediaCacheMap = HashMap<String, MediaCacheValue>;
 
+/// Blurry stand-ins decoded from blurhashes and thumbhashes, by hash
+pub type PlaceholderMap = HashMap<String, TextureHandle>;
+
 pub enum TexturedImage {
     Static(TextureHandle),
     Animated(Animation),
@@ -55,22 +72,92 @@ pub struct ImageFrame {
     pub image: ColorImage,
 }
 
+impl TexturedImage {
+    /// Roughly how much memory the textures take up
+    pub fn byte_size(&self) -> usize {
+        match self {
+            TexturedImage::Static(texture) => texture_bytes(texture),
+            TexturedImage::Animated(animation) => {
+                texture_bytes(&animation.first_frame.texture)
+                    + animation
+                        .other_frames
+                        .iter()
+                        .map(|frame| texture_bytes(&frame.texture))
+                        .sum::<usize>()
+            }
+        }
+    }
+}
+
+fn texture_bytes(texture: &TextureHandle) -> usize {
+    let [w, h] = texture.size();
+    w * h * 4
+}
+
+/// How much a [`MediaCache`] may keep around
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MediaCacheLimits {
+    /// Cached files on disk
+    pub disk_bytes: u64,
+
+    /// Decoded textures in memory
+    pub texture_bytes: usize,
+}
+
+/// Size budgets for everything in [`Images`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheLimits {
+    pub img: MediaCacheLimits,
+    pub gif: MediaCacheLimits,
+
+    /// Number of url mime types we remember
+    pub url_entries: usize,
+}
+
+impl Default for CacheLimits {
+    fn default() -> Self {
+        Self {
+            img: MediaCacheLimits {
+                disk_bytes: 1024 * MB,
+                texture_bytes: 256 * MB as usize,
+            },
+            gif: MediaCacheLimits {
+                disk_bytes: 512 * MB,
+                texture_bytes: 256 * MB as usize,
+            },
+            url_entries: 100_000,
+        }
+    }
+}
+
 pub struct MediaCache {
     pub cache_dir: path::PathBuf,
     url_imgs: MediaCacheMap,
+    last_used: HashMap<String, Instant>,
+    limits: MediaCacheLimits,
+    last_texture_check: Instant,
+    last_disk_check: Option<Instant>,
+    disk_scan: Option<Promise<u64>>,
+    disk_usage: Option<u64>,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MediaCacheType {
     Image,
     Gif,
 }
 
 impl MediaCache {
-    pub fn new(cache_dir: path::PathBuf) -> Self {
+    pub fn new(cache_dir: path::PathBuf, limits: MediaCacheLimits) -> Self {
         Self {
             cache_dir,
             url_imgs: HashMap::new(),
+            last_used: HashMap::new(),
+            limits,
+            last_texture_check: Instant::now(),
+            last_disk_check: None,
+            disk_scan: None,
+            disk_usage: None,
         }
     }
 
@@ -177,6 +264,194 @@ impl MediaCache {
     pub fn map_mut(&mut self) -> &mut MediaCacheMap {
         &mut self.url_imgs
     }
+
+    /// Remember that `url` is on screen, so its texture is kept around
+    pub fn mark_used(&mut self, url: &str) {
+        if let Some(last_used) = self.last_used.get_mut(url) {
+            *last_used = Instant::now();
+        } else {
+            self.last_used.insert(url.to_owned(), Instant::now());
+        }
+    }
+
+    /// Bump the modification time of a cached file when we read it, so
+    /// disk eviction drops the least recently used files first
+    pub fn touch(path: &path::Path) {
+        let res = File::options()
+            .write(true)
+            .open(path)
+            .and_then(|f| f.set_modified(SystemTime::now()));
+        if let Err(e) = res {
+            warn!("could not touch {}: {e}", path.display());
+        }
+    }
+
+    /// Bytes of cached files on disk, once we've looked
+    pub fn disk_usage(&self) -> Option<u64> {
+        self.disk_usage
+    }
+
+    pub fn limits(&self) -> &MediaCacheLimits {
+        &self.limits
+    }
+
+    /// Bytes of textures we are holding on to
+    pub fn texture_usage(&self) -> usize {
+        self.url_imgs
+            .values()
+            .filter_map(|p| p.ready())
+            .filter_map(|r| r.as_ref().ok())
+            .map(TexturedImage::byte_size)
+            .sum()
+    }
+
+    /// Keep textures and files on disk within our limits. Returns the urls
+    /// whose textures were dropped.
+    pub fn handle_io(&mut self) -> Vec<String> {
+        if let Some(promise) = &self.disk_scan {
+            if let Some(usage) = promise.ready() {
+                self.disk_usage = Some(*usage);
+                self.disk_scan = None;
+            }
+        }
+
+        let check_disk = self
+            .last_disk_check
+            .is_none_or(|t| t.elapsed() >= DISK_CHECK_INTERVAL);
+        if check_disk && self.disk_scan.is_none() {
+            self.last_disk_check = Some(Instant::now());
+            let cache_dir = self.cache_dir.clone();
+            let budget = self.limits.disk_bytes;
+            self.disk_scan = Some(Promise::spawn_thread("evict_disk", move || {
+                evict_disk(&cache_dir, budget)
+            }));
+        }
+
+        if self.last_texture_check.elapsed() < TEXTURE_CHECK_INTERVAL {
+            return vec![];
+        }
+        self.last_texture_check = Instant::now();
+
+        self.evict_textures()
+    }
+
+    /// Drop the least recently drawn textures until we're within budget.
+    /// Anything still on screen is kept, even if that's over budget.
+    fn evict_textures(&mut self) -> Vec<String> {
+        let mut total = 0;
+        let mut candidates = vec![];
+        for (url, promise) in &self.url_imgs {
+            let Some(Ok(img)) = promise.ready() else {
+                continue;
+            };
+            let size = img.byte_size();
+            total += size;
+
+            let last_used = self.last_used.get(url).copied();
+            if last_used.is_none_or(|t| t.elapsed() >= OFF_SCREEN_AFTER) {
+                candidates.push((last_used, url.clone(), size));
+            }
+        }
+
+        if total <= self.limits.texture_bytes {
+            return vec![];
+        }
+
+        // never drawn sorts first
+        candidates.sort_by_key(|(last_used, _, _)| *last_used);
+
+        let mut evicted = vec![];
+        for (_, url, size) in candidates {
+            if total <= self.limits.texture_bytes {
+                break;
+            }
+            self.url_imgs.remove(&url);
+            self.last_used.remove(&url);
+            total -= size;
+            evicted.push(url);
+        }
+
+        evicted
+    }
+
+    /// Forget everything, in memory and on disk
+    pub fn clear(&mut self) {
+        self.url_imgs.clear();
+        self.last_used.clear();
+
+        let cache_dir = self.cache_dir.clone();
+        self.disk_usage = None;
+        self.last_disk_check = Some(Instant::now());
+        self.disk_scan = Some(Promise::spawn_thread("clear_cache", move || {
+            if let Err(e) = clear_dir(&cache_dir) {
+                tracing::error!("could not clear {}: {e}", cache_dir.display());
+            }
+            evict_disk(&cache_dir, u64::MAX)
+        }));
+    }
+}
+
+fn clear_dir(dir: &path::Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            std::fs::remove_dir_all(path)?;
+        } else {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+fn cached_files(dir: &path::Path, files: &mut Vec<(PathBuf, u64, SystemTime)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+
+        if meta.is_dir() {
+            cached_files(&entry.path(), files);
+        } else {
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.push((entry.path(), meta.len(), modified));
+        }
+    }
+}
+
+/// Delete the least recently used files in `dir` until they fit in
+/// `budget`, returning how many bytes are left. We go a bit under budget so
+/// we aren't back here after the next few images.
+fn evict_disk(dir: &path::Path, budget: u64) -> u64 {
+    let mut files = vec![];
+    cached_files(dir, &mut files);
+
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    if total <= budget {
+        return total;
+    }
+
+    let target = budget / 10 * 9;
+    files.sort_by_key(|(_, _, modified)| *modified);
+
+    let mut removed = 0;
+    for (path, len, _) in files {
+        if total <= target {
+            break;
+        }
+        if let Err(e) = std::fs::remove_file(&path) {
+            warn!("could not evict {}: {e}", path.display());
+            continue;
+        }
+        total -= len;
+        removed += 1;
+    }
+
+    info!("evicted {removed} files from {}", dir.display());
+    total
 }
 
 fn color_image_to_rgba(color_image: ColorImage) -> image::RgbaImage {
@@ -198,16 +473,27 @@ pub struct Images {
     pub gifs: MediaCache,
     pub urls: UrlMimes,
     pub gif_states: GifStateMap,
+    pub placeholders: PlaceholderMap,
 }
 
 impl Images {
     /// path to directory to place [`MediaCache`]s
-    pub fn new(path: path::PathBuf) -> Self {
+    pub fn new(path: path::PathBuf, limits: CacheLimits) -> Self {
         Self {
-            static_imgs: MediaCache::new(path.join(MediaCache::rel_dir(MediaCacheType::Image))),
-            gifs: MediaCache::new(path.join(MediaCache::rel_dir(MediaCacheType::Gif))),
-            urls: UrlMimes::new(UrlCache::new(path.join(UrlCache::rel_dir()))),
+            static_imgs: MediaCache::new(
+                path.join(MediaCache::rel_dir(MediaCacheType::Image)),
+                limits.img,
+            ),
+            gifs: MediaCache::new(
+                path.join(MediaCache::rel_dir(MediaCacheType::Gif)),
+                limits.gif,
+            ),
+            urls: UrlMimes::new(UrlCache::new(
+                path.join(UrlCache::rel_dir()),
+                limits.url_entries,
+            )),
             gif_states: Default::default(),
+            placeholders: Default::default(),
         }
     }
 
@@ -215,6 +501,34 @@ impl Images {
         self.static_imgs.migrate_v0()?;
         self.gifs.migrate_v0()
     }
+
+    /// Load and save the url cache and keep the media caches within
+    /// their limits. Call once per frame.
+    pub fn handle_io(&mut self) {
+        self.urls.cache.handle_io();
+        self.static_imgs.handle_io();
+        for url in self.gifs.handle_io() {
+            self.gif_states.remove(&url);
+        }
+
+        // cheap to decode again
+        if self.placeholders.len() > MAX_PLACEHOLDERS {
+            self.placeholders.clear();
+        }
+    }
+
+    /// Bytes of cached media on disk, once we've looked
+    pub fn disk_usage(&self) -> Option<u64> {
+        Some(self.static_imgs.disk_usage()? + self.gifs.disk_usage()?)
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.static_imgs.clear();
+        self.gifs.clear();
+        self.gif_states.clear();
+        self.placeholders.clear();
+        self.urls.clear();
+    }
 }
 
 pub type GifStateMap = HashMap<String, GifState>;
@@ -225,3 +539,71 @@ pub struct GifState {
     pub next_frame_time: Option<SystemTime>,
     pub last_frame_index: usize,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_cached(dir: &path::Path, name: &str, len: usize, age_secs: u64) -> PathBuf {
+        let path = dir.join(MediaCache::key(name));
+        create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, vec![0u8; len]).unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(age_secs))
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn test_evict_disk_lru() {
+        let dir = tempfile::tempdir().unwrap();
+        let oldest = write_cached(dir.path(), "a", 1000, 300);
+        let old = write_cached(dir.path(), "b", 1000, 200);
+        let new = write_cached(dir.path(), "c", 1000, 100);
+
+        assert_eq!(evict_disk(dir.path(), 3000), 3000);
+        assert!(oldest.exists());
+
+        // reading a file makes it the most recently used
+        MediaCache::touch(&oldest);
+
+        assert_eq!(evict_disk(dir.path(), 2500), 2000);
+        assert!(oldest.exists());
+        assert!(!old.exists());
+        assert!(new.exists());
+    }
+
+    #[test]
+    fn test_evict_off_screen_textures() {
+        let ctx = egui::Context::default();
+        let mut cache = MediaCache::new(
+            PathBuf::from("unused"),
+            MediaCacheLimits {
+                disk_bytes: 0,
+                texture_bytes: 2 * 10 * 10 * 4,
+            },
+        );
+
+        for url in ["a", "b", "c"] {
+            let texture = ctx.load_texture(
+                url,
+                ColorImage::new([10, 10], egui::Color32::WHITE),
+                Default::default(),
+            );
+            cache.map_mut().insert(
+                url.to_owned(),
+                Promise::from_ready(Ok(TexturedImage::Static(texture))),
+            );
+        }
+        cache.mark_used("a");
+
+        let evicted = cache.evict_textures();
+        assert_eq!(evicted.len(), 1);
+        assert_ne!(evicted[0], "a", "on screen textures are kept");
+        assert_eq!(cache.map().len(), 2);
+        assert_eq!(cache.texture_usage(), 2 * 10 * 10 * 4);
+    }
+}
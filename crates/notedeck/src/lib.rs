@@ -2,26 +2,34 @@ pub mod abbrev;
 mod accounts;
 mod app;
 mod args;
+pub mod blurhash;
+mod bookmarks;
 mod context;
 pub mod debouncer;
 mod error;
 pub mod filter;
 pub mod fonts;
 mod frame_history;
+mod imeta;
 mod imgcache;
+pub mod lists;
 mod muted;
 pub mod name;
+mod nip05;
 pub mod note;
 mod notecache;
 mod persist;
 pub mod platform;
+pub mod poll;
 pub mod profile;
 pub mod relay_debug;
+pub mod relay_hints;
 pub mod relayspec;
 mod result;
 pub mod storage;
 mod style;
 pub mod theme;
+pub mod thumbhash;
 mod time;
 mod timecache;
 mod timed_serializer;
@@ -35,19 +43,22 @@ mod zaps;
 pub use accounts::{AccountData, Accounts, AccountsAction, AddAccountAction, SwitchAccountAction};
 pub use app::{App, Notedeck};
 pub use args::Args;
+pub use bookmarks::{Bookmark, Bookmarks};
 pub use context::AppContext;
 pub use error::{Error, FilterError, ZapError};
 pub use filter::{FilterState, FilterStates, UnifiedSubscription};
 pub use fonts::NamedFontFamily;
+pub use imeta::{note_imeta, Imeta};
 pub use imgcache::{
-    Animation, GifState, GifStateMap, ImageFrame, Images, MediaCache, MediaCacheType,
-    MediaCacheValue, TextureFrame, TexturedImage,
+    Animation, CacheLimits, GifState, GifStateMap, ImageFrame, Images, MediaCache,
+    MediaCacheLimits, MediaCacheType, MediaCacheValue, PlaceholderMap, TextureFrame, TexturedImage,
 };
 pub use muted::{MuteFun, Muted};
 pub use name::NostrName;
+pub use nip05::{Nip05Cache, Nip05Status};
 pub use note::{
-    BroadcastContext, ContextSelection, NoteAction, NoteContext, NoteContextSelection, NoteRef,
-    RootIdError, RootNoteId, RootNoteIdBuf, ZapAction,
+    BookmarkContext, BroadcastContext, ContextSelection, NoteAction, NoteContext,
+    NoteContextSelection, NoteRef, PollVote, RootIdError, RootNoteId, RootNoteIdBuf, ZapAction,
 };
 pub use notecache::{CachedNote, NoteCache};
 pub use persist::*;
@@ -61,7 +72,10 @@ pub use theme::ColorTheme;
 pub use time::time_ago_since;
 pub use timecache::TimeCached;
 pub use unknowns::{get_unknown_note_ids, NoteRefsUnkIdAction, SingleUnkIdAction, UnknownIds};
-pub use urls::{supported_mime_hosted_at_url, SupportedMimeType, UrlMimes};
+pub use urls::{
+    supported_media_hosted_at_url, supported_mime_hosted_at_url, MediaKind, SupportedMimeType,
+    UrlMimes,
+};
 pub use user_account::UserAccount;
 pub use wallet::{
     get_wallet_for_mut, GlobalWallet, Wallet, WalletError, WalletState, WalletType, WalletUIState,
@@ -0,0 +1,132 @@
+//! [NIP-51](https://github.com/nostr-protocol/nips/blob/master/51.md) lists
+
+use nostrdb::{Filter, Ndb, Note, Transaction};
+
+/// People, grouped under a `d` tag
+pub const KIND_FOLLOW_SET: u32 = 30000;
+/// The account's bookmarks, one per pubkey
+pub const KIND_BOOKMARKS: u32 = 10003;
+/// Bookmarks, grouped under a `d` tag
+pub const KIND_BOOKMARK_SET: u32 = 30003;
+
+/// We don't expect anyone to have more lists than this
+const MAX_LISTS: i32 = 500;
+
+/// The value of the first tag named `name`
+pub fn tag_value<'a>(note: &Note<'a>, name: &str) -> Option<&'a str> {
+    note.tags()
+        .into_iter()
+        .find(|tag| tag.count() >= 2 && tag.get_str(0) == Some(name))
+        .and_then(|tag| tag.get_str(1))
+}
+
+/// The `d` tag of a parameterized list
+pub fn list_identifier<'a>(note: &Note<'a>) -> Option<&'a str> {
+    tag_value(note, "d")
+}
+
+/// What to call a list. Sets have a `title`, older ones only a `name`
+/// or their `d` tag.
+pub fn list_title<'a>(note: &Note<'a>) -> Option<&'a str> {
+    tag_value(note, "title")
+        .or_else(|| tag_value(note, "name"))
+        .or_else(|| list_identifier(note))
+        .filter(|title| !title.is_empty())
+}
+
+/// The pubkeys in the public part of a list
+pub fn list_pubkeys<'a>(note: &Note<'a>) -> Vec<&'a [u8; 32]> {
+    tag_ids(note, "p")
+}
+
+/// The notes in the public part of a list, in the order they were added
+pub fn list_note_ids<'a>(note: &Note<'a>) -> Vec<&'a [u8; 32]> {
+    tag_ids(note, "e")
+}
+
+fn tag_ids<'a>(note: &Note<'a>, name: &str) -> Vec<&'a [u8; 32]> {
+    let mut ids: Vec<&'a [u8; 32]> = Vec::new();
+    for tag in note.tags() {
+        if tag.count() < 2 || tag.get_str(0) != Some(name) {
+            continue;
+        }
+
+        if let Some(id) = tag.get_id(1) {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    ids
+}
+
+/// The lists of `kinds` we have locally for `pubkey`, newest version of
+/// each only
+pub fn query_lists<'a>(
+    ndb: &Ndb,
+    txn: &'a Transaction,
+    pubkey: &[u8; 32],
+    kinds: &[u32],
+) -> Vec<Note<'a>> {
+    let filter = Filter::new()
+        .authors([pubkey])
+        .kinds(kinds.iter().map(|kind| *kind as u64))
+        .limit(MAX_LISTS as u64)
+        .build();
+
+    let Ok(results) = ndb.query(txn, &[filter], MAX_LISTS) else {
+        return Vec::new();
+    };
+
+    let mut lists: Vec<Note<'a>> = Vec::new();
+    for note in results.into_iter().map(|qr| qr.note) {
+        let same = lists.iter().position(|list| {
+            list.kind() == note.kind() && list_identifier(list) == list_identifier(&note)
+        });
+
+        match same {
+            Some(i) if lists[i].created_at() >= note.created_at() => {}
+            Some(i) => lists[i] = note,
+            None => lists.push(note),
+        }
+    }
+
+    lists.sort_by(|a, b| list_title(a).cmp(&list_title(b)));
+    lists
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostrdb::NoteBuilder;
+
+    #[test]
+    fn test_list_tags() {
+        let note = NoteBuilder::new()
+            .kind(KIND_BOOKMARK_SET)
+            .content("")
+            .start_tag()
+            .tag_str("d")
+            .tag_str("reading")
+            .start_tag()
+            .tag_str("e")
+            .tag_str(&hex::encode([2; 32]))
+            .start_tag()
+            .tag_str("e")
+            .tag_str(&hex::encode([1; 32]))
+            .start_tag()
+            .tag_str("e")
+            .tag_str(&hex::encode([2; 32]))
+            .start_tag()
+            .tag_str("p")
+            .tag_str(&hex::encode([3; 32]))
+            .sign(&[1; 32])
+            .build()
+            .unwrap();
+
+        assert_eq!(list_identifier(&note), Some("reading"));
+        assert_eq!(list_title(&note), Some("reading"));
+        assert_eq!(list_note_ids(&note), vec![&[2; 32], &[1; 32]]);
+        assert_eq!(list_pubkeys(&note), vec![&[3; 32]]);
+    }
+}
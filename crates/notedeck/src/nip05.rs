@@ -0,0 +1,448 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Read, Write},
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use nostrdb::ProfileRecord;
+use poll_promise::Promise;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::Error;
+
+const FILE_NAME: &str = "nip05_v1.bin";
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long a successful lookup is trusted before we check again
+const VERIFIED_TTL: u64 = 60 * 60 * 24;
+
+/// How long we trust a domain that lists someone else, or nobody, for
+/// the name
+const INVALID_TTL: u64 = 60 * 60;
+
+/// Failed lookups are often just a server being down, so we retry those
+/// soon
+const FAILED_TTL: u64 = 60 * 5;
+
+/// The minimum time between two requests to the same domain. Timelines
+/// are full of people on the same few NIP-05 providers, we don't want to
+/// hammer them.
+const DOMAIN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Whether a profile's `nip05` identifier checks out
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Nip05Status {
+    /// We haven't checked this identifier yet
+    Unknown,
+
+    /// The domain lists this pubkey under the identifier's name
+    Verified,
+
+    /// The domain lists someone else or nobody, or the identifier is
+    /// malformed
+    Invalid,
+
+    /// We couldn't reach the domain, or it didn't send a valid
+    /// `nostr.json`. We'll try again later.
+    Failed,
+}
+
+/// What we got when we asked a domain about a name
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+enum Nip05Lookup {
+    /// The domain answered, with the pubkey it lists for the name, if any
+    Listed(Option<[u8; 32]>),
+
+    /// The request failed, or the response wasn't a `nostr.json`
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Nip05Entry {
+    lookup: Nip05Lookup,
+
+    /// When we checked, in unix seconds
+    checked_at: u64,
+}
+
+impl Nip05Entry {
+    fn is_expired(&self, now: u64) -> bool {
+        let ttl = match self.lookup {
+            Nip05Lookup::Listed(Some(_)) => VERIFIED_TTL,
+            Nip05Lookup::Listed(None) => INVALID_TTL,
+            Nip05Lookup::Failed => FAILED_TTL,
+        };
+
+        now.saturating_sub(self.checked_at) >= ttl
+    }
+
+    fn status(&self, pubkey: &[u8; 32]) -> Nip05Status {
+        match &self.lookup {
+            Nip05Lookup::Listed(Some(listed)) if listed == pubkey => Nip05Status::Verified,
+            Nip05Lookup::Listed(_) => Nip05Status::Invalid,
+            Nip05Lookup::Failed => Nip05Status::Failed,
+        }
+    }
+}
+
+type Nip05Entries = HashMap<String, Nip05Entry>;
+
+/// Verifies NIP-05 identifiers against their domain's
+/// `.well-known/nostr.json` in the background. Results are cached on disk
+/// and refreshed once they expire.
+pub struct Nip05Cache {
+    ctx: egui::Context,
+    path: PathBuf,
+    last_saved: SystemTime,
+    dirty: bool,
+    entries: Nip05Entries,
+    from_disk_promise: Option<Promise<Option<Nip05Entries>>>,
+    in_flight: HashMap<String, Promise<Nip05Lookup>>,
+    last_request: HashMap<String, Instant>,
+}
+
+impl Nip05Cache {
+    /// The cache file, relative to the cache directory
+    pub fn rel_path() -> &'static str {
+        FILE_NAME
+    }
+
+    pub fn new(ctx: &egui::Context, path: PathBuf) -> Self {
+        Self {
+            ctx: ctx.clone(),
+            path: path.clone(),
+            last_saved: SystemTime::now(),
+            dirty: false,
+            entries: Default::default(),
+            from_disk_promise: Some(read_from_disk(path)),
+            in_flight: Default::default(),
+            last_request: Default::default(),
+        }
+    }
+
+    /// The verification status of `nip05` for `pubkey`. Unchecked or
+    /// expired identifiers are looked up in the background, expired ones
+    /// keep their old status until the new result comes in.
+    pub fn status(&mut self, pubkey: &[u8; 32], nip05: &str) -> Nip05Status {
+        let identifier = nip05.to_lowercase();
+        let Some((_name, domain)) = parse_nip05(&identifier) else {
+            return Nip05Status::Invalid;
+        };
+        let domain = domain.to_owned();
+
+        let (status, needs_refresh) = match self.entries.get(&identifier) {
+            Some(entry) => (entry.status(pubkey), entry.is_expired(unix_now())),
+            None => (Nip05Status::Unknown, true),
+        };
+
+        if needs_refresh {
+            self.request(identifier, domain);
+        }
+
+        status
+    }
+
+    /// The verification status of a profile's `nip05`, if it has one
+    pub fn profile_status(
+        &mut self,
+        pubkey: &[u8; 32],
+        profile: Option<&ProfileRecord<'_>>,
+    ) -> Nip05Status {
+        let nip05 = profile
+            .and_then(|p| p.record().profile())
+            .and_then(|p| p.nip05());
+
+        match nip05 {
+            Some(nip05) => self.status(pubkey, nip05),
+            None => Nip05Status::Unknown,
+        }
+    }
+
+    fn request(&mut self, identifier: String, domain: String) {
+        if self.in_flight.contains_key(&identifier) {
+            return;
+        }
+
+        if let Some(last) = self.last_request.get(&domain) {
+            let elapsed = last.elapsed();
+            if elapsed < DOMAIN_INTERVAL {
+                // come back once the domain is allowed again
+                self.ctx.request_repaint_after(DOMAIN_INTERVAL - elapsed);
+                return;
+            }
+        }
+
+        tracing::debug!("verifying nip05 {identifier}");
+        self.last_request.insert(domain, Instant::now());
+        let promise = fetch_nip05_pubkey(&self.ctx, &identifier);
+        self.in_flight.insert(identifier, promise);
+    }
+
+    /// Collect finished lookups, load our cache from disk and save it
+    /// every now and then. Call this once per frame.
+    pub fn handle_io(&mut self) {
+        if let Some(promise) = &mut self.from_disk_promise {
+            if let Some(maybe_entries) = promise.ready_mut() {
+                if let Some(entries) = maybe_entries.take() {
+                    for (identifier, entry) in entries {
+                        // anything we've checked since startup is newer
+                        self.entries.entry(identifier).or_insert(entry);
+                    }
+                }
+
+                self.from_disk_promise = None;
+            }
+        }
+
+        let now = unix_now();
+        self.in_flight.retain(|identifier, promise| {
+            let Some(lookup) = promise.ready() else {
+                return true;
+            };
+
+            self.entries.insert(
+                identifier.clone(),
+                Nip05Entry {
+                    lookup: *lookup,
+                    checked_at: now,
+                },
+            );
+            self.dirty = true;
+
+            false
+        });
+
+        if !self.dirty {
+            return;
+        }
+
+        if let Ok(cur_duration) = SystemTime::now().duration_since(self.last_saved) {
+            if cur_duration >= SAVE_INTERVAL {
+                save_to_disk(self.path.clone(), self.entries.clone());
+                self.last_saved = SystemTime::now();
+                self.dirty = false;
+            }
+        }
+    }
+}
+
+/// Split `name@domain` into its parts
+fn parse_nip05(nip05: &str) -> Option<(&str, &str)> {
+    let (name, domain) = nip05.split_once('@')?;
+
+    if name.is_empty() || domain.is_empty() || domain.contains(['@', '/', '?', '#']) {
+        return None;
+    }
+
+    Some((name, domain))
+}
+
+#[derive(Deserialize)]
+struct Nip05Response {
+    names: HashMap<String, String>,
+}
+
+/// The pubkey a `nostr.json` document lists for `name`
+fn pubkey_for_name(json: &[u8], name: &str) -> Nip05Lookup {
+    let response: Nip05Response = match serde_json::from_slice(json) {
+        Ok(response) => response,
+        Err(err) => {
+            tracing::debug!("invalid nostr.json: {err}");
+            return Nip05Lookup::Failed;
+        }
+    };
+
+    let pubkey = response
+        .names
+        .get(name)
+        .and_then(|pubkey| hex::decode(pubkey).ok()?.try_into().ok());
+    Nip05Lookup::Listed(pubkey)
+}
+
+/// Where to look up `name` on `domain`
+fn nip05_url(name: &str, domain: &str) -> Option<Url> {
+    let mut url = Url::parse(&format!("https://{domain}/.well-known/nostr.json")).ok()?;
+    url.query_pairs_mut().append_pair("name", name);
+    Some(url)
+}
+
+fn fetch_nip05_pubkey(ctx: &egui::Context, identifier: &str) -> Promise<Nip05Lookup> {
+    let (sender, promise) = Promise::new();
+
+    let Some((name, domain)) = parse_nip05(identifier) else {
+        sender.send(Nip05Lookup::Failed);
+        return promise;
+    };
+
+    let Some(url) = nip05_url(name, domain) else {
+        sender.send(Nip05Lookup::Failed);
+        return promise;
+    };
+
+    let name = name.to_owned();
+    let ctx = ctx.clone();
+    ehttp::fetch(ehttp::Request::get(url), move |response| {
+        let lookup = match response {
+            Ok(resp) if resp.ok => pubkey_for_name(&resp.bytes, &name),
+            Ok(resp) => {
+                tracing::debug!("nip05 lookup for {name}: status {}", resp.status);
+                Nip05Lookup::Failed
+            }
+            Err(err) => {
+                tracing::debug!("nip05 lookup for {name} failed: {err}");
+                Nip05Lookup::Failed
+            }
+        };
+
+        sender.send(lookup);
+        ctx.request_repaint();
+    });
+
+    promise
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn read_from_disk(path: PathBuf) -> Promise<Option<Nip05Entries>> {
+    let (sender, promise) = Promise::new();
+
+    std::thread::spawn(move || {
+        let result: Result<Nip05Entries, Error> = (|| {
+            let mut file = File::open(path)?;
+            let mut buffer = Vec::new();
+            file.read_to_end(&mut buffer)?;
+            let data: Nip05Entries =
+                bincode::deserialize(&buffer).map_err(|e| Error::Generic(e.to_string()))?;
+            Ok(data)
+        })();
+
+        match result {
+            Ok(data) => sender.send(Some(data)),
+            Err(e) => {
+                tracing::error!("problem deserializing Nip05Cache: {e}");
+                sender.send(None)
+            }
+        }
+    });
+
+    promise
+}
+
+fn save_to_disk(path: PathBuf, entries: Nip05Entries) {
+    std::thread::spawn(move || {
+        let result: Result<(), Error> = (|| {
+            let encoded =
+                bincode::serialize(&entries).map_err(|e| Error::Generic(e.to_string()))?;
+            let mut file = File::create(&path)?;
+            file.write_all(&encoded)?;
+            file.sync_all()?;
+            tracing::debug!("Saved Nip05Cache to disk.");
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            tracing::error!("Failed to save Nip05Cache: {}", e);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nip05() {
+        assert_eq!(parse_nip05("bob@damus.io"), Some(("bob", "damus.io")));
+        assert_eq!(parse_nip05("_@jb55.com"), Some(("_", "jb55.com")));
+        assert_eq!(parse_nip05("damus.io"), None);
+        assert_eq!(parse_nip05("@damus.io"), None);
+        assert_eq!(parse_nip05("bob@evil.com/path"), None);
+        assert_eq!(parse_nip05("bob@evil.com?x=1"), None);
+    }
+
+    #[test]
+    fn test_nip05_url_encodes_name() {
+        let url = nip05_url("bob&name=alice#", "damus.io").expect("url");
+        assert_eq!(
+            url.as_str(),
+            "https://damus.io/.well-known/nostr.json?name=bob%26name%3Dalice%23"
+        );
+        assert_eq!(url.host_str(), Some("damus.io"));
+    }
+
+    #[test]
+    fn test_pubkey_for_name() {
+        let hex = "32e1827635450ebb3c5a7d12c1f8e7b2b514439ac10a67eef3d9fd9c5c68e245";
+        let json = format!(r#"{{"names":{{"bob":"{hex}"}},"relays":{{}}}}"#);
+
+        let expected: [u8; 32] = hex::decode(hex).unwrap().try_into().unwrap();
+        assert_eq!(
+            pubkey_for_name(json.as_bytes(), "bob"),
+            Nip05Lookup::Listed(Some(expected))
+        );
+        assert_eq!(
+            pubkey_for_name(json.as_bytes(), "alice"),
+            Nip05Lookup::Listed(None)
+        );
+        assert_eq!(pubkey_for_name(b"not json", "bob"), Nip05Lookup::Failed);
+    }
+
+    #[test]
+    fn test_entry_status_and_expiry() {
+        let now = 1_700_000_000;
+        let verified = Nip05Entry {
+            lookup: Nip05Lookup::Listed(Some([1; 32])),
+            checked_at: now,
+        };
+        let unlisted = Nip05Entry {
+            lookup: Nip05Lookup::Listed(None),
+            checked_at: now,
+        };
+        let failed = Nip05Entry {
+            lookup: Nip05Lookup::Failed,
+            checked_at: now,
+        };
+
+        assert_eq!(verified.status(&[1; 32]), Nip05Status::Verified);
+        assert_eq!(verified.status(&[2; 32]), Nip05Status::Invalid);
+        assert_eq!(unlisted.status(&[1; 32]), Nip05Status::Invalid);
+        // a server being down isn't a reason to warn about someone
+        assert_eq!(failed.status(&[1; 32]), Nip05Status::Failed);
+
+        assert!(!verified.is_expired(now + INVALID_TTL));
+        assert!(verified.is_expired(now + VERIFIED_TTL));
+        assert!(!unlisted.is_expired(now + FAILED_TTL));
+        assert!(unlisted.is_expired(now + INVALID_TTL));
+        assert!(failed.is_expired(now + FAILED_TTL));
+    }
+
+    #[test]
+    fn test_cached_status() {
+        let ctx = egui::Context::default();
+        let mut cache = Nip05Cache::new(&ctx, PathBuf::from("does-not-exist"));
+        cache.entries.insert(
+            "bob@damus.io".to_owned(),
+            Nip05Entry {
+                lookup: Nip05Lookup::Listed(Some([1; 32])),
+                checked_at: unix_now(),
+            },
+        );
+
+        // identifiers are case insensitive
+        assert_eq!(
+            cache.status(&[1; 32], "Bob@Damus.io"),
+            Nip05Status::Verified
+        );
+        assert_eq!(cache.status(&[2; 32], "bob@damus.io"), Nip05Status::Invalid);
+        assert_eq!(cache.status(&[1; 32], "no-at-sign"), Nip05Status::Invalid);
+        assert!(cache.in_flight.is_empty());
+    }
+}
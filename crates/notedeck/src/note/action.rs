@@ -24,6 +24,17 @@ pub enum NoteAction {
 
     /// User has clicked the zap action
     Zap(ZapAction),
+
+    /// User has voted in a poll
+    Vote(PollVote),
+}
+
+/// A response to a NIP-88 poll
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct PollVote {
+    pub poll: NoteId,
+    /// The option ids chosen
+    pub choices: Vec<String>,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
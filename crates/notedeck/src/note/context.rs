@@ -1,5 +1,6 @@
+use crate::Accounts;
 use enostr::{ClientMessage, NoteId, Pubkey, RelayPool};
-use nostrdb::{Note, NoteKey};
+use nostrdb::{Ndb, Note, NoteKey};
 use tracing::error;
 
 /// When broadcasting notes, this determines whether to broadcast
@@ -10,6 +11,14 @@ pub enum BroadcastContext {
     Everywhere,
 }
 
+/// Bookmarks are public unless we ask for them to be encrypted
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BookmarkContext {
+    Public,
+    Private,
+    Remove,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 #[allow(clippy::enum_variant_names)]
 pub enum NoteContextSelection {
@@ -18,6 +27,7 @@ pub enum NoteContextSelection {
     CopyNoteId,
     CopyNoteJSON,
     Broadcast(BroadcastContext),
+    Bookmark(BookmarkContext),
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -27,7 +37,14 @@ pub struct ContextSelection {
 }
 
 impl NoteContextSelection {
-    pub fn process(&self, ui: &mut egui::Ui, note: &Note<'_>, pool: &mut RelayPool) {
+    pub fn process(
+        &self,
+        ui: &mut egui::Ui,
+        ndb: &Ndb,
+        note: &Note<'_>,
+        pool: &mut RelayPool,
+        accounts: &mut Accounts,
+    ) {
         match self {
             NoteContextSelection::Broadcast(context) => {
                 tracing::info!("Broadcasting note {}", hex::encode(note.id()));
@@ -41,6 +58,9 @@ impl NoteContextSelection {
                     }
                 }
             }
+            NoteContextSelection::Bookmark(context) => {
+                accounts.bookmark(ndb, pool, note.id(), context);
+            }
             NoteContextSelection::CopyText => {
                 ui.ctx().copy_text(note.content().to_string());
             }
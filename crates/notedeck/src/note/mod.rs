@@ -1,10 +1,10 @@
 mod action;
 mod context;
 
-pub use action::{NoteAction, ZapAction};
-pub use context::{BroadcastContext, ContextSelection, NoteContextSelection};
+pub use action::{NoteAction, PollVote, ZapAction};
+pub use context::{BookmarkContext, BroadcastContext, ContextSelection, NoteContextSelection};
 
-use crate::{notecache::NoteCache, zaps::Zaps, Images};
+use crate::{notecache::NoteCache, poll::Polls, zaps::Zaps, Bookmarks, Images, Nip05Cache};
 use enostr::{NoteId, RelayPool};
 use nostrdb::{Ndb, Note, NoteKey, QueryResult, Transaction};
 use std::borrow::Borrow;
@@ -18,7 +18,11 @@ pub struct NoteContext<'d> {
     pub img_cache: &'d mut Images,
     pub note_cache: &'d mut NoteCache,
     pub zaps: &'d mut Zaps,
+    pub nip05: &'d mut Nip05Cache,
+    pub polls: &'d mut Polls,
     pub pool: &'d mut RelayPool,
+    /// The selected account's bookmarks
+    pub bookmarks: &'d Bookmarks,
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
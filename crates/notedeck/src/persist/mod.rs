@@ -1,9 +1,13 @@
 mod app_size;
 mod theme_handler;
+mod toggle;
 mod token_handler;
+mod upload_server;
 mod zoom;
 
 pub use app_size::AppSizeHandler;
 pub use theme_handler::ThemeHandler;
+pub use toggle::ToggleHandler;
 pub use token_handler::TokenHandler;
+pub use upload_server::UploadServerHandler;
 pub use zoom::ZoomHandler;
@@ -0,0 +1,71 @@
+use tracing::{error, info};
+
+use crate::{storage, DataPath, DataPathType, Directory};
+
+/// Remembers a setting that's on or off, like whether videos start
+/// playing on their own
+pub struct ToggleHandler {
+    directory: Directory,
+    file: &'static str,
+}
+
+impl ToggleHandler {
+    /// Whether videos start playing on their own
+    pub fn autoplay(path: &DataPath) -> Self {
+        Self::new(path, "autoplay.txt")
+    }
+
+    /// Whether we play media with the ffmpeg tools installed on the system
+    pub fn ffmpeg(path: &DataPath) -> Self {
+        Self::new(path, "ffmpeg.txt")
+    }
+
+    fn new(path: &DataPath, file: &'static str) -> Self {
+        let directory = Directory::new(path.path(DataPathType::Setting));
+        Self { directory, file }
+    }
+
+    /// Off unless they turned it on
+    pub fn load(&self) -> bool {
+        match self.directory.get_file(self.file.to_owned()) {
+            Ok(contents) => match deserialize_toggle(&contents) {
+                Some(on) => on,
+                None => {
+                    error!(
+                        "Could not deserialize setting {contents:?} in {}",
+                        self.file
+                    );
+                    false
+                }
+            },
+            Err(_) => false,
+        }
+    }
+
+    pub fn save(&self, on: bool) {
+        match storage::write_file(
+            &self.directory.file_path,
+            self.file.to_owned(),
+            toggle_to_serialized(on),
+        ) {
+            Ok(_) => info!("Saved {on} to {}", self.file),
+            Err(_) => error!("Could not save {on} to {}", self.file),
+        }
+    }
+}
+
+fn toggle_to_serialized(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn deserialize_toggle(serialized: &str) -> Option<bool> {
+    match serialized.trim() {
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => None,
+    }
+}
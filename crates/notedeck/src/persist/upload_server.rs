@@ -0,0 +1,43 @@
+use tracing::{error, info};
+
+use crate::{storage, DataPath, DataPathType, Directory};
+
+static UPLOAD_SERVER_FILE: &str = "upload_server.txt";
+
+/// Remembers where media goes when the account has no media server list
+pub struct UploadServerHandler {
+    directory: Directory,
+}
+
+impl UploadServerHandler {
+    pub fn new(path: &DataPath) -> Self {
+        let directory = Directory::new(path.path(DataPathType::Setting));
+        Self { directory }
+    }
+
+    /// The server's url, `None` if they never picked one
+    pub fn load(&self) -> Option<String> {
+        let contents = self
+            .directory
+            .get_file(UPLOAD_SERVER_FILE.to_owned())
+            .ok()?;
+        let url = contents.trim();
+
+        if url.is_empty() {
+            None
+        } else {
+            Some(url.to_owned())
+        }
+    }
+
+    pub fn save(&self, url: &str) {
+        match storage::write_file(
+            &self.directory.file_path,
+            UPLOAD_SERVER_FILE.to_owned(),
+            url,
+        ) {
+            Ok(_) => info!("Saved upload server {url}"),
+            Err(_) => error!("Could not save upload server {url}"),
+        }
+    }
+}
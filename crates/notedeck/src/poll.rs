@@ -0,0 +1,449 @@
+//! [NIP-88](https://github.com/nostr-protocol/nips/blob/master/88.md) polls,
+//! and counting the votes in them.
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use enostr::NoteId;
+use nostrdb::{Filter, Ndb, Note, NoteBuilder, Subscription, Transaction};
+use tracing::error;
+
+pub const KIND_POLL: u32 = 1068;
+pub const KIND_POLL_RESPONSE: u32 = 1018;
+
+/// We only count this many responses
+const MAX_RESPONSES: i32 = 10_000;
+
+/// We stop following the votes in a poll once it's been off screen this
+/// long
+const FORGET_AFTER: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum PollType {
+    #[default]
+    SingleChoice,
+    MultipleChoice,
+}
+
+impl PollType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PollType::SingleChoice => "singlechoice",
+            PollType::MultipleChoice => "multiplechoice",
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        if s == "multiplechoice" {
+            PollType::MultipleChoice
+        } else {
+            PollType::SingleChoice
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PollOption {
+    pub id: String,
+    pub label: String,
+}
+
+/// A kind 1068 poll
+#[derive(Debug, Clone)]
+pub struct Poll {
+    pub question: String,
+    pub options: Vec<PollOption>,
+    pub poll_type: PollType,
+    pub ends_at: Option<u64>,
+    /// Where the poll wants its responses
+    pub relays: Vec<String>,
+}
+
+/// The votes in a poll, one per voter
+#[derive(Debug, Default)]
+pub struct PollResults {
+    votes: HashMap<String, usize>,
+    pub voters: usize,
+    /// What the current account chose, if it voted
+    pub my_choices: Vec<String>,
+}
+
+impl PollResults {
+    pub fn votes(&self, option_id: &str) -> usize {
+        self.votes.get(option_id).copied().unwrap_or(0)
+    }
+}
+
+impl Poll {
+    pub fn from_note(note: &Note) -> Option<Self> {
+        if note.kind() != KIND_POLL {
+            return None;
+        }
+
+        let mut poll = Poll {
+            question: note.content().to_owned(),
+            options: Vec::new(),
+            poll_type: PollType::default(),
+            ends_at: None,
+            relays: Vec::new(),
+        };
+
+        for tag in note.tags() {
+            match tag.get_str(0) {
+                Some("option") => {
+                    let (Some(id), Some(label)) = (tag.get_str(1), tag.get_str(2)) else {
+                        continue;
+                    };
+                    if !poll.options.iter().any(|option| option.id == id) {
+                        poll.options.push(PollOption {
+                            id: id.to_owned(),
+                            label: label.to_owned(),
+                        });
+                    }
+                }
+                Some("polltype") => {
+                    poll.poll_type = PollType::parse(tag.get_str(1).unwrap_or_default());
+                }
+                Some("endsAt") => {
+                    poll.ends_at = tag.get_str(1).and_then(|s| s.parse().ok());
+                }
+                Some("relay") => {
+                    if let Some(relay) = tag.get_str(1) {
+                        poll.relays.push(relay.to_owned());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        (!poll.options.is_empty()).then_some(poll)
+    }
+
+    pub fn has_ended(&self, now: u64) -> bool {
+        self.ends_at.is_some_and(|ends_at| ends_at <= now)
+    }
+
+    /// The responses we should subscribe to on relays
+    pub fn responses_filter(poll_id: &[u8; 32]) -> Filter {
+        Filter::new()
+            .kinds([KIND_POLL_RESPONSE as u64])
+            .event(poll_id)
+            .limit(MAX_RESPONSES as u64)
+            .build()
+    }
+
+    /// Count the responses to this poll that we have in nostrdb
+    pub fn tally(
+        &self,
+        ndb: &Ndb,
+        txn: &Transaction,
+        poll_id: &[u8; 32],
+        me: Option<&[u8; 32]>,
+    ) -> PollResults {
+        let responses = ndb
+            .query(txn, &[Self::responses_filter(poll_id)], MAX_RESPONSES)
+            .unwrap_or_default();
+
+        self.tally_responses(responses.iter().map(|result| &result.note), me)
+    }
+
+    /// Each pubkey's latest response before the poll ended counts. Choices
+    /// that aren't options are ignored, and a single choice poll only
+    /// takes the first.
+    pub fn tally_responses<'a>(
+        &self,
+        responses: impl IntoIterator<Item = &'a Note<'a>>,
+        me: Option<&[u8; 32]>,
+    ) -> PollResults {
+        let mut latest: HashMap<&[u8; 32], (u64, Vec<String>)> = HashMap::new();
+
+        for response in responses {
+            if response.kind() != KIND_POLL_RESPONSE
+                || self
+                    .ends_at
+                    .is_some_and(|ends_at| response.created_at() > ends_at)
+            {
+                continue;
+            }
+
+            if latest
+                .get(response.pubkey())
+                .is_some_and(|(created_at, _)| *created_at >= response.created_at())
+            {
+                continue;
+            }
+
+            let choices = self.choices(response);
+            if !choices.is_empty() {
+                latest.insert(response.pubkey(), (response.created_at(), choices));
+            }
+        }
+
+        let mut results = PollResults {
+            voters: latest.len(),
+            ..Default::default()
+        };
+
+        for (pubkey, (_, choices)) in latest {
+            if Some(pubkey) == me {
+                results.my_choices = choices.clone();
+            }
+
+            for choice in choices {
+                *results.votes.entry(choice).or_default() += 1;
+            }
+        }
+
+        results
+    }
+
+    fn choices(&self, response: &Note) -> Vec<String> {
+        let mut choices: Vec<String> = Vec::new();
+        for tag in response.tags() {
+            if tag.get_str(0) != Some("response") {
+                continue;
+            }
+
+            let Some(id) = tag.get_str(1) else {
+                continue;
+            };
+
+            if self.options.iter().any(|option| option.id == id)
+                && !choices.iter().any(|choice| choice == id)
+            {
+                choices.push(id.to_owned());
+            }
+        }
+
+        if self.poll_type == PollType::SingleChoice {
+            choices.truncate(1);
+        }
+
+        choices
+    }
+}
+
+struct PollEntry {
+    /// Where the poll wants its responses
+    relays: Vec<String>,
+
+    /// Tells us when nostrdb has new responses
+    sub: Option<Subscription>,
+
+    /// `None` until counted, and again after new responses came in
+    results: Option<PollResults>,
+
+    /// Who `results.my_choices` is for
+    me: Option<[u8; 32]>,
+    last_shown: Instant,
+}
+
+/// The polls on screen and their results. Counting means going through
+/// every response, so we only count again when new ones come in.
+#[derive(Default)]
+pub struct Polls {
+    polls: HashMap<NoteId, PollEntry>,
+}
+
+impl Polls {
+    /// The results of a poll we're showing
+    pub fn results(
+        &mut self,
+        ndb: &Ndb,
+        txn: &Transaction,
+        poll: &Poll,
+        poll_id: &[u8; 32],
+        me: Option<&[u8; 32]>,
+    ) -> &PollResults {
+        let entry = self
+            .polls
+            .entry(NoteId::new(*poll_id))
+            .or_insert_with(|| PollEntry {
+                relays: poll.relays.clone(),
+                sub: match ndb.subscribe(&[Poll::responses_filter(poll_id)]) {
+                    Ok(sub) => Some(sub),
+                    Err(err) => {
+                        error!("could not subscribe to poll responses: {err}");
+                        None
+                    }
+                },
+                results: None,
+                me: None,
+                last_shown: Instant::now(),
+            });
+
+        entry.last_shown = Instant::now();
+        if entry.me.as_ref() != me {
+            entry.me = me.copied();
+            entry.results = None;
+        }
+
+        entry
+            .results
+            .get_or_insert_with(|| poll.tally(ndb, txn, poll_id, me))
+    }
+
+    /// Count again the polls that got new responses, and forget the ones
+    /// that went off screen
+    pub fn update(&mut self, ndb: &mut Ndb) {
+        let now = Instant::now();
+        self.polls.retain(|_, entry| {
+            if now - entry.last_shown < FORGET_AFTER {
+                if let Some(sub) = entry.sub {
+                    if !ndb.poll_for_notes(sub, 100).is_empty() {
+                        entry.results = None;
+                    }
+                }
+                return true;
+            }
+
+            if let Some(sub) = entry.sub.take() {
+                if let Err(err) = ndb.unsubscribe(sub) {
+                    error!("could not unsubscribe from poll responses: {err}");
+                }
+            }
+            false
+        });
+    }
+
+    /// The polls on screen, and the relays they want responses from
+    pub fn shown(&self) -> impl Iterator<Item = (&NoteId, &[String])> {
+        self.polls
+            .iter()
+            .map(|(id, entry)| (id, entry.relays.as_slice()))
+    }
+}
+
+/// A kind 1018 vote for `choices` in a poll
+pub fn response_note(
+    poll_id: &[u8; 32],
+    choices: &[String],
+    seckey: &[u8; 32],
+) -> Option<Note<'static>> {
+    let mut builder = NoteBuilder::new()
+        .kind(KIND_POLL_RESPONSE)
+        .content("")
+        .start_tag()
+        .tag_str("e")
+        .tag_str(&hex::encode(poll_id));
+
+    for choice in choices {
+        builder = builder.start_tag().tag_str("response").tag_str(choice);
+    }
+
+    builder.sign(seckey).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poll_note(poll_type: &str) -> Note<'static> {
+        NoteBuilder::new()
+            .kind(KIND_POLL)
+            .content("Best relay?")
+            .start_tag()
+            .tag_str("option")
+            .tag_str("a")
+            .tag_str("damus")
+            .start_tag()
+            .tag_str("option")
+            .tag_str("b")
+            .tag_str("nos.lol")
+            .start_tag()
+            .tag_str("polltype")
+            .tag_str(poll_type)
+            .start_tag()
+            .tag_str("endsAt")
+            .tag_str("5000")
+            .sign(&[1; 32])
+            .build()
+            .unwrap()
+    }
+
+    fn vote(
+        seckey: [u8; 32],
+        poll_id: &[u8; 32],
+        choices: &[&str],
+        created_at: u64,
+    ) -> Note<'static> {
+        let mut builder = NoteBuilder::new()
+            .kind(KIND_POLL_RESPONSE)
+            .content("")
+            .created_at(created_at)
+            .start_tag()
+            .tag_str("e")
+            .tag_str(&hex::encode(poll_id));
+        for choice in choices {
+            builder = builder.start_tag().tag_str("response").tag_str(choice);
+        }
+        builder.sign(&seckey).build().unwrap()
+    }
+
+    #[test]
+    fn test_parse_poll() {
+        let note = poll_note("multiplechoice");
+        let poll = Poll::from_note(&note).unwrap();
+
+        assert_eq!(poll.question, "Best relay?");
+        assert_eq!(poll.options.len(), 2);
+        assert_eq!(poll.options[1].label, "nos.lol");
+        assert_eq!(poll.poll_type, PollType::MultipleChoice);
+        assert_eq!(poll.ends_at, Some(5000));
+        assert!(poll.has_ended(5000));
+        assert!(!poll.has_ended(4999));
+    }
+
+    #[test]
+    fn test_tally_latest_vote_wins() {
+        let note = poll_note("singlechoice");
+        let poll = Poll::from_note(&note).unwrap();
+        let id = note.id();
+
+        let votes = [
+            vote([2; 32], id, &["a"], 1000),
+            vote([2; 32], id, &["b"], 2000),
+            // single choice polls only count the first
+            vote([3; 32], id, &["b", "a"], 1000),
+            // not an option
+            vote([4; 32], id, &["z"], 1000),
+            // too late
+            vote([5; 32], id, &["a"], 6000),
+        ];
+
+        let me = votes[0].pubkey();
+        let results = poll.tally_responses(&votes, Some(me));
+        assert_eq!(results.voters, 2);
+        assert_eq!(results.votes("a"), 0);
+        assert_eq!(results.votes("b"), 2);
+        assert_eq!(results.my_choices, vec!["b".to_owned()]);
+    }
+
+    #[test]
+    fn test_tally_multiple_choice() {
+        let note = poll_note("multiplechoice");
+        let poll = Poll::from_note(&note).unwrap();
+
+        let votes = [vote([2; 32], note.id(), &["a", "b", "a"], 1000)];
+        let results = poll.tally_responses(&votes, None);
+
+        assert_eq!(results.voters, 1);
+        assert_eq!(results.votes("a"), 1);
+        assert_eq!(results.votes("b"), 1);
+        assert!(results.my_choices.is_empty());
+    }
+
+    #[test]
+    fn test_response_note() {
+        let choices = vec!["a".to_owned(), "b".to_owned()];
+        let response = response_note(&[7; 32], &choices, &[2; 32]).unwrap();
+
+        assert_eq!(response.kind(), KIND_POLL_RESPONSE);
+        let tags: Vec<_> = response.tags().into_iter().collect();
+        assert_eq!(tags[0].get_id(1), Some(&[7; 32]));
+        assert_eq!(tags[1].get_str(1), Some("a"));
+        assert_eq!(tags[2].get_str(1), Some("b"));
+    }
+}
@@ -0,0 +1,82 @@
+//! Relays we can point other clients to when we reference a note or a
+//! profile, so they can find it.
+
+use nostrdb::{Filter, Ndb, Note, NoteKey, Transaction};
+
+use crate::accounts::AccountRelayData;
+
+/// Nobody needs more than a few
+pub const MAX_RELAY_HINTS: usize = 3;
+
+/// The NIP-65 write relays of `pubkey`, where their notes should be
+pub fn pubkey_relay_hints(ndb: &Ndb, txn: &Transaction, pubkey: &[u8; 32]) -> Vec<String> {
+    let filter = Filter::new()
+        .authors([pubkey])
+        .kinds([10002])
+        .limit(1)
+        .build();
+
+    let nks: Vec<NoteKey> = ndb
+        .query(txn, &[filter], 1)
+        .map(|results| results.iter().map(|qr| qr.note_key).collect())
+        .unwrap_or_default();
+
+    let mut hints = Vec::new();
+    for spec in AccountRelayData::harvest_nip65_relays(ndb, txn, &nks) {
+        if spec.is_writable() {
+            push_hint(&mut hints, &spec.url);
+        }
+    }
+    hints
+}
+
+/// The relays we got `note` from, then its author's write relays
+pub fn note_relay_hints(ndb: &Ndb, txn: &Transaction, note: &Note) -> Vec<String> {
+    let mut hints = Vec::new();
+
+    if note.key().is_some() {
+        for relay in note.relays(txn) {
+            push_hint(&mut hints, relay);
+        }
+    }
+
+    for relay in pubkey_relay_hints(ndb, txn, note.pubkey()) {
+        push_hint(&mut hints, &relay);
+    }
+
+    hints
+}
+
+fn push_hint(hints: &mut Vec<String>, relay: &str) {
+    // multicast and other local relays are no use to anyone else
+    let relay = relay.trim_end_matches('/');
+    if hints.len() >= MAX_RELAY_HINTS
+        || !(relay.starts_with("wss://") || relay.starts_with("ws://"))
+        || hints.iter().any(|hint| hint == relay)
+    {
+        return;
+    }
+
+    hints.push(relay.to_owned());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_hint() {
+        let mut hints = Vec::new();
+        push_hint(&mut hints, "wss://relay.damus.io/");
+        push_hint(&mut hints, "wss://relay.damus.io");
+        push_hint(&mut hints, "multicast");
+        push_hint(&mut hints, "wss://nos.lol");
+        push_hint(&mut hints, "wss://nostr.wine");
+        push_hint(&mut hints, "wss://one.too.many");
+
+        assert_eq!(
+            hints,
+            vec!["wss://relay.damus.io", "wss://nos.lol", "wss://nostr.wine"]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Decoding [ThumbHash](https://evanw.github.io/thumbhash/), like
+//! [`crate::blurhash`] but it also knows the aspect ratio and alpha.
+
+use std::f32::consts::PI;
+
+use base64::{prelude::BASE64_STANDARD_NO_PAD, Engine};
+
+/// The hash from its base64 form, as it's found in `imeta` tags
+pub fn from_base64(hash: &str) -> Option<Vec<u8>> {
+    BASE64_STANDARD_NO_PAD
+        .decode(hash.trim_end_matches('='))
+        .ok()
+        .filter(|bytes| bytes.len() >= 5)
+}
+
+/// Width over height of the original image, roughly
+pub fn aspect_ratio(hash: &[u8]) -> Option<f32> {
+    if hash.len() < 5 {
+        return None;
+    }
+
+    let has_alpha = (hash[2] & 0x80) != 0;
+    let l_max = if has_alpha { 5 } else { 7 };
+    let l_min = (hash[3] & 7).max(1);
+    let is_landscape = (hash[4] & 0x80) != 0;
+    let (lx, ly) = if is_landscape {
+        (l_max, l_min)
+    } else {
+        (l_min, l_max)
+    };
+
+    Some(lx as f32 / ly as f32)
+}
+
+/// Decode the hash into rgba pixels, at most 32 on a side. Returns the
+/// width, height and pixels.
+pub fn decode(hash: &[u8]) -> Option<(usize, usize, Vec<u8>)> {
+    let ratio = aspect_ratio(hash)?;
+
+    let header24 = hash[0] as u32 | (hash[1] as u32) << 8 | (hash[2] as u32) << 16;
+    let header16 = hash[3] as u16 | (hash[4] as u16) << 8;
+    let l_dc = (header24 & 63) as f32 / 63.0;
+    let p_dc = ((header24 >> 6) & 63) as f32 / 31.5 - 1.0;
+    let q_dc = ((header24 >> 12) & 63) as f32 / 31.5 - 1.0;
+    let l_scale = ((header24 >> 18) & 31) as f32 / 31.0;
+    let has_alpha = (header24 >> 23) != 0;
+    let p_scale = ((header16 >> 3) & 63) as f32 / 63.0;
+    let q_scale = ((header16 >> 9) & 63) as f32 / 63.0;
+    let is_landscape = (header16 >> 15) != 0;
+    let l_max = if has_alpha { 5 } else { 7 };
+    let (lx, ly) = if is_landscape {
+        (l_max, (header16 & 7) as usize)
+    } else {
+        ((header16 & 7) as usize, l_max)
+    };
+    let (lx, ly) = (lx.max(3), ly.max(3));
+
+    let (a_dc, a_scale) = if has_alpha {
+        let byte = *hash.get(5)?;
+        ((byte & 15) as f32 / 15.0, (byte >> 4) as f32 / 15.0)
+    } else {
+        (1.0, 1.0)
+    };
+
+    let ac_start = if has_alpha { 6 } else { 5 };
+    let mut ac_index = 0;
+    let mut decode_channel = |nx: usize, ny: usize, scale: f32| -> Option<Vec<f32>> {
+        let mut ac = Vec::with_capacity(nx * ny);
+        for cy in 0..ny {
+            let mut cx = if cy > 0 { 0 } else { 1 };
+            while cx * ny < nx * (ny - cy) {
+                let byte = *hash.get(ac_start + (ac_index >> 1))?;
+                let nibble = (byte >> ((ac_index & 1) << 2)) & 15;
+                ac.push((nibble as f32 / 7.5 - 1.0) * scale);
+                ac_index += 1;
+                cx += 1;
+            }
+        }
+        Some(ac)
+    };
+
+    // saturation is boosted to make up for quantization
+    let l_ac = decode_channel(lx, ly, l_scale)?;
+    let p_ac = decode_channel(3, 3, p_scale * 1.25)?;
+    let q_ac = decode_channel(3, 3, q_scale * 1.25)?;
+    let a_ac = if has_alpha {
+        decode_channel(5, 5, a_scale)?
+    } else {
+        vec![]
+    };
+
+    let (w, h) = if ratio > 1.0 {
+        (32, ((32.0 / ratio).round() as usize).max(1))
+    } else {
+        (((32.0 * ratio).round() as usize).max(1), 32)
+    };
+
+    let mut rgba = Vec::with_capacity(w * h * 4);
+    let mut fx = [0.0f32; 7];
+    let mut fy = [0.0f32; 7];
+    let min_components = if has_alpha { 5 } else { 3 };
+
+    for y in 0..h {
+        for x in 0..w {
+            let (mut l, mut p, mut q, mut a) = (l_dc, p_dc, q_dc, a_dc);
+
+            for (cx, f) in fx.iter_mut().enumerate().take(lx.max(min_components)) {
+                *f = (PI / w as f32 * (x as f32 + 0.5) * cx as f32).cos();
+            }
+            for (cy, f) in fy.iter_mut().enumerate().take(ly.max(min_components)) {
+                *f = (PI / h as f32 * (y as f32 + 0.5) * cy as f32).cos();
+            }
+
+            let mut j = 0;
+            for (cy, fy) in fy.iter().enumerate().take(ly) {
+                let fy2 = fy * 2.0;
+                let mut cx = if cy > 0 { 0 } else { 1 };
+                while cx * ly < lx * (ly - cy) {
+                    l += l_ac[j] * fx[cx] * fy2;
+                    j += 1;
+                    cx += 1;
+                }
+            }
+
+            let mut j = 0;
+            for (cy, fy) in fy.iter().enumerate().take(3) {
+                let fy2 = fy * 2.0;
+                for fx in &fx[if cy > 0 { 0 } else { 1 }..3 - cy] {
+                    let f = fx * fy2;
+                    p += p_ac[j] * f;
+                    q += q_ac[j] * f;
+                    j += 1;
+                }
+            }
+
+            if has_alpha {
+                let mut j = 0;
+                for (cy, fy) in fy.iter().enumerate().take(5) {
+                    let fy2 = fy * 2.0;
+                    for fx in &fx[if cy > 0 { 0 } else { 1 }..5 - cy] {
+                        a += a_ac[j] * fx * fy2;
+                        j += 1;
+                    }
+                }
+            }
+
+            let b = l - 2.0 / 3.0 * p;
+            let r = (3.0 * l - b + q) / 2.0;
+            let g = r - q;
+            rgba.extend(
+                [r, g, b, a]
+                    .into_iter()
+                    .map(|c| (c.clamp(0.0, 1.0) * 255.0) as u8),
+            );
+        }
+    }
+
+    Some((w, h, rgba))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thumbhash_decode() {
+        // a portrait photo from the thumbhash demo
+        let hash = from_base64("1QcSHQRnh493V4dIh4eXh1h4kJUI").unwrap();
+        let ratio = aspect_ratio(&hash).unwrap();
+        assert!(ratio < 1.0, "{ratio}");
+
+        let (w, h, rgba) = decode(&hash).unwrap();
+        assert_eq!(h, 32);
+        assert!(w < 32);
+        assert_eq!(rgba.len(), w * h * 4);
+        assert!(rgba.chunks(4).all(|px| px[3] == 255));
+
+        assert!(from_base64("nope").is_none());
+    }
+}
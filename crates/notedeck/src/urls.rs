@@ -9,19 +9,40 @@ use std::{
 
 use egui::TextBuffer;
 use poll_promise::Promise;
+use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{Error, MediaCacheType};
 
-const FILE_NAME: &str = "urls.bin";
+const FILE_NAME: &str = "urls_v1.bin";
+
+/// Before we remembered when urls were last used
+const LEGACY_FILE_NAME: &str = "urls.bin";
 const SAVE_INTERVAL: Duration = Duration::from_secs(60);
 
-type UrlsToMime = HashMap<String, String>;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UrlMime {
+    mime_type: String,
+
+    /// Seconds since the epoch
+    last_used: u64,
+}
+
+type UrlsToMime = HashMap<String, UrlMime>;
 
-/// caches mime type for a URL. saves to disk on interval [`SAVE_INTERVAL`]
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// caches mime type for a URL. saves to disk on interval [`SAVE_INTERVAL`],
+/// forgetting the least recently used urls beyond `max_entries`
 pub struct UrlCache {
     last_saved: SystemTime,
     path: PathBuf,
+    max_entries: usize,
     cache: Arc<RwLock<UrlsToMime>>,
     from_disk_promise: Option<Promise<Option<UrlsToMime>>>,
 }
@@ -31,23 +52,49 @@ impl UrlCache {
         FILE_NAME
     }
 
-    pub fn new(path: PathBuf) -> Self {
+    pub fn new(path: PathBuf, max_entries: usize) -> Self {
         Self {
             last_saved: SystemTime::now(),
             path: path.clone(),
+            max_entries,
             cache: Default::default(),
             from_disk_promise: Some(read_from_disk(path)),
         }
     }
 
     pub fn get_type(&self, url: &str) -> Option<String> {
-        self.cache.read().ok()?.get(url).cloned()
+        let mut locked_cache = self.cache.write().ok()?;
+        let entry = locked_cache.get_mut(url)?;
+        entry.last_used = now_secs();
+        Some(entry.mime_type.clone())
     }
 
     pub fn set_type(&mut self, url: String, mime_type: String) {
         if let Ok(mut locked_cache) = self.cache.write() {
-            locked_cache.insert(url, mime_type);
+            locked_cache.insert(
+                url,
+                UrlMime {
+                    mime_type,
+                    last_used: now_secs(),
+                },
+            );
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.read().map(|c| c.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        if let Ok(mut locked_cache) = self.cache.write() {
+            locked_cache.clear();
         }
+        save_to_disk(self.path.clone(), self.cache.clone(), self.max_entries);
+        self.last_saved = SystemTime::now();
     }
 
     pub fn handle_io(&mut self) {
@@ -63,7 +110,7 @@ impl UrlCache {
 
         if let Ok(cur_duration) = SystemTime::now().duration_since(self.last_saved) {
             if cur_duration >= SAVE_INTERVAL {
-                save_to_disk(self.path.clone(), self.cache.clone());
+                save_to_disk(self.path.clone(), self.cache.clone(), self.max_entries);
                 self.last_saved = SystemTime::now();
             }
         }
@@ -78,18 +125,72 @@ fn merge_cache(cur_cache: Arc<RwLock<UrlsToMime>>, from_disk: UrlsToMime) {
     });
 }
 
+/// Forget the least recently used urls so at most `max_entries` are left
+fn evict_oldest(cache: &mut UrlsToMime, max_entries: usize) {
+    if cache.len() <= max_entries {
+        return;
+    }
+
+    let mut last_used: Vec<u64> = cache.values().map(|entry| entry.last_used).collect();
+    let num_evict = cache.len() - max_entries;
+    let (_, cutoff, _) = last_used.select_nth_unstable(num_evict - 1);
+    let cutoff = *cutoff;
+
+    let mut left = num_evict;
+    cache.retain(|_, entry| {
+        if left > 0 && entry.last_used <= cutoff {
+            left -= 1;
+            false
+        } else {
+            true
+        }
+    });
+}
+
+fn read_file(path: &std::path::Path) -> Result<Vec<u8>, Error> {
+    let mut file = File::open(path)?;
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+/// Read the cache from before we tracked when urls were used, everything
+/// in it counts as used now
+fn read_legacy(path: &std::path::Path) -> Result<UrlsToMime, Error> {
+    let data: HashMap<String, String> =
+        bincode::deserialize(&read_file(path)?).map_err(|e| Error::Generic(e.to_string()))?;
+    let now = now_secs();
+
+    if let Err(e) = std::fs::remove_file(path) {
+        tracing::warn!("could not remove {}: {e}", path.display());
+    }
+
+    Ok(data
+        .into_iter()
+        .map(|(url, mime_type)| {
+            (
+                url,
+                UrlMime {
+                    mime_type,
+                    last_used: now,
+                },
+            )
+        })
+        .collect())
+}
+
 fn read_from_disk(path: PathBuf) -> Promise<Option<UrlsToMime>> {
     let (sender, promise) = Promise::new();
 
     std::thread::spawn(move || {
-        let result: Result<UrlsToMime, Error> = (|| {
-            let mut file = File::open(path)?;
-            let mut buffer = Vec::new();
-            file.read_to_end(&mut buffer)?;
-            let data: UrlsToMime =
-                bincode::deserialize(&buffer).map_err(|e| Error::Generic(e.to_string()))?;
-            Ok(data)
-        })();
+        let legacy_path = path.with_file_name(LEGACY_FILE_NAME);
+        let result: Result<UrlsToMime, Error> = if !path.exists() && legacy_path.exists() {
+            read_legacy(&legacy_path)
+        } else {
+            read_file(&path).and_then(|buffer| {
+                bincode::deserialize(&buffer).map_err(|e| Error::Generic(e.to_string()))
+            })
+        };
 
         match result {
             Ok(data) => sender.send(Some(data)),
@@ -103,23 +204,23 @@ fn read_from_disk(path: PathBuf) -> Promise<Option<UrlsToMime>> {
     promise
 }
 
-fn save_to_disk(path: PathBuf, cache: Arc<RwLock<UrlsToMime>>) {
+fn save_to_disk(path: PathBuf, cache: Arc<RwLock<UrlsToMime>>, max_entries: usize) {
     std::thread::spawn(move || {
         let result: Result<(), Error> = (|| {
-            if let Ok(cache) = cache.read() {
-                let cache = &*cache;
-                let encoded =
-                    bincode::serialize(cache).map_err(|e| Error::Generic(e.to_string()))?;
-                let mut file = File::create(&path)?;
-                file.write_all(&encoded)?;
-                file.sync_all()?;
-                tracing::debug!("Saved UrlCache to disk.");
-                Ok(())
+            let encoded = if let Ok(mut cache) = cache.write() {
+                evict_oldest(&mut cache, max_entries);
+                bincode::serialize(&*cache).map_err(|e| Error::Generic(e.to_string()))?
             } else {
-                Err(Error::Generic(
+                return Err(Error::Generic(
                     "Could not read UrlMimes behind RwLock".to_owned(),
-                ))
-            }
+                ));
+            };
+
+            let mut file = File::create(&path)?;
+            file.write_all(&encoded)?;
+            file.sync_all()?;
+            tracing::debug!("Saved UrlCache to disk.");
+            Ok(())
         })();
 
         if let Err(e) = result {
@@ -180,6 +281,11 @@ impl UrlMimes {
         }
     }
 
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.in_flight.clear();
+    }
+
     pub fn get(&mut self, url: &str) -> Option<String> {
         if let Some(mime_type) = self.cache.get_type(url) {
             Some(mime_type)
@@ -250,10 +356,30 @@ impl SupportedMimeType {
             MediaCacheType::Image
         }
     }
+
+    pub fn media_kind(&self) -> MediaKind {
+        if self.mime.type_() == mime_guess::mime::VIDEO {
+            MediaKind::Video
+        } else if self.mime.type_() == mime_guess::mime::AUDIO {
+            MediaKind::Audio
+        } else {
+            MediaKind::Image(self.to_cache_type())
+        }
+    }
+}
+
+/// The kinds of media we can show inline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MediaKind {
+    Image(MediaCacheType),
+    Video,
+    Audio,
 }
 
 fn is_mime_supported(mime: &mime_guess::Mime) -> bool {
     mime.type_() == mime_guess::mime::IMAGE
+        || mime.type_() == mime_guess::mime::VIDEO
+        || mime.type_() == mime_guess::mime::AUDIO
 }
 
 fn url_has_supported_mime(url: &str) -> MimeHostedAtUrl {
@@ -265,7 +391,7 @@ fn url_has_supported_mime(url: &str) -> MimeHostedAtUrl {
                     .and_then(|ext| ext.to_str())
                 {
                     if let Ok(supported) = SupportedMimeType::from_extension(ext) {
-                        return MimeHostedAtUrl::Yes(supported.to_cache_type());
+                        return MimeHostedAtUrl::Yes(supported.media_kind());
                     } else {
                         return MimeHostedAtUrl::No;
                     }
@@ -276,23 +402,86 @@ fn url_has_supported_mime(url: &str) -> MimeHostedAtUrl {
     MimeHostedAtUrl::Maybe
 }
 
+/// The kind of image at a url, if it's an image we can show
 pub fn supported_mime_hosted_at_url(urls: &mut UrlMimes, url: &str) -> Option<MediaCacheType> {
+    match supported_media_hosted_at_url(urls, url)? {
+        MediaKind::Image(cache_type) => Some(cache_type),
+        MediaKind::Video | MediaKind::Audio => None,
+    }
+}
+
+/// The kind of media at a url, if it's something we can show or play
+pub fn supported_media_hosted_at_url(urls: &mut UrlMimes, url: &str) -> Option<MediaKind> {
     match url_has_supported_mime(url) {
-        MimeHostedAtUrl::Yes(cache_type) => Some(cache_type),
+        MimeHostedAtUrl::Yes(kind) => Some(kind),
         MimeHostedAtUrl::Maybe => urls
             .get(url)
             .and_then(|s| s.parse::<mime_guess::mime::Mime>().ok())
             .and_then(|mime: mime_guess::mime::Mime| {
                 SupportedMimeType::from_mime(mime)
                     .ok()
-                    .map(|s| s.to_cache_type())
+                    .map(|s| s.media_kind())
             }),
         MimeHostedAtUrl::No => None,
     }
 }
 
 enum MimeHostedAtUrl {
-    Yes(MediaCacheType),
+    Yes(MediaKind),
     Maybe,
     No,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(url: &str) -> Option<MediaKind> {
+        match url_has_supported_mime(url) {
+            MimeHostedAtUrl::Yes(kind) => Some(kind),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_media_kind_from_url() {
+        assert_eq!(
+            kind("https://example.com/a.png"),
+            Some(MediaKind::Image(MediaCacheType::Image))
+        );
+        assert_eq!(
+            kind("https://example.com/a.gif"),
+            Some(MediaKind::Image(MediaCacheType::Gif))
+        );
+        assert_eq!(kind("https://example.com/a.mp4"), Some(MediaKind::Video));
+        assert_eq!(kind("https://example.com/a.webm"), Some(MediaKind::Video));
+        // playlists pull in other urls, we don't hand those to ffmpeg
+        assert_eq!(kind("https://example.com/live.m3u8"), None);
+        assert_eq!(kind("https://example.com/a.mp3"), Some(MediaKind::Audio));
+        assert_eq!(kind("https://example.com/a.pdf"), None);
+    }
+
+    #[test]
+    fn test_evict_oldest_urls() {
+        let mut cache: UrlsToMime = (0..10)
+            .map(|i| {
+                (
+                    format!("https://example.com/{i}.png"),
+                    UrlMime {
+                        mime_type: "image/png".to_owned(),
+                        last_used: 100 + i,
+                    },
+                )
+            })
+            .collect();
+
+        evict_oldest(&mut cache, 10);
+        assert_eq!(cache.len(), 10);
+
+        evict_oldest(&mut cache, 4);
+        assert_eq!(cache.len(), 4);
+        for i in 6..10 {
+            assert!(cache.contains_key(&format!("https://example.com/{i}.png")));
+        }
+    }
+}
@@ -7,8 +7,8 @@ use crate::{
 use enostr::{Pubkey, RelayPool};
 use nostrdb::{Ndb, NoteKey, Transaction};
 use notedeck::{
-    get_wallet_for_mut, Accounts, GlobalWallet, NoteAction, NoteCache, NoteZapTargetOwned,
-    UnknownIds, ZapAction, ZapTarget, ZappingError, Zaps,
+    get_wallet_for_mut, poll, Accounts, GlobalWallet, NoteAction, NoteCache, NoteZapTargetOwned,
+    PollVote, UnknownIds, ZapAction, ZapTarget, ZappingError, Zaps,
 };
 use tracing::error;
 
@@ -99,11 +99,16 @@ fn execute_note_action(
             None
         }
 
+        NoteAction::Vote(vote) => {
+            send_vote(ndb, accounts, pool, vote);
+            None
+        }
+
         NoteAction::Context(context) => {
             match ndb.get_note_by_key(txn, context.note_key) {
                 Err(err) => tracing::error!("{err}"),
                 Ok(note) => {
-                    context.action.process(ui, &note, pool);
+                    context.action.process(ui, ndb, &note, pool, accounts);
                 }
             }
             None
@@ -150,7 +155,12 @@ fn send_zap(sender: &Pubkey, zaps: &mut Zaps, pool: &RelayPool, target: &NoteZap
     let default_zap_msats = 10_000; // TODO(kernelkind): allow the user to set this default
     let zap_target = ZapTarget::Note(target.into());
 
-    let sender_relays: Vec<String> = pool.relays.iter().map(|r| r.url().to_string()).collect();
+    let sender_relays: Vec<String> = pool
+        .relays
+        .iter()
+        .filter(|r| !r.is_exclusive())
+        .map(|r| r.url().to_string())
+        .collect();
     zaps.send_zap(sender.bytes(), sender_relays, zap_target, default_zap_msats);
 }
 
@@ -158,6 +168,36 @@ fn clear_zap_error(sender: &Pubkey, zaps: &mut Zaps, target: &NoteZapTargetOwned
     zaps.clear_error_for(sender.bytes(), ZapTarget::Note(target.into()));
 }
 
+/// Publish a poll response, and keep it locally so the tally updates
+/// right away
+fn send_vote(ndb: &Ndb, accounts: &Accounts, pool: &mut RelayPool, vote: &PollVote) {
+    let Some(kp) = accounts
+        .get_selected_account()
+        .and_then(|acc| acc.key.to_full())
+    else {
+        error!("can't vote without a secret key");
+        return;
+    };
+
+    let seckey = kp.secret_key.to_secret_bytes();
+    let Some(note) = poll::response_note(vote.poll.bytes(), &vote.choices, &seckey) else {
+        error!("could not build poll response");
+        return;
+    };
+
+    let Ok(json) = note.json() else {
+        error!("could not serialize poll response");
+        return;
+    };
+
+    let raw_msg = format!("[\"EVENT\",{json}]");
+    if let Err(err) = ndb.process_event_with(&raw_msg, nostrdb::IngestMetadata::new().client(true))
+    {
+        error!("could not process poll response: {err}");
+    }
+    pool.send(&enostr::ClientMessage::raw(raw_msg));
+}
+
 impl TimelineOpenResult {
     pub fn new_notes(notes: Vec<NoteKey>, id: TimelineKind) -> Self {
         Self::NewNotes(NewNotes::new(notes, id))
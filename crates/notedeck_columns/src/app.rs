@@ -3,7 +3,13 @@ use crate::{
     column::Columns,
     decks::{Decks, DecksCache, FALLBACK_PUBKEY},
     draft::Drafts,
-    nav, storage,
+    followers::Followers,
+    media_upload, nav,
+    poll_subs::PollSubs,
+    relay_info::RelayInfoCache,
+    scheduled::{self, ScheduledPosts},
+    search::{RemoteSearch, SearchTarget},
+    storage,
     subscriptions::{SubKind, Subscriptions},
     support::Support,
     timeline::{self, TimelineCache},
@@ -12,7 +18,10 @@ use crate::{
     Result,
 };
 
-use notedeck::{Accounts, AppContext, DataPath, DataPathType, FilterState, UnknownIds};
+use notedeck::{
+    Accounts, AppContext, DataPath, DataPathType, FilterState, ToggleHandler, UnknownIds,
+    UploadServerHandler,
+};
 use notedeck_ui::NoteOptions;
 
 use enostr::{ClientMessage, Keypair, PoolRelay, Pubkey, RelayEvent, RelayMessage, RelayPool};
@@ -26,6 +35,7 @@ use std::collections::{BTreeSet, HashMap};
 use std::path::Path;
 use std::time::Duration;
 use tracing::{debug, error, info, trace, warn};
+use url::Url;
 
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub enum DamusState {
@@ -39,8 +49,13 @@ pub struct Damus {
     pub decks_cache: DecksCache,
     pub view_state: ViewState,
     pub drafts: Drafts,
+    drafts_saver: storage::DraftsSaver,
+    pub scheduled: ScheduledPosts,
     pub timeline_cache: TimelineCache,
     pub subscriptions: Subscriptions,
+    poll_subs: PollSubs,
+    pub followers: Followers,
+    pub relay_info: RelayInfoCache,
     pub support: Support,
 
     //frame_history: crate::frame_history::FrameHistory,
@@ -52,6 +67,16 @@ pub struct Damus {
     pub since_optimize: bool,
     pub note_options: NoteOptions,
 
+    /// Remembers the autoplay setting, `--autoplay` turns it on for the run
+    pub autoplay: ToggleHandler,
+
+    /// Remembers whether we play media with ffmpeg
+    pub ffmpeg: ToggleHandler,
+
+    /// Remembers where media goes for accounts without a media server
+    /// list, `--upload-server` overrides it for the run
+    pub upload_server: UploadServerHandler,
+
     pub unrecognized_args: BTreeSet<String>,
 }
 
@@ -95,6 +120,11 @@ fn try_process_event(
 
     app_ctx.pool.keepalive_ping(wakeup);
 
+    // stay connected to the relays our relay feeds browse
+    if let Some(relays) = damus.timeline_cache.changed_relay_feeds() {
+        app_ctx.accounts.set_column_relays(relays);
+    }
+
     // NOTE: we don't use the while let loop due to borrow issues
     #[allow(clippy::while_let_loop)]
     loop {
@@ -106,6 +136,8 @@ fn try_process_event(
 
         match (&ev.event).into() {
             RelayEvent::Opened => {
+                damus.relay_info.fetch(&ev.relay);
+
                 app_ctx
                     .accounts
                     .send_initial_filters(app_ctx.pool, &ev.relay);
@@ -129,12 +161,39 @@ fn try_process_event(
         }
     }
 
-    for (_kind, timeline) in damus.timeline_cache.timelines.iter_mut() {
+    damus.relay_info.poll();
+
+    damus
+        .poll_subs
+        .update(app_ctx.polls, app_ctx.pool, &mut damus.subscriptions);
+    damus
+        .followers
+        .update(app_ctx.ndb, app_ctx.pool, &mut damus.subscriptions);
+
+    for (kind, timeline) in damus.timeline_cache.timelines.iter_mut() {
+        if let Some(remote_search) = &mut timeline.remote_search {
+            remote_search.update(
+                &SearchTarget::Timeline(kind.clone()),
+                &damus.relay_info,
+                app_ctx.pool,
+                &mut damus.subscriptions,
+            );
+        }
+
+        timeline
+            .backfill
+            .update(app_ctx.pool, &mut damus.subscriptions);
+        timeline
+            .coverage
+            .update(app_ctx.pool, &mut damus.subscriptions);
+
         let is_ready =
             timeline::is_timeline_ready(app_ctx.ndb, app_ctx.pool, app_ctx.note_cache, timeline);
 
         if is_ready {
             let txn = Transaction::new(app_ctx.ndb).expect("txn");
+            timeline.coverage.poll_received(app_ctx.ndb, &txn);
+
             // only thread timelines are reversed
             //let reversed = false; // No longer needed here
 
@@ -147,6 +206,28 @@ fn try_process_event(
             ) {
                 error!("poll_notes_into_pending: {err}");
             }
+
+            if let Err(err) = timeline.poll_remote_search(
+                app_ctx.ndb,
+                &txn,
+                app_ctx.unknown_ids,
+                app_ctx.note_cache,
+            ) {
+                error!("poll_remote_search: {err}");
+            }
+
+            if let Err(err) = timeline.backfill(
+                app_ctx.ndb,
+                &txn,
+                app_ctx.unknown_ids,
+                app_ctx.note_cache,
+                app_ctx.pool,
+                &mut damus.subscriptions,
+            ) {
+                error!("backfill: {err}");
+            }
+
+            timeline.fill_gap(app_ctx.pool, &mut damus.subscriptions);
         } else {
             // TODO: show loading?
         }
@@ -172,8 +253,6 @@ fn unknown_id_send(unknown_ids: &mut UnknownIds, pool: &mut RelayPool) {
 }
 
 fn update_damus(damus: &mut Damus, app_ctx: &mut AppContext<'_>, ctx: &egui::Context) {
-    app_ctx.img_cache.urls.cache.handle_io();
-
     match damus.state {
         DamusState::Initializing => {
             damus.state = DamusState::Initialized;
@@ -196,11 +275,23 @@ fn update_damus(damus: &mut Damus, app_ctx: &mut AppContext<'_>, ctx: &egui::Con
     if let Err(err) = try_process_event(damus, app_ctx, ctx) {
         error!("error processing event: {}", err);
     }
+
+    damus.drafts_saver.try_save(&damus.drafts);
+
+    let now = scheduled::unix_now();
+    damus
+        .scheduled
+        .publish_due(now, app_ctx.accounts, app_ctx.ndb, app_ctx.pool);
+    storage::save_scheduled(app_ctx.path, &mut damus.scheduled);
+    if let Some(due_in) = damus.scheduled.next_due_in(now) {
+        ctx.request_repaint_after(due_in);
+    }
 }
 
 fn handle_eose(
-    subscriptions: &Subscriptions,
+    subscriptions: &mut Subscriptions,
     timeline_cache: &mut TimelineCache,
+    view_state: &mut ViewState,
     ctx: &mut AppContext<'_>,
     subid: &str,
     relay_url: &str,
@@ -208,6 +299,10 @@ fn handle_eose(
     let sub_kind = if let Some(sub_kind) = subscriptions.subs.get(subid) {
         sub_kind
     } else {
+        if ctx.accounts.eose_received(subid) {
+            return Ok(());
+        }
+
         let n_subids = subscriptions.subs.len();
         warn!(
             "got unknown eose subid {}, {} tracked subscriptions",
@@ -220,7 +315,11 @@ fn handle_eose(
         SubKind::Timeline(_) => {
             // eose on timeline? whatevs
         }
-        SubKind::Initial => {
+        SubKind::Initial(kind) => {
+            if let Some(timeline) = timeline_cache.timelines.get_mut(kind) {
+                timeline.coverage.relay_finished(subid, relay_url);
+            }
+
             //let txn = Transaction::new(ctx.ndb)?;
             //unknowns::update_from_columns(
             //    &txn,
@@ -241,6 +340,44 @@ fn handle_eose(
             ctx.pool.send_to(&msg, relay_url);
         }
 
+        // relay search results are one-shot as well
+        SubKind::RemoteSearch(target) => {
+            let msg = ClientMessage::close(subid.to_string());
+            ctx.pool.send_to(&msg, relay_url);
+
+            if let Some(remote_search) = remote_search_mut(target, timeline_cache, view_state) {
+                remote_search.relay_finished(relay_url);
+            }
+        }
+
+        // backfill requests are closed per relay, the timeline decides
+        // whether there's anything older left once all of them are done
+        SubKind::Backfill(kind) => {
+            let msg = ClientMessage::close(subid.to_string());
+            ctx.pool.send_to(&msg, relay_url);
+
+            let kind = kind.clone();
+            if let Some(timeline) = timeline_cache.timelines.get_mut(&kind) {
+                timeline.backfill.relay_finished(relay_url, subscriptions);
+            }
+        }
+
+        // gap requests too, once every relay has answered we forget it
+        SubKind::FillGap(kind) => {
+            let msg = ClientMessage::close(subid.to_string());
+            ctx.pool.send_to(&msg, relay_url);
+
+            let kind = kind.clone();
+            if let Some(timeline) = timeline_cache.timelines.get_mut(&kind) {
+                if timeline.coverage.relay_finished(subid, relay_url) {
+                    subscriptions.subs.remove(subid);
+                }
+            }
+        }
+
+        // poll responses and follows stay open for new contact lists and votes
+        SubKind::PollResponses(_) | SubKind::ProfileFollows(_) => {}
+
         SubKind::FetchingContactList(timeline_uid) => {
             let timeline = if let Some(tl) = timeline_cache.timelines.get_mut(timeline_uid) {
                 tl
@@ -286,9 +423,66 @@ fn handle_eose(
     Ok(())
 }
 
+/// Find the relay search a search subscription belongs to
+fn remote_search_mut<'a>(
+    target: &SearchTarget,
+    timeline_cache: &'a mut TimelineCache,
+    view_state: &'a mut ViewState,
+) -> Option<&'a mut RemoteSearch> {
+    match target {
+        SearchTarget::View(id) => view_state.searches.get_mut(id).map(|s| &mut s.remote),
+        SearchTarget::Timeline(kind) => timeline_cache
+            .timelines
+            .get_mut(kind)
+            .and_then(|tl| tl.remote_search.as_mut()),
+    }
+}
+
 fn process_message(damus: &mut Damus, ctx: &mut AppContext<'_>, relay: &str, msg: &RelayMessage) {
     match msg {
-        RelayMessage::Event(_subid, ev) => {
+        RelayMessage::Event(subid, ev) => {
+            match damus.subscriptions.subs.get(*subid) {
+                Some(SubKind::RemoteSearch(target)) => {
+                    if let Some(note_id) = enostr::event_note_id(ev) {
+                        if let Some(remote_search) = remote_search_mut(
+                            target,
+                            &mut damus.timeline_cache,
+                            &mut damus.view_state,
+                        ) {
+                            remote_search.note_received(relay, note_id);
+                        }
+                    }
+                }
+
+                Some(SubKind::Backfill(kind)) => {
+                    if let Some(timeline) = damus.timeline_cache.timelines.get_mut(kind) {
+                        timeline.backfill.note_received(relay);
+                        if let Some(id) = enostr::event_note_id(ev) {
+                            timeline.note_requested(id);
+                        }
+                    }
+                }
+
+                Some(SubKind::Initial(kind)) | Some(SubKind::FillGap(kind)) => {
+                    let fill = matches!(
+                        damus.subscriptions.subs.get(*subid),
+                        Some(SubKind::FillGap(_))
+                    );
+                    if let Some(timeline) = damus.timeline_cache.timelines.get_mut(kind) {
+                        if let Some(id) = enostr::event_note_id(ev) {
+                            if timeline.coverage.is_waiting_on(subid, relay) {
+                                timeline.coverage.note_received(subid, relay, id);
+                            }
+                            if fill {
+                                timeline.note_requested(id);
+                            }
+                        }
+                    }
+                }
+
+                _ => {}
+            }
+
             let relay = if let Some(relay) = ctx.pool.relays.iter().find(|r| r.url() == relay) {
                 relay
             } else {
@@ -322,11 +516,19 @@ fn process_message(damus: &mut Damus, ctx: &mut AppContext<'_>, relay: &str, msg
             }
         }
         RelayMessage::Notice(msg) => warn!("Notice from {}: {}", relay, msg),
-        RelayMessage::OK(cr) => info!("OK {:?}", cr),
+        RelayMessage::OK(cr) => {
+            info!("OK {:?}", cr);
+            damus
+                .scheduled
+                .ok_received(relay, cr.event_id(), cr.is_accepted(), cr.message());
+            ctx.accounts
+                .ok_received(relay, cr.event_id(), cr.is_accepted());
+        }
         RelayMessage::Eose(sid) => {
             if let Err(err) = handle_eose(
-                &damus.subscriptions,
+                &mut damus.subscriptions,
                 &mut damus.timeline_cache,
+                &mut damus.view_state,
                 ctx,
                 sid,
                 relay,
@@ -429,14 +631,34 @@ impl Damus {
         note_options.set_textmode(parsed_args.textmode);
         note_options.set_scramble_text(parsed_args.scramble);
         note_options.set_hide_media(parsed_args.no_media);
+        let autoplay = ToggleHandler::autoplay(ctx.path);
+        note_options.set_autoplay_media(parsed_args.autoplay || autoplay.load());
+        let ffmpeg = ToggleHandler::ffmpeg(ctx.path);
+        notedeck_ui::video::set_ffmpeg_enabled(ffmpeg.load());
+        let upload_server = UploadServerHandler::new(ctx.path);
+        if let Some(url) = parsed_args.upload_server.or_else(|| upload_server.load()) {
+            match Url::parse(&url) {
+                Ok(url) => media_upload::set_default_upload_server(url),
+                Err(e) => error!("invalid upload server {url}: {e}"),
+            }
+        }
+        note_options.set_blur_media(parsed_args.blur_media);
 
         Self {
             subscriptions: Subscriptions::default(),
+            poll_subs: PollSubs::default(),
+            followers: Followers::default(),
+            relay_info: RelayInfoCache::default(),
             since_optimize: parsed_args.since_optimize,
             timeline_cache,
-            drafts: Drafts::default(),
+            drafts: storage::load_drafts(ctx.path).unwrap_or_default(),
+            drafts_saver: storage::DraftsSaver::new(ctx.path),
+            scheduled: storage::load_scheduled(ctx.path).unwrap_or_default(),
             state: DamusState::Initializing,
             note_options,
+            autoplay,
+            ffmpeg,
+            upload_server,
             //frame_history: FrameHistory::default(),
             view_state: ViewState::default(),
             tmp_columns,
@@ -476,11 +698,19 @@ impl Damus {
         Self {
             debug,
             subscriptions: Subscriptions::default(),
+            poll_subs: PollSubs::default(),
+            followers: Followers::default(),
+            relay_info: RelayInfoCache::default(),
             since_optimize: true,
             timeline_cache: TimelineCache::default(),
             drafts: Drafts::default(),
+            drafts_saver: storage::DraftsSaver::new(&path),
+            scheduled: ScheduledPosts::default(),
             state: DamusState::Initializing,
             note_options: NoteOptions::default(),
+            autoplay: ToggleHandler::autoplay(&path),
+            ffmpeg: ToggleHandler::ffmpeg(&path),
+            upload_server: UploadServerHandler::new(&path),
             tmp_columns: true,
             //frame_history: FrameHistory::default(),
             view_state: ViewState::default(),
@@ -638,6 +868,13 @@ fn timelines_view(ui: &mut egui::Ui, sizes: Size, app: &mut Damus, ctx: &mut App
     }
 }
 
+impl Drop for Damus {
+    fn drop(&mut self) {
+        // don't lose what was typed since the last save
+        self.drafts_saver.flush(&self.drafts);
+    }
+}
+
 impl notedeck::App for Damus {
     fn update(&mut self, ctx: &mut AppContext<'_>, ui: &mut egui::Ui) {
         /*
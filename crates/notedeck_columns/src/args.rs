@@ -1,7 +1,7 @@
 use std::collections::BTreeSet;
 
-use crate::timeline::TimelineKind;
-use enostr::{Filter, Pubkey};
+use crate::timeline::{GenericFilter, TimelineKind};
+use enostr::Pubkey;
 use tracing::{debug, error, info};
 
 pub struct ColumnsArgs {
@@ -10,6 +10,9 @@ pub struct ColumnsArgs {
     pub textmode: bool,
     pub scramble: bool,
     pub no_media: bool,
+    pub autoplay: bool,
+    pub blur_media: bool,
+    pub upload_server: Option<String>,
 }
 
 impl ColumnsArgs {
@@ -21,6 +24,9 @@ impl ColumnsArgs {
             textmode: false,
             scramble: false,
             no_media: false,
+            autoplay: false,
+            blur_media: false,
+            upload_server: None,
         };
 
         let mut i = 0;
@@ -36,6 +42,18 @@ impl ColumnsArgs {
                 res.scramble = true;
             } else if arg == "--no-media" {
                 res.no_media = true;
+            } else if arg == "--autoplay" {
+                res.autoplay = true;
+            } else if arg == "--blur-media" {
+                res.blur_media = true;
+            } else if arg == "--upload-server" {
+                i += 1;
+                if let Some(next_arg) = args.get(i) {
+                    res.upload_server = Some(next_arg.to_owned());
+                } else {
+                    error!("upload server argument missing?");
+                    continue;
+                }
             } else if arg == "--filter" {
                 i += 1;
                 let filter = if let Some(next_arg) = args.get(i) {
@@ -45,10 +63,9 @@ impl ColumnsArgs {
                     continue;
                 };
 
-                if let Ok(filter) = Filter::from_json(filter) {
-                    res.columns.push(ArgColumn::Generic(vec![filter]));
-                } else {
-                    error!("failed to parse filter '{}'", filter);
+                match GenericFilter::from_json(filter) {
+                    Ok(filter) => res.columns.push(ArgColumn::Generic(filter)),
+                    Err(err) => error!("failed to parse filter '{}': {err}", filter),
                 }
             } else if arg == "--column" || arg == "-c" {
                 i += 1;
@@ -149,9 +166,9 @@ impl ColumnsArgs {
 
                 if let Some(filter) = std::str::from_utf8(&data)
                     .ok()
-                    .and_then(|s| Filter::from_json(s).ok())
+                    .and_then(|s| GenericFilter::from_json(s).ok())
                 {
-                    res.columns.push(ArgColumn::Generic(vec![filter]));
+                    res.columns.push(ArgColumn::Generic(filter));
                 } else {
                     error!("failed to parse filter in '{}'", filter_file);
                 }
@@ -171,16 +188,13 @@ impl ColumnsArgs {
 #[derive(Debug)]
 pub enum ArgColumn {
     Timeline(TimelineKind),
-    Generic(Vec<Filter>),
+    Generic(GenericFilter),
 }
 
 impl ArgColumn {
     pub fn into_timeline_kind(self) -> TimelineKind {
         match self {
-            ArgColumn::Generic(_filters) => {
-                // TODO: fix generic filters by referencing some filter map
-                TimelineKind::Generic(0)
-            }
+            ArgColumn::Generic(filter) => TimelineKind::Generic(filter),
             ArgColumn::Timeline(tk) => tk,
         }
     }
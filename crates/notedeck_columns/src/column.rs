@@ -194,4 +194,6 @@ pub enum IntermediaryRoute {
 pub enum ColumnsAction {
     Switch(usize, usize), // from Switch.0 to Switch.1,
     Remove(usize),
+    /// Give a custom column a new title
+    Rename(usize, String),
 }
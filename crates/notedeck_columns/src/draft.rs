@@ -1,17 +1,123 @@
 use egui::text::LayoutJob;
 use poll_promise::Promise;
 
-use crate::{media_upload::Nip94Event, post::PostBuffer, ui::note::PostType, Error};
+use crate::{
+    media_upload::Nip94Event,
+    post::{NewPoll, PostBuffer},
+    scheduled::{format_utc, parse_utc, Schedule},
+    ui::note::PostType,
+    Error,
+};
+use enostr::{NoteId, Pubkey};
+use nostrdb::NoteKey;
+use notedeck::poll::{PollOption, PollType};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use uuid::Uuid;
 
 #[derive(Default)]
 pub struct Draft {
     pub buffer: PostBuffer,
     pub cur_layout: Option<(String, LayoutJob)>, // `PostBuffer::text_buffer` to current `LayoutJob`
     pub cur_mention_hint: Option<MentionHint>,
+    pub cur_note_hint: Option<NoteHint>,
     pub uploaded_media: Vec<Nip94Event>, // media uploads to include
-    pub uploading_media: Vec<Promise<Result<Nip94Event, Error>>>, // promises that aren't ready yet
+    pub uploading_media: Vec<PendingUpload>, // uploads that aren't done yet
     pub upload_errors: Vec<String>,      // media upload errors to show the user
+    pub content_warning: Option<String>, // NIP-36 reason, if the post has a content warning
+    pub schedule: Option<ScheduleDraft>, // when to publish it, if not now
+    pub poll: Option<PollDraft>,         // NIP-88 poll options, if it's a poll
+}
+
+/// The options of a poll being written
+pub struct PollDraft {
+    pub options: Vec<String>,
+    pub poll_type: PollType,
+    /// `YYYY-MM-DD HH:MM` in UTC, or empty if it doesn't end
+    pub ends_at: String,
+}
+
+impl PollDraft {
+    pub fn new(ends_at: u64) -> Self {
+        PollDraft {
+            options: vec![String::new(), String::new()],
+            poll_type: PollType::SingleChoice,
+            ends_at: format_utc(ends_at),
+        }
+    }
+
+    /// When it ends. `None` if the time isn't valid or is in the past.
+    pub fn ends_at(&self, now: u64) -> Option<Option<u64>> {
+        if self.ends_at.trim().is_empty() {
+            return Some(None);
+        }
+
+        parse_utc(&self.ends_at)
+            .filter(|ends_at| *ends_at > now)
+            .map(Some)
+    }
+
+    /// The poll, if it has at least two options and a valid end
+    pub fn to_poll(&self, now: u64) -> Option<NewPoll> {
+        let labels: Vec<&str> = self
+            .options
+            .iter()
+            .map(|label| label.trim())
+            .filter(|label| !label.is_empty())
+            .collect();
+
+        if labels.len() < 2 {
+            return None;
+        }
+
+        let options = labels
+            .into_iter()
+            .map(|label| PollOption {
+                id: Uuid::new_v4().simple().to_string()[..9].to_owned(),
+                label: label.to_owned(),
+            })
+            .collect();
+
+        Some(NewPoll {
+            options,
+            poll_type: self.poll_type,
+            ends_at: self.ends_at(now)?,
+        })
+    }
+}
+
+/// The "schedule for…" fields in the composer
+pub struct ScheduleDraft {
+    /// `YYYY-MM-DD HH:MM` in UTC, see [`crate::scheduled::parse_utc`]
+    pub time: String,
+    pub sign_at_send: bool,
+}
+
+impl ScheduleDraft {
+    pub fn new(publish_at: u64) -> Self {
+        ScheduleDraft {
+            time: format_utc(publish_at),
+            sign_at_send: false,
+        }
+    }
+
+    /// The schedule, if the time is valid and in the future
+    pub fn schedule(&self, now: u64) -> Option<Schedule> {
+        parse_utc(&self.time)
+            .filter(|publish_at| *publish_at > now)
+            .map(|publish_at| Schedule {
+                publish_at,
+                sign_at_send: self.sign_at_send,
+            })
+    }
+}
+
+/// Media we're still uploading
+pub struct PendingUpload {
+    /// The file on disk, so we can show it while we wait. Pasted images
+    /// aren't on disk yet.
+    pub local_path: Option<PathBuf>,
+    pub promise: Promise<Result<Nip94Event, Error>>,
 }
 
 pub struct MentionHint {
@@ -20,14 +126,40 @@ pub struct MentionHint {
     pub text: String,
 }
 
+/// Notes matching a `~` search in the composer
+pub struct NoteHint {
+    pub query: String,
+    pub results: Vec<NoteKey>,
+}
+
+/// Unsent posts, kept per account
 #[derive(Default)]
 pub struct Drafts {
+    accounts: HashMap<Pubkey, AccountDrafts>,
+}
+
+impl Drafts {
+    pub fn account_mut(&mut self, pubkey: &Pubkey) -> &mut AccountDrafts {
+        self.accounts.entry(*pubkey).or_default()
+    }
+
+    pub fn account(&self, pubkey: &Pubkey) -> Option<&AccountDrafts> {
+        self.accounts.get(pubkey)
+    }
+
+    pub fn accounts(&self) -> impl Iterator<Item = (&Pubkey, &AccountDrafts)> {
+        self.accounts.iter()
+    }
+}
+
+#[derive(Default)]
+pub struct AccountDrafts {
     replies: HashMap<[u8; 32], Draft>,
     quotes: HashMap<[u8; 32], Draft>,
     compose: Draft,
 }
 
-impl Drafts {
+impl AccountDrafts {
     pub fn compose_mut(&mut self) -> &mut Draft {
         &mut self.compose
     }
@@ -47,6 +179,43 @@ impl Drafts {
     pub fn quote_mut(&mut self, id: &[u8; 32]) -> &mut Draft {
         self.quotes.entry(*id).or_default()
     }
+
+    /// Drafts with something in them. The new note comes first, then
+    /// replies and quotes.
+    pub fn unsent(&self) -> Vec<(PostType, &Draft)> {
+        let mut replies: Vec<(PostType, &Draft)> = self
+            .replies
+            .iter()
+            .map(|(id, draft)| (PostType::Reply(NoteId::new(*id)), draft))
+            .collect();
+        let mut quotes: Vec<(PostType, &Draft)> = self
+            .quotes
+            .iter()
+            .map(|(id, draft)| (PostType::Quote(NoteId::new(*id)), draft))
+            .collect();
+
+        // keep the list from jumping around between frames
+        replies.sort_by_key(|(post_type, _)| post_type.target().map(|id| *id.bytes()));
+        quotes.sort_by_key(|(post_type, _)| post_type.target().map(|id| *id.bytes()));
+
+        std::iter::once((PostType::New, &self.compose))
+            .chain(replies)
+            .chain(quotes)
+            .filter(|(_, draft)| !draft.is_empty())
+            .collect()
+    }
+
+    pub fn remove(&mut self, post_type: &PostType) {
+        match post_type {
+            PostType::New => self.compose.clear(),
+            PostType::Quote(note_id) => {
+                self.quotes.remove(note_id.bytes());
+            }
+            PostType::Reply(note_id) => {
+                self.replies.remove(note_id.bytes());
+            }
+        }
+    }
 }
 
 impl Draft {
@@ -54,10 +223,23 @@ impl Draft {
         Draft::default()
     }
 
+    /// Nothing has been written, uploaded or set up yet
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+            && self.uploaded_media.is_empty()
+            && self.content_warning.is_none()
+            && self.schedule.is_none()
+            && self.poll.is_none()
+    }
+
     pub fn clear(&mut self) {
         self.buffer = PostBuffer::default();
         self.upload_errors = Vec::new();
         self.uploaded_media = Vec::new();
         self.uploading_media = Vec::new();
+        self.content_warning = None;
+        self.schedule = None;
+        self.poll = None;
+        self.cur_note_hint = None;
     }
 }
@@ -0,0 +1,178 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{self, TryRecvError};
+use std::time::{Duration, Instant};
+
+use enostr::{ClientMessage, Pubkey, RelayPool, RelayStatus};
+use nostrdb::{Filter, Ndb, Subscription, Transaction};
+use tracing::error;
+
+use crate::{
+    profile::{followers_filter, get_followers},
+    subscriptions::{self, SubKind, Subscriptions},
+};
+
+/// We stop following someone's contact lists once their profile has been
+/// off screen this long
+const FORGET_AFTER: Duration = Duration::from_secs(10);
+
+/// The contact lists of an open profile: their own, and the ones that
+/// mention them
+struct ProfileFollows {
+    /// The relay subscription, once it's registered
+    subid: Option<String>,
+
+    /// Relays we sent the REQ to while they were connected
+    sent_to: HashSet<String>,
+
+    /// Tells us when nostrdb has new contact lists for them
+    sub: Option<Subscription>,
+
+    followers: Vec<Pubkey>,
+
+    /// New contact lists came in since we last counted
+    stale: bool,
+    counting: Option<mpsc::Receiver<Vec<Pubkey>>>,
+    last_shown: Instant,
+}
+
+/// Who follows the profiles on screen. Counting goes through a lot of
+/// contact lists, so it happens on a background thread, and only again
+/// when new ones come in.
+#[derive(Default)]
+pub struct Followers {
+    profiles: HashMap<Pubkey, ProfileFollows>,
+}
+
+impl Followers {
+    /// Their followers as of the last count
+    pub fn get(&mut self, ndb: &Ndb, ctx: &egui::Context, pubkey: &Pubkey) -> &[Pubkey] {
+        let follows = self
+            .profiles
+            .entry(*pubkey)
+            .or_insert_with(|| ProfileFollows {
+                subid: None,
+                sent_to: HashSet::new(),
+                sub: match ndb.subscribe(&filters(pubkey)) {
+                    Ok(sub) => Some(sub),
+                    Err(err) => {
+                        error!("could not subscribe to contact lists: {err}");
+                        None
+                    }
+                },
+                followers: Vec::new(),
+                stale: true,
+                counting: None,
+                last_shown: Instant::now(),
+            });
+        follows.last_shown = Instant::now();
+
+        if let Some(counting) = &follows.counting {
+            match counting.try_recv() {
+                Ok(followers) => {
+                    follows.followers = followers;
+                    follows.counting = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => follows.counting = None,
+            }
+        }
+
+        if follows.stale && follows.counting.is_none() {
+            follows.stale = false;
+            follows.counting = Some(count(ndb, ctx, *pubkey));
+        }
+
+        &follows.followers
+    }
+
+    /// Keep the relays up to date with the profiles on screen, and notice
+    /// new contact lists
+    pub fn update(&mut self, ndb: &mut Ndb, pool: &mut RelayPool, subs: &mut Subscriptions) {
+        let now = Instant::now();
+        self.profiles.retain(|_, follows| {
+            if now - follows.last_shown < FORGET_AFTER {
+                return true;
+            }
+
+            if let Some(subid) = follows.subid.take() {
+                let msg = ClientMessage::close(subid.clone());
+                for relay in &follows.sent_to {
+                    pool.send_to(&msg, relay);
+                }
+                subs.subs.remove(&subid);
+            }
+            if let Some(sub) = follows.sub.take() {
+                if let Err(err) = ndb.unsubscribe(sub) {
+                    error!("could not unsubscribe from contact lists: {err}");
+                }
+            }
+            false
+        });
+
+        let connected: HashSet<String> = pool
+            .relays
+            .iter()
+            .filter(|relay| matches!(relay.status(), RelayStatus::Connected))
+            .filter(|relay| !relay.is_exclusive())
+            .map(|relay| relay.url().to_owned())
+            .collect();
+
+        for (pubkey, follows) in self.profiles.iter_mut() {
+            if let Some(sub) = follows.sub {
+                if !ndb.poll_for_notes(sub, 100).is_empty() {
+                    follows.stale = true;
+                }
+            }
+
+            let subid = follows.subid.get_or_insert_with(|| {
+                let subid = subscriptions::new_sub_id();
+                subs.subs
+                    .insert(subid.clone(), SubKind::ProfileFollows(*pubkey));
+                subid
+            });
+
+            // it goes again to relays that dropped and came back
+            follows.sent_to.retain(|relay| connected.contains(relay));
+
+            let msg = ClientMessage::req(subid.clone(), filters(pubkey));
+            for relay in &connected {
+                if follows.sent_to.insert(relay.clone()) {
+                    pool.send_to(&msg, relay);
+                }
+            }
+        }
+    }
+}
+
+/// Their contact list, and the contact lists that mention them
+fn filters(pubkey: &Pubkey) -> Vec<Filter> {
+    let contact_list = Filter::new()
+        .authors([pubkey.bytes()])
+        .kinds([3])
+        .limit(1)
+        .build();
+    vec![contact_list, followers_filter(pubkey)]
+}
+
+fn count(ndb: &Ndb, ctx: &egui::Context, pubkey: Pubkey) -> mpsc::Receiver<Vec<Pubkey>> {
+    let (sender, receiver) = mpsc::channel();
+
+    let ndb = ndb.clone();
+    let ctx = ctx.clone();
+    std::thread::spawn(move || {
+        let txn = match Transaction::new(&ndb) {
+            Ok(txn) => txn,
+            Err(err) => {
+                error!("followers: could not open transaction: {err}");
+                return;
+            }
+        };
+
+        let followers = get_followers(&ndb, &txn, &pubkey);
+        if sender.send(followers).is_ok() {
+            ctx.request_repaint();
+        }
+    });
+
+    receiver
+}
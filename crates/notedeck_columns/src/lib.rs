@@ -12,16 +12,20 @@ mod column;
 mod deck_state;
 mod decks;
 mod draft;
+mod followers;
 mod key_parsing;
 pub mod login_manager;
 mod media_upload;
 mod multi_subscriber;
 mod nav;
+mod poll_subs;
 mod post;
 mod profile;
 mod profile_state;
+mod relay_info;
 pub mod relay_pool_manager;
 mod route;
+mod scheduled;
 mod search;
 mod subscriptions;
 mod support;
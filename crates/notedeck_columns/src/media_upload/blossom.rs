@@ -0,0 +1,412 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::{prelude::BASE64_STANDARD, Engine};
+use ehttp::Request;
+use nostrdb::{Note, NoteBuilder};
+use poll_promise::Promise;
+use serde::Deserialize;
+use tracing::{error, info};
+use url::Url;
+
+use super::{read_media, sha256_hex, MediaDescription, MediaPath, Nip94Event, UploadBackend};
+use crate::Error;
+
+/// How long a server may hold on to our upload authorization
+const AUTH_EXPIRATION: Duration = Duration::from_secs(5 * 60);
+
+/// A Blossom server (BUD-01/02), which stores blobs by their sha256. An
+/// optional second server mirrors every upload (BUD-04) in case the first
+/// one goes away.
+pub struct BlossomServer {
+    url: Url,
+    mirror: Option<Url>,
+}
+
+impl BlossomServer {
+    pub fn new(url: Url) -> Self {
+        Self { url, mirror: None }
+    }
+
+    pub fn mirror(mut self, mirror: Option<Url>) -> Self {
+        self.mirror = mirror;
+        self
+    }
+}
+
+impl UploadBackend for BlossomServer {
+    fn upload(
+        &self,
+        seckey: [u8; 32],
+        media_path: MediaPath,
+    ) -> Promise<Result<Nip94Event, Error>> {
+        let server = self.url.clone();
+        let mirror = self.mirror.clone();
+        let (sender, promise) = Promise::new();
+        std::thread::spawn(move || {
+            let res = read_media(&media_path).and_then(|file_bytes| {
+                internal_blossom_upload(seckey, server, mirror, media_path, file_bytes)
+                    .block_and_take()
+            });
+            sender.send(res);
+        });
+        promise
+    }
+}
+
+/// What a Blossom server tells us about a blob it stored
+#[derive(Debug, Deserialize)]
+struct BlobDescriptor {
+    url: String,
+    sha256: String,
+    #[serde(rename = "type")]
+    mime_type: Option<String>,
+
+    /// BUD-08 servers describe the blob with NIP-94 tags
+    nip94: Option<Vec<Vec<String>>>,
+}
+
+impl BlobDescriptor {
+    fn into_nip94(self) -> Nip94Event {
+        if let Some(tags) = self.nip94 {
+            if let Ok(mut ev) = Nip94Event::from_tags_and_content(tags, String::new()) {
+                ev.x = ev.x.or(Some(self.sha256));
+                ev.media_type = ev.media_type.or(self.mime_type);
+                return ev;
+            }
+        }
+
+        Nip94Event {
+            url: self.url,
+            ox: None,
+            x: Some(self.sha256),
+            media_type: self.mime_type,
+            dimensions: None,
+            blurhash: None,
+            thumb: None,
+            alt: None,
+            content: String::new(),
+        }
+    }
+}
+
+fn create_blossom_auth_note(seckey: &[u8; 32], file_name: &str, file_hash: &str) -> Note {
+    let expiration = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        + AUTH_EXPIRATION;
+
+    NoteBuilder::new()
+        .kind(24242)
+        .content(&format!("Upload {file_name}"))
+        .start_tag()
+        .tag_str("t")
+        .tag_str("upload")
+        .start_tag()
+        .tag_str("x")
+        .tag_str(file_hash)
+        .start_tag()
+        .tag_str("expiration")
+        .tag_str(&expiration.as_secs().to_string())
+        .sign(seckey)
+        .build()
+        .expect("build note")
+}
+
+/// `path` relative to the server root, keeping any path the server lives under
+fn endpoint(server: &Url, path: &str) -> Url {
+    let mut url = server.clone();
+    let base = server.path().trim_end_matches('/');
+    url.set_path(&format!("{base}/{path}"));
+    url
+}
+
+fn put_request(url: Url, content_type: &str, auth_base64: &str, body: Vec<u8>) -> Request {
+    let headers = ehttp::Headers::new(&[
+        ("Content-Type", content_type),
+        ("Authorization", format!("Nostr {auth_base64}").as_str()),
+    ]);
+
+    Request {
+        method: "PUT".to_string(),
+        url: url.to_string(),
+        headers,
+        body,
+    }
+}
+
+fn fetch_blob_descriptor(request: Request) -> Promise<Result<BlobDescriptor, Error>> {
+    let (sender, promise) = Promise::new();
+
+    ehttp::fetch(request, move |response| {
+        let result = match response {
+            Ok(response) => {
+                if response.ok {
+                    serde_json::from_slice::<BlobDescriptor>(&response.bytes)
+                        .map_err(|e| Error::Generic(e.to_string()))
+                } else {
+                    // servers explain why they rejected us in X-Reason
+                    let reason = response
+                        .headers
+                        .get("x-reason")
+                        .unwrap_or(&response.status_text)
+                        .to_owned();
+                    Err(Error::Generic(format!(
+                        "blossom server returned {}: {reason}",
+                        response.status
+                    )))
+                }
+            }
+            Err(e) => Err(Error::Generic(e)),
+        };
+
+        sender.send(result);
+    });
+
+    promise
+}
+
+fn internal_blossom_upload(
+    seckey: [u8; 32],
+    server: Url,
+    mirror: Option<Url>,
+    media_path: MediaPath,
+    file_contents: Vec<u8>,
+) -> Promise<Result<Nip94Event, Error>> {
+    let file_hash = sha256_hex(&file_contents);
+    let auth_note = create_blossom_auth_note(&seckey, &media_path.file_name, &file_hash);
+
+    let auth_base64 = match auth_note.json() {
+        Ok(json) => BASE64_STANDARD.encode(json),
+        Err(e) => return Promise::from_ready(Err(Error::Generic(e.to_string()))),
+    };
+
+    let (sender, promise) = Promise::new();
+    std::thread::spawn(move || {
+        let description =
+            MediaDescription::describe(media_path.media_type.to_mime(), &file_contents);
+        let request = put_request(
+            endpoint(&server, "upload"),
+            media_path.media_type.to_mime(),
+            &auth_base64,
+            file_contents,
+        );
+
+        let blob = match fetch_blob_descriptor(request).block_and_take() {
+            Ok(blob) => blob,
+            Err(e) => {
+                sender.send(Err(e));
+                return;
+            }
+        };
+
+        if blob.sha256 != file_hash {
+            sender.send(Err(Error::Generic(format!(
+                "blossom server stored {} but we sent {file_hash}",
+                blob.sha256
+            ))));
+            return;
+        }
+
+        if let Some(mirror) = mirror {
+            let body = serde_json::json!({ "url": blob.url })
+                .to_string()
+                .into_bytes();
+            let request = put_request(
+                endpoint(&mirror, "mirror"),
+                "application/json",
+                &auth_base64,
+                body,
+            );
+
+            // the upload still worked, we just have one copy
+            match fetch_blob_descriptor(request).block_and_take() {
+                Ok(mirrored) => info!("mirrored {} to {}", blob.url, mirrored.url),
+                Err(e) => error!("could not mirror {} to {mirror}: {e}", blob.url),
+            }
+        }
+
+        sender.send(Ok(description.fill(blob.into_nip94())));
+    });
+
+    promise
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        path::PathBuf,
+        sync::mpsc,
+    };
+
+    use enostr::FullKeypair;
+
+    use super::*;
+
+    struct ReceivedRequest {
+        request_line: String,
+        headers: HashMap<String, String>,
+        body: Vec<u8>,
+    }
+
+    /// A tiny HTTP server that answers one request with `status` and
+    /// `response`, and hands us what it received
+    fn stand_in_server(
+        status: &str,
+        extra_headers: &str,
+        response: String,
+    ) -> (Url, mpsc::Receiver<ReceivedRequest>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let (tx, rx) = mpsc::channel();
+        let status = status.to_owned();
+        let extra_headers = extra_headers.to_owned();
+
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut headers = HashMap::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((k, v)) = line.split_once(':') {
+                    headers.insert(k.trim().to_lowercase(), v.trim().to_owned());
+                }
+            }
+
+            let len = headers
+                .get("content-length")
+                .and_then(|l| l.parse().ok())
+                .unwrap_or(0);
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Type: application/json\r\n{extra_headers}Content-Length: {}\r\nConnection: close\r\n\r\n{response}",
+                response.len()
+            )
+            .unwrap();
+
+            tx.send(ReceivedRequest {
+                request_line: request_line.trim_end().to_owned(),
+                headers,
+                body,
+            })
+            .unwrap();
+        });
+
+        (url, rx)
+    }
+
+    fn descriptor(hash: &str) -> String {
+        serde_json::json!({
+            "url": format!("https://cdn.example.com/{hash}.png"),
+            "sha256": hash,
+            "size": 3,
+            "type": "image/png",
+            "uploaded": 1700000000,
+        })
+        .to_string()
+    }
+
+    fn auth_note(req: &ReceivedRequest) -> serde_json::Value {
+        let auth = req.headers.get("authorization").expect("authorization");
+        let b64 = auth.strip_prefix("Nostr ").expect("nostr auth");
+        serde_json::from_slice(&BASE64_STANDARD.decode(b64).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_endpoint() {
+        let server = Url::parse("https://cdn.example.com").unwrap();
+        assert_eq!(
+            endpoint(&server, "upload").as_str(),
+            "https://cdn.example.com/upload"
+        );
+
+        let server = Url::parse("https://example.com/blossom/").unwrap();
+        assert_eq!(
+            endpoint(&server, "mirror").as_str(),
+            "https://example.com/blossom/mirror"
+        );
+    }
+
+    #[test]
+    fn test_blossom_upload_and_mirror() {
+        let bytes = vec![1, 2, 3];
+        let hash = sha256_hex(&bytes);
+
+        let (server, uploads) = stand_in_server("200 OK", "", descriptor(&hash));
+        let (mirror, mirrors) = stand_in_server("200 OK", "", descriptor(&hash));
+        let media_path = MediaPath::new(PathBuf::from("cat.png")).unwrap();
+        let kp = FullKeypair::generate();
+
+        let uploaded = internal_blossom_upload(
+            kp.secret_key.secret_bytes(),
+            server,
+            Some(mirror),
+            media_path,
+            bytes.clone(),
+        )
+        .block_and_take()
+        .expect("upload");
+
+        assert_eq!(uploaded.url, format!("https://cdn.example.com/{hash}.png"));
+        assert_eq!(uploaded.x.as_deref(), Some(hash.as_str()));
+        assert_eq!(uploaded.media_type.as_deref(), Some("image/png"));
+
+        let upload = uploads.recv().unwrap();
+        assert_eq!(upload.request_line, "PUT /upload HTTP/1.1");
+        assert_eq!(upload.headers["content-type"], "image/png");
+        assert_eq!(upload.body, bytes);
+
+        let auth = auth_note(&upload);
+        assert_eq!(auth["kind"], 24242);
+        assert_eq!(auth["pubkey"], kp.pubkey.hex());
+        let tags = auth["tags"].as_array().unwrap();
+        assert!(tags.contains(&serde_json::json!(["t", "upload"])));
+        assert!(tags.contains(&serde_json::json!(["x", hash])));
+
+        let mirrored = mirrors.recv().unwrap();
+        assert_eq!(mirrored.request_line, "PUT /mirror HTTP/1.1");
+        let body: serde_json::Value = serde_json::from_slice(&mirrored.body).unwrap();
+        assert_eq!(body["url"], uploaded.url);
+    }
+
+    #[test]
+    fn test_blossom_rejected() {
+        let (server, requests) = stand_in_server(
+            "401 Unauthorized",
+            "X-Reason: pubkey not allowed\r\n",
+            "{}".to_owned(),
+        );
+        let media_path = MediaPath::new(PathBuf::from("cat.png")).unwrap();
+        let kp = FullKeypair::generate();
+
+        let res = internal_blossom_upload(
+            kp.secret_key.secret_bytes(),
+            server,
+            None,
+            media_path,
+            vec![1, 2, 3],
+        )
+        .block_and_take();
+
+        let err = res.unwrap_err().to_string();
+        assert!(err.contains("pubkey not allowed"), "{err}");
+
+        let req = requests.recv().unwrap();
+        assert_eq!(req.request_line, "PUT /upload HTTP/1.1");
+    }
+}
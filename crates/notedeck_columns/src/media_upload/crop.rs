@@ -0,0 +1,107 @@
+use std::path::Path;
+
+use egui::{pos2, vec2, Rect, Vec2};
+use image::{imageops::FilterType, ImageFormat};
+use uuid::Uuid;
+
+use super::MediaPath;
+use crate::Error;
+
+/// Which part of an image to keep
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crop {
+    /// Width over height of the part we keep
+    pub aspect: f32,
+    /// 1.0 keeps as much of the image as the aspect ratio allows
+    pub zoom: f32,
+    /// Where the middle of the crop is, as a fraction of the image size
+    pub center: Vec2,
+}
+
+impl Crop {
+    pub fn new(aspect: f32) -> Self {
+        Self {
+            aspect,
+            zoom: 1.0,
+            center: vec2(0.5, 0.5),
+        }
+    }
+
+    /// The part of a `width` x `height` image we keep, in pixels. It never
+    /// goes past the edges.
+    pub fn rect(&self, width: f32, height: f32) -> Rect {
+        let (w, h) = if width / height > self.aspect {
+            (height * self.aspect, height)
+        } else {
+            (width, width / self.aspect)
+        };
+
+        let zoom = self.zoom.max(1.0);
+        let (w, h) = (w / zoom, h / zoom);
+
+        let x = (self.center.x * width).clamp(w / 2.0, width - w / 2.0);
+        let y = (self.center.y * height).clamp(h / 2.0, height - h / 2.0);
+
+        Rect::from_center_size(pos2(x, y), vec2(w, h))
+    }
+}
+
+/// Crop the image at `path`, scale it down to `max_width` and save it
+/// somewhere we can upload it from
+pub fn crop_and_save(path: &Path, crop: &Crop, max_width: u32) -> Result<MediaPath, Error> {
+    let img = image::open(path)
+        .map_err(|e| Error::Generic(format!("could not open {}: {e}", path.display())))?;
+
+    let rect = crop.rect(img.width() as f32, img.height() as f32);
+    let mut cropped = img.crop_imm(
+        rect.min.x.round() as u32,
+        rect.min.y.round() as u32,
+        (rect.width().round() as u32).max(1),
+        (rect.height().round() as u32).max(1),
+    );
+
+    if cropped.width() > max_width {
+        let height = (max_width as f32 / crop.aspect).round() as u32;
+        cropped = cropped.resize_exact(max_width, height.max(1), FilterType::Lanczos3);
+    }
+
+    let path = std::env::temp_dir().join(format!("notedeck-crop-{}.png", Uuid::new_v4()));
+    cropped
+        .save_with_format(&path, ImageFormat::Png)
+        .map_err(|e| Error::Generic(format!("could not save cropped image: {e}")))?;
+
+    MediaPath::new(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crop_rect() {
+        // a square out of a wide image, from the middle
+        let crop = Crop::new(1.0);
+        assert_eq!(
+            crop.rect(400.0, 200.0),
+            Rect::from_min_size(pos2(100.0, 0.0), vec2(200.0, 200.0))
+        );
+
+        // zoomed in and pushed past the edge, it stays inside
+        let crop = Crop {
+            aspect: 1.0,
+            zoom: 2.0,
+            center: vec2(1.0, 0.0),
+        };
+        assert_eq!(
+            crop.rect(400.0, 200.0),
+            Rect::from_min_size(pos2(300.0, 0.0), vec2(100.0, 100.0))
+        );
+
+        // a banner out of a tall image
+        let crop = Crop::new(3.0);
+        assert_eq!(
+            crop.rect(300.0, 600.0),
+            Rect::from_min_size(pos2(0.0, 250.0), vec2(300.0, 100.0))
+        );
+    }
+}
@@ -1,7 +1,12 @@
-use std::path::PathBuf;
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
 
 use base64::{prelude::BASE64_URL_SAFE, Engine};
 use ehttp::Request;
+use image::{codecs::jpeg::JpegEncoder, DynamicImage, ImageDecoder, ImageFormat, ImageReader};
 use nostrdb::{Note, NoteBuilder};
 use notedeck::SupportedMimeType;
 use poll_promise::Promise;
@@ -10,10 +15,36 @@ use url::Url;
 
 use crate::Error;
 use notedeck_ui::images::fetch_binary_from_disk;
+use uuid::Uuid;
+
+mod blossom;
+mod crop;
+
+pub use blossom::BlossomServer;
+pub use crop::{crop_and_save, Crop};
 
 pub const NOSTR_BUILD_URL: fn() -> Url = || Url::parse("http://nostr.build").unwrap();
 const NIP96_WELL_KNOWN: &str = ".well-known/nostr/nip96.json";
 
+/// Quality we re-encode jpegs at
+const JPEG_QUALITY: u8 = 90;
+
+/// Where we upload when the account has no media server list, nostr.build
+/// until they pick another one
+static DEFAULT_UPLOAD_SERVER: RwLock<Option<Url>> = RwLock::new(None);
+
+pub fn set_default_upload_server(url: Url) {
+    *DEFAULT_UPLOAD_SERVER.write().unwrap() = Some(url);
+}
+
+pub fn default_upload_server() -> Url {
+    DEFAULT_UPLOAD_SERVER
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(NOSTR_BUILD_URL)
+}
+
 fn get_upload_url(nip96_url: Url) -> Promise<Result<String, Error>> {
     let request = Request::get(nip96_url);
     let (sender, promise) = Promise::new();
@@ -69,10 +100,6 @@ fn get_upload_url_from_provider(mut provider_url: Url) -> Promise<Result<String,
     get_upload_url(provider_url)
 }
 
-pub fn get_nostr_build_upload_url() -> Promise<Result<String, Error>> {
-    get_upload_url_from_provider(NOSTR_BUILD_URL())
-}
-
 fn create_nip98_note(seckey: &[u8; 32], upload_url: String, payload_hash: String) -> Note {
     NoteBuilder::new()
         .kind(27235)
@@ -122,7 +149,7 @@ fn create_nip96_request(
     }
 }
 
-fn sha256_hex(contents: &Vec<u8>) -> String {
+fn sha256_hex(contents: &[u8]) -> String {
     let mut hasher = Sha256::new();
     hasher.update(contents);
     let hash = hasher.finalize();
@@ -134,42 +161,221 @@ pub fn nip96_upload(
     upload_url: String,
     media_path: MediaPath,
 ) -> Promise<Result<Nip94Event, Error>> {
-    let bytes_res = fetch_binary_from_disk(media_path.full_path.clone());
-
-    let file_bytes = match bytes_res {
+    let file_bytes = match read_media(&media_path) {
         Ok(bytes) => bytes,
-        Err(e) => {
-            return Promise::from_ready(Err(Error::Generic(format!(
-                "could not read contents of file to upload: {e}"
-            ))))
-        }
+        Err(e) => return Promise::from_ready(Err(e)),
     };
 
     internal_nip96_upload(seckey, upload_url, media_path, file_bytes)
 }
 
-pub fn nostrbuild_nip96_upload(
+/// Read a file we're about to upload, without its metadata
+fn read_media(media_path: &MediaPath) -> Result<Vec<u8>, Error> {
+    let bytes = fetch_binary_from_disk(media_path.full_path.clone())
+        .map_err(|e| Error::Generic(format!("could not read contents of file to upload: {e}")))?;
+
+    strip_metadata(media_path.media_type.to_mime(), bytes)
+}
+
+/// Re-encode an image so that none of its metadata (EXIF, GPS location,
+/// camera details) leaves the device. The orientation is applied to the
+/// pixels first since we drop the tag that carried it. Anything we can't
+/// re-encode without losing something, like animated gifs, is left alone.
+fn strip_metadata(mime: &str, bytes: Vec<u8>) -> Result<Vec<u8>, Error> {
+    let Some(format) = ImageFormat::from_mime_type(mime)
+        .filter(|f| matches!(f, ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP))
+    else {
+        return Ok(bytes);
+    };
+
+    let to_err = |e: image::ImageError| Error::Generic(format!("could not re-encode image: {e}"));
+
+    let mut decoder = ImageReader::with_format(Cursor::new(&bytes), format)
+        .into_decoder()
+        .map_err(to_err)?;
+    let orientation = decoder.orientation().map_err(to_err)?;
+    let mut img = DynamicImage::from_decoder(decoder).map_err(to_err)?;
+    img.apply_orientation(orientation);
+
+    let mut out = Cursor::new(Vec::with_capacity(bytes.len()));
+    match format {
+        ImageFormat::Jpeg => {
+            let encoder = JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY);
+            DynamicImage::ImageRgb8(img.to_rgb8())
+                .write_with_encoder(encoder)
+                .map_err(to_err)?;
+        }
+        ImageFormat::WebP => DynamicImage::ImageRgba8(img.to_rgba8())
+            .write_to(&mut out, format)
+            .map_err(to_err)?,
+        _ => img.write_to(&mut out, format).map_err(to_err)?,
+    }
+
+    Ok(out.into_inner())
+}
+
+/// What we can tell about an image ourselves, for servers that don't
+/// tell us its size or blurhash
+#[derive(Debug, Default)]
+struct MediaDescription {
+    dimensions: Option<(u32, u32)>,
+    blurhash: Option<String>,
+}
+
+impl MediaDescription {
+    /// Blurhashes are made from a tiny version of the image
+    const BLURHASH_SIZE: u32 = 32;
+
+    fn describe(mime: &str, bytes: &[u8]) -> Self {
+        if !mime.starts_with("image/") {
+            return Self::default();
+        }
+
+        let img = match image::load_from_memory(bytes) {
+            Ok(img) => img,
+            Err(e) => {
+                tracing::warn!("could not describe {mime} upload: {e}");
+                return Self::default();
+            }
+        };
+
+        let small = img
+            .thumbnail(Self::BLURHASH_SIZE, Self::BLURHASH_SIZE)
+            .to_rgba8();
+        let (num_x, num_y) = if img.width() >= img.height() {
+            (4, 3)
+        } else {
+            (3, 4)
+        };
+        let blurhash = notedeck::blurhash::encode(
+            num_x,
+            num_y,
+            small.width() as usize,
+            small.height() as usize,
+            small.as_raw(),
+        );
+
+        Self {
+            dimensions: Some((img.width(), img.height())),
+            blurhash: Some(blurhash),
+        }
+    }
+
+    /// Fill in whatever the server left out
+    fn fill(self, mut ev: Nip94Event) -> Nip94Event {
+        ev.dimensions = ev.dimensions.or(self.dimensions);
+        ev.blurhash = ev.blurhash.or(self.blurhash);
+        ev
+    }
+}
+
+/// An image from the clipboard
+pub struct PastedImage {
+    pub width: usize,
+    pub height: usize,
+    pub rgba: Vec<u8>,
+}
+
+/// Upload an image from the clipboard. It's encoded to a temporary file
+/// off the UI thread, and the file is removed once the upload is done,
+/// whether it worked or not.
+pub fn upload_pasted_image(
+    media_servers: Vec<String>,
     seckey: [u8; 32],
-    media_path: MediaPath,
+    image: PastedImage,
 ) -> Promise<Result<Nip94Event, Error>> {
     let (sender, promise) = Promise::new();
     std::thread::spawn(move || {
-        let upload_url = match get_nostr_build_upload_url().block_and_take() {
-            Ok(url) => url,
-            Err(e) => {
-                sender.send(Err(Error::Generic(format!(
-                    "could not get nostrbuild upload url: {e}"
-                ))));
-                return;
+        let res = save_pasted_image(image).and_then(|media_path| {
+            let path = media_path.full_path().to_path_buf();
+            let res = upload_backend(&media_servers)
+                .upload(seckey, media_path)
+                .block_and_take();
+
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::error!("could not remove {}: {e}", path.display());
             }
-        };
 
-        let res = nip96_upload(seckey, upload_url, media_path).block_and_take();
+            res
+        });
         sender.send(res);
     });
     promise
 }
 
+fn save_pasted_image(pasted: PastedImage) -> Result<MediaPath, Error> {
+    let img = image::RgbaImage::from_raw(pasted.width as u32, pasted.height as u32, pasted.rgba)
+        .ok_or_else(|| Error::Generic("pasted image has the wrong size".to_owned()))?;
+
+    let path = std::env::temp_dir().join(format!("notedeck-paste-{}.png", Uuid::new_v4()));
+    img.save_with_format(&path, ImageFormat::Png)
+        .map_err(|e| Error::Generic(format!("could not save pasted image: {e}")))?;
+
+    MediaPath::new(path)
+}
+
+/// Somewhere we can upload media to
+pub trait UploadBackend {
+    fn upload(&self, seckey: [u8; 32], media_path: MediaPath)
+        -> Promise<Result<Nip94Event, Error>>;
+}
+
+/// Any server that implements NIP-96
+pub struct Nip96Server {
+    provider_url: Url,
+}
+
+impl Nip96Server {
+    pub fn new(provider_url: Url) -> Self {
+        Self { provider_url }
+    }
+}
+
+impl UploadBackend for Nip96Server {
+    fn upload(
+        &self,
+        seckey: [u8; 32],
+        media_path: MediaPath,
+    ) -> Promise<Result<Nip94Event, Error>> {
+        let provider_url = self.provider_url.clone();
+        let (sender, promise) = Promise::new();
+        std::thread::spawn(move || {
+            let upload_url =
+                match get_upload_url_from_provider(provider_url.clone()).block_and_take() {
+                    Ok(url) => url,
+                    Err(e) => {
+                        sender.send(Err(Error::Generic(format!(
+                            "could not get upload url from {provider_url}: {e}"
+                        ))));
+                        return;
+                    }
+                };
+
+            let res = nip96_upload(seckey, upload_url, media_path).block_and_take();
+            sender.send(res);
+        });
+        promise
+    }
+}
+
+/// Pick where to upload to from the user's kind 10063 server list. The
+/// first server gets the upload and the second one mirrors it. Users
+/// without a list upload to the [`default_upload_server`].
+pub fn upload_backend(media_servers: &[String]) -> Box<dyn UploadBackend> {
+    let mut servers = media_servers.iter().filter_map(|s| match Url::parse(s) {
+        Ok(url) => Some(url),
+        Err(e) => {
+            tracing::error!("invalid media server {s}: {e}");
+            None
+        }
+    });
+
+    match servers.next() {
+        Some(server) => Box::new(BlossomServer::new(server).mirror(servers.next())),
+        None => Box::new(Nip96Server::new(default_upload_server())),
+    }
+}
+
 fn internal_nip96_upload(
     seckey: [u8; 32],
     upload_url: String,
@@ -184,6 +390,7 @@ fn internal_nip96_upload(
         Err(e) => return Promise::from_ready(Err(Error::Generic(e.to_string()))),
     };
 
+    let description = MediaDescription::describe(media_path.media_type.to_mime(), &file_contents);
     let request = create_nip96_request(&upload_url, media_path, file_contents, &nip98_base64);
 
     let (sender, promise) = Promise::new();
@@ -206,7 +413,7 @@ fn internal_nip96_upload(
             Err(e) => Err(Error::Generic(e)),
         };
 
-        sender.send(maybe_uploaded_media);
+        sender.send(maybe_uploaded_media.map(|ev| description.fill(ev)));
     });
 
     promise
@@ -259,9 +466,13 @@ impl MediaPath {
             )))
         }
     }
+
+    pub fn full_path(&self) -> &Path {
+        &self.full_path
+    }
 }
 
-#[derive(Clone, Debug, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Nip94Event {
     pub url: String,
     pub ox: Option<String>,
@@ -270,6 +481,10 @@ pub struct Nip94Event {
     pub dimensions: Option<(u32, u32)>,
     pub blurhash: Option<String>,
     pub thumb: Option<String>,
+
+    /// Describes the media for people who can't see it
+    #[serde(default)]
+    pub alt: Option<String>,
     pub content: String,
 }
 
@@ -283,6 +498,7 @@ impl Nip94Event {
             dimensions: Some((width, height)),
             blurhash: None,
             thumb: None,
+            alt: None,
             content: String::new(),
         }
     }
@@ -295,6 +511,7 @@ const M: &str = "m";
 const DIM: &str = "dim";
 const BLURHASH: &str = "blurhash";
 const THUMB: &str = "thumb";
+const ALT: &str = "alt";
 
 impl Nip94Event {
     fn from_tags_and_content(
@@ -308,6 +525,7 @@ impl Nip94Event {
         let mut dimensions = None;
         let mut blurhash = None;
         let mut thumb = None;
+        let mut alt = None;
 
         for tag in tags {
             match tag.as_slice() {
@@ -324,6 +542,7 @@ impl Nip94Event {
                 }
                 [key, value] if key == BLURHASH => blurhash = Some(value.to_string()),
                 [key, value] if key == THUMB => thumb = Some(value.to_string()),
+                [key, value] if key == ALT => alt = Some(value.to_string()),
                 _ => {}
             }
         }
@@ -336,6 +555,7 @@ impl Nip94Event {
             dimensions,
             blurhash,
             thumb,
+            alt,
             content,
         })
     }
@@ -348,10 +568,10 @@ mod tests {
     use enostr::FullKeypair;
 
     use crate::media_upload::{
-        get_upload_url_from_provider, nostrbuild_nip96_upload, MediaPath, NOSTR_BUILD_URL,
+        get_upload_url_from_provider, MediaPath, Nip96Server, UploadBackend, NOSTR_BUILD_URL,
     };
 
-    use super::internal_nip96_upload;
+    use super::{internal_nip96_upload, strip_metadata, MediaDescription, Nip94Event};
 
     #[test]
     fn test_nostrbuild_upload_url() {
@@ -368,7 +588,7 @@ mod tests {
         // just a random image to test image upload
         let file_path = PathBuf::from_str("../../../assets/damus_rounded_80.png").unwrap();
         let media_path = MediaPath::new(file_path).unwrap();
-        let img_bytes = include_bytes!("../../../assets/damus_rounded_80.png");
+        let img_bytes = include_bytes!("../../../../assets/damus_rounded_80.png");
         let promise = get_upload_url_from_provider(NOSTR_BUILD_URL());
         let kp = FullKeypair::generate();
         println!("Using pubkey: {:?}", kp.pubkey);
@@ -398,9 +618,61 @@ mod tests {
         let kp = FullKeypair::generate();
         println!("Using pubkey: {:?}", kp.pubkey);
 
-        let promise = nostrbuild_nip96_upload(kp.secret_key.secret_bytes(), media_path);
+        let promise =
+            Nip96Server::new(NOSTR_BUILD_URL()).upload(kp.secret_key.secret_bytes(), media_path);
 
         let out = promise.block_and_take();
         assert!(out.is_ok());
     }
+
+    #[test]
+    fn test_strip_metadata() {
+        let img = image::RgbImage::from_pixel(4, 2, image::Rgb([200, 10, 10]));
+        let mut jpeg = std::io::Cursor::new(Vec::new());
+        img.write_to(&mut jpeg, image::ImageFormat::Jpeg).unwrap();
+        let jpeg = jpeg.into_inner();
+
+        // an APP1 segment right after SOI, like a phone camera would write
+        let exif = b"Exif\0\0MM\0*\0\0\0\x08\0\0GPS 37.7749 N";
+        let mut tagged = jpeg[..2].to_vec();
+        tagged.extend([0xFF, 0xE1]);
+        tagged.extend(((exif.len() + 2) as u16).to_be_bytes());
+        tagged.extend(exif);
+        tagged.extend(&jpeg[2..]);
+
+        let stripped = strip_metadata("image/jpeg", tagged).unwrap();
+        assert!(!stripped.windows(4).any(|w| w == b"Exif"));
+
+        let decoded = image::load_from_memory(&stripped).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (4, 2));
+
+        // gifs might be animated, so they go up as they are
+        assert_eq!(
+            strip_metadata("image/gif", vec![1, 2, 3]).unwrap(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_describe_media() {
+        let img = image::RgbImage::from_pixel(120, 80, image::Rgb([10, 200, 10]));
+        let mut png = std::io::Cursor::new(Vec::new());
+        img.write_to(&mut png, image::ImageFormat::Png).unwrap();
+
+        let description = MediaDescription::describe("image/png", png.get_ref());
+        assert_eq!(description.dimensions, Some((120, 80)));
+        let blurhash = description.blurhash.clone().unwrap();
+        assert!(notedeck::blurhash::is_valid(&blurhash));
+
+        // what the server tells us wins
+        let mut ev = Nip94Event::new("https://example.com/a.png".to_owned(), 12, 8);
+        ev.blurhash = None;
+        let ev = description.fill(ev);
+        assert_eq!(ev.dimensions, Some((12, 8)));
+        assert_eq!(ev.blurhash, Some(blurhash));
+
+        assert!(MediaDescription::describe("video/mp4", &[])
+            .blurhash
+            .is_none());
+    }
 }
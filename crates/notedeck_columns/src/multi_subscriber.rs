@@ -10,6 +10,9 @@ pub struct MultiSubscriber {
     pub remote_subid: Option<String>,
     local_subscribers: u32,
     remote_subscribers: u32,
+    /// Whether we mirror the subscription on all of our relays. Some
+    /// timelines, like search, manage their remote requests themselves
+    remote: bool,
 }
 
 impl MultiSubscriber {
@@ -28,9 +31,16 @@ impl MultiSubscriber {
             remote_subid: None,
             local_subscribers: 0,
             remote_subscribers: 0,
+            remote: true,
         }
     }
 
+    /// Only subscribe to nostrdb, don't send our filters to relays
+    pub fn local_only(mut self) -> Self {
+        self.remote = false;
+        self
+    }
+
     fn unsubscribe_remote(&mut self, ndb: &Ndb, pool: &mut RelayPool) {
         let remote_subid = if let Some(remote_subid) = &self.remote_subid {
             remote_subid
@@ -78,7 +88,9 @@ impl MultiSubscriber {
         if self.local_subscribers == 0 && self.remote_subscribers == 0 {
             self.info_log(ndb, "Locally unsubscribing");
             self.unsubscribe_local(ndb);
-            self.unsubscribe_remote(ndb, pool);
+            if self.remote {
+                self.unsubscribe_remote(ndb, pool);
+            }
             self.local_subscribers = 0;
             self.remote_subscribers = 0;
             true
@@ -109,7 +121,7 @@ impl MultiSubscriber {
         self.local_subscribers += 1;
         self.remote_subscribers += 1;
 
-        if self.remote_subscribers == 1 {
+        if self.remote && self.remote_subscribers == 1 {
             if self.remote_subid.is_some() {
                 self.err_log(
                     ndb,
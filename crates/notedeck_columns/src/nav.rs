@@ -4,20 +4,23 @@ use crate::{
     column::ColumnsAction,
     deck_state::DeckState,
     decks::{Deck, DecksAction, DecksCache},
-    profile::{ProfileAction, SaveProfileChanges},
+    media_upload,
+    profile::{get_profile_note, ProfileAction, SaveProfileChanges},
     profile_state::ProfileState,
     relay_pool_manager::RelayPoolManager,
     route::Route,
-    timeline::{route::render_timeline_route, TimelineCache},
+    timeline::{route::render_timeline_route, TimelineCache, TimelineKind},
     ui::{
         self,
         add_column::render_add_column_routes,
         column::NavTitle,
         configure_deck::ConfigureDeckView,
+        drafts::{DraftsAction, DraftsView},
         edit_deck::{EditDeckResponse, EditDeckView},
         note::{NewPostAction, PostAction, PostType},
         profile::EditProfileView,
-        search::{FocusState, SearchView},
+        scheduled::{ScheduledAction, ScheduledView},
+        search::{FocusState, SearchAction, SearchRelays, SearchView},
         support::SupportView,
         wallet::{WalletAction, WalletView},
         RelayView,
@@ -28,7 +31,6 @@ use crate::{
 use egui_nav::{Nav, NavAction, NavResponse, NavUiType};
 use nostrdb::Transaction;
 use notedeck::{AccountsAction, AppContext, NoteAction, NoteContext, WalletState};
-use notedeck_ui::View;
 use tracing::error;
 
 #[allow(clippy::enum_variant_names)]
@@ -70,10 +72,10 @@ impl SwitchingAction {
                 }
                 AccountsAction::Remove(index) => ctx.accounts.remove_account(*index),
             },
-            SwitchingAction::Columns(columns_action) => match *columns_action {
+            SwitchingAction::Columns(columns_action) => match columns_action {
                 ColumnsAction::Remove(index) => {
                     let kinds_to_pop =
-                        get_active_columns_mut(ctx.accounts, decks_cache).delete_column(index);
+                        get_active_columns_mut(ctx.accounts, decks_cache).delete_column(*index);
                     for kind in &kinds_to_pop {
                         if let Err(err) = timeline_cache.pop(kind, ctx.ndb, ctx.pool) {
                             error!("error popping timeline: {err}");
@@ -82,7 +84,17 @@ impl SwitchingAction {
                 }
 
                 ColumnsAction::Switch(from, to) => {
-                    get_active_columns_mut(ctx.accounts, decks_cache).move_col(from, to);
+                    get_active_columns_mut(ctx.accounts, decks_cache).move_col(*from, *to);
+                }
+
+                ColumnsAction::Rename(index, title) => {
+                    let top = get_active_columns_mut(ctx.accounts, decks_cache)
+                        .column_mut(*index)
+                        .router_mut()
+                        .top_mut();
+                    if let Route::Timeline(TimelineKind::Generic(filter)) = top {
+                        filter.title = title.clone();
+                    }
                 }
             },
             SwitchingAction::Decks(decks_action) => match *decks_action {
@@ -171,7 +183,13 @@ impl RenderNavResponse {
 
                 RenderNavAction::PostAction(new_post_action) => {
                     let txn = Transaction::new(ctx.ndb).expect("txn");
-                    match new_post_action.execute(ctx.ndb, &txn, ctx.pool, &mut app.drafts) {
+                    match new_post_action.execute(
+                        ctx.ndb,
+                        &txn,
+                        ctx.pool,
+                        &mut app.drafts,
+                        &mut app.scheduled,
+                    ) {
                         Err(err) => tracing::error!("Error executing post action: {err}"),
                         Ok(_) => tracing::debug!("Post action executed"),
                     }
@@ -274,17 +292,22 @@ fn render_nav_body(
     col: usize,
     inner_rect: egui::Rect,
 ) -> Option<RenderNavAction> {
+    let bookmarks = ctx.accounts.bookmarks();
     let mut note_context = NoteContext {
         ndb: ctx.ndb,
         img_cache: ctx.img_cache,
         note_cache: ctx.note_cache,
         zaps: ctx.zaps,
+        nip05: ctx.nip05,
+        polls: ctx.polls,
         pool: ctx.pool,
+        bookmarks: &bookmarks,
     };
     match top {
         Route::Timeline(kind) => render_timeline_route(
             ctx.unknown_ids,
             &mut app.timeline_cache,
+            &mut app.followers,
             ctx.accounts,
             kind,
             col,
@@ -313,8 +336,101 @@ fn render_nav_body(
         }
 
         Route::Relays => {
+            egui::Frame::new()
+                .inner_margin(egui::Margin::symmetric(10, 0))
+                .show(ui, |ui| {
+                    if notedeck_ui::video::FFMPEG_SUPPORTED {
+                        let mut ffmpeg = notedeck_ui::video::is_ffmpeg_enabled();
+                        if ui
+                            .checkbox(&mut ffmpeg, "Play video and audio with ffmpeg")
+                            .on_hover_text(
+                                "Runs ffmpeg, ffprobe and ffplay installed on your system",
+                            )
+                            .changed()
+                        {
+                            notedeck_ui::video::set_ffmpeg_enabled(ffmpeg);
+                            app.ffmpeg.save(ffmpeg);
+                        }
+                        if ffmpeg {
+                            ui.weak(notedeck_ui::video::ffmpeg_status(ui.ctx()));
+                        }
+                    }
+
+                    let mut autoplay = app.note_options.has_autoplay_media();
+                    if ui
+                        .checkbox(&mut autoplay, "Autoplay videos (muted)")
+                        .changed()
+                    {
+                        app.note_options.set_autoplay_media(autoplay);
+                        app.autoplay.save(autoplay);
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label("Upload media to").on_hover_text(
+                            "Used when your account doesn't have a media server list",
+                        );
+
+                        let id = ui.id().with("upload_server");
+                        let text =
+                            app.view_state.id_string_map.entry(id).or_insert_with(|| {
+                                media_upload::default_upload_server().to_string()
+                            });
+                        if ui.text_edit_singleline(text).lost_focus() {
+                            match url::Url::parse(text.trim()) {
+                                Ok(server) => {
+                                    app.upload_server.save(server.as_str());
+                                    media_upload::set_default_upload_server(server);
+                                }
+                                Err(e) => error!("invalid upload server {text}: {e}"),
+                            }
+                            *text = media_upload::default_upload_server().to_string();
+                        }
+                    });
+                });
+
             let manager = RelayPoolManager::new(ctx.pool);
-            RelayView::new(ctx.accounts, manager, &mut app.view_state.id_string_map).ui(ui);
+            let relay_to_browse =
+                RelayView::new(ctx.accounts, manager, &mut app.view_state.id_string_map).show(ui);
+
+            if let Some(kind) = relay_to_browse.and_then(|url| TimelineKind::relay(&url)) {
+                let txn = Transaction::new(ctx.ndb).expect("txn");
+                let is_new = !app.timeline_cache.timelines.contains_key(&kind);
+                if let Some(result) = get_active_columns_mut(ctx.accounts, &mut app.decks_cache)
+                    .add_new_timeline_column(
+                        &mut app.timeline_cache,
+                        &txn,
+                        ctx.ndb,
+                        ctx.note_cache,
+                        ctx.pool,
+                        &kind,
+                    )
+                {
+                    result.process(
+                        ctx.ndb,
+                        ctx.note_cache,
+                        &txn,
+                        &mut app.timeline_cache,
+                        ctx.unknown_ids,
+                    );
+                }
+
+                // relay feeds only subscribe on their relay, which we
+                // might already be connected to
+                if let (true, Some(timeline)) =
+                    (is_new, app.timeline_cache.timelines.get_mut(&kind))
+                {
+                    for relay in &mut ctx.pool.relays {
+                        crate::timeline::send_initial_timeline_filter(
+                            ctx.ndb,
+                            app.since_optimize,
+                            &mut app.subscriptions,
+                            relay,
+                            timeline,
+                        );
+                    }
+                }
+            }
+
             None
         }
 
@@ -337,7 +453,7 @@ fn render_nav_body(
             let poster = ctx.accounts.selected_or_first_nsec()?;
 
             let action = {
-                let draft = app.drafts.reply_mut(note.id());
+                let draft = app.drafts.account_mut(poster.pubkey).reply_mut(note.id());
 
                 let response = egui::ScrollArea::vertical()
                     .show(ui, |ui| {
@@ -350,6 +466,7 @@ fn render_nav_body(
                             app.note_options,
                         )
                         .id_source(id)
+                        .media_servers(ctx.accounts.selected_media_servers())
                         .show(ui)
                     })
                     .inner;
@@ -373,7 +490,7 @@ fn render_nav_body(
             let id = egui::Id::new(("post", col, note.key().unwrap()));
 
             let poster = ctx.accounts.selected_or_first_nsec()?;
-            let draft = app.drafts.quote_mut(note.id());
+            let draft = app.drafts.account_mut(poster.pubkey).quote_mut(note.id());
 
             let response = egui::ScrollArea::vertical()
                 .show(ui, |ui| {
@@ -386,6 +503,7 @@ fn render_nav_body(
                         app.note_options,
                     )
                     .id_source(id)
+                    .media_servers(ctx.accounts.selected_media_servers())
                     .show(ui)
                 })
                 .inner;
@@ -395,7 +513,39 @@ fn render_nav_body(
 
         Route::ComposeNote => {
             let kp = ctx.accounts.get_selected_account()?.key.to_full()?;
-            let draft = app.drafts.compose_mut();
+            let account_drafts = app.drafts.account_mut(kp.pubkey);
+
+            // other unsent drafts are one click away
+            let num_unsent = account_drafts
+                .unsent()
+                .iter()
+                .filter(|(post_type, _)| *post_type != PostType::New)
+                .count();
+            let num_scheduled = app.scheduled.for_account(kp.pubkey).count();
+            let mut link_route = None;
+            if num_unsent > 0 || num_scheduled > 0 {
+                ui.horizontal(|ui| {
+                    if num_unsent > 0 && ui.link(format!("Drafts ({num_unsent})")).clicked() {
+                        link_route = Some(Route::Drafts);
+                    }
+
+                    if num_scheduled > 0
+                        && ui.link(format!("Scheduled ({num_scheduled})")).clicked()
+                    {
+                        link_route = Some(Route::Scheduled);
+                    }
+                });
+            }
+
+            if let Some(route) = link_route {
+                get_active_columns_mut(ctx.accounts, &mut app.decks_cache)
+                    .column_mut(col)
+                    .router_mut()
+                    .route_to(route);
+                return None;
+            }
+
+            let draft = app.drafts.account_mut(kp.pubkey).compose_mut();
 
             let txn = Transaction::new(ctx.ndb).expect("txn");
             let post_response = ui::PostView::new(
@@ -406,6 +556,7 @@ fn render_nav_body(
                 inner_rect,
                 app.note_options,
             )
+            .media_servers(ctx.accounts.selected_media_servers())
             .ui(&txn, ui);
 
             post_response.action.map(Into::into)
@@ -418,7 +569,73 @@ fn render_nav_body(
         }
 
         Route::Support => {
-            SupportView::new(&mut app.support).show(ui);
+            SupportView::new(&mut app.support, ctx.img_cache).show(ui);
+            None
+        }
+
+        Route::Drafts => {
+            let pubkey = *ctx.accounts.selected_account_pubkey()?;
+            let txn = Transaction::new(ctx.ndb).expect("txn");
+            let account_drafts = app.drafts.account_mut(&pubkey);
+
+            let action = DraftsView::new(ctx.ndb, &txn, account_drafts).ui(ui)?;
+            match action {
+                DraftsAction::Resume(post_type) => {
+                    let route = match post_type {
+                        PostType::New => Route::ComposeNote,
+                        PostType::Reply(note_id) => Route::Reply(note_id),
+                        PostType::Quote(note_id) => Route::Quote(note_id),
+                    };
+                    get_active_columns_mut(ctx.accounts, &mut app.decks_cache)
+                        .column_mut(col)
+                        .router_mut()
+                        .route_to(route);
+                }
+
+                DraftsAction::Delete(post_type) => account_drafts.remove(&post_type),
+            }
+
+            None
+        }
+
+        Route::Scheduled => {
+            let pubkey = *ctx.accounts.selected_account_pubkey()?;
+
+            let action = ScheduledView::new(&app.scheduled, &pubkey).ui(ui)?;
+            let (id, publish_at, content) = match action {
+                ScheduledAction::Cancel(id) => {
+                    app.scheduled.remove(id);
+                    return None;
+                }
+                ScheduledAction::SendNow(id) => {
+                    let content = app
+                        .scheduled
+                        .for_account(&pubkey)
+                        .find(|post| post.id == id)?
+                        .content();
+                    (id, crate::scheduled::unix_now(), content)
+                }
+                ScheduledAction::Reschedule {
+                    id,
+                    publish_at,
+                    content,
+                } => (id, publish_at, content),
+            };
+
+            // changing it means signing it again
+            let Some(kp) = ctx.accounts.get_full(pubkey.bytes()) else {
+                error!("can't change scheduled post {id}: no secret key");
+                return None;
+            };
+            let seckey = kp.secret_key.to_secret_bytes();
+            if app
+                .scheduled
+                .reschedule(id, publish_at, &content, &seckey)
+                .is_none()
+            {
+                error!("could not reschedule post {id}");
+            }
+
             None
         }
 
@@ -441,16 +658,62 @@ fn render_nav_body(
                 search_buffer.focus_state = FocusState::ShouldRequestFocus;
             }
 
-            SearchView::new(
+            let search_action = SearchView::new(
                 &txn,
                 &ctx.accounts.mutefun(),
                 app.note_options,
                 search_buffer,
                 &mut note_context,
                 &ctx.accounts.get_selected_account().map(|a| (&a.key).into()),
+                SearchRelays {
+                    id,
+                    relay_info: &app.relay_info,
+                    subscriptions: &mut app.subscriptions,
+                },
             )
-            .show(ui, ctx.clipboard)
-            .map(RenderNavAction::NoteAction)
+            .show(ui, ctx.clipboard);
+
+            match search_action? {
+                SearchAction::Note(note_action) => Some(RenderNavAction::NoteAction(note_action)),
+
+                SearchAction::Follow(pubkey) => {
+                    let kp = ctx
+                        .accounts
+                        .selected_account_pubkey()
+                        .and_then(|pk| ctx.accounts.get_full(pk.bytes()));
+                    let Some(kp) = kp else {
+                        error!("can't follow without a full keypair");
+                        return None;
+                    };
+                    Some(RenderNavAction::ProfileAction(ProfileAction::Follow(
+                        kp.to_full(),
+                        pubkey,
+                    )))
+                }
+
+                SearchAction::AddColumn(pubkey) => {
+                    let kind = TimelineKind::profile(pubkey);
+                    if let Some(result) = get_active_columns_mut(ctx.accounts, &mut app.decks_cache)
+                        .add_new_timeline_column(
+                            &mut app.timeline_cache,
+                            &txn,
+                            ctx.ndb,
+                            ctx.note_cache,
+                            ctx.pool,
+                            &kind,
+                        )
+                    {
+                        result.process(
+                            ctx.ndb,
+                            ctx.note_cache,
+                            &txn,
+                            &mut app.timeline_cache,
+                            ctx.unknown_ids,
+                        );
+                    }
+                    None
+                }
+            }
         }
 
         Route::NewDeck => {
@@ -520,13 +783,25 @@ fn render_nav_body(
                     .entry(*kp.pubkey)
                     .or_insert_with(|| {
                         let txn = Transaction::new(ctx.ndb).expect("txn");
-                        if let Ok(record) = ctx.ndb.get_profile_by_pubkey(&txn, kp.pubkey.bytes()) {
+                        if let Some(state) = get_profile_note(ctx.ndb, &txn, kp.pubkey)
+                            .and_then(|note| ProfileState::from_metadata(note.content()))
+                        {
+                            state
+                        } else if let Ok(record) =
+                            ctx.ndb.get_profile_by_pubkey(&txn, kp.pubkey.bytes())
+                        {
                             ProfileState::from_profile(&record)
                         } else {
                             ProfileState::default()
                         }
                     });
-                if EditProfileView::new(state, ctx.img_cache).ui(ui) {
+                if EditProfileView::new(state, ctx.img_cache)
+                    .uploader(
+                        kp.secret_key.to_secret_bytes(),
+                        ctx.accounts.selected_media_servers(),
+                    )
+                    .ui(ui)
+                {
                     if let Some(taken_state) =
                         app.view_state.pubkey_to_profile_state.remove(kp.pubkey)
                     {
@@ -0,0 +1,79 @@
+use std::collections::{HashMap, HashSet};
+
+use enostr::{ClientMessage, NoteId, RelayPool, RelayStatus};
+use notedeck::poll::{Poll, Polls};
+
+use crate::subscriptions::{self, SubKind, Subscriptions};
+
+/// A relay subscription for the responses to one poll
+struct PollSub {
+    subid: String,
+
+    /// Relays we sent the REQ to while they were connected
+    sent_to: HashSet<String>,
+}
+
+/// Relay subscriptions for the responses to the polls on screen. They're
+/// sent to the relays the poll asks for, or all of ours if we have none of
+/// those, and closed once the poll goes off screen.
+#[derive(Default)]
+pub struct PollSubs {
+    subs: HashMap<NoteId, PollSub>,
+}
+
+impl PollSubs {
+    pub fn update(&mut self, polls: &Polls, pool: &mut RelayPool, subs: &mut Subscriptions) {
+        let shown: HashMap<&NoteId, &[String]> = polls.shown().collect();
+
+        self.subs.retain(|id, sub| {
+            if shown.contains_key(id) {
+                return true;
+            }
+
+            let msg = ClientMessage::close(sub.subid.clone());
+            for relay in &sub.sent_to {
+                pool.send_to(&msg, relay);
+            }
+            subs.subs.remove(&sub.subid);
+            false
+        });
+
+        let connected: HashSet<String> = pool
+            .relays
+            .iter()
+            .filter(|relay| matches!(relay.status(), RelayStatus::Connected))
+            .filter(|relay| !relay.is_exclusive())
+            .map(|relay| relay.url().to_owned())
+            .collect();
+
+        for (id, poll_relays) in shown {
+            let sub = self.subs.entry(*id).or_insert_with(|| {
+                let subid = subscriptions::new_sub_id();
+                subs.subs.insert(subid.clone(), SubKind::PollResponses(*id));
+                PollSub {
+                    subid,
+                    sent_to: HashSet::new(),
+                }
+            });
+
+            // it goes again to relays that dropped and came back
+            sub.sent_to.retain(|relay| connected.contains(relay));
+
+            let wanted: Vec<&String> = poll_relays
+                .iter()
+                .filter(|relay| pool.has(relay) && !pool.is_exclusive(relay))
+                .collect();
+            let targets = connected
+                .iter()
+                .filter(|relay| wanted.is_empty() || wanted.contains(relay));
+
+            let msg =
+                ClientMessage::req(sub.subid.clone(), vec![Poll::responses_filter(id.bytes())]);
+            for relay in targets {
+                if sub.sent_to.insert(relay.clone()) {
+                    pool.send_to(&msg, relay);
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,10 @@
 use egui::{text::LayoutJob, TextBuffer, TextFormat};
-use enostr::{FullKeypair, Pubkey};
-use nostrdb::{Note, NoteBuilder, NoteReply};
+use enostr::{FullKeypair, NoteId, Pubkey};
+use nostrdb::{Ndb, Note, NoteBuilder, NoteReply, Transaction};
+use notedeck::{
+    poll::{PollOption, PollType, KIND_POLL},
+    relay_hints::{note_relay_hints, pubkey_relay_hints},
+};
 use std::{
     any::TypeId,
     collections::{BTreeMap, HashMap, HashSet},
@@ -9,13 +13,117 @@ use std::{
 };
 use tracing::error;
 
-use crate::media_upload::Nip94Event;
+use crate::{media_upload::Nip94Event, ui::note::PostType};
 
 pub struct NewPost {
     pub content: String,
     pub account: FullKeypair,
     pub media: Vec<Nip94Event>,
     pub mentions: Vec<Pubkey>,
+    /// NIP-36 content warning, with an optional reason
+    pub content_warning: Option<String>,
+    pub relay_hints: RelayHints,
+    /// Backdate or, for scheduled posts, postdate the note
+    pub created_at: Option<u64>,
+    /// Make it a NIP-88 poll, asking the content
+    pub poll: Option<NewPoll>,
+}
+
+pub struct NewPoll {
+    pub options: Vec<PollOption>,
+    pub poll_type: PollType,
+    pub ends_at: Option<u64>,
+}
+
+/// Relays where other clients can find the notes and people a post
+/// references
+#[derive(Default)]
+pub struct RelayHints {
+    notes: HashMap<[u8; 32], Vec<String>>,
+    pubkeys: HashMap<[u8; 32], Vec<String>>,
+    authors: HashMap<[u8; 32], Pubkey>,
+}
+
+impl RelayHints {
+    /// Hints for everything a post of `post_type` mentioning `mentions`
+    /// and `references` will reference
+    pub fn for_post(
+        ndb: &Ndb,
+        txn: &Transaction,
+        post_type: &PostType,
+        mentions: &[Pubkey],
+        references: &[NoteId],
+    ) -> Self {
+        let mut hints = RelayHints::default();
+
+        for mention in mentions {
+            hints.add_pubkey(ndb, txn, mention.bytes());
+        }
+
+        for reference in references {
+            if let Ok(note) = ndb.get_note_by_id(txn, reference.bytes()) {
+                hints.add_note(ndb, txn, &note);
+            }
+        }
+
+        let Some(target) = post_type
+            .target()
+            .and_then(|id| ndb.get_note_by_id(txn, id.bytes()).ok())
+        else {
+            return hints;
+        };
+
+        if let PostType::Reply(_) = post_type {
+            if let Some(root) = NoteReply::new(target.tags()).root() {
+                if let Ok(root) = ndb.get_note_by_id(txn, root.id) {
+                    hints.add_note(ndb, txn, &root);
+                }
+            }
+        }
+        hints.add_note(ndb, txn, &target);
+
+        hints
+    }
+
+    pub fn add_note(&mut self, ndb: &Ndb, txn: &Transaction, note: &Note) {
+        self.notes
+            .insert(*note.id(), note_relay_hints(ndb, txn, note));
+        self.authors.insert(*note.id(), Pubkey::new(*note.pubkey()));
+        self.add_pubkey(ndb, txn, note.pubkey());
+    }
+
+    pub fn add_pubkey(&mut self, ndb: &Ndb, txn: &Transaction, pubkey: &[u8; 32]) {
+        if !self.pubkeys.contains_key(pubkey) {
+            self.pubkeys
+                .insert(*pubkey, pubkey_relay_hints(ndb, txn, pubkey));
+        }
+    }
+
+    pub fn note(&self, id: &[u8; 32]) -> &[String] {
+        self.notes.get(id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn pubkey(&self, pubkey: &[u8; 32]) -> &[String] {
+        self.pubkeys
+            .get(pubkey)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The relay hint that goes in an `e` or `q` tag
+    fn note_tag_hint(&self, id: &[u8; 32]) -> &str {
+        self.note(id).first().map(String::as_str).unwrap_or("")
+    }
+
+    /// `nprofile` if we have relays for it, `npub` otherwise
+    fn profile_entity(&self, pubkey: &Pubkey) -> Option<String> {
+        let relays = self.pubkey(pubkey.bytes());
+        if relays.is_empty() {
+            pubkey.to_bech()
+        } else {
+            pubkey.to_nprofile(relays)
+        }
+    }
 }
 
 fn add_client_tag(builder: NoteBuilder<'_>) -> NoteBuilder<'_> {
@@ -37,28 +145,76 @@ impl NewPost {
             account,
             media,
             mentions,
+            content_warning: None,
+            relay_hints: RelayHints::default(),
+            created_at: None,
+            poll: None,
         }
     }
 
+    pub fn poll(mut self, poll: Option<NewPoll>) -> Self {
+        self.poll = poll;
+        self
+    }
+
+    pub fn created_at(mut self, created_at: u64) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+
+    fn add_created_at<'a>(&self, builder: NoteBuilder<'a>) -> NoteBuilder<'a> {
+        match self.created_at {
+            Some(created_at) => builder.created_at(created_at),
+            None => builder,
+        }
+    }
+
+    pub fn relay_hints(mut self, relay_hints: RelayHints) -> Self {
+        self.relay_hints = relay_hints;
+        self
+    }
+
+    pub fn content_warning(mut self, reason: Option<String>) -> Self {
+        self.content_warning = reason;
+        self
+    }
+
     pub fn to_note(&self, seckey: &[u8; 32]) -> Note {
         let mut content = self.content.clone();
         append_urls(&mut content, &self.media);
 
-        let mut builder = add_client_tag(NoteBuilder::new()).kind(1).content(&content);
+        let kind = if self.poll.is_some() { KIND_POLL } else { 1 };
+        let mut builder = add_client_tag(NoteBuilder::new())
+            .kind(kind)
+            .content(&content);
 
         for hashtag in Self::extract_hashtags(&self.content) {
             builder = builder.start_tag().tag_str("t").tag_str(&hashtag);
         }
 
+        if let Some(poll) = &self.poll {
+            let relays = self.relay_hints.pubkey(self.account.pubkey.bytes());
+            builder = add_poll_tags(builder, poll, relays);
+        }
+
         if !self.media.is_empty() {
             builder = add_imeta_tags(builder, &self.media);
         }
 
         if !self.mentions.is_empty() {
-            builder = add_mention_tags(builder, &self.mentions);
+            builder = add_mention_tags(builder, &self.mentions, &self.relay_hints);
         }
 
-        builder.sign(seckey).build().expect("note should be ok")
+        if let Some(reason) = &self.content_warning {
+            builder = add_content_warning_tag(builder, reason);
+        }
+
+        builder = add_quote_tags(builder, &self.content, None, &self.relay_hints);
+
+        self.add_created_at(builder)
+            .sign(seckey)
+            .build()
+            .expect("note should be ok")
     }
 
     pub fn to_reply(&self, seckey: &[u8; 32], replying_to: &Note) -> Note {
@@ -68,18 +224,19 @@ impl NewPost {
         let builder = add_client_tag(NoteBuilder::new()).kind(1).content(&content);
 
         let nip10 = NoteReply::new(replying_to.tags());
+        let hints = &self.relay_hints;
 
         let mut builder = if let Some(root) = nip10.root() {
             builder
                 .start_tag()
                 .tag_str("e")
                 .tag_str(&hex::encode(root.id))
-                .tag_str("")
+                .tag_str(hints.note_tag_hint(root.id))
                 .tag_str("root")
                 .start_tag()
                 .tag_str("e")
                 .tag_str(&hex::encode(replying_to.id()))
-                .tag_str("")
+                .tag_str(hints.note_tag_hint(replying_to.id()))
                 .tag_str("reply")
                 .sign(seckey)
         } else {
@@ -89,17 +246,14 @@ impl NewPost {
                 .start_tag()
                 .tag_str("e")
                 .tag_str(&hex::encode(replying_to.id()))
-                .tag_str("")
+                .tag_str(hints.note_tag_hint(replying_to.id()))
                 .tag_str("root")
                 .sign(seckey)
         };
 
         let mut seen_p: HashSet<&[u8; 32]> = HashSet::new();
 
-        builder = builder
-            .start_tag()
-            .tag_str("p")
-            .tag_str(&hex::encode(replying_to.pubkey()));
+        builder = add_p_tag(builder, replying_to.pubkey(), hints);
 
         seen_p.insert(replying_to.pubkey());
 
@@ -124,7 +278,7 @@ impl NewPost {
 
             seen_p.insert(id);
 
-            builder = builder.start_tag().tag_str("p").tag_str(&hex::encode(id));
+            builder = add_p_tag(builder, id, hints);
         }
 
         if !self.media.is_empty() {
@@ -132,20 +286,30 @@ impl NewPost {
         }
 
         if !self.mentions.is_empty() {
-            builder = add_mention_tags(builder, &self.mentions);
+            builder = add_mention_tags(builder, &self.mentions, &self.relay_hints);
         }
 
-        builder
+        if let Some(reason) = &self.content_warning {
+            builder = add_content_warning_tag(builder, reason);
+        }
+
+        builder = add_quote_tags(builder, &self.content, None, &self.relay_hints);
+
+        self.add_created_at(builder)
             .sign(seckey)
             .build()
             .expect("expected build to work")
     }
 
     pub fn to_quote(&self, seckey: &[u8; 32], quoting: &Note) -> Note {
+        let hints = &self.relay_hints;
+        let author = Pubkey::new(*quoting.pubkey());
         let mut new_content = format!(
             "{}\nnostr:{}",
             self.content,
-            enostr::NoteId::new(*quoting.id()).to_bech().unwrap()
+            NoteId::new(*quoting.id())
+                .to_nevent(hints.note(quoting.id()), Some(&author))
+                .unwrap()
         );
 
         append_urls(&mut new_content, &self.media);
@@ -161,16 +325,25 @@ impl NewPost {
         }
 
         if !self.mentions.is_empty() {
-            builder = add_mention_tags(builder, &self.mentions);
+            builder = add_mention_tags(builder, &self.mentions, &self.relay_hints);
         }
 
-        builder
+        if let Some(reason) = &self.content_warning {
+            builder = add_content_warning_tag(builder, reason);
+        }
+
+        builder = add_quote_tags(builder, &self.content, Some(quoting.id()), hints);
+
+        builder = builder
             .start_tag()
             .tag_str("q")
             .tag_str(&hex::encode(quoting.id()))
-            .start_tag()
-            .tag_str("p")
-            .tag_str(&hex::encode(quoting.pubkey()))
+            .tag_str(hints.note_tag_hint(quoting.id()))
+            .tag_str(&author.hex());
+
+        builder = add_p_tag(builder, quoting.pubkey(), hints);
+
+        self.add_created_at(builder)
             .sign(seckey)
             .build()
             .expect("expected build to work")
@@ -199,16 +372,177 @@ fn append_urls(content: &mut String, media: &Vec<Nip94Event>) {
     }
 }
 
-fn add_mention_tags<'a>(builder: NoteBuilder<'a>, mentions: &Vec<Pubkey>) -> NoteBuilder<'a> {
+fn add_mention_tags<'a>(
+    builder: NoteBuilder<'a>,
+    mentions: &Vec<Pubkey>,
+    hints: &RelayHints,
+) -> NoteBuilder<'a> {
     let mut builder = builder;
 
     for mention in mentions {
-        builder = builder.start_tag().tag_str("p").tag_str(&mention.hex());
+        builder = add_p_tag(builder, mention.bytes(), hints);
     }
 
     builder
 }
 
+fn add_p_tag<'a>(
+    builder: NoteBuilder<'a>,
+    pubkey: &[u8; 32],
+    hints: &RelayHints,
+) -> NoteBuilder<'a> {
+    let builder = builder
+        .start_tag()
+        .tag_str("p")
+        .tag_str(&hex::encode(pubkey));
+
+    match hints.pubkey(pubkey).first() {
+        Some(relay) => builder.tag_str(relay),
+        None => builder,
+    }
+}
+
+/// `q` tags for the notes referenced in `content`, except `quoting` which
+/// gets its own
+fn add_quote_tags<'a>(
+    builder: NoteBuilder<'a>,
+    content: &str,
+    quoting: Option<&[u8; 32]>,
+    hints: &RelayHints,
+) -> NoteBuilder<'a> {
+    let mut builder = builder;
+
+    for id in referenced_notes(content) {
+        if Some(id.bytes()) == quoting {
+            continue;
+        }
+
+        builder = builder
+            .start_tag()
+            .tag_str("q")
+            .tag_str(&id.hex())
+            .tag_str(hints.note_tag_hint(id.bytes()));
+
+        if let Some(author) = hints.authors.get(id.bytes()) {
+            builder = builder.tag_str(&author.hex());
+        }
+    }
+
+    builder
+}
+
+/// What `~` searches for in the composer, to insert a reference to a note
+pub const NOTE_SEARCH_INDICATOR: char = '~';
+
+/// A NIP-19 `note` or `nevent`, optionally with the `nostr:` prefix and
+/// punctuation after it
+fn note_reference(token: &str) -> Option<NoteId> {
+    let token = token.strip_prefix("nostr:").unwrap_or(token);
+    let token = token.trim_end_matches(|c: char| c.is_ascii_punctuation());
+    if !(token.starts_with("note1") || token.starts_with("nevent1")) {
+        return None;
+    }
+
+    NoteId::from_bech(token)
+}
+
+/// The notes referenced in `text`, in order and without repeats
+pub fn referenced_notes(text: &str) -> Vec<NoteId> {
+    let mut notes = Vec::new();
+    for id in text.split_whitespace().filter_map(note_reference) {
+        if !notes.contains(&id) {
+            notes.push(id);
+        }
+    }
+    notes
+}
+
+/// Note references that were pasted without their `nostr:` prefix need
+/// it for other clients to pick them up
+fn prefix_note_references(text: &str) -> String {
+    text.split_inclusive(char::is_whitespace)
+        .map(|token| {
+            if !token.starts_with("nostr:") && note_reference(token.trim_end()).is_some() {
+                format!("nostr:{token}")
+            } else {
+                token.to_owned()
+            }
+        })
+        .collect()
+}
+
+/// The note search being typed at `cursor`: the char range of the
+/// search, including the indicator, and what to search for
+pub fn note_search_query(text: &str, cursor: usize) -> Option<(Range<usize>, &str)> {
+    let before: Vec<(usize, char)> = text.char_indices().take(cursor).collect();
+    let word_start = before
+        .iter()
+        .rposition(|(_, c)| c.is_whitespace())
+        .map_or(0, |i| i + 1);
+
+    let (byte_start, indicator) = *before.get(word_start)?;
+    if indicator != NOTE_SEARCH_INDICATOR {
+        return None;
+    }
+
+    let byte_end = text
+        .char_indices()
+        .nth(cursor)
+        .map_or(text.len(), |(i, _)| i);
+    let query = &text[byte_start + indicator.len_utf8()..byte_end];
+    if query.is_empty() {
+        return None;
+    }
+
+    Some((word_start..cursor, query))
+}
+
+fn add_poll_tags<'a>(
+    builder: NoteBuilder<'a>,
+    poll: &NewPoll,
+    relays: &[String],
+) -> NoteBuilder<'a> {
+    let mut builder = builder;
+
+    for option in &poll.options {
+        builder = builder
+            .start_tag()
+            .tag_str("option")
+            .tag_str(&option.id)
+            .tag_str(&option.label);
+    }
+
+    builder = builder
+        .start_tag()
+        .tag_str("polltype")
+        .tag_str(poll.poll_type.as_str());
+
+    if let Some(ends_at) = poll.ends_at {
+        builder = builder
+            .start_tag()
+            .tag_str("endsAt")
+            .tag_str(&ends_at.to_string());
+    }
+
+    // where the responses should go
+    for relay in relays {
+        builder = builder.start_tag().tag_str("relay").tag_str(relay);
+    }
+
+    builder
+}
+
+fn add_content_warning_tag<'a>(builder: NoteBuilder<'a>, reason: &str) -> NoteBuilder<'a> {
+    let builder = builder.start_tag().tag_str("content-warning");
+
+    let reason = reason.trim();
+    if reason.is_empty() {
+        builder
+    } else {
+        builder.tag_str(reason)
+    }
+}
+
 fn add_imeta_tags<'a>(builder: NoteBuilder<'a>, media: &Vec<Nip94Event>) -> NoteBuilder<'a> {
     let mut builder = builder;
     for item in media {
@@ -235,6 +569,9 @@ fn add_imeta_tags<'a>(builder: NoteBuilder<'a>, media: &Vec<Nip94Event>) -> Note
         if let Some(thumb) = &item.thumb {
             builder = builder.tag_str(&format!("thumb {thumb}"));
         }
+        if let Some(alt) = item.alt.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+            builder = builder.tag_str(&format!("alt {alt}"));
+        }
     }
     builder
 }
@@ -330,6 +667,21 @@ impl PostBuffer {
         }
     }
 
+    /// Put back a mention we saved earlier, eg. from a draft on disk
+    pub fn restore_mention(&mut self, start_index: usize, end_index: usize, pk: Pubkey) {
+        let mention_key = self.get_new_mentions_key();
+        self.mentions.insert(
+            mention_key,
+            MentionInfo {
+                start_index,
+                end_index,
+                mention_type: MentionType::Finalized(pk),
+            },
+        );
+        self.mention_starts.insert(start_index, mention_key);
+        self.mention_ends.insert(end_index, mention_key);
+    }
+
     pub fn delete_mention(&mut self, mention_key: usize) {
         if let Some(mention_info) = self.mentions.get(&mention_key) {
             self.mention_starts.remove(&mention_info.start_index);
@@ -343,12 +695,17 @@ impl PostBuffer {
     }
 
     pub fn output(&self) -> PostOutput {
+        self.output_with_hints(&RelayHints::default())
+    }
+
+    /// Mentions become `nprofile`s when we know relays for them
+    pub fn output_with_hints(&self, hints: &RelayHints) -> PostOutput {
         let mut out = self.text_buffer.clone();
         let mut mentions = Vec::new();
         for (cur_end_ind, mention_ind) in self.mention_ends.iter().rev() {
             if let Some(info) = self.mentions.get(mention_ind) {
                 if let MentionType::Finalized(pk) = info.mention_type {
-                    if let Some(bech) = pk.to_bech() {
+                    if let Some(bech) = hints.profile_entity(&pk) {
                         if let Some(byte_range) =
                             char_indices_to_byte(&out, info.start_index..*cur_end_ind)
                         {
@@ -362,7 +719,7 @@ impl PostBuffer {
         mentions.reverse();
 
         PostOutput {
-            text: out,
+            text: prefix_note_references(&out),
             mentions,
         }
     }
@@ -850,6 +1207,47 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_imeta_alt() {
+        let mut described = Nip94Event::new("https://example.com/cat.png".to_owned(), 10, 20);
+        described.alt = Some(" a cat asleep on a keyboard ".to_owned());
+        let mut blank = Nip94Event::new("https://example.com/dog.png".to_owned(), 10, 20);
+        blank.alt = Some("  ".to_owned());
+
+        let kp = enostr::FullKeypair::generate();
+        let post = NewPost::new("hi".to_owned(), kp.clone(), vec![described, blank], vec![]);
+        let note = post.to_note(&kp.secret_key.secret_bytes());
+
+        let imeta: Vec<Vec<String>> = note
+            .tags()
+            .iter()
+            .filter(|tag| tag.get_str(0) == Some("imeta"))
+            .map(|tag| {
+                (1..tag.count())
+                    .filter_map(|i| tag.get_str(i).map(str::to_owned))
+                    .collect()
+            })
+            .collect();
+
+        assert_eq!(imeta.len(), 2);
+        assert!(imeta[0].contains(&"alt a cat asleep on a keyboard".to_owned()));
+        assert!(!imeta[1].iter().any(|entry| entry.starts_with("alt")));
+    }
+
+    #[test]
+    fn test_restore_mention() {
+        let mut buf = PostBuffer::default();
+        buf.insert_text("hi @jb55 there", 0);
+        buf.restore_mention(3, 8, JB55());
+
+        let mention = buf.get_mention(5).expect("mention");
+        assert_eq!(mention.info.bounds(), 3..8);
+        assert_eq!(
+            buf.output().text,
+            format!("hi nostr:{} there", JB55().to_bech().unwrap())
+        );
+    }
+
     #[test]
     fn test_insert_single_mention() {
         let mut buf = PostBuffer::default();
@@ -1302,4 +1700,154 @@ mod tests {
         assert!(tags_iter.next().is_none());
         assert_eq!(note.content(), "test @jb55 test");
     }
+
+    #[test]
+    fn note_poll() {
+        let kp = FullKeypair::generate();
+        let option = |id: &str, label: &str| PollOption {
+            id: id.to_owned(),
+            label: label.to_owned(),
+        };
+        let poll = NewPoll {
+            options: vec![option("a1", "yes"), option("b2", "no")],
+            poll_type: PollType::MultipleChoice,
+            ends_at: Some(1_700_000_000),
+        };
+        let post = NewPost::new("ship it?".to_owned(), kp.clone(), Vec::new(), Vec::new())
+            .poll(Some(poll));
+        let note = post.to_note(&kp.pubkey);
+
+        assert_eq!(note.kind(), KIND_POLL);
+        let parsed = notedeck::poll::Poll::from_note(&note).expect("poll");
+        assert_eq!(parsed.question, "ship it?");
+        assert_eq!(
+            parsed.options,
+            vec![option("a1", "yes"), option("b2", "no")]
+        );
+        assert_eq!(parsed.poll_type, PollType::MultipleChoice);
+        assert_eq!(parsed.ends_at, Some(1_700_000_000));
+    }
+
+    #[test]
+    fn note_content_warning() {
+        let kp = FullKeypair::generate();
+        let post = NewPost::new("spoilers".to_owned(), kp.clone(), Vec::new(), Vec::new())
+            .content_warning(Some(" movie ending ".to_owned()));
+        let note = post.to_note(&kp.pubkey);
+
+        let tag = note
+            .tags()
+            .iter()
+            .find(|tag| tag.get_str(0) == Some("content-warning"))
+            .expect("content-warning tag");
+        assert_eq!(tag.count(), 2);
+        assert_eq!(tag.get_str(1), Some("movie ending"));
+
+        let post = NewPost::new("spoilers".to_owned(), kp.clone(), Vec::new(), Vec::new())
+            .content_warning(Some(String::new()));
+        let note = post.to_note(&kp.pubkey);
+        let tag = note
+            .tags()
+            .iter()
+            .find(|tag| tag.get_str(0) == Some("content-warning"))
+            .expect("content-warning tag");
+        assert_eq!(tag.count(), 1);
+    }
+
+    #[test]
+    fn note_reply_relay_hints() {
+        let kp = FullKeypair::generate();
+        let replying_to = NoteBuilder::new()
+            .content("gm")
+            .sign(&[1; 32])
+            .build()
+            .unwrap();
+
+        let mut hints = RelayHints::default();
+        hints
+            .notes
+            .insert(*replying_to.id(), vec!["wss://relay.damus.io".to_owned()]);
+        hints
+            .pubkeys
+            .insert(*replying_to.pubkey(), vec!["wss://nos.lol".to_owned()]);
+        hints.pubkeys.insert(*JB55().bytes(), vec![]);
+
+        let mut buf = PostBuffer::default();
+        buf.insert_text("hi ", 0);
+        buf.insert_text("@jb55", 3);
+        buf.select_full_mention(0, JB55());
+        let out = buf.output_with_hints(&hints);
+        assert!(out.text.starts_with("hi nostr:npub1"), "{}", out.text);
+
+        hints
+            .pubkeys
+            .insert(*JB55().bytes(), vec!["wss://nostr.wine".to_owned()]);
+        let out = buf.output_with_hints(&hints);
+        assert!(out.text.starts_with("hi nostr:nprofile1"), "{}", out.text);
+
+        let post = NewPost::new(out.text, kp.clone(), Vec::new(), out.mentions).relay_hints(hints);
+        let note = post.to_reply(&kp.secret_key.secret_bytes(), &replying_to);
+
+        let tags: Vec<Vec<String>> = note
+            .tags()
+            .iter()
+            .map(|tag| {
+                (0..tag.count())
+                    .map(|i| {
+                        tag.get_str(i)
+                            .map(str::to_owned)
+                            .or_else(|| tag.get_id(i).map(hex::encode))
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let root = tags.iter().find(|tag| tag[0] == "e").unwrap();
+        assert_eq!(root[2], "wss://relay.damus.io");
+        assert_eq!(root[3], "root");
+
+        let p_tags: Vec<&Vec<String>> = tags.iter().filter(|tag| tag[0] == "p").collect();
+        assert_eq!(p_tags[0][2], "wss://nos.lol");
+        assert_eq!(p_tags[1][1], JB55().hex());
+        assert_eq!(p_tags[1][2], "wss://nostr.wine");
+    }
+
+    #[test]
+    fn note_references() {
+        let id = NoteId::new([3; 32]);
+        let note1 = id.to_bech().unwrap();
+        let nevent = id.to_nevent(&["wss://nos.lol".to_owned()], None).unwrap();
+
+        let text = format!("look {note1}, and nostr:{nevent} and {note1} again");
+        assert_eq!(referenced_notes(&text), vec![id]);
+
+        assert_eq!(
+            prefix_note_references(&format!("look {note1}, ok nostr:{nevent}")),
+            format!("look nostr:{note1}, ok nostr:{nevent}")
+        );
+
+        let kp = FullKeypair::generate();
+        let post = NewPost::new(format!("see {note1}"), kp.clone(), Vec::new(), Vec::new());
+        let note = post.to_note(&kp.secret_key.secret_bytes());
+        let q = note
+            .tags()
+            .iter()
+            .find(|tag| tag.get_str(0) == Some("q"))
+            .expect("q tag");
+        assert_eq!(q.get_id(1), Some(id.bytes()));
+    }
+
+    #[test]
+    fn note_search_queries() {
+        assert_eq!(
+            note_search_query("hi ~bitcoin", 11),
+            Some((3..11, "bitcoin"))
+        );
+        assert_eq!(note_search_query("hi ~bitcoin", 7), Some((3..7, "bit")));
+        assert_eq!(note_search_query("~élan", 5), Some((0..5, "élan")));
+        assert_eq!(note_search_query("hi ~", 4), None);
+        assert_eq!(note_search_query("hi a~b", 6), None);
+        assert_eq!(note_search_query("", 0), None);
+    }
 }
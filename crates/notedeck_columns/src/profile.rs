@@ -1,11 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use enostr::{FullKeypair, Pubkey, RelayPool};
-use nostrdb::{Ndb, Note, NoteBuildOptions, NoteBuilder};
+use nostrdb::{Filter, Ndb, Note, NoteBuildOptions, NoteBuilder, Transaction};
 
-use tracing::info;
+use tracing::{error, info};
 
 use crate::{
+    error::Error,
     profile_state::ProfileState,
     route::{Route, Router},
 };
@@ -19,11 +20,12 @@ impl SaveProfileChanges {
     pub fn new(kp: FullKeypair, state: ProfileState) -> Self {
         Self { kp, state }
     }
-    pub fn to_note(&self) -> Note {
+    /// The new kind 0, keeping what we don't edit from `latest`
+    pub fn to_note(&self, latest: Option<&Note>) -> Note {
         let sec = &self.kp.secret_key.to_secret_bytes();
         add_client_tag(NoteBuilder::new())
             .kind(0)
-            .content(&self.state.to_json())
+            .content(&self.state.to_json(latest.map(|note| note.content())))
             .options(NoteBuildOptions::default().created_at(true).sign(sec))
             .build()
             .expect("should build")
@@ -37,9 +39,213 @@ fn add_client_tag(builder: NoteBuilder<'_>) -> NoteBuilder<'_> {
         .tag_str("Damus Notedeck")
 }
 
+/// The latest kind 0 we have locally for this pubkey
+pub fn get_profile_note<'a>(ndb: &Ndb, txn: &'a Transaction, pubkey: &Pubkey) -> Option<Note<'a>> {
+    let filter = Filter::new()
+        .authors([pubkey.bytes()])
+        .kinds([0])
+        .limit(1)
+        .build();
+
+    ndb.query(txn, &[filter], 1)
+        .ok()?
+        .into_iter()
+        .next()
+        .map(|qr| qr.note)
+}
+
+/// The latest contact list we have locally for this pubkey
+pub fn get_contact_list<'a>(ndb: &Ndb, txn: &'a Transaction, pubkey: &Pubkey) -> Option<Note<'a>> {
+    let filter = Filter::new()
+        .authors([pubkey.bytes()])
+        .kinds([3])
+        .limit(1)
+        .build();
+
+    ndb.query(txn, &[filter], 1)
+        .ok()?
+        .into_iter()
+        .next()
+        .map(|qr| qr.note)
+}
+
+/// The pubkeys followed in a contact list
+pub fn contact_pubkeys(contact_list: &Note) -> HashSet<Pubkey> {
+    let mut pubkeys = HashSet::new();
+
+    for tag in contact_list.tags() {
+        if tag.count() < 2 || tag.get_str(0) != Some("p") {
+            continue;
+        }
+
+        let variant = tag.get_unchecked(1).variant();
+        if let Some(id) = variant.id() {
+            pubkeys.insert(Pubkey::new(*id));
+        } else if let Some(pk) = variant.str().and_then(|s| Pubkey::from_hex(s).ok()) {
+            pubkeys.insert(pk);
+        }
+    }
+
+    pubkeys
+}
+
+/// Someone's most recent contact list out of `contact_lists`
+fn latest_per_author<'a>(
+    contact_lists: impl IntoIterator<Item = Note<'a>>,
+) -> HashMap<Pubkey, Note<'a>> {
+    let mut latest: HashMap<Pubkey, Note<'a>> = HashMap::new();
+    for note in contact_lists {
+        let author = Pubkey::new(*note.pubkey());
+        if latest
+            .get(&author)
+            .is_some_and(|seen| seen.created_at() >= note.created_at())
+        {
+            continue;
+        }
+        latest.insert(author, note);
+    }
+    latest
+}
+
+/// We only look at this many contact lists when counting followers
+const MAX_FOLLOWERS: i32 = 10_000;
+
+/// The contact lists that mention `pubkey`
+pub fn followers_filter(pubkey: &Pubkey) -> Filter {
+    Filter::new()
+        .kinds([3])
+        .pubkeys([pubkey.bytes()])
+        .limit(MAX_FOLLOWERS as u64)
+        .build()
+}
+
+/// Who follows `pubkey`, out of the contact lists we have locally. Only
+/// the latest contact list of each author counts, so people who unfollowed
+/// aren't counted.
+pub fn get_followers(ndb: &Ndb, txn: &Transaction, pubkey: &Pubkey) -> Vec<Pubkey> {
+    let mentions = ndb
+        .query(txn, &[followers_filter(pubkey)], MAX_FOLLOWERS)
+        .unwrap_or_default();
+    if mentions.is_empty() {
+        return Vec::new();
+    }
+
+    // a newer list from the same author might not mention them anymore
+    let authors: Vec<[u8; 32]> = latest_per_author(mentions.into_iter().map(|qr| qr.note))
+        .into_keys()
+        .map(|pk| *pk.bytes())
+        .collect();
+    let filter = Filter::new()
+        .authors(authors.iter())
+        .kinds([3])
+        .limit(MAX_FOLLOWERS as u64)
+        .build();
+    let contact_lists = ndb.query(txn, &[filter], MAX_FOLLOWERS).unwrap_or_default();
+
+    followers_in(contact_lists.into_iter().map(|qr| qr.note), pubkey)
+}
+
+fn followers_in<'a>(
+    contact_lists: impl IntoIterator<Item = Note<'a>>,
+    pubkey: &Pubkey,
+) -> Vec<Pubkey> {
+    let mut followers: Vec<Pubkey> = latest_per_author(contact_lists)
+        .into_iter()
+        .filter(|(_, contact_list)| contact_pubkeys(contact_list).contains(pubkey))
+        .map(|(author, _)| author)
+        .collect();
+    followers.sort();
+    followers
+}
+
+/// A new contact list with `pubkey` followed or unfollowed. Everything
+/// else in the existing list is kept as is.
+fn contact_list_note<'a>(
+    contact_list: &'a Note,
+    kp: &FullKeypair,
+    pubkey: &Pubkey,
+    follow: bool,
+) -> Result<Note<'a>, Error> {
+    let mut builder = NoteBuilder::new().kind(3).content(contact_list.content());
+
+    for tag in contact_list.tags() {
+        if tag.count() >= 2 && tag.get_str(0) == Some("p") {
+            let variant = tag.get_unchecked(1).variant();
+            let pk = variant
+                .id()
+                .map(|id| Pubkey::new(*id))
+                .or_else(|| variant.str().and_then(|s| Pubkey::from_hex(s).ok()));
+            if pk.as_ref() == Some(pubkey) {
+                continue;
+            }
+        }
+
+        builder = builder.start_tag();
+        for i in 0..tag.count() {
+            let variant = tag.get_unchecked(i).variant();
+            if let Some(s) = variant.str() {
+                builder = builder.tag_str(s);
+            } else if let Some(id) = variant.id() {
+                builder = builder.tag_str(&hex::encode(id));
+            }
+        }
+    }
+
+    if follow {
+        builder = builder.start_tag().tag_str("p").tag_str(&pubkey.hex());
+    }
+
+    let sec = &kp.secret_key.to_secret_bytes();
+    builder
+        .options(NoteBuildOptions::default().created_at(true).sign(sec))
+        .build()
+        .ok_or_else(|| Error::Generic("could not build contact list".to_owned()))
+}
+
+/// Publish our contact list with `pubkey` followed or unfollowed
+fn update_follows(
+    ndb: &Ndb,
+    pool: &mut RelayPool,
+    kp: &FullKeypair,
+    pubkey: &Pubkey,
+    follow: bool,
+) -> Result<(), Error> {
+    let txn = Transaction::new(ndb)?;
+
+    // we don't want to wipe someone's follows by publishing
+    // a contact list we built from nothing
+    let contact_list = get_contact_list(ndb, &txn, &kp.pubkey)
+        .ok_or_else(|| Error::Generic("we don't have our contact list yet".to_owned()))?;
+
+    if contact_pubkeys(&contact_list).contains(pubkey) == follow {
+        return Ok(());
+    }
+
+    send_note(
+        &contact_list_note(&contact_list, kp, pubkey, follow)?,
+        ndb,
+        pool,
+    );
+    Ok(())
+}
+
+fn send_note(note: &Note, ndb: &Ndb, pool: &mut RelayPool) {
+    let raw_msg = format!("[\"EVENT\",{}]", note.json().unwrap());
+
+    let _ = ndb.process_event_with(
+        raw_msg.as_str(),
+        nostrdb::IngestMetadata::new().client(true),
+    );
+
+    info!("sending {}", raw_msg);
+    pool.send(&enostr::ClientMessage::raw(raw_msg));
+}
+
 pub enum ProfileAction {
     Edit(FullKeypair),
     SaveChanges(SaveProfileChanges),
+    Follow(FullKeypair, Pubkey),
+    Unfollow(FullKeypair, Pubkey),
 }
 
 impl ProfileAction {
@@ -55,19 +261,77 @@ impl ProfileAction {
                 router.route_to(Route::EditProfile(kp.pubkey));
             }
             ProfileAction::SaveChanges(changes) => {
-                let raw_msg = format!("[\"EVENT\",{}]", changes.to_note().json().unwrap());
-
-                let _ = ndb.process_event_with(
-                    raw_msg.as_str(),
-                    nostrdb::IngestMetadata::new().client(true),
-                );
+                let txn = Transaction::new(ndb).expect("txn");
+                let latest = get_profile_note(ndb, &txn, &changes.kp.pubkey);
+                send_note(&changes.to_note(latest.as_ref()), ndb, pool);
                 let _ = state_map.remove_entry(&changes.kp.pubkey);
 
-                info!("sending {}", raw_msg);
-                pool.send(&enostr::ClientMessage::raw(raw_msg));
-
                 router.go_back();
             }
+            ProfileAction::Follow(kp, pubkey) => {
+                if let Err(err) = update_follows(ndb, pool, kp, pubkey, true) {
+                    error!("can't follow {}: {err}", pubkey.hex());
+                }
+            }
+            ProfileAction::Unfollow(kp, pubkey) => {
+                if let Err(err) = update_follows(ndb, pool, kp, pubkey, false) {
+                    error!("can't unfollow {}: {err}", pubkey.hex());
+                }
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contact_list(seckey: &[u8; 32], follows: &[&Pubkey], created_at: u64) -> Note<'static> {
+        let mut builder = NoteBuilder::new()
+            .kind(3)
+            .content("")
+            .created_at(created_at);
+        for pk in follows {
+            builder = builder.start_tag().tag_str("p").tag_str(&pk.hex());
+        }
+        builder.sign(seckey).build().unwrap()
+    }
+
+    #[test]
+    fn test_unfollow_keeps_the_rest() {
+        let kp = FullKeypair::generate();
+        let (a, b) = (Pubkey::new([1; 32]), Pubkey::new([2; 32]));
+        let list = contact_list(&kp.secret_key.to_secret_bytes(), &[&a, &b], 1000);
+
+        let unfollowed = contact_list_note(&list, &kp, &a, false).expect("contact list");
+        assert_eq!(contact_pubkeys(&unfollowed), HashSet::from([b]));
+
+        let followed = contact_list_note(&unfollowed, &kp, &a, true).expect("contact list");
+        assert_eq!(contact_pubkeys(&followed), HashSet::from([a, b]));
+
+        // following twice doesn't add them twice
+        let again = contact_list_note(&followed, &kp, &a, true).expect("contact list");
+        let p_tags = again
+            .tags()
+            .into_iter()
+            .filter(|tag| tag.get_str(0) == Some("p"))
+            .count();
+        assert_eq!(p_tags, 2);
+    }
+
+    #[test]
+    fn test_followers_use_latest_contact_list() {
+        let me = Pubkey::new([9; 32]);
+        let (alice, bob) = ([3; 32], [4; 32]);
+
+        let lists = vec![
+            contact_list(&alice, &[&me], 1000),
+            // bob followed and then unfollowed
+            contact_list(&bob, &[&me], 1000),
+            contact_list(&bob, &[], 2000),
+        ];
+        let alice_pk = Pubkey::new(*lists[0].pubkey());
+
+        assert_eq!(followers_in(lists, &me), vec![alice_pk]);
+    }
+}
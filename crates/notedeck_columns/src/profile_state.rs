@@ -1,6 +1,15 @@
+use std::path::PathBuf;
+
 use nostrdb::{NdbProfile, ProfileRecord};
+use poll_promise::Promise;
+use serde_json::{Map, Value};
+
+use crate::{
+    media_upload::{Crop, Nip94Event},
+    Error,
+};
 
-#[derive(Default, Debug)]
+#[derive(Default)]
 pub struct ProfileState {
     pub display_name: String,
     pub name: String,
@@ -9,9 +18,70 @@ pub struct ProfileState {
     pub about: String,
     pub website: String,
     pub lud16: String,
+    pub lud06: String,
     pub nip05: String,
+    pub pronouns: String,
+    pub bot: bool,
+
+    /// A new picture or banner being cropped
+    pub image_edit: Option<ProfileImageEdit>,
+    pub uploads: Vec<ProfileUpload>,
+    pub upload_error: Option<String>,
+}
+
+/// The kind 0 fields that hold images
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProfileImage {
+    Picture,
+    Banner,
 }
 
+impl ProfileImage {
+    /// Width over height
+    pub fn aspect(&self) -> f32 {
+        match self {
+            ProfileImage::Picture => 1.0,
+            ProfileImage::Banner => 3.0,
+        }
+    }
+
+    /// Nobody needs them bigger than this
+    pub fn max_width(&self) -> u32 {
+        match self {
+            ProfileImage::Picture => 512,
+            ProfileImage::Banner => 1500,
+        }
+    }
+}
+
+/// An image picked from disk, before it's cropped and uploaded
+pub struct ProfileImageEdit {
+    pub image: ProfileImage,
+    pub path: PathBuf,
+    pub texture: egui::TextureHandle,
+    pub crop: Crop,
+}
+
+pub struct ProfileUpload {
+    pub image: ProfileImage,
+    pub promise: Promise<Result<Nip94Event, Error>>,
+}
+
+/// The fields we edit. Everything else in the kind 0 is left alone.
+const EDITED_FIELDS: [&str; 11] = [
+    "display_name",
+    "name",
+    "picture",
+    "banner",
+    "about",
+    "website",
+    "lud16",
+    "lud06",
+    "nip05",
+    "pronouns",
+    "bot",
+];
+
 impl ProfileState {
     pub fn from_profile(record: &ProfileRecord<'_>) -> Self {
         let display_name = get_item(record, |p| p.display_name());
@@ -32,38 +102,75 @@ impl ProfileState {
             website,
             lud16,
             nip05,
+            ..Default::default()
         }
     }
 
-    pub fn to_json(&self) -> String {
-        let mut fields = Vec::new();
+    /// From the json content of a kind 0, which has the fields nostrdb
+    /// doesn't keep
+    pub fn from_metadata(content: &str) -> Option<Self> {
+        let metadata: Map<String, Value> = serde_json::from_str(content).ok()?;
+        let get = |key: &str| {
+            metadata
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_owned()
+        };
 
-        if !self.display_name.is_empty() {
-            fields.push(format!(r#""display_name":"{}""#, self.display_name));
-        }
-        if !self.name.is_empty() {
-            fields.push(format!(r#""name":"{}""#, self.name));
-        }
-        if !self.picture.is_empty() {
-            fields.push(format!(r#""picture":"{}""#, self.picture));
-        }
-        if !self.banner.is_empty() {
-            fields.push(format!(r#""banner":"{}""#, self.banner));
-        }
-        if !self.about.is_empty() {
-            fields.push(format!(r#""about":"{}""#, self.about));
-        }
-        if !self.website.is_empty() {
-            fields.push(format!(r#""website":"{}""#, self.website));
+        Some(Self {
+            display_name: get("display_name"),
+            name: get("name"),
+            picture: get("picture"),
+            banner: get("banner"),
+            about: get("about"),
+            website: get("website"),
+            lud16: get("lud16"),
+            lud06: get("lud06"),
+            nip05: get("nip05"),
+            pronouns: get("pronouns"),
+            bot: metadata
+                .get("bot")
+                .is_some_and(|bot| bot.as_bool() == Some(true) || bot.as_str() == Some("true")),
+            ..Default::default()
+        })
+    }
+
+    /// The kind 0 content, on top of `latest` so fields set by other
+    /// clients are kept
+    pub fn to_json(&self, latest: Option<&str>) -> String {
+        let mut metadata: Map<String, Value> = latest
+            .and_then(|content| serde_json::from_str(content).ok())
+            .unwrap_or_default();
+
+        for key in EDITED_FIELDS {
+            metadata.remove(key);
         }
-        if !self.lud16.is_empty() {
-            fields.push(format!(r#""lud16":"{}""#, self.lud16));
+
+        let fields = [
+            ("display_name", &self.display_name),
+            ("name", &self.name),
+            ("picture", &self.picture),
+            ("banner", &self.banner),
+            ("about", &self.about),
+            ("website", &self.website),
+            ("lud16", &self.lud16),
+            ("lud06", &self.lud06),
+            ("nip05", &self.nip05),
+            ("pronouns", &self.pronouns),
+        ];
+        for (key, value) in fields {
+            let value = value.trim();
+            if !value.is_empty() {
+                metadata.insert(key.to_owned(), Value::String(value.to_owned()));
+            }
         }
-        if !self.nip05.is_empty() {
-            fields.push(format!(r#""nip05":"{}""#, self.nip05));
+
+        if self.bot {
+            metadata.insert("bot".to_owned(), Value::Bool(true));
         }
 
-        format!("{{{}}}", fields.join(","))
+        Value::Object(metadata).to_string()
     }
 }
 
@@ -77,3 +184,30 @@ fn get_item<'a>(
         .and_then(item_retriever)
         .map_or_else(String::new, ToString::to_string)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_keeps_unknown_fields() {
+        let latest = r#"{"name":"jb55","about":"old","displayName":"Will","nostr_wallet":{"x":1},"bot":true}"#;
+
+        let mut state = ProfileState::from_metadata(latest).unwrap();
+        assert_eq!(state.name, "jb55");
+        assert!(state.bot);
+
+        state.about = "says \"hi\"".to_owned();
+        state.pronouns = "he/him".to_owned();
+        state.bot = false;
+        state.name = String::new();
+
+        let json: Map<String, Value> = serde_json::from_str(&state.to_json(Some(latest))).unwrap();
+        assert_eq!(json["about"], "says \"hi\"");
+        assert_eq!(json["pronouns"], "he/him");
+        assert_eq!(json["displayName"], "Will");
+        assert_eq!(json["nostr_wallet"]["x"], 1);
+        assert!(!json.contains_key("name"));
+        assert!(!json.contains_key("bot"));
+    }
+}
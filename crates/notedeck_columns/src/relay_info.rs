@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use ehttp::Request;
+use enostr::{RelayPool, RelayStatus};
+use poll_promise::Promise;
+use tracing::{debug, error};
+
+use crate::Error;
+
+/// NIP-50: search capability
+pub const NIP_SEARCH: u32 = 50;
+
+/// The parts of a NIP-11 relay information document we care about
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct RelayInfo {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub supported_nips: Vec<u32>,
+}
+
+impl RelayInfo {
+    pub fn supports(&self, nip: u32) -> bool {
+        self.supported_nips.contains(&nip)
+    }
+}
+
+/// How long we wait before fetching a document again after the first
+/// failure, doubled for each failure after that
+const RETRY_AFTER: Duration = Duration::from_secs(30);
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30 * 60);
+
+enum RelayInfoState {
+    Fetching {
+        promise: Promise<Result<RelayInfo, Error>>,
+
+        /// Failed fetches before this one
+        failures: u32,
+    },
+    Ready(RelayInfo),
+    Failed {
+        failures: u32,
+        retry_at: Instant,
+    },
+}
+
+fn retry_delay(failures: u32) -> Duration {
+    RETRY_AFTER
+        .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(MAX_RETRY_AFTER)
+}
+
+/// Caches NIP-11 relay information documents. We fetch these when a
+/// relay connection opens so that we know which relays can do things
+/// like NIP-50 search.
+#[derive(Default)]
+pub struct RelayInfoCache {
+    infos: HashMap<String, RelayInfoState>,
+}
+
+impl RelayInfoCache {
+    /// Start fetching the relay information document for this relay if
+    /// we haven't already
+    pub fn fetch(&mut self, relay_url: &str) {
+        if relay_url == "multicast" || self.infos.contains_key(relay_url) {
+            return;
+        }
+
+        debug!("fetching NIP-11 relay info for {relay_url}");
+        self.infos.insert(
+            relay_url.to_owned(),
+            RelayInfoState::Fetching {
+                promise: fetch_relay_info(relay_url),
+                failures: 0,
+            },
+        );
+    }
+
+    /// Move finished fetches into the ready state, and retry failed ones
+    /// once they've waited long enough
+    pub fn poll(&mut self) {
+        let now = Instant::now();
+        for (url, state) in self.infos.iter_mut() {
+            let new_state = match state {
+                RelayInfoState::Fetching { promise, failures } => match promise.ready() {
+                    None => continue,
+                    Some(Ok(info)) => RelayInfoState::Ready(info.clone()),
+                    Some(Err(err)) => {
+                        let failures = *failures + 1;
+                        let delay = retry_delay(failures);
+                        error!("relay info for {url} failed, retrying in {delay:?}: {err}");
+                        RelayInfoState::Failed {
+                            failures,
+                            retry_at: now + delay,
+                        }
+                    }
+                },
+
+                RelayInfoState::Failed { failures, retry_at } if now >= *retry_at => {
+                    debug!("fetching NIP-11 relay info for {url} again");
+                    RelayInfoState::Fetching {
+                        promise: fetch_relay_info(url),
+                        failures: *failures,
+                    }
+                }
+
+                RelayInfoState::Failed { .. } | RelayInfoState::Ready(_) => continue,
+            };
+
+            *state = new_state;
+        }
+    }
+
+    pub fn get(&self, relay_url: &str) -> Option<&RelayInfo> {
+        if let Some(RelayInfoState::Ready(info)) = self.infos.get(relay_url) {
+            Some(info)
+        } else {
+            None
+        }
+    }
+
+    pub fn supports(&self, relay_url: &str, nip: u32) -> bool {
+        self.get(relay_url).is_some_and(|info| info.supports(nip))
+    }
+
+    /// The connected relays in the pool that advertise NIP-50 search support
+    pub fn search_relays(&self, pool: &RelayPool) -> Vec<String> {
+        pool.relays
+            .iter()
+            .filter(|r| matches!(r.status(), RelayStatus::Connected))
+            .filter(|r| !r.is_exclusive())
+            .map(|r| r.url())
+            .filter(|url| self.supports(url, NIP_SEARCH))
+            .map(|url| url.to_owned())
+            .collect()
+    }
+}
+
+/// NIP-11 documents are served over http(s) at the same address as the
+/// websocket endpoint
+fn relay_info_url(relay_url: &str) -> Option<String> {
+    if let Some(rest) = relay_url.strip_prefix("wss://") {
+        Some(format!("https://{rest}"))
+    } else {
+        relay_url
+            .strip_prefix("ws://")
+            .map(|rest| format!("http://{rest}"))
+    }
+}
+
+fn parse_relay_info(json: &str) -> Result<RelayInfo, Error> {
+    serde_json::from_str::<RelayInfo>(json).map_err(|e| Error::Generic(e.to_string()))
+}
+
+fn fetch_relay_info(relay_url: &str) -> Promise<Result<RelayInfo, Error>> {
+    let Some(url) = relay_info_url(relay_url) else {
+        return Promise::from_ready(Err(Error::Generic(format!(
+            "not a websocket relay url: {relay_url}"
+        ))));
+    };
+
+    let request = Request {
+        method: "GET".to_string(),
+        url,
+        headers: ehttp::Headers::new(&[("Accept", "application/nostr+json")]),
+        body: vec![],
+    };
+
+    let (sender, promise) = Promise::new();
+
+    ehttp::fetch(request, move |response| {
+        let result = match response {
+            Ok(resp) if resp.ok => match resp.text() {
+                Some(text) => parse_relay_info(text),
+                None => Err(Error::Generic(
+                    "ehttp::Response payload is not text".to_owned(),
+                )),
+            },
+            Ok(resp) => Err(Error::Generic(format!(
+                "ehttp::Response status: {}",
+                resp.status
+            ))),
+            Err(e) => Err(Error::Generic(e)),
+        };
+
+        sender.send(result);
+    });
+
+    promise
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relay_info_url() {
+        assert_eq!(
+            relay_info_url("wss://relay.damus.io/").as_deref(),
+            Some("https://relay.damus.io/")
+        );
+        assert_eq!(
+            relay_info_url("ws://localhost:8080").as_deref(),
+            Some("http://localhost:8080")
+        );
+        assert_eq!(relay_info_url("multicast"), None);
+    }
+
+    #[test]
+    fn test_parse_relay_info() {
+        let json = r#"{"name":"search relay","supported_nips":[1,11,50],"software":"x"}"#;
+        let info = parse_relay_info(json).expect("relay info");
+        assert_eq!(info.name.as_deref(), Some("search relay"));
+        assert!(info.supports(NIP_SEARCH));
+
+        let info = parse_relay_info(r#"{"name":"plain"}"#).expect("relay info");
+        assert!(!info.supports(NIP_SEARCH));
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(1), RETRY_AFTER);
+        assert_eq!(retry_delay(2), RETRY_AFTER * 2);
+        assert_eq!(retry_delay(3), RETRY_AFTER * 4);
+        assert_eq!(retry_delay(100), MAX_RETRY_AFTER);
+    }
+}
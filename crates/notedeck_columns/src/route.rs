@@ -22,6 +22,8 @@ pub enum Route {
     Quote(NoteId),
     Relays,
     ComposeNote,
+    Drafts,
+    Scheduled,
     AddColumn(AddColumnRoute),
     EditProfile(Pubkey),
     Support,
@@ -102,6 +104,12 @@ impl Route {
             Route::ComposeNote => {
                 writer.write_token("compose");
             }
+            Route::Drafts => {
+                writer.write_token("drafts");
+            }
+            Route::Scheduled => {
+                writer.write_token("scheduled");
+            }
             Route::Support => {
                 writer.write_token("support");
             }
@@ -175,6 +183,18 @@ impl Route {
                         Ok(Route::ComposeNote)
                     })
                 },
+                |p| {
+                    p.parse_all(|p| {
+                        p.parse_token("drafts")?;
+                        Ok(Route::Drafts)
+                    })
+                },
+                |p| {
+                    p.parse_all(|p| {
+                        p.parse_token("scheduled")?;
+                        Ok(Route::Scheduled)
+                    })
+                },
                 |p| {
                     p.parse_all(|p| {
                         p.parse_token("support")?;
@@ -212,6 +232,8 @@ impl Route {
                 AccountsRoute::AddAccount => ColumnTitle::simple("Add Account"),
             },
             Route::ComposeNote => ColumnTitle::simple("Compose Note"),
+            Route::Drafts => ColumnTitle::simple("Drafts"),
+            Route::Scheduled => ColumnTitle::simple("Scheduled"),
             Route::AddColumn(c) => match c {
                 AddColumnRoute::Base => ColumnTitle::simple("Add Column"),
                 AddColumnRoute::Algo(r) => match r {
@@ -225,6 +247,9 @@ impl Route {
                     ColumnTitle::simple("Add External Notifications Column")
                 }
                 AddColumnRoute::Hashtag => ColumnTitle::simple("Add Hashtag Column"),
+                AddColumnRoute::Lists => ColumnTitle::simple("Add List Column"),
+                AddColumnRoute::Generic => ColumnTitle::simple("Add Custom Column"),
+                AddColumnRoute::Relay => ColumnTitle::simple("Add Relay Column"),
                 AddColumnRoute::UndecidedIndividual => {
                     ColumnTitle::simple("Subscribe to someone's notes")
                 }
@@ -317,6 +342,10 @@ impl<R: Clone> Router<R> {
         self.routes.last().expect("routes can't be empty")
     }
 
+    pub fn top_mut(&mut self) -> &mut R {
+        self.routes.last_mut().expect("routes can't be empty")
+    }
+
     pub fn prev(&self) -> Option<&R> {
         self.routes.get(self.routes.len() - 2)
     }
@@ -331,11 +360,16 @@ impl fmt::Display for Route {
         match self {
             Route::Timeline(kind) => match kind {
                 TimelineKind::List(ListKind::Contact(_pk)) => write!(f, "Contacts"),
+                TimelineKind::List(list_kind) => write!(f, "{}", list_kind.name()),
                 TimelineKind::Algo(AlgoTimeline::LastPerPubkey(ListKind::Contact(_))) => {
                     write!(f, "Last Per Pubkey (Contact)")
                 }
+                TimelineKind::Algo(AlgoTimeline::LastPerPubkey(list_kind)) => {
+                    write!(f, "Last Per Pubkey ({})", list_kind.name())
+                }
                 TimelineKind::Notifications(_) => write!(f, "Notifications"),
                 TimelineKind::Universe => write!(f, "Universe"),
+                TimelineKind::Relay(url) => write!(f, "Relay ({})", url),
                 TimelineKind::Generic(_) => write!(f, "Custom"),
                 TimelineKind::Search(_) => write!(f, "Search"),
                 TimelineKind::Hashtag(ht) => write!(f, "Hashtag ({})", ht),
@@ -353,6 +387,8 @@ impl fmt::Display for Route {
                 AccountsRoute::AddAccount => write!(f, "Add Account"),
             },
             Route::ComposeNote => write!(f, "Compose Note"),
+            Route::Drafts => write!(f, "Drafts"),
+            Route::Scheduled => write!(f, "Scheduled"),
 
             Route::AddColumn(_) => write!(f, "Add Column"),
             Route::Support => write!(f, "Support"),
@@ -0,0 +1,437 @@
+use std::{
+    collections::BTreeSet,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use enostr::{ClientMessage, Pubkey, RelayPool, RelayStatus};
+use nostrdb::{Ndb, Note, NoteBuilder};
+use notedeck::Accounts;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+/// A post waiting in the queue to be published
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScheduledPost {
+    pub id: u64,
+    pub pubkey: Pubkey,
+    /// unix timestamp
+    pub publish_at: u64,
+    /// The signed note. When it's signed at send time this is just the
+    /// template, it's signed again with the time it goes out.
+    pub note_json: String,
+    pub sign_at_send: bool,
+
+    /// It came due and went out. We keep it until a relay accepts it.
+    #[serde(default)]
+    pub sending: bool,
+
+    /// Connected relays we sent it to
+    #[serde(skip)]
+    sent_to: BTreeSet<String>,
+}
+
+impl ScheduledPost {
+    pub fn content(&self) -> String {
+        enostr::Note::from_json(&self.note_json)
+            .map(|note| note.content)
+            .unwrap_or_default()
+    }
+
+    fn note_id_hex(&self) -> Option<String> {
+        enostr::Note::from_json(&self.note_json)
+            .ok()
+            .map(|note| note.id.hex())
+    }
+}
+
+/// When a new post should go out
+#[derive(Clone, Copy, Debug)]
+pub struct Schedule {
+    pub publish_at: u64,
+    pub sign_at_send: bool,
+}
+
+/// Posts we publish later, kept on disk until they're sent
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct ScheduledPosts {
+    posts: Vec<ScheduledPost>,
+    next_id: u64,
+
+    /// Changed since we last saved
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl ScheduledPosts {
+    pub fn add(&mut self, pubkey: Pubkey, schedule: Schedule, note: &Note) -> crate::Result<()> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.posts.push(ScheduledPost {
+            id,
+            pubkey,
+            publish_at: schedule.publish_at,
+            note_json: note.json()?,
+            sign_at_send: schedule.sign_at_send,
+            sending: false,
+            sent_to: BTreeSet::new(),
+        });
+        self.posts.sort_by_key(|post| post.publish_at);
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<ScheduledPost> {
+        let index = self.posts.iter().position(|post| post.id == id)?;
+        self.dirty = true;
+        Some(self.posts.remove(index))
+    }
+
+    /// Change when and what we post. The note is signed again, so we need
+    /// the account's secret key.
+    pub fn reschedule(
+        &mut self,
+        id: u64,
+        publish_at: u64,
+        content: &str,
+        seckey: &[u8; 32],
+    ) -> Option<()> {
+        let post = self.posts.iter_mut().find(|post| post.id == id)?;
+        post.note_json = resign(&post.note_json, Some(content), seckey, publish_at)?;
+        post.publish_at = publish_at;
+        post.sending = false;
+        post.sent_to.clear();
+
+        self.posts.sort_by_key(|post| post.publish_at);
+        self.dirty = true;
+        Some(())
+    }
+
+    pub fn for_account<'a>(
+        &'a self,
+        pubkey: &'a Pubkey,
+    ) -> impl Iterator<Item = &'a ScheduledPost> {
+        self.posts.iter().filter(move |post| post.pubkey == *pubkey)
+    }
+
+    /// How long until the next post is due
+    pub fn next_due_in(&self, now: u64) -> Option<Duration> {
+        self.posts
+            .iter()
+            .filter(|post| !post.sending)
+            .map(|post| Duration::from_secs(post.publish_at.saturating_sub(now)))
+            .min()
+    }
+
+    /// Send the posts that are due. We wait until a relay is connected, so
+    /// posts that came due while the app was closed go out soon after
+    /// startup. Posts stay in the queue until a relay accepts them, and go
+    /// to each relay as it connects.
+    pub fn publish_due(&mut self, now: u64, accounts: &Accounts, ndb: &Ndb, pool: &mut RelayPool) {
+        if !self.posts.iter().any(|post| post.publish_at <= now) {
+            return;
+        }
+
+        let connected: BTreeSet<String> = pool
+            .relays
+            .iter()
+            .filter(|relay| matches!(relay.status(), RelayStatus::Connected))
+            .filter(|relay| !relay.is_exclusive())
+            .map(|relay| relay.url().to_owned())
+            .collect();
+        if connected.is_empty() {
+            return;
+        }
+
+        let mut dropped = Vec::new();
+        for post in self.posts.iter_mut() {
+            if post.publish_at > now {
+                continue;
+            }
+
+            if !post.sending {
+                if let Err(err) = post_is_due(post, accounts, ndb, now) {
+                    error!("dropping scheduled post {}: {err}", post.id);
+                    dropped.push(post.id);
+                    continue;
+                }
+                self.dirty = true;
+            }
+
+            // it goes again to relays that dropped and came back
+            post.sent_to.retain(|url| connected.contains(url));
+
+            let msg = match ClientMessage::event_json(post.note_json.clone()) {
+                Ok(msg) => msg,
+                Err(err) => {
+                    error!("scheduled post {}: {err}", post.id);
+                    continue;
+                }
+            };
+
+            for url in &connected {
+                if post.sent_to.insert(url.clone()) {
+                    info!("publishing scheduled post {} to {url}", post.id);
+                    pool.send_to(&msg, url);
+                }
+            }
+        }
+
+        if !dropped.is_empty() {
+            self.posts.retain(|post| !dropped.contains(&post.id));
+            self.dirty = true;
+        }
+    }
+
+    /// A relay answered one of the posts we sent. The first one to accept
+    /// it takes it off the queue.
+    pub fn ok_received(&mut self, relay: &str, event_id: &str, accepted: bool, message: &str) {
+        let Some(index) = self
+            .posts
+            .iter()
+            .position(|post| post.sending && post.note_id_hex().as_deref() == Some(event_id))
+        else {
+            return;
+        };
+
+        if accepted {
+            info!(
+                "scheduled post {} accepted by {relay}",
+                self.posts[index].id
+            );
+            self.posts.remove(index);
+            self.dirty = true;
+        } else {
+            warn!(
+                "scheduled post {} rejected by {relay}: {message}",
+                self.posts[index].id
+            );
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.posts.is_empty()
+    }
+
+    /// Has it changed since the last time we asked?
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+}
+
+/// Sign the post for good if it's signed at send time, and put it in our
+/// own db so it shows up right away like any other post
+fn post_is_due(
+    post: &mut ScheduledPost,
+    accounts: &Accounts,
+    ndb: &Ndb,
+    now: u64,
+) -> Result<(), String> {
+    if post.sign_at_send {
+        let Some(kp) = accounts.get_full(post.pubkey.bytes()) else {
+            return Err(format!("no secret key for {}", post.pubkey));
+        };
+
+        let seckey = kp.secret_key.to_secret_bytes();
+        post.note_json = resign(&post.note_json, None, &seckey, now)
+            .ok_or_else(|| "could not sign it".to_owned())?;
+        post.sign_at_send = false;
+    }
+    post.sending = true;
+
+    let raw_msg = format!("[\"EVENT\",{}]", post.note_json);
+    if let Err(err) = ndb.process_event_with(&raw_msg, nostrdb::IngestMetadata::new().client(true))
+    {
+        error!("could not process scheduled post {}: {err}", post.id);
+    }
+
+    Ok(())
+}
+
+/// Sign a note again with a new time, and maybe new content
+fn resign(
+    note_json: &str,
+    content: Option<&str>,
+    seckey: &[u8; 32],
+    created_at: u64,
+) -> Option<String> {
+    let note = enostr::Note::from_json(note_json).ok()?;
+
+    let mut builder = NoteBuilder::new()
+        .kind(note.kind as u32)
+        .content(content.unwrap_or(&note.content))
+        .created_at(created_at);
+
+    for tag in &note.tags {
+        builder = builder.start_tag();
+        for value in tag {
+            builder = builder.tag_str(value);
+        }
+    }
+
+    builder.sign(seckey).build()?.json().ok()
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// `YYYY-MM-DD HH:MM`, in UTC so everyone coordinating agrees on it
+pub fn format_utc(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let secs = timestamp % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        secs / 3600,
+        (secs % 3600) / 60
+    )
+}
+
+/// The reverse of [`format_utc`]
+pub fn parse_utc(s: &str) -> Option<u64> {
+    let (date, time) = s.trim().split_once(' ')?;
+
+    let mut date = date.splitn(3, '-').map(str::parse::<u32>);
+    let (year, month, day) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+
+    let (hour, minute) = time.trim().split_once(':')?;
+    let (hour, minute) = (hour.parse::<u64>().ok()?, minute.parse::<u64>().ok()?);
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return None;
+    }
+
+    let days = days_from_civil(year as i64, month, day);
+    // catches days past the end of the month
+    if civil_from_days(days) != (year as i64, month, day) {
+        return None;
+    }
+
+    u64::try_from(days * 86_400)
+        .ok()
+        .map(|secs| secs + hour * 3600 + minute * 60)
+}
+
+// Howard Hinnant's algorithms, http://howardhinnant.github.io/date_algorithms.html
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enostr::FullKeypair;
+
+    #[test]
+    fn test_utc_round_trip() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00");
+        assert_eq!(format_utc(1_700_000_000), "2023-11-14 22:13");
+        assert_eq!(parse_utc("2023-11-14 22:13"), Some(1_699_999_980));
+        assert_eq!(parse_utc(" 2024-02-29 08:05 "), Some(1_709_193_900));
+
+        assert_eq!(parse_utc("2023-02-29 08:05"), None);
+        assert_eq!(parse_utc("2023-11-14"), None);
+        assert_eq!(parse_utc("2023-11-14 24:00"), None);
+        assert_eq!(parse_utc("tomorrow"), None);
+    }
+
+    #[test]
+    fn test_schedule_and_reschedule() {
+        let kp = FullKeypair::generate();
+        let seckey = kp.secret_key.to_secret_bytes();
+        let note = NoteBuilder::new()
+            .kind(1)
+            .content("launch day")
+            .start_tag()
+            .tag_str("t")
+            .tag_str("launch")
+            .created_at(2000)
+            .sign(&seckey)
+            .build()
+            .unwrap();
+
+        let mut scheduled = ScheduledPosts::default();
+        let schedule = Schedule {
+            publish_at: 2000,
+            sign_at_send: false,
+        };
+        scheduled.add(kp.pubkey, schedule, &note).unwrap();
+        assert!(scheduled.take_dirty());
+        assert_eq!(scheduled.next_due_in(1000), Some(Duration::from_secs(1000)));
+
+        let id = scheduled.for_account(&kp.pubkey).next().unwrap().id;
+        scheduled
+            .reschedule(id, 3000, "launch day, for real", &seckey)
+            .unwrap();
+
+        let post = scheduled.for_account(&kp.pubkey).next().unwrap();
+        let resigned = enostr::Note::from_json(&post.note_json).unwrap();
+        assert_eq!(post.publish_at, 3000);
+        assert_eq!(resigned.created_at, 3000);
+        assert_eq!(resigned.content, "launch day, for real");
+        assert_eq!(
+            resigned.tags,
+            vec![vec!["t".to_owned(), "launch".to_owned()]]
+        );
+
+        assert!(scheduled.remove(id).is_some());
+        assert!(scheduled.is_empty());
+    }
+
+    #[test]
+    fn test_kept_until_accepted() {
+        let kp = FullKeypair::generate();
+        let seckey = kp.secret_key.to_secret_bytes();
+        let note = NoteBuilder::new()
+            .kind(1)
+            .content("going out")
+            .created_at(2000)
+            .sign(&seckey)
+            .build()
+            .unwrap();
+        let note_id = hex::encode(note.id());
+
+        let mut scheduled = ScheduledPosts::default();
+        let schedule = Schedule {
+            publish_at: 2000,
+            sign_at_send: false,
+        };
+        scheduled.add(kp.pubkey, schedule, &note).unwrap();
+        scheduled.posts[0].sending = true;
+        assert_eq!(scheduled.next_due_in(3000), None);
+
+        scheduled.ok_received("wss://a", &note_id, false, "blocked");
+        assert!(!scheduled.is_empty());
+
+        scheduled.ok_received("wss://b", &"00".repeat(32), true, "");
+        assert!(!scheduled.is_empty());
+
+        scheduled.ok_received("wss://c", &note_id, true, "");
+        assert!(scheduled.is_empty());
+    }
+}
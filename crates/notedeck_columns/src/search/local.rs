@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    mpsc::{self, TryRecvError},
+    Arc,
+};
+
+use nostrdb::{Filter, Ndb, Transaction};
+use notedeck::NoteRef;
+use tracing::{debug, error};
+
+/// How many results we query at a time. Smaller pages get the first
+/// results on screen sooner
+const PAGE_SIZE: u64 = 50;
+
+/// A nostrdb fulltext search running on a background thread. Results are
+/// queried a page at a time, newest first, and streamed back to the UI as
+/// they are found so that large databases don't block rendering.
+#[derive(Debug, Default)]
+pub struct LocalSearch {
+    job: Option<SearchJob>,
+}
+
+#[derive(Debug)]
+struct SearchJob {
+    cancelled: Arc<AtomicBool>,
+    pages: mpsc::Receiver<Vec<NoteRef>>,
+    done: bool,
+}
+
+impl LocalSearch {
+    /// Start a new search, cancelling the one in progress. `filter` builds
+    /// the query for each page, given the `until` bound of the page.
+    pub fn start<F>(&mut self, ndb: &Ndb, ctx: &egui::Context, max_results: usize, filter: F)
+    where
+        F: Fn(Option<u64>) -> Filter + Send + 'static,
+    {
+        self.cancel();
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (sender, pages) = mpsc::channel();
+
+        let ndb = ndb.clone();
+        let ctx = ctx.clone();
+        let flag = cancelled.clone();
+        std::thread::spawn(move || {
+            let mut found = 0;
+            let mut seen = HashSet::new();
+            let mut until: Option<u64> = None;
+
+            let txn = match Transaction::new(&ndb) {
+                Ok(txn) => txn,
+                Err(err) => {
+                    error!("search: could not open transaction: {err}");
+                    return;
+                }
+            };
+
+            while found < max_results && !flag.load(Ordering::Relaxed) {
+                let page_filter = filter(until).limit_mut(PAGE_SIZE);
+                let results: Vec<NoteRef> = match ndb.query(&txn, &[page_filter], PAGE_SIZE as i32)
+                {
+                    Ok(qrs) => qrs.into_iter().map(NoteRef::from_query_result).collect(),
+                    Err(err) => {
+                        error!("fulltext query failed: {err}");
+                        break;
+                    }
+                };
+
+                let Some(oldest) = results.iter().map(|nr| nr.created_at).min() else {
+                    break;
+                };
+
+                // `until` is inclusive, so we'll see the notes at the page
+                // boundary twice
+                let page: Vec<NoteRef> = results
+                    .into_iter()
+                    .filter(|nr| seen.insert(nr.key))
+                    .collect();
+
+                if page.is_empty() {
+                    // a full page of notes with the same timestamp, step
+                    // past them
+                    until = Some(oldest.saturating_sub(1));
+                    continue;
+                }
+
+                found += page.len();
+                until = Some(oldest);
+
+                if sender.send(page).is_err() {
+                    // nobody is listening anymore
+                    break;
+                }
+                ctx.request_repaint();
+            }
+
+            debug!("search finished with {found} results");
+        });
+
+        self.job = Some(SearchJob {
+            cancelled,
+            pages,
+            done: false,
+        });
+    }
+
+    /// Stop the search in progress, if any. Pages that are in flight are
+    /// discarded.
+    pub fn cancel(&mut self) {
+        if let Some(job) = self.job.take() {
+            job.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Collect the results that have come in since the last poll
+    pub fn poll(&mut self) -> Vec<NoteRef> {
+        let Some(job) = &mut self.job else {
+            return Vec::new();
+        };
+
+        let mut notes = Vec::new();
+        loop {
+            match job.pages.try_recv() {
+                Ok(page) => notes.extend(page),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    job.done = true;
+                    break;
+                }
+            }
+        }
+
+        notes
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.job.as_ref().is_some_and(|job| !job.done)
+    }
+}
@@ -0,0 +1,475 @@
+use enostr::Pubkey;
+use nostrdb::{Filter, FilterBuilder, Note};
+use rmpv::Value;
+use tokenator::{ParseError, TokenParser, TokenSerializable, TokenWriter};
+
+pub mod local;
+pub mod people;
+pub mod remote;
+mod syntax;
+
+pub use local::LocalSearch;
+pub use people::{PeopleSearch, PersonResult};
+pub use remote::{RemoteSearch, SearchTarget};
+pub use syntax::SearchSyntaxError;
+
+/// A search, as typed into the search box. Besides free text this can
+/// hold the operators of our search syntax, see [`SearchQuery::parse`].
+#[derive(Debug, Eq, PartialEq, Clone, Hash, Default)]
+pub struct SearchQuery {
+    author: Option<Pubkey>,
+    pub search: String,
+    pub kinds: Vec<u64>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub hashtags: Vec<String>,
+    pub mentions: Vec<Pubkey>,
+    pub has: Vec<ContentFeature>,
+    /// Words that must not appear in the note
+    pub excluded: Vec<String>,
+}
+
+/// Things a note can contain, for `has:` searches
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
+pub enum ContentFeature {
+    Image,
+    Video,
+    /// An image or a video
+    Media,
+    Link,
+}
+
+impl ContentFeature {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContentFeature::Image => "image",
+            ContentFeature::Video => "video",
+            ContentFeature::Media => "media",
+            ContentFeature::Link => "link",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "image" => Some(ContentFeature::Image),
+            "video" => Some(ContentFeature::Video),
+            "media" => Some(ContentFeature::Media),
+            "link" => Some(ContentFeature::Link),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, note: &Note) -> bool {
+        let mut urls = note
+            .content()
+            .split_whitespace()
+            .filter(|word| word.starts_with("https://") || word.starts_with("http://"));
+
+        match self {
+            ContentFeature::Link => urls.next().is_some(),
+            ContentFeature::Image => urls.any(is_image_url) || imeta_has_mime(note, "image/"),
+            ContentFeature::Video => urls.any(is_video_url) || imeta_has_mime(note, "video/"),
+            ContentFeature::Media => {
+                urls.any(|url| is_image_url(url) || is_video_url(url))
+                    || imeta_has_mime(note, "image/")
+                    || imeta_has_mime(note, "video/")
+            }
+        }
+    }
+}
+
+fn url_extension(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next()?;
+    let (_, ext) = path.rsplit_once('.')?;
+    Some(ext.to_lowercase())
+}
+
+fn is_image_url(url: &str) -> bool {
+    url_extension(url).is_some_and(|ext| {
+        matches!(
+            ext.as_str(),
+            "jpg" | "jpeg" | "png" | "gif" | "webp" | "avif"
+        )
+    })
+}
+
+fn is_video_url(url: &str) -> bool {
+    url_extension(url).is_some_and(|ext| matches!(ext.as_str(), "mp4" | "webm" | "mov"))
+}
+
+/// Does the note have an `imeta` tag with a mime type starting with `prefix`?
+fn imeta_has_mime(note: &Note, prefix: &str) -> bool {
+    note.tags().into_iter().any(|tag| {
+        if tag.get_str(0) != Some("imeta") {
+            return false;
+        }
+
+        (1..tag.count()).any(|i| {
+            tag.get_str(i)
+                .and_then(|entry| entry.strip_prefix("m "))
+                .is_some_and(|mime| mime.starts_with(prefix))
+        })
+    })
+}
+
+/// Marks the operators that are applied locally, which follow the nfilter
+const LOCAL_TOKEN: &str = "local";
+
+impl TokenSerializable for SearchQuery {
+    fn serialize_tokens(&self, writer: &mut TokenWriter) {
+        writer.write_token(&self.to_nfilter());
+
+        let local = self.local_query_string();
+        if !local.is_empty() {
+            // excluded words are free text and could contain our delimiter
+            writer.write_token(LOCAL_TOKEN);
+            writer.write_token(&hex::encode(local));
+        }
+    }
+
+    fn parse_from_tokens<'a>(parser: &mut TokenParser<'a>) -> Result<Self, ParseError<'a>> {
+        let Some(mut query) = SearchQuery::from_nfilter(parser.pull_token()?) else {
+            return Err(ParseError::DecodeFailed);
+        };
+
+        if parser.try_parse(|p| p.parse_token(LOCAL_TOKEN)).is_ok() {
+            let bytes =
+                hex::decode(parser.pull_token()?).map_err(|_| ParseError::HexDecodeFailed)?;
+            let local = String::from_utf8(bytes).map_err(|_| ParseError::DecodeFailed)?;
+            let local =
+                SearchQuery::parse(&local, |_| None).map_err(|_| ParseError::DecodeFailed)?;
+            query.has = local.has;
+            query.excluded = local.excluded;
+        }
+
+        Ok(query)
+    }
+}
+
+impl SearchQuery {
+    pub fn new(search: String) -> Self {
+        Self {
+            search,
+            ..Default::default()
+        }
+    }
+
+    /// Convert the query to a filter-compatible MessagePack value. The
+    /// `has:` and `-word` operators aren't filter fields, so they are left
+    /// out, see [`SearchQuery::serialize_tokens`]
+    fn to_msgpack_value(&self) -> Value {
+        let mut values: Vec<(Value, Value)> = Vec::with_capacity(2);
+        let search_str: &str = &self.search;
+        values.push(("search".into(), search_str.into()));
+        if let Some(pubkey) = self.author() {
+            values.push((
+                "authors".into(),
+                Value::Array(vec![Value::Binary(pubkey.bytes().to_vec())]),
+            ))
+        }
+
+        if !self.kinds.is_empty() {
+            values.push((
+                "kinds".into(),
+                Value::Array(self.kinds.iter().map(|k| (*k).into()).collect()),
+            ))
+        }
+
+        if let Some(since) = self.since {
+            values.push(("since".into(), since.into()))
+        }
+
+        if let Some(until) = self.until {
+            values.push(("until".into(), until.into()))
+        }
+
+        if !self.hashtags.is_empty() {
+            values.push((
+                "#t".into(),
+                Value::Array(self.hashtags.iter().map(|t| t.as_str().into()).collect()),
+            ))
+        }
+
+        if !self.mentions.is_empty() {
+            values.push((
+                "#p".into(),
+                Value::Array(
+                    self.mentions
+                        .iter()
+                        .map(|pk| Value::Binary(pk.bytes().to_vec()))
+                        .collect(),
+                ),
+            ))
+        }
+
+        Value::Map(values)
+    }
+
+    pub fn to_nfilter(&self) -> String {
+        let hrp = bech32::Hrp::parse_unchecked("nfilter");
+        let msgpack_value = self.to_msgpack_value();
+        let mut buf = vec![];
+        rmpv::encode::write_value(&mut buf, &msgpack_value)
+            .expect("expected nfilter to encode ok. too big?");
+
+        bech32::encode::<bech32::Bech32>(hrp, &buf).expect("expected bech32 nfilter to encode ok")
+    }
+
+    fn decode_value(value: &Value) -> Option<Self> {
+        let mut search: Option<String> = None;
+        let mut query = SearchQuery::default();
+
+        let values = if let Value::Map(values) = value {
+            values
+        } else {
+            return None;
+        };
+
+        for (key, value) in values {
+            let key_str: &str = if let Value::String(s) = key {
+                s.as_str()?
+            } else {
+                continue;
+            };
+
+            if key_str == "search" {
+                if let Value::String(search_str) = value {
+                    search = search_str.clone().into_str();
+                } else {
+                    continue;
+                }
+            } else if key_str == "authors" {
+                let authors = if let Value::Array(authors) = value {
+                    authors
+                } else {
+                    continue;
+                };
+
+                let author_value = if let Some(author_value) = authors.first() {
+                    author_value
+                } else {
+                    continue;
+                };
+
+                let author_bytes: &[u8] = if let Value::Binary(author_bytes) = author_value {
+                    author_bytes
+                } else {
+                    continue;
+                };
+
+                let pubkey = Pubkey::new(author_bytes.try_into().ok()?);
+                query.author = Some(pubkey);
+            } else if key_str == "kinds" {
+                query.kinds = array_values(value, Value::as_u64);
+            } else if key_str == "since" {
+                query.since = value.as_u64();
+            } else if key_str == "until" {
+                query.until = value.as_u64();
+            } else if key_str == "#t" {
+                query.hashtags = array_values(value, |v| v.as_str().map(|s| s.to_owned()));
+            } else if key_str == "#p" {
+                query.mentions =
+                    array_values(value, |v| Some(Pubkey::new(v.as_slice()?.try_into().ok()?)));
+            }
+        }
+
+        query.search = search?;
+
+        Some(query)
+    }
+
+    /// The parts of the query that relays understand. The `has:` and `-word`
+    /// operators are only applied locally, see [`SearchQuery::local_filter`]
+    pub fn filter(&self) -> FilterBuilder {
+        let kinds = if self.kinds.is_empty() {
+            vec![1]
+        } else {
+            self.kinds.clone()
+        };
+
+        let mut filter = Filter::new().kinds(kinds);
+
+        if !self.search.is_empty() {
+            filter = filter.search(&self.search);
+        }
+
+        if let Some(author) = &self.author {
+            filter = filter.authors([author.bytes()]);
+        }
+
+        if !self.mentions.is_empty() {
+            filter = filter.pubkeys(self.mentions.iter().map(|pk| pk.bytes()));
+        }
+
+        if !self.hashtags.is_empty() {
+            filter = filter.tags(self.hashtags.iter().map(|t| t.as_str()), 't');
+        }
+
+        if let Some(since) = self.since {
+            filter = filter.since(since);
+        }
+
+        if let Some(until) = self.until {
+            filter = filter.until(until);
+        }
+
+        filter
+    }
+
+    /// The full query for nostrdb, including the operators relays don't
+    /// support
+    pub fn local_filter(&self) -> FilterBuilder {
+        let filter = self.filter();
+
+        if self.has.is_empty() && self.excluded.is_empty() {
+            return filter;
+        }
+
+        let has = self.has.clone();
+        let excluded: Vec<String> = self.excluded.iter().map(|w| w.to_lowercase()).collect();
+        filter.custom(move |note| {
+            let content = note.content().to_lowercase();
+            has.iter().all(|feature| feature.matches(&note))
+                && !excluded.iter().any(|word| content.contains(word.as_str()))
+        })
+    }
+
+    pub fn from_nfilter(nfilter: &str) -> Option<Self> {
+        let (hrp, msgpack_data) = bech32::decode(nfilter).ok()?;
+        if hrp.as_str() != "nfilter" {
+            return None;
+        }
+
+        let value = rmpv::decode::read_value(&mut &msgpack_data[..]).ok()?;
+
+        Self::decode_value(&value)
+    }
+
+    pub fn author(&self) -> Option<&Pubkey> {
+        self.author.as_ref()
+    }
+}
+
+fn array_values<T>(value: &Value, f: impl Fn(&Value) -> Option<T>) -> Vec<T> {
+    value
+        .as_array()
+        .map(|values| values.iter().filter_map(f).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enostr::Pubkey;
+    use rmpv::Value;
+    use tokenator::{TokenParser, TokenSerializable, TokenWriter};
+
+    fn test_pubkey() -> Pubkey {
+        let bytes: [u8; 32] = [1; 32]; // Example public key
+        Pubkey::new(bytes)
+    }
+
+    #[test]
+    fn test_to_msgpack_value() {
+        let query = SearchQuery {
+            author: Some(test_pubkey()),
+            search: "nostrdb".to_string(),
+            ..Default::default()
+        };
+        let msgpack_value = query.to_msgpack_value();
+
+        if let Value::Map(values) = msgpack_value {
+            assert!(values
+                .iter()
+                .any(|(k, v)| *k == Value::String("search".into())
+                    && *v == Value::String("nostrdb".into())));
+            assert!(values
+                .iter()
+                .any(|(k, _v)| *k == Value::String("authors".into())));
+        } else {
+            panic!("Failed to encode SearchQuery to MessagePack");
+        }
+    }
+
+    #[test]
+    fn test_to_nfilter() {
+        let query = SearchQuery {
+            author: Some(test_pubkey()),
+            search: "nostrdb".to_string(),
+            ..Default::default()
+        };
+        let encoded = query.to_nfilter();
+        assert!(encoded.starts_with("nfilter"), "nfilter encoding failed");
+    }
+
+    #[test]
+    fn test_from_nfilter() {
+        let query = SearchQuery {
+            author: Some(test_pubkey()),
+            search: "nostrdb".to_string(),
+            ..Default::default()
+        };
+        let encoded = query.to_nfilter();
+        let decoded = SearchQuery::from_nfilter(&encoded).expect("Failed to decode nfilter");
+        assert_eq!(query, decoded);
+    }
+
+    #[test]
+    fn test_nfilter_roundtrip() {
+        let queries = vec![
+            SearchQuery {
+                author: None,
+                search: "nostrdb".to_string(),
+                ..Default::default()
+            },
+            SearchQuery {
+                author: Some(test_pubkey()),
+                search: "test".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        for query in queries {
+            let encoded = query.to_nfilter();
+            let decoded =
+                SearchQuery::from_nfilter(&encoded).expect("Failed to decode valid nfilter");
+            assert_eq!(query, decoded, "Roundtrip encoding/decoding failed");
+        }
+    }
+
+    #[test]
+    fn test_invalid_nfilter() {
+        let invalid_nfilter = "nfilter1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq";
+        assert!(SearchQuery::from_nfilter(invalid_nfilter).is_none());
+    }
+
+    #[test]
+    fn test_invalid_hrp() {
+        let invalid_nfilter = "invalid1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq";
+        assert!(SearchQuery::from_nfilter(invalid_nfilter).is_none());
+    }
+
+    #[test]
+    fn test_parse_from_tokens() {
+        let query = SearchQuery {
+            author: Some(test_pubkey()),
+            search: "nostrdb".to_string(),
+            ..Default::default()
+        };
+        let mut writer = TokenWriter::default();
+        query.serialize_tokens(&mut writer);
+        let tokens = [writer.str()];
+        let mut parser = TokenParser::new(&tokens);
+
+        let parsed =
+            SearchQuery::parse_from_tokens(&mut parser).expect("Failed to parse from tokens");
+
+        assert_eq!(query, parsed);
+    }
+
+    #[test]
+    fn test_parse_from_invalid_tokens() {
+        let mut parser = TokenParser::new(&[]);
+        assert!(SearchQuery::parse_from_tokens(&mut parser).is_err());
+    }
+}
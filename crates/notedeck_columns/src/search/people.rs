@@ -0,0 +1,306 @@
+use std::collections::HashSet;
+use std::sync::mpsc::{self, TryRecvError};
+
+use enostr::Pubkey;
+use nostrdb::{Filter, Ndb, Transaction};
+use notedeck::Nip05Status;
+use tracing::error;
+
+const MAX_PEOPLE_RESULTS: u32 = 100;
+
+/// A person found by a people search
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PersonResult {
+    pub pubkey: Pubkey,
+
+    /// The selected account follows them
+    pub followed: bool,
+
+    /// They have a NIP-05 identifier in their profile
+    pub has_nip05: bool,
+
+    /// Their NIP-05 identifier has been verified
+    pub nip05_verified: bool,
+}
+
+/// A people search running on a background thread
+#[derive(Debug, Default)]
+pub struct PeopleSearch {
+    pending: Option<mpsc::Receiver<Vec<PersonResult>>>,
+    pub results: Vec<PersonResult>,
+
+    /// Some identifiers in the results haven't been checked yet
+    verifying: bool,
+}
+
+impl PeopleSearch {
+    /// Start searching for people. A search that is still running gets
+    /// replaced, and its results are dropped.
+    pub fn start(
+        &mut self,
+        ndb: &Ndb,
+        ctx: &egui::Context,
+        query: String,
+        follows: HashSet<Pubkey>,
+    ) {
+        let (sender, receiver) = mpsc::channel();
+
+        let ndb = ndb.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let txn = match Transaction::new(&ndb) {
+                Ok(txn) => txn,
+                Err(err) => {
+                    error!("people search: could not open transaction: {err}");
+                    return;
+                }
+            };
+
+            let results = search_people(&ndb, &txn, &query, &follows, MAX_PEOPLE_RESULTS);
+            if sender.send(results).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+
+        self.pending = Some(receiver);
+    }
+
+    pub fn clear(&mut self) {
+        self.pending = None;
+        self.results.clear();
+        self.verifying = false;
+    }
+
+    /// Pick up the results of the search, if it finished. Returns true
+    /// when new results came in
+    pub fn poll(&mut self) -> bool {
+        let Some(pending) = &self.pending else {
+            return false;
+        };
+
+        match pending.try_recv() {
+            Ok(results) => {
+                self.verifying = results.iter().any(|r| r.has_nip05);
+                self.results = results;
+                self.pending = None;
+                true
+            }
+            Err(TryRecvError::Empty) => false,
+            Err(TryRecvError::Disconnected) => {
+                self.pending = None;
+                false
+            }
+        }
+    }
+
+    /// Rank people with a verified NIP-05 identifier ahead of everyone
+    /// else, including those that merely claim one. Identifiers are
+    /// verified in the background, so this needs calling again until
+    /// they've all been checked, see [`PeopleSearch::is_verifying`].
+    pub fn rank_verified(&mut self, mut status: impl FnMut(&Pubkey) -> Nip05Status) {
+        let mut changed = false;
+        self.verifying = false;
+
+        for result in self.results.iter_mut().filter(|r| r.has_nip05) {
+            let status = status(&result.pubkey);
+            self.verifying |= status == Nip05Status::Unknown;
+
+            let verified = status == Nip05Status::Verified;
+            changed |= verified != result.nip05_verified;
+            result.nip05_verified = verified;
+        }
+
+        if changed {
+            rank_people(&mut self.results);
+        }
+    }
+
+    /// We're still waiting on some NIP-05 identifiers to verify
+    pub fn is_verifying(&self) -> bool {
+        self.verifying
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+/// Search kind 0 profiles. Names and display names are looked up in
+/// nostrdb's profile index, and we then look for matches in `nip05` and
+/// `about` as well. Followed accounts come first, then accounts with a
+/// verified NIP-05 identifier, otherwise we keep the order nostrdb gave
+/// us. Verification happens later, see [`PeopleSearch::rank_verified`].
+pub fn search_people(
+    ndb: &Ndb,
+    txn: &Transaction,
+    query: &str,
+    follows: &HashSet<Pubkey>,
+    max_results: u32,
+) -> Vec<PersonResult> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut seen: HashSet<[u8; 32]> = HashSet::new();
+    let mut pubkeys: Vec<Pubkey> = Vec::new();
+
+    match ndb.search_profile(txn, query, max_results) {
+        Ok(results) => {
+            for pk in results {
+                if seen.insert(*pk) {
+                    pubkeys.push(Pubkey::new(*pk));
+                }
+            }
+        }
+        Err(err) => error!("profile search failed: {err}"),
+    }
+
+    let needle = query.to_lowercase();
+    let filter = Filter::new()
+        .kinds([0])
+        .limit(max_results as u64)
+        .custom(move |note| profile_fields_match(note.content(), &needle))
+        .build();
+
+    match ndb.query(txn, &[filter], max_results as i32) {
+        Ok(results) => {
+            for qr in results {
+                let pk = *qr.note.pubkey();
+                if seen.insert(pk) {
+                    pubkeys.push(Pubkey::new(pk));
+                }
+            }
+        }
+        Err(err) => error!("profile field search failed: {err}"),
+    }
+
+    let mut results: Vec<PersonResult> = pubkeys
+        .into_iter()
+        .take(max_results as usize)
+        .map(|pubkey| PersonResult {
+            followed: follows.contains(&pubkey),
+            has_nip05: has_nip05(ndb, txn, &pubkey),
+            nip05_verified: false,
+            pubkey,
+        })
+        .collect();
+
+    rank_people(&mut results);
+
+    results
+}
+
+/// Does the `nip05` or `about` of this kind 0 content contain `needle`?
+fn profile_fields_match(content: &str, needle: &str) -> bool {
+    // cheap check before we parse anything
+    if !content.to_lowercase().contains(needle) {
+        return false;
+    }
+
+    let Ok(profile) = serde_json::from_str::<serde_json::Value>(content) else {
+        return false;
+    };
+
+    ["nip05", "about"].iter().any(|field| {
+        profile
+            .get(field)
+            .and_then(|v| v.as_str())
+            .is_some_and(|v| v.to_lowercase().contains(needle))
+    })
+}
+
+fn has_nip05(ndb: &Ndb, txn: &Transaction, pubkey: &Pubkey) -> bool {
+    ndb.get_profile_by_pubkey(txn, pubkey.bytes())
+        .is_ok_and(|p| p.record().profile().and_then(|p| p.nip05()).is_some())
+}
+
+fn rank_people(results: &mut [PersonResult]) {
+    // sort is stable, so the original order is kept within each group
+    results.sort_by_key(|r| (!r.followed, !r.nip05_verified));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn person(n: u8, followed: bool, has_nip05: bool) -> PersonResult {
+        PersonResult {
+            pubkey: Pubkey::new([n; 32]),
+            followed,
+            has_nip05,
+            nip05_verified: false,
+        }
+    }
+
+    fn verified(n: u8, followed: bool) -> PersonResult {
+        PersonResult {
+            nip05_verified: true,
+            ..person(n, followed, true)
+        }
+    }
+
+    #[test]
+    fn test_rank_people() {
+        let mut results = vec![
+            person(1, false, false),
+            person(2, false, true),
+            verified(3, false),
+            person(4, true, false),
+            verified(5, true),
+        ];
+
+        rank_people(&mut results);
+
+        // an unverified nip05 doesn't count for anything
+        let order: Vec<u8> = results.iter().map(|r| r.pubkey.bytes()[0]).collect();
+        assert_eq!(order, vec![5, 4, 3, 1, 2]);
+    }
+
+    #[test]
+    fn test_rank_verified() {
+        // already ranked, the way search_people returns them
+        let mut search = PeopleSearch {
+            results: vec![
+                person(4, true, false),
+                person(1, false, true),
+                person(2, false, false),
+                person(3, false, true),
+            ],
+            ..Default::default()
+        };
+
+        // claiming a nip05 isn't enough, it has to verify
+        let mut statuses = HashMap::from([
+            (1, Nip05Status::Unknown),
+            (2, Nip05Status::Verified),
+            (3, Nip05Status::Unknown),
+        ]);
+        search.rank_verified(|pk| statuses[&pk.bytes()[0]]);
+
+        let order: Vec<u8> = search.results.iter().map(|r| r.pubkey.bytes()[0]).collect();
+        assert_eq!(order, vec![4, 1, 2, 3]);
+        assert!(!search.results[2].nip05_verified);
+        assert!(search.is_verifying());
+
+        // ranks change as the cache resolves
+        statuses.insert(1, Nip05Status::Failed);
+        statuses.insert(3, Nip05Status::Verified);
+        search.rank_verified(|pk| statuses[&pk.bytes()[0]]);
+
+        let order: Vec<u8> = search.results.iter().map(|r| r.pubkey.bytes()[0]).collect();
+        assert_eq!(order, vec![4, 3, 1, 2]);
+        assert!(!search.is_verifying());
+    }
+
+    #[test]
+    fn test_profile_fields_match() {
+        let content = r#"{"name":"bob","nip05":"bob@damus.io","about":"Building Nostr clients","picture":"https://example.com/nostr.png"}"#;
+
+        assert!(profile_fields_match(content, "damus.io"));
+        assert!(profile_fields_match(content, "nostr clients"));
+        assert!(!profile_fields_match(content, "example.com"));
+        assert!(!profile_fields_match(content, "alice"));
+    }
+}
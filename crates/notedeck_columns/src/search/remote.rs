@@ -0,0 +1,181 @@
+use std::collections::HashSet;
+use std::time::Instant;
+
+use enostr::{ClientMessage, NoteId, RelayPool};
+use nostrdb::{Filter, Ndb, NoteKey, Transaction};
+use notedeck::NoteRef;
+use tracing::debug;
+
+use crate::{
+    relay_info::RelayInfoCache,
+    subscriptions::{self, PendingRelays, SubKind, Subscriptions, RELAY_TIMEOUT},
+    timeline::TimelineKind,
+};
+
+/// Where the results of a relay search should be delivered
+#[derive(Debug, Clone)]
+pub enum SearchTarget {
+    /// The search view, keyed by its view state id
+    View(egui::Id),
+
+    /// A search column
+    Timeline(TimelineKind),
+}
+
+/// A NIP-50 search request sent to the relays that support it. Relays
+/// send us their stored results followed by EOSE, at which point we close
+/// the subscription on that relay. Relays that disconnect or go quiet
+/// are given up on. Notes we receive are ingested by
+/// nostrdb like any other event, we just keep track of their ids so that
+/// we can merge them into the local results once they are available.
+#[derive(Debug, Default)]
+pub struct RemoteSearch {
+    filter: Option<Filter>,
+    subid: Option<String>,
+
+    /// Relays we've sent the current search to
+    relays: PendingRelays,
+
+    /// Note ids we got from relays that may not be in nostrdb yet, and
+    /// when. nostrdb doesn't keep every note, so we give up on them after
+    /// a while.
+    incoming: Vec<(NoteId, Instant)>,
+
+    /// Results that we only found via relay search
+    pub remote_keys: HashSet<NoteKey>,
+}
+
+impl RemoteSearch {
+    /// A search that will be sent to relays on the first
+    /// [`RemoteSearch::update`]
+    pub fn new(filter: Filter) -> Self {
+        Self {
+            filter: Some(filter),
+            ..Default::default()
+        }
+    }
+
+    /// Start a new search, cancelling any search in progress. The request
+    /// is sent to relays on the next [`RemoteSearch::update`]
+    pub fn start(&mut self, filter: Filter, pool: &mut RelayPool, subs: &mut Subscriptions) {
+        self.stop(pool, subs);
+        self.remote_keys.clear();
+        self.filter = Some(filter);
+    }
+
+    /// Close our subscription on any relays that are still sending results
+    pub fn stop(&mut self, pool: &mut RelayPool, subs: &mut Subscriptions) {
+        if let Some(subid) = self.subid.take() {
+            for relay in self.relays.waiting() {
+                pool.send_to(&ClientMessage::close(subid.clone()), relay);
+            }
+            subs.subs.remove(&subid);
+        }
+
+        self.filter = None;
+        self.relays.clear();
+        self.incoming.clear();
+    }
+
+    /// Send the search to any search relays we haven't sent it to yet.
+    /// Relay info documents arrive asynchronously, so this is called
+    /// regularly to pick up relays we only just learned about, and to
+    /// stop waiting on relays that went away.
+    pub fn update(
+        &mut self,
+        target: &SearchTarget,
+        relay_info: &RelayInfoCache,
+        pool: &mut RelayPool,
+        subs: &mut Subscriptions,
+    ) {
+        let Some(filter) = &self.filter else {
+            return;
+        };
+
+        let now = Instant::now();
+        if let Some(subid) = &self.subid {
+            for relay in self.relays.expire(pool, now) {
+                debug!("search on {relay} timed out");
+                pool.send_to(&ClientMessage::close(subid.clone()), &relay);
+            }
+        }
+
+        for relay in relay_info.search_relays(pool) {
+            if self.relays.contains(&relay) {
+                continue;
+            }
+
+            let subid = self
+                .subid
+                .get_or_insert_with(|| {
+                    let subid = subscriptions::new_sub_id();
+                    subs.subs
+                        .insert(subid.clone(), SubKind::RemoteSearch(target.clone()));
+                    subid
+                })
+                .clone();
+
+            debug!("sending search to {relay}");
+            pool.send_to(&ClientMessage::req(subid, vec![filter.clone()]), &relay);
+            self.relays.sent(&relay, now);
+        }
+    }
+
+    /// A relay sent us a search result
+    pub fn note_received(&mut self, relay: &str, note_id: NoteId) {
+        if self.subid.is_some() {
+            self.relays.heard_from(relay, Instant::now());
+            self.incoming.push((note_id, Instant::now()));
+        }
+    }
+
+    /// A relay has sent all of its stored results
+    pub fn relay_finished(&mut self, relay: &str) {
+        self.relays.finish(relay);
+    }
+
+    pub fn is_searching(&self) -> bool {
+        !self.relays.is_done() || !self.incoming.is_empty()
+    }
+
+    /// The number of relays we sent the search to
+    pub fn num_relays(&self) -> usize {
+        self.relays.len()
+    }
+
+    /// Collect results that have made it into nostrdb. Notes that haven't
+    /// been ingested yet are kept around for the next poll, unless nostrdb
+    /// didn't take them (bad signature, kinds it drops).
+    pub fn poll_results(&mut self, ndb: &Ndb, txn: &Transaction) -> Vec<NoteRef> {
+        let mut found = Vec::new();
+        let now = Instant::now();
+
+        self.incoming.retain(|(note_id, received)| {
+            let Ok(note) = ndb.get_note_by_id(txn, note_id.bytes()) else {
+                let gave_up = now.duration_since(*received) > RELAY_TIMEOUT;
+                if gave_up {
+                    debug!("search result {} never made it into nostrdb", note_id.hex());
+                }
+                return !gave_up;
+            };
+
+            if let Some(key) = note.key() {
+                found.push(NoteRef {
+                    key,
+                    created_at: note.created_at(),
+                });
+            }
+
+            false
+        });
+
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    /// Remember which results only showed up because of a relay search
+    pub fn mark_remote(&mut self, refs: &[NoteRef]) {
+        self.remote_keys.extend(refs.iter().map(|nr| nr.key));
+    }
+}
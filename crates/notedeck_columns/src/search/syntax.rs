@@ -0,0 +1,342 @@
+use enostr::Pubkey;
+
+use super::{ContentFeature, SearchQuery};
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+#[derive(Debug, Clone, Eq, PartialEq, thiserror::Error)]
+pub enum SearchSyntaxError {
+    #[error("unknown user '{0}'")]
+    UnknownUser(String),
+
+    #[error("invalid kind '{0}'")]
+    InvalidKind(String),
+
+    #[error("invalid date '{0}', expected YYYY-MM-DD or a unix timestamp")]
+    InvalidDate(String),
+
+    #[error("unknown has: '{0}', expected image, video, media or link")]
+    UnknownFeature(String),
+}
+
+impl SearchQuery {
+    /// Parse a query typed into the search box. Besides free text we
+    /// support these operators:
+    ///
+    /// - `from:npub…` or `from:name`: notes by this user
+    /// - `mentions:npub…` or `mentions:name`: notes tagging this user
+    /// - `kind:30023`, or a comma separated list of kinds
+    /// - `since:2025-01-01` and `until:2025-02-01`, or unix timestamps
+    /// - `#hashtag`
+    /// - `has:image`, `has:video`, `has:media` or `has:link`
+    /// - `-word`: exclude notes containing this word
+    ///
+    /// Names are looked up with `resolve_name`.
+    pub fn parse(
+        input: &str,
+        resolve_name: impl Fn(&str) -> Option<Pubkey>,
+    ) -> Result<Self, SearchSyntaxError> {
+        let mut query = SearchQuery::default();
+        let mut words: Vec<&str> = Vec::new();
+
+        for token in input.split_whitespace() {
+            if let Some(user) = token.strip_prefix("from:") {
+                query.author = Some(parse_user(user, &resolve_name)?);
+            } else if let Some(user) = token.strip_prefix("mentions:") {
+                query.mentions.push(parse_user(user, &resolve_name)?);
+            } else if let Some(kinds) = token.strip_prefix("kind:") {
+                for kind in kinds.split(',') {
+                    let kind = kind
+                        .parse()
+                        .map_err(|_| SearchSyntaxError::InvalidKind(kind.to_owned()))?;
+                    query.kinds.push(kind);
+                }
+            } else if let Some(date) = token.strip_prefix("since:") {
+                query.since = Some(parse_date(date, false)?);
+            } else if let Some(date) = token.strip_prefix("until:") {
+                query.until = Some(parse_date(date, true)?);
+            } else if let Some(feature) = token.strip_prefix("has:") {
+                let feature = ContentFeature::from_name(feature)
+                    .ok_or_else(|| SearchSyntaxError::UnknownFeature(feature.to_owned()))?;
+                query.has.push(feature);
+            } else if let Some(hashtag) = token.strip_prefix('#').filter(|t| !t.is_empty()) {
+                query.hashtags.push(hashtag.to_lowercase());
+            } else if let Some(word) = token.strip_prefix('-').filter(|w| !w.is_empty()) {
+                query.excluded.push(word.to_owned());
+            } else {
+                words.push(token);
+            }
+        }
+
+        query.search = words.join(" ");
+
+        Ok(query)
+    }
+
+    /// The query in search box syntax. Users are written as npubs.
+    pub fn to_query_string(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+
+        if !self.search.is_empty() {
+            parts.push(self.search.clone());
+        }
+
+        if let Some(author) = self.author().and_then(|pk| pk.to_bech()) {
+            parts.push(format!("from:{author}"));
+        }
+
+        for mention in self.mentions.iter().filter_map(|pk| pk.to_bech()) {
+            parts.push(format!("mentions:{mention}"));
+        }
+
+        if !self.kinds.is_empty() {
+            let kinds: Vec<String> = self.kinds.iter().map(|k| k.to_string()).collect();
+            parts.push(format!("kind:{}", kinds.join(",")));
+        }
+
+        if let Some(since) = self.since {
+            parts.push(format!("since:{}", format_date(since, false)));
+        }
+
+        if let Some(until) = self.until {
+            parts.push(format!("until:{}", format_date(until, true)));
+        }
+
+        for hashtag in &self.hashtags {
+            parts.push(format!("#{hashtag}"));
+        }
+
+        let local = self.local_query_string();
+        if !local.is_empty() {
+            parts.push(local);
+        }
+
+        parts.join(" ")
+    }
+
+    /// Just the `has:` and `-word` operators in search box syntax. These
+    /// aren't nostr filter fields, so they are saved separately from the
+    /// nfilter.
+    pub(super) fn local_query_string(&self) -> String {
+        let has = self
+            .has
+            .iter()
+            .map(|feature| format!("has:{}", feature.name()));
+        let excluded = self.excluded.iter().map(|word| format!("-{word}"));
+        has.chain(excluded).collect::<Vec<String>>().join(" ")
+    }
+}
+
+fn parse_user(
+    user: &str,
+    resolve_name: impl Fn(&str) -> Option<Pubkey>,
+) -> Result<Pubkey, SearchSyntaxError> {
+    let user = user.trim_start_matches('@');
+
+    if let Ok(pubkey) = Pubkey::parse(user) {
+        return Ok(pubkey);
+    }
+
+    resolve_name(user).ok_or_else(|| SearchSyntaxError::UnknownUser(user.to_owned()))
+}
+
+/// Parse a `YYYY-MM-DD` date or a unix timestamp. Dates are in UTC. For
+/// `until:` we want the whole day included, so we use the end of the day.
+fn parse_date(date: &str, end_of_day: bool) -> Result<u64, SearchSyntaxError> {
+    let invalid = || SearchSyntaxError::InvalidDate(date.to_owned());
+
+    if let Ok(timestamp) = date.parse::<u64>() {
+        return Ok(timestamp);
+    }
+
+    let mut parts = date.splitn(3, '-');
+    let (Some(year), Some(month), Some(day)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+
+    let year: i64 = year.parse().map_err(|_| invalid())?;
+    let month: i64 = month.parse().map_err(|_| invalid())?;
+    let day: i64 = day.parse().map_err(|_| invalid())?;
+
+    if year < 1970 || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(invalid());
+    }
+
+    let days = days_from_civil(year, month, day);
+    // catches days past the end of the month, like 2025-02-30
+    if civil_from_days(days) != (year, month, day) {
+        return Err(invalid());
+    }
+
+    let start = days as u64 * SECONDS_PER_DAY;
+    if end_of_day {
+        Ok(start + SECONDS_PER_DAY - 1)
+    } else {
+        Ok(start)
+    }
+}
+
+/// The inverse of [`parse_date`]. Timestamps on a day boundary are written
+/// as dates, anything else as a unix timestamp.
+fn format_date(timestamp: u64, end_of_day: bool) -> String {
+    let day_start = if end_of_day { timestamp + 1 } else { timestamp };
+
+    if day_start % SECONDS_PER_DAY != 0 {
+        return timestamp.to_string();
+    }
+
+    let (year, month, day) = civil_from_days((day_start / SECONDS_PER_DAY) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Days since the unix epoch for a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = if days >= 0 { days } else { days - 146096 } / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokenator::{TokenParser, TokenSerializable, TokenWriter};
+
+    fn test_pubkey() -> Pubkey {
+        Pubkey::new([1; 32])
+    }
+
+    fn no_names(_name: &str) -> Option<Pubkey> {
+        None
+    }
+
+    #[test]
+    fn test_parse_operators() {
+        let npub = test_pubkey().to_bech().expect("npub");
+        let input = format!(
+            "nostrdb rocks from:{npub} kind:1,30023 #Nostr has:image -spam mentions:{}",
+            test_pubkey().hex()
+        );
+        let query = SearchQuery::parse(&input, no_names).expect("query");
+
+        assert_eq!(query.search, "nostrdb rocks");
+        assert_eq!(query.author(), Some(&test_pubkey()));
+        assert_eq!(query.kinds, vec![1, 30023]);
+        assert_eq!(query.hashtags, vec!["nostr".to_string()]);
+        assert_eq!(query.has, vec![ContentFeature::Image]);
+        assert_eq!(query.excluded, vec!["spam".to_string()]);
+        assert_eq!(query.mentions, vec![test_pubkey()]);
+    }
+
+    #[test]
+    fn test_parse_names() {
+        let query = SearchQuery::parse("from:@jb55 hello", |name| {
+            (name == "jb55").then(test_pubkey)
+        })
+        .expect("query");
+        assert_eq!(query.author(), Some(&test_pubkey()));
+        assert_eq!(query.search, "hello");
+
+        assert_eq!(
+            SearchQuery::parse("from:nobody", no_names),
+            Err(SearchSyntaxError::UnknownUser("nobody".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_dates() {
+        let query =
+            SearchQuery::parse("since:2025-01-01 until:2025-01-31", no_names).expect("query");
+        assert_eq!(query.since, Some(1735689600));
+        assert_eq!(query.until, Some(1738367999));
+
+        let query = SearchQuery::parse("since:1700000000", no_names).expect("query");
+        assert_eq!(query.since, Some(1700000000));
+
+        let query = SearchQuery::parse("since:2024-02-29", no_names).expect("leap day");
+        assert_eq!(query.since, Some(1709164800));
+
+        assert!(SearchQuery::parse("since:2025-13-01", no_names).is_err());
+        assert!(SearchQuery::parse("since:2025-02-29", no_names).is_err());
+        assert!(SearchQuery::parse("until:2025-02-31", no_names).is_err());
+        assert!(SearchQuery::parse("until:2025-04-31", no_names).is_err());
+        assert!(SearchQuery::parse("since:2100-02-29", no_names).is_err());
+        assert!(SearchQuery::parse("since:2025-06-00", no_names).is_err());
+        assert!(SearchQuery::parse("until:yesterday", no_names).is_err());
+    }
+
+    #[test]
+    fn test_invalid_operators() {
+        assert_eq!(
+            SearchQuery::parse("kind:note", no_names),
+            Err(SearchSyntaxError::InvalidKind("note".to_string()))
+        );
+        assert_eq!(
+            SearchQuery::parse("has:gif", no_names),
+            Err(SearchSyntaxError::UnknownFeature("gif".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_query_string_roundtrip() {
+        let npub = test_pubkey().to_bech().expect("npub");
+        let input = format!(
+            "damus from:{npub} mentions:{npub} kind:1 since:2024-02-29 until:1700000000 #zaps has:video -gm"
+        );
+        let query = SearchQuery::parse(&input, no_names).expect("query");
+
+        assert_eq!(query.to_query_string(), input);
+        assert_eq!(
+            SearchQuery::parse(&query.to_query_string(), no_names).expect("query"),
+            query
+        );
+    }
+
+    #[test]
+    fn test_structured_nfilter_roundtrip() {
+        let input = format!(
+            "nostrdb from:{} kind:30023 since:2025-01-01 until:2025-02-01 #nostr mentions:{}",
+            test_pubkey().hex(),
+            test_pubkey().hex()
+        );
+        let query = SearchQuery::parse(&input, no_names).expect("query");
+
+        let decoded = SearchQuery::from_nfilter(&query.to_nfilter()).expect("nfilter");
+        assert_eq!(query, decoded);
+    }
+
+    #[test]
+    fn test_local_operators_stay_out_of_nfilter() {
+        let query =
+            SearchQuery::parse("nostrdb #nostr has:media -spam:eggs", no_names).expect("query");
+
+        let decoded = SearchQuery::from_nfilter(&query.to_nfilter()).expect("nfilter");
+        assert!(decoded.has.is_empty());
+        assert!(decoded.excluded.is_empty());
+        assert_eq!(decoded.hashtags, query.hashtags);
+
+        let mut writer = TokenWriter::default();
+        query.serialize_tokens(&mut writer);
+        let tokens: Vec<&str> = writer.str().split(':').collect();
+        let mut parser = TokenParser::new(&tokens);
+        let parsed = SearchQuery::parse_from_tokens(&mut parser).expect("tokens");
+        assert_eq!(parsed, query);
+        assert!(parser.is_eof());
+    }
+}
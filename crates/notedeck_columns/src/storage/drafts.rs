@@ -0,0 +1,387 @@
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use enostr::{NoteId, Pubkey};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::{
+    draft::{Draft, Drafts, PollDraft, ScheduleDraft},
+    media_upload::Nip94Event,
+    post::{MentionType, PostBuffer},
+    ui::note::PostType,
+};
+
+use notedeck::{debouncer::Debouncer, poll::PollType, storage, DataPath, DataPathType, Directory};
+
+pub static DRAFTS_FILE: &str = "drafts.json";
+
+/// How often we check whether drafts need saving while typing
+const SAVE_DELAY: Duration = Duration::from_secs(2);
+
+pub fn load_drafts(path: &DataPath) -> Option<Drafts> {
+    let data_path = path.path(DataPathType::Setting);
+
+    let drafts_str = match Directory::new(data_path).get_file(DRAFTS_FILE.to_owned()) {
+        Ok(s) => s,
+        Err(e) => {
+            info!("Could not read drafts from file {}: {}", DRAFTS_FILE, e);
+            return None;
+        }
+    };
+
+    match serde_json::from_str::<SerializableDrafts>(&drafts_str) {
+        Ok(drafts) => Some(drafts.drafts()),
+        Err(e) => {
+            error!("Could not parse drafts: {}", e);
+            None
+        }
+    }
+}
+
+/// Writes drafts to disk when they change, at most every few seconds. The
+/// writing happens on a background thread, and whatever is left gets
+/// written when we shut down, see [`DraftsSaver::flush`].
+pub struct DraftsSaver {
+    debouncer: Debouncer,
+    last_saved: Option<String>,
+    writer: Option<mpsc::Sender<String>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DraftsSaver {
+    pub fn new(path: &DataPath) -> Self {
+        let data_path = path.path(DataPathType::Setting);
+        let (writer, serialized) = mpsc::channel::<String>();
+        let thread = std::thread::spawn(move || {
+            for serialized in serialized {
+                if let Err(e) =
+                    storage::write_file(&data_path, DRAFTS_FILE.to_string(), &serialized)
+                {
+                    error!("Could not write drafts to file {}: {}", DRAFTS_FILE, e);
+                }
+            }
+        });
+
+        Self {
+            debouncer: Debouncer::new(SAVE_DELAY),
+            last_saved: None,
+            writer: Some(writer),
+            thread: Some(thread),
+        }
+    }
+
+    pub fn try_save(&mut self, drafts: &Drafts) {
+        if !self.debouncer.should_act() {
+            return;
+        }
+        self.debouncer.bounce();
+
+        self.save(drafts);
+    }
+
+    /// Save right away, eg. when the app is closing
+    pub fn flush(&mut self, drafts: &Drafts) {
+        self.save(drafts);
+    }
+
+    fn save(&mut self, drafts: &Drafts) {
+        let serialized = match serde_json::to_string(&SerializableDrafts::from_drafts(drafts)) {
+            Ok(s) => s,
+            Err(e) => {
+                error!("Could not serialize drafts: {}", e);
+                return;
+            }
+        };
+
+        if self.last_saved.as_ref() == Some(&serialized) {
+            return;
+        }
+
+        // don't write an empty file on startup before anything was typed
+        if self.last_saved.is_none() && drafts.accounts().all(|(_, d)| d.unsent().is_empty()) {
+            self.last_saved = Some(serialized);
+            return;
+        }
+
+        let Some(writer) = &self.writer else {
+            return;
+        };
+
+        if writer.send(serialized.clone()).is_err() {
+            error!("drafts writer thread is gone");
+            return;
+        }
+
+        self.last_saved = Some(serialized);
+    }
+}
+
+impl Drop for DraftsSaver {
+    fn drop(&mut self) {
+        // closing the channel stops the thread once the last write is done
+        self.writer.take();
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                error!("drafts writer thread panicked");
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializableDrafts {
+    accounts: Vec<SerializableAccountDrafts>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializableAccountDrafts {
+    pubkey: Pubkey,
+    drafts: Vec<SerializableDraft>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum SerializablePostType {
+    New,
+    Reply { note_id: NoteId },
+    Quote { note_id: NoteId },
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializableMention {
+    start: usize,
+    end: usize,
+    pubkey: Pubkey,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializableDraft {
+    post_type: SerializablePostType,
+    text: String,
+
+    /// Only mentions that were picked from the list, unfinished ones are
+    /// just text
+    mentions: Vec<SerializableMention>,
+    media: Vec<Nip94Event>,
+    #[serde(default)]
+    content_warning: Option<String>,
+    #[serde(default)]
+    schedule: Option<SerializableSchedule>,
+    #[serde(default)]
+    poll: Option<SerializablePoll>,
+}
+
+/// The composer's fields as typed, they're checked when sending
+#[derive(Serialize, Deserialize)]
+struct SerializableSchedule {
+    time: String,
+    sign_at_send: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializablePoll {
+    options: Vec<String>,
+    poll_type: String,
+    ends_at: String,
+}
+
+impl SerializableDrafts {
+    fn from_drafts(drafts: &Drafts) -> Self {
+        let mut accounts: Vec<SerializableAccountDrafts> = drafts
+            .accounts()
+            .map(|(pubkey, account)| SerializableAccountDrafts {
+                pubkey: *pubkey,
+                drafts: account
+                    .unsent()
+                    .into_iter()
+                    .map(|(post_type, draft)| SerializableDraft::from_draft(&post_type, draft))
+                    .collect(),
+            })
+            .filter(|account| !account.drafts.is_empty())
+            .collect();
+
+        // so we can tell when nothing changed
+        accounts.sort_by_key(|account| *account.pubkey.bytes());
+
+        SerializableDrafts { accounts }
+    }
+
+    fn drafts(self) -> Drafts {
+        let mut drafts = Drafts::default();
+
+        for account in self.accounts {
+            let account_drafts = drafts.account_mut(&account.pubkey);
+            for saved in account.drafts {
+                let post_type = match saved.post_type {
+                    SerializablePostType::New => PostType::New,
+                    SerializablePostType::Reply { note_id } => PostType::Reply(note_id),
+                    SerializablePostType::Quote { note_id } => PostType::Quote(note_id),
+                };
+
+                saved.restore(account_drafts.get_from_post_type(&post_type));
+            }
+        }
+
+        drafts
+    }
+}
+
+impl SerializableDraft {
+    fn from_draft(post_type: &PostType, draft: &Draft) -> Self {
+        let post_type = match post_type {
+            PostType::New => SerializablePostType::New,
+            PostType::Reply(note_id) => SerializablePostType::Reply { note_id: *note_id },
+            PostType::Quote(note_id) => SerializablePostType::Quote { note_id: *note_id },
+        };
+
+        let mut mentions: Vec<SerializableMention> = draft
+            .buffer
+            .mentions
+            .values()
+            .filter_map(|info| match info.mention_type {
+                MentionType::Finalized(pubkey) => Some(SerializableMention {
+                    start: info.start_index,
+                    end: info.end_index,
+                    pubkey,
+                }),
+                MentionType::Pending => None,
+            })
+            .collect();
+        mentions.sort_by_key(|m| m.start);
+
+        SerializableDraft {
+            post_type,
+            text: draft.buffer.text_buffer.clone(),
+            mentions,
+            media: draft.uploaded_media.clone(),
+            content_warning: draft.content_warning.clone(),
+            schedule: draft
+                .schedule
+                .as_ref()
+                .map(|schedule| SerializableSchedule {
+                    time: schedule.time.clone(),
+                    sign_at_send: schedule.sign_at_send,
+                }),
+            poll: draft.poll.as_ref().map(|poll| SerializablePoll {
+                options: poll.options.clone(),
+                poll_type: poll.poll_type.as_str().to_owned(),
+                ends_at: poll.ends_at.clone(),
+            }),
+        }
+    }
+
+    fn restore(self, draft: &mut Draft) {
+        let mut buffer = PostBuffer::default();
+        buffer.text_buffer = self.text;
+
+        let num_chars = buffer.text_buffer.chars().count();
+        for mention in self.mentions {
+            if mention.start < mention.end && mention.end <= num_chars {
+                buffer.restore_mention(mention.start, mention.end, mention.pubkey);
+            }
+        }
+
+        draft.buffer = buffer;
+        draft.uploaded_media = self.media;
+        draft.content_warning = self.content_warning;
+        draft.schedule = self.schedule.map(|schedule| ScheduleDraft {
+            time: schedule.time,
+            sign_at_send: schedule.sign_at_send,
+        });
+        draft.poll = self.poll.map(|poll| PollDraft {
+            options: poll.options,
+            poll_type: PollType::parse(&poll.poll_type),
+            ends_at: poll.ends_at,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduled::format_utc;
+
+    fn jb55() -> Pubkey {
+        Pubkey::from_hex("32e1827635450ebb3c5a7d12c1f8e7b2b514439ac10a67eef3d9fd9c5c68e245")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_drafts_round_trip() {
+        let account = jb55();
+        let reply_to = NoteId::new([1; 32]);
+
+        let mut drafts = Drafts::default();
+        {
+            let account_drafts = drafts.account_mut(&account);
+
+            let compose = account_drafts.compose_mut();
+            compose.buffer.text_buffer = "hi @jb55".to_owned();
+            compose.buffer.restore_mention(3, 8, jb55());
+            compose.uploaded_media.push(Nip94Event::new(
+                "https://example.com/a.png".to_owned(),
+                10,
+                20,
+            ));
+            compose.content_warning = Some("spoilers".to_owned());
+            compose.schedule = Some(ScheduleDraft::new(2_000_000_000));
+
+            account_drafts
+                .reply_mut(reply_to.bytes())
+                .buffer
+                .text_buffer = "nice".to_owned();
+
+            // empty drafts aren't saved
+            account_drafts.quote_mut(&[2; 32]);
+
+            // a poll is something, even without any text
+            let mut poll = PollDraft::new(2_000_000_000);
+            poll.options = vec!["yes".to_owned(), "no".to_owned()];
+            poll.poll_type = PollType::MultipleChoice;
+            account_drafts.quote_mut(&[3; 32]).poll = Some(poll);
+        }
+
+        let json = serde_json::to_string(&SerializableDrafts::from_drafts(&drafts)).unwrap();
+        let restored = serde_json::from_str::<SerializableDrafts>(&json)
+            .unwrap()
+            .drafts();
+
+        let unsent = restored.account(&account).expect("account").unsent();
+        assert_eq!(unsent.len(), 3);
+
+        let (post_type, compose) = &unsent[0];
+        assert_eq!(*post_type, PostType::New);
+        assert_eq!(compose.buffer.text_buffer, "hi @jb55");
+        assert_eq!(compose.uploaded_media[0].url, "https://example.com/a.png");
+        assert_eq!(compose.content_warning.as_deref(), Some("spoilers"));
+        let schedule = compose.schedule.as_ref().expect("schedule");
+        assert_eq!(schedule.time, format_utc(2_000_000_000));
+        assert!(!schedule.sign_at_send);
+        assert_eq!(
+            compose.buffer.output().mentions,
+            vec![jb55()],
+            "mentions survive a restart"
+        );
+
+        let (post_type, reply) = &unsent[1];
+        assert_eq!(*post_type, PostType::Reply(reply_to));
+        assert_eq!(reply.buffer.text_buffer, "nice");
+
+        let (post_type, quote) = &unsent[2];
+        assert_eq!(*post_type, PostType::Quote(NoteId::new([3; 32])));
+        let poll = quote.poll.as_ref().expect("poll");
+        assert_eq!(poll.options, vec!["yes", "no"]);
+        assert_eq!(poll.poll_type, PollType::MultipleChoice);
+        assert_eq!(poll.ends_at, format_utc(2_000_000_000));
+    }
+
+    #[test]
+    fn test_content_warning_alone_is_a_draft() {
+        let mut draft = Draft::new();
+        assert!(draft.is_empty());
+        draft.content_warning = Some(String::new());
+        assert!(!draft.is_empty());
+    }
+}
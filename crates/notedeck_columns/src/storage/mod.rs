@@ -1,3 +1,7 @@
 mod decks;
+mod drafts;
+mod scheduled;
 
 pub use decks::{load_decks_cache, save_decks_cache, DECKS_CACHE_FILE};
+pub use drafts::{load_drafts, DraftsSaver, DRAFTS_FILE};
+pub use scheduled::{load_scheduled, save_scheduled, SCHEDULED_FILE};
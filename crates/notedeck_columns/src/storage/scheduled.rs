@@ -0,0 +1,53 @@
+use tracing::{error, info};
+
+use crate::scheduled::ScheduledPosts;
+
+use notedeck::{storage, DataPath, DataPathType, Directory};
+
+pub static SCHEDULED_FILE: &str = "scheduled.json";
+
+pub fn load_scheduled(path: &DataPath) -> Option<ScheduledPosts> {
+    let data_path = path.path(DataPathType::Setting);
+
+    let scheduled_str = match Directory::new(data_path).get_file(SCHEDULED_FILE.to_owned()) {
+        Ok(s) => s,
+        Err(e) => {
+            info!(
+                "Could not read scheduled posts from file {}: {}",
+                SCHEDULED_FILE, e
+            );
+            return None;
+        }
+    };
+
+    match serde_json::from_str::<ScheduledPosts>(&scheduled_str) {
+        Ok(scheduled) => Some(scheduled),
+        Err(e) => {
+            error!("Could not parse scheduled posts: {}", e);
+            None
+        }
+    }
+}
+
+/// Writes the queue to disk if it changed
+pub fn save_scheduled(path: &DataPath, scheduled: &mut ScheduledPosts) {
+    if !scheduled.take_dirty() {
+        return;
+    }
+
+    let serialized = match serde_json::to_string(scheduled) {
+        Ok(s) => s,
+        Err(e) => {
+            error!("Could not serialize scheduled posts: {}", e);
+            return;
+        }
+    };
+
+    let data_path = path.path(DataPathType::Setting);
+    if let Err(e) = storage::write_file(&data_path, SCHEDULED_FILE.to_string(), &serialized) {
+        error!(
+            "Could not write scheduled posts to file {}: {}",
+            SCHEDULED_FILE, e
+        );
+    }
+}
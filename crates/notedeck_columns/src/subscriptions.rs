@@ -1,12 +1,14 @@
-use crate::timeline::TimelineKind;
-use std::collections::HashMap;
+use crate::{search::SearchTarget, timeline::TimelineKind};
+use enostr::{NoteId, Pubkey, RelayPool, RelayStatus};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
 #[derive(Debug, Clone)]
 pub enum SubKind {
     /// Initial subscription. This is the first time we do a remote subscription
     /// for a timeline
-    Initial,
+    Initial(TimelineKind),
 
     /// One shot requests, we can just close after we receive EOSE
     OneShot,
@@ -17,6 +19,26 @@ pub enum SubKind {
     /// Filter.
     // TODO: generalize this to any list?
     FetchingContactList(TimelineKind),
+
+    /// A NIP-50 search sent to relays that support it. Closed per relay
+    /// once that relay sends EOSE.
+    RemoteSearch(SearchTarget),
+
+    /// An `until`-bounded request for notes older than the ones a
+    /// timeline has. Closed per relay on EOSE.
+    Backfill(TimelineKind),
+
+    /// A `since`/`until`-bounded request for the notes missing between
+    /// two notes of a timeline. Closed per relay on EOSE.
+    FillGap(TimelineKind),
+
+    /// The responses to a poll on screen. Kept open for new votes until
+    /// the poll goes off screen.
+    PollResponses(NoteId),
+
+    /// The contact lists of a profile on screen: theirs, and the ones
+    /// that mention them. Kept open until the profile closes.
+    ProfileFollows(Pubkey),
 }
 
 /// Subscriptions that need to be tracked at various stages. Sometimes we
@@ -30,3 +52,125 @@ pub struct Subscriptions {
 pub fn new_sub_id() -> String {
     Uuid::new_v4().to_string()
 }
+
+/// How long we wait on a relay that has gone quiet before we stop waiting
+/// for its EOSE
+pub const RELAY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The relays a one-shot request was sent to, and which of them we're
+/// still waiting on. A relay is done once it sends EOSE, disconnects, or
+/// goes quiet for longer than [`RELAY_TIMEOUT`], so that a dead relay
+/// can't keep a request open forever.
+#[derive(Debug, Default)]
+pub struct PendingRelays {
+    /// When we last heard from each relay we're waiting on
+    waiting: HashMap<String, Instant>,
+    finished: HashSet<String>,
+}
+
+impl PendingRelays {
+    pub fn sent(&mut self, relay: &str, now: Instant) {
+        self.finished.remove(relay);
+        self.waiting.insert(relay.to_owned(), now);
+    }
+
+    /// Whether the request was sent to this relay at all
+    pub fn contains(&self, relay: &str) -> bool {
+        self.waiting.contains_key(relay) || self.finished.contains(relay)
+    }
+
+    pub fn is_waiting_on(&self, relay: &str) -> bool {
+        self.waiting.contains_key(relay)
+    }
+
+    /// The relay sent us something, so it isn't dead yet
+    pub fn heard_from(&mut self, relay: &str, now: Instant) {
+        if let Some(last) = self.waiting.get_mut(relay) {
+            *last = now;
+        }
+    }
+
+    /// Returns true if we were waiting on this relay
+    pub fn finish(&mut self, relay: &str) -> bool {
+        if self.waiting.remove(relay).is_none() {
+            return false;
+        }
+
+        self.finished.insert(relay.to_owned());
+        true
+    }
+
+    /// Stop waiting on relays that disconnected or timed out. Returns the
+    /// ones that timed out, which still have our subscription open.
+    pub fn expire(&mut self, pool: &RelayPool, now: Instant) -> Vec<String> {
+        let connected: HashSet<&str> = pool
+            .relays
+            .iter()
+            .filter(|relay| matches!(relay.status(), RelayStatus::Connected))
+            .map(|relay| relay.url())
+            .collect();
+
+        let mut timed_out = Vec::new();
+        let mut gone = Vec::new();
+        for (relay, last) in &self.waiting {
+            if !connected.contains(relay.as_str()) {
+                gone.push(relay.clone());
+            } else if now.duration_since(*last) > RELAY_TIMEOUT {
+                timed_out.push(relay.clone());
+            }
+        }
+
+        for relay in gone.iter().chain(timed_out.iter()) {
+            self.finish(relay);
+        }
+
+        timed_out
+    }
+
+    /// Every relay is done
+    pub fn is_done(&self) -> bool {
+        self.waiting.is_empty()
+    }
+
+    /// The number of relays we sent the request to
+    pub fn len(&self) -> usize {
+        self.waiting.len() + self.finished.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn waiting(&self) -> impl Iterator<Item = &str> {
+        self.waiting.keys().map(String::as_str)
+    }
+
+    pub fn clear(&mut self) {
+        self.waiting.clear();
+        self.finished.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pending_relays_give_up_on_dead_relays() {
+        let now = Instant::now();
+        let mut pending = PendingRelays::default();
+        pending.sent("wss://relay.damus.io/", now);
+        pending.sent("wss://nos.lol/", now);
+        assert_eq!(pending.len(), 2);
+
+        assert!(pending.finish("wss://nos.lol/"));
+        assert!(!pending.finish("wss://nos.lol/"));
+        assert!(!pending.is_done());
+
+        // neither relay is connected in an empty pool
+        let pool = RelayPool::new();
+        assert!(pending.expire(&pool, now).is_empty());
+        assert!(pending.is_done());
+        assert!(pending.contains("wss://relay.damus.io/"));
+    }
+}
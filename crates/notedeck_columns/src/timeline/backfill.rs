@@ -0,0 +1,316 @@
+use std::time::Instant;
+
+use enostr::{ClientMessage, RelayPool, RelayStatus};
+use nostrdb::Filter;
+use notedeck::filter::default_remote_limit;
+use tracing::debug;
+
+use crate::subscriptions::{self, PendingRelays, SubKind, Subscriptions};
+
+use super::TimelineKind;
+
+/// Loads notes older than the oldest note in a timeline. When the user
+/// scrolls to the bottom of a column we first look in nostrdb, and once
+/// that runs dry we ask relays with an `until`-bounded REQ. When relays
+/// don't have anything older either, we stop asking. Relays that
+/// disconnect or stop answering are given up on.
+#[derive(Debug, Default)]
+pub struct Backfill {
+    /// The user has reached the bottom of the timeline
+    requested: bool,
+
+    /// Our request for older notes, if we're waiting on relays
+    remote: Option<RemoteBackfill>,
+
+    /// Where our last request to relays stopped
+    last_until: Option<u64>,
+
+    /// Relays had nothing older than what we have
+    exhausted: bool,
+}
+
+#[derive(Debug)]
+struct RemoteBackfill {
+    subid: String,
+
+    /// Relays we've sent the request to
+    relays: PendingRelays,
+
+    /// At least one relay sent EOSE, rather than going away
+    answered: bool,
+
+    /// Notes relays sent us for this request
+    received: usize,
+}
+
+impl Backfill {
+    /// Ask for older notes. This is cheap to call every frame, we only
+    /// load more once the previous request has finished.
+    pub fn request(&mut self) {
+        if !self.exhausted && self.remote.is_none() {
+            self.requested = true;
+        }
+    }
+
+    /// Take the pending request, if any
+    pub fn take_request(&mut self) -> bool {
+        std::mem::take(&mut self.requested)
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.requested || self.remote.is_some()
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.exhausted
+    }
+
+    /// Send `until`-bounded versions of the timeline's filters to every
+    /// relay in the pool it uses. If we've already asked for this exact
+    /// range, relays didn't get us any further and we're done.
+    pub fn send_remote(
+        &mut self,
+        kind: &TimelineKind,
+        filters: &[Filter],
+        until: u64,
+        pool: &mut RelayPool,
+        subs: &mut Subscriptions,
+    ) {
+        if self.last_until == Some(until) {
+            debug!("backfill for {:?} is stuck at {until}, stopping", kind);
+            self.exhausted = true;
+            return;
+        }
+
+        let filters = until_filters(filters, until);
+        let subid = subscriptions::new_sub_id();
+        subs.subs
+            .insert(subid.clone(), SubKind::Backfill(kind.clone()));
+
+        let relays: Vec<String> = pool
+            .relays
+            .iter()
+            .filter(|r| matches!(r.status(), RelayStatus::Connected))
+            .filter(|r| kind.uses_relay(r))
+            .map(|r| r.url().to_owned())
+            .filter(|url| url != "multicast")
+            .collect();
+
+        let now = Instant::now();
+        let mut sent = PendingRelays::default();
+        for relay in relays {
+            pool.send_to(&ClientMessage::req(subid.clone(), filters.clone()), &relay);
+            sent.sent(&relay, now);
+        }
+
+        if sent.is_empty() {
+            // nowhere to look for older notes
+            subs.subs.remove(&subid);
+            self.exhausted = true;
+            return;
+        }
+
+        debug!(
+            "backfilling {:?} before {until} from {} relays",
+            kind,
+            sent.len()
+        );
+
+        self.last_until = Some(until);
+        self.remote = Some(RemoteBackfill {
+            subid,
+            relays: sent,
+            answered: false,
+            received: 0,
+        });
+    }
+
+    /// A relay sent us a note for our request
+    pub fn note_received(&mut self, relay: &str) {
+        if let Some(remote) = &mut self.remote {
+            remote.relays.heard_from(relay, Instant::now());
+            remote.received += 1;
+        }
+    }
+
+    /// A relay has sent everything it has for our request. Once every
+    /// relay is done, we know whether there's anything older out there.
+    pub fn relay_finished(&mut self, relay: &str, subs: &mut Subscriptions) {
+        let Some(remote) = &mut self.remote else {
+            return;
+        };
+
+        if remote.relays.finish(relay) {
+            remote.answered = true;
+        }
+
+        self.finish_if_done(subs);
+    }
+
+    /// Stop waiting on relays that disconnected or went quiet. Call this
+    /// regularly while a request is out.
+    pub fn update(&mut self, pool: &mut RelayPool, subs: &mut Subscriptions) {
+        let Some(remote) = &mut self.remote else {
+            return;
+        };
+
+        for relay in remote.relays.expire(pool, Instant::now()) {
+            debug!("backfill on {relay} timed out");
+            pool.send_to(&ClientMessage::close(remote.subid.clone()), &relay);
+        }
+
+        self.finish_if_done(subs);
+    }
+
+    fn finish_if_done(&mut self, subs: &mut Subscriptions) {
+        let Some(remote) = &self.remote else {
+            return;
+        };
+
+        if !remote.relays.is_done() {
+            return;
+        }
+
+        if !remote.answered {
+            // nobody got to answer, so this doesn't tell us anything. We
+            // can ask for the same range again.
+            self.last_until = None;
+        } else if remote.received == 0 {
+            debug!("relays have no older notes, backfill done");
+            self.exhausted = true;
+        }
+
+        subs.subs.remove(&remote.subid);
+        self.remote = None;
+    }
+}
+
+/// The timeline's filters, bounded to notes at or before `until`
+pub fn until_filters(filters: &[Filter], until: u64) -> Vec<Filter> {
+    filters
+        .iter()
+        .map(|filter| {
+            let limit = filter
+                .limit()
+                .unwrap_or(default_remote_limit())
+                .min(default_remote_limit());
+
+            filter.clone().until_mut(until).limit_mut(limit)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote(n_relays: usize) -> RemoteBackfill {
+        let mut relays = PendingRelays::default();
+        for i in 0..n_relays {
+            relays.sent(&format!("wss://relay{i}"), Instant::now());
+        }
+
+        RemoteBackfill {
+            subid: "sub".to_owned(),
+            relays,
+            answered: false,
+            received: 0,
+        }
+    }
+
+    #[test]
+    fn test_backfill_exhausted_when_relays_have_nothing() {
+        let mut subs = Subscriptions::default();
+        let mut backfill = Backfill {
+            remote: Some(remote(2)),
+            ..Default::default()
+        };
+
+        // can't ask again while relays are still answering
+        backfill.request();
+        assert!(!backfill.take_request());
+
+        backfill.relay_finished("wss://relay0", &mut subs);
+        assert!(backfill.is_loading());
+
+        backfill.relay_finished("wss://relay1", &mut subs);
+        assert!(!backfill.is_loading());
+        assert!(backfill.is_exhausted());
+
+        backfill.request();
+        assert!(!backfill.take_request());
+    }
+
+    #[test]
+    fn test_backfill_continues_when_relays_send_notes() {
+        let mut subs = Subscriptions::default();
+        let mut backfill = Backfill {
+            remote: Some(remote(1)),
+            ..Default::default()
+        };
+
+        backfill.note_received("wss://relay0");
+        backfill.relay_finished("wss://relay0", &mut subs);
+        assert!(!backfill.is_exhausted());
+
+        backfill.request();
+        assert!(backfill.take_request());
+        assert!(!backfill.take_request());
+    }
+
+    #[test]
+    fn test_backfill_gives_up_on_disconnected_relays() {
+        let mut subs = Subscriptions::default();
+        let mut pool = RelayPool::new();
+        let mut backfill = Backfill {
+            remote: Some(remote(2)),
+            last_until: Some(1700000000),
+            ..Default::default()
+        };
+
+        // none of the relays are connected
+        backfill.update(&mut pool, &mut subs);
+        assert!(!backfill.is_loading());
+
+        // that isn't the same as relays having nothing older
+        assert!(!backfill.is_exhausted());
+        backfill.request();
+        assert!(backfill.take_request());
+        assert_eq!(backfill.last_until, None);
+    }
+
+    #[test]
+    fn test_backfill_stops_when_stuck() {
+        let mut subs = Subscriptions::default();
+        let mut pool = RelayPool::new();
+        let mut backfill = Backfill {
+            last_until: Some(1700000000),
+            ..Default::default()
+        };
+
+        let filters = vec![Filter::new().kinds([1]).build()];
+        backfill.send_remote(
+            &TimelineKind::Universe,
+            &filters,
+            1700000000,
+            &mut pool,
+            &mut subs,
+        );
+
+        assert!(backfill.is_exhausted());
+        assert!(subs.subs.is_empty());
+    }
+
+    #[test]
+    fn test_until_filters() {
+        let filters = vec![
+            Filter::new().kinds([1]).limit(500).build(),
+            Filter::new().kinds([1]).limit(10).build(),
+        ];
+
+        let bounded = until_filters(&filters, 1700000000);
+        assert_eq!(bounded[0].until(), Some(1700000000));
+        assert_eq!(bounded[0].limit(), Some(default_remote_limit()));
+        assert_eq!(bounded[1].limit(), Some(10));
+    }
+}
@@ -10,12 +10,17 @@ use notedeck::{filter, FilterState, NoteCache, NoteRef};
 
 use enostr::RelayPool;
 use nostrdb::{Filter, Ndb, Transaction};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use tracing::{debug, error, info, warn};
 
 #[derive(Default)]
 pub struct TimelineCache {
     pub timelines: HashMap<TimelineKind, Timeline>,
+
+    /// The relays our relay feeds browse, and whether that changed since
+    /// we last asked
+    relay_feeds: BTreeSet<String>,
+    relay_feeds_changed: bool,
 }
 
 pub enum Vitality<'a, M> {
@@ -63,6 +68,10 @@ impl TimelineCache {
                     id
                 );
                 self.timelines.remove(id);
+                if let Some(url) = id.relay_url() {
+                    self.relay_feeds.remove(url);
+                    self.relay_feeds_changed = true;
+                }
             }
 
             Ok(())
@@ -96,9 +105,23 @@ impl TimelineCache {
 
         // insert initial notes into timeline
         timeline.insert_new(txn, ndb, note_cache, notes);
+        if let Some(url) = id.relay_url() {
+            self.relay_feeds.insert(url.to_owned());
+            self.relay_feeds_changed = true;
+        }
         self.timelines.insert(id, timeline);
     }
 
+    /// The relays our relay feeds browse, if they changed since we last
+    /// asked
+    pub fn changed_relay_feeds(&mut self) -> Option<&BTreeSet<String>> {
+        if !std::mem::take(&mut self.relay_feeds_changed) {
+            return None;
+        }
+
+        Some(&self.relay_feeds)
+    }
+
     /// Get and/or update the notes associated with this timeline
     pub fn notes<'a>(
         &'a mut self,
@@ -186,6 +209,11 @@ impl TimelineCache {
         } else if let Some(filter) = timeline.filter.get_any_ready() {
             debug!("got open with *new* subscription for {:?}", &timeline.kind);
             let mut multi_sub = MultiSubscriber::new(filter.clone());
+            // relay feeds get their remote subscription when we send
+            // initial filters to their relay
+            if !timeline.kind.should_subscribe_everywhere() {
+                multi_sub = multi_sub.local_only();
+            }
             multi_sub.subscribe(ndb, pool);
             timeline.subscription = Some(multi_sub);
         } else {
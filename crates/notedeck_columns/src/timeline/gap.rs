@@ -0,0 +1,550 @@
+use std::collections::{HashMap, HashSet};
+
+use std::time::Instant;
+
+use enostr::{ClientMessage, NoteId, RelayPool};
+use nostrdb::{Ndb, Transaction};
+use notedeck::NoteRef;
+use tracing::debug;
+
+use crate::subscriptions::{PendingRelays, Subscriptions, RELAY_TIMEOUT};
+
+/// A time range in unix seconds, both ends included
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct TimeRange {
+    pub since: u64,
+    pub until: u64,
+}
+
+impl TimeRange {
+    pub fn new(since: u64, until: u64) -> Self {
+        Self { since, until }
+    }
+
+    fn overlaps(&self, other: &TimeRange) -> bool {
+        self.since <= other.until && other.since <= self.until
+    }
+}
+
+/// A set of time ranges, kept sorted and merged
+#[derive(Debug, Default, Clone)]
+struct Ranges(Vec<TimeRange>);
+
+impl Ranges {
+    fn add(&mut self, range: TimeRange) {
+        self.0.push(range);
+        self.0.sort_by_key(|r| r.since);
+
+        let mut merged: Vec<TimeRange> = Vec::with_capacity(self.0.len());
+        for range in self.0.drain(..) {
+            match merged.last_mut() {
+                Some(last) if range.since <= last.until.saturating_add(1) => {
+                    last.until = last.until.max(range.until);
+                }
+                _ => merged.push(range),
+            }
+        }
+
+        self.0 = merged;
+    }
+
+    /// The range that contains this point in time
+    fn containing(&self, time: u64) -> Option<&TimeRange> {
+        self.0.iter().find(|r| r.since <= time && time <= r.until)
+    }
+
+    /// Forget anything after `until`
+    fn cap(&mut self, until: u64) {
+        self.0.retain(|r| r.since <= until);
+        for range in &mut self.0 {
+            range.until = range.until.min(until);
+        }
+    }
+}
+
+/// A missing time range between two notes in a timeline
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Gap {
+    pub range: TimeRange,
+
+    /// We're asking relays for the notes in this gap
+    pub loading: bool,
+}
+
+/// What relays sent back for a request over a time range
+#[derive(Debug, Default)]
+struct RelayProgress {
+    received: u64,
+    oldest: Option<u64>,
+
+    /// Notes we haven't found in nostrdb yet, so we don't know when they
+    /// were created
+    unresolved: Vec<NoteId>,
+}
+
+impl RelayProgress {
+    fn resolve(&mut self, created_at: &mut impl FnMut(&NoteId) -> Option<u64>) {
+        self.unresolved.retain(|id| {
+            let Some(created_at) = created_at(id) else {
+                return true;
+            };
+
+            self.oldest = Some(self.oldest.map_or(created_at, |o| o.min(created_at)));
+            false
+        });
+    }
+}
+
+#[derive(Debug)]
+struct RangeRequest {
+    range: TimeRange,
+    limit: u64,
+
+    /// Relays that haven't sent EOSE yet
+    pending: PendingRelays,
+    progress: HashMap<String, RelayProgress>,
+}
+
+impl RangeRequest {
+    /// Requests for the open end of a timeline are its live subscription,
+    /// we never close those
+    fn is_live(&self) -> bool {
+        self.range.until == u64::MAX
+    }
+}
+
+/// A relay sent EOSE, but some of its notes haven't made it into nostrdb
+/// yet
+#[derive(Debug)]
+struct Settling {
+    relay: String,
+    range: TimeRange,
+    limit: u64,
+    progress: RelayProgress,
+    since: Instant,
+}
+
+/// Tracks which time ranges of a timeline we have every note for, so that
+/// we can tell where notes are missing. A relay covers a range once it
+/// sends EOSE for a request over it, unless it hit the request's limit,
+/// in which case it only covers back to the oldest note it sent. Relays
+/// that disconnect or time out before EOSE don't cover anything.
+///
+/// Notes that came from nostrdb, and notes loaded at the bottom of the
+/// timeline, are assumed to be complete.
+#[derive(Debug, Default)]
+pub struct Coverage {
+    local: Ranges,
+    relays: HashMap<String, Ranges>,
+
+    /// Requests relays haven't finished yet, by subscription id
+    requests: HashMap<String, RangeRequest>,
+
+    /// Finished requests waiting on nostrdb
+    settling: Vec<Settling>,
+
+    /// A gap the user wants filled
+    fill: Option<TimeRange>,
+
+    /// Gaps we've already filled on our own when they scrolled into view
+    auto_filled: HashSet<TimeRange>,
+}
+
+impl Coverage {
+    /// Notes in this range came from nostrdb
+    pub fn cover_local(&mut self, since: u64, until: u64) {
+        if since <= until {
+            self.local.add(TimeRange::new(since, until));
+        }
+    }
+
+    /// We sent a request for `range` to `relay`
+    pub fn track(&mut self, subid: &str, relay: &str, range: TimeRange, limit: u64) {
+        let request = self
+            .requests
+            .entry(subid.to_owned())
+            .or_insert_with(|| RangeRequest {
+                range,
+                limit,
+                pending: PendingRelays::default(),
+                progress: HashMap::new(),
+            });
+
+        request.pending.sent(relay, Instant::now());
+        request
+            .progress
+            .insert(relay.to_owned(), RelayProgress::default());
+    }
+
+    /// We're subscribing to `relay` again, eg. after a reconnect. Whatever
+    /// it covered up to now stops at `until`, we don't know what we
+    /// missed while we weren't connected.
+    pub fn relay_restarted(&mut self, relay: &str, until: u64) {
+        if let Some(ranges) = self.relays.get_mut(relay) {
+            ranges.cap(until);
+        }
+    }
+
+    /// We're still waiting on `relay` to finish this request
+    pub fn is_waiting_on(&self, subid: &str, relay: &str) -> bool {
+        self.requests
+            .get(subid)
+            .is_some_and(|req| req.pending.is_waiting_on(relay))
+    }
+
+    /// A relay sent a note for one of our requests. We find out when it
+    /// was created once it's in nostrdb, see [`Coverage::poll_received`].
+    pub fn note_received(&mut self, subid: &str, relay: &str, id: NoteId) {
+        let Some(request) = self.requests.get_mut(subid) else {
+            return;
+        };
+
+        request.pending.heard_from(relay, Instant::now());
+        if let Some(progress) = request.progress.get_mut(relay) {
+            progress.received += 1;
+            progress.unresolved.push(id);
+        }
+    }
+
+    /// Look up the notes relays sent us in nostrdb
+    pub fn poll_received(&mut self, ndb: &Ndb, txn: &Transaction) {
+        self.resolve(|id| {
+            ndb.get_note_by_id(txn, id.bytes())
+                .ok()
+                .map(|note| note.created_at())
+        });
+    }
+
+    fn resolve(&mut self, mut created_at: impl FnMut(&NoteId) -> Option<u64>) {
+        for request in self.requests.values_mut() {
+            for progress in request.progress.values_mut() {
+                progress.resolve(&mut created_at);
+            }
+        }
+
+        let now = Instant::now();
+        for mut settling in std::mem::take(&mut self.settling) {
+            settling.progress.resolve(&mut created_at);
+
+            // notes nostrdb never takes, eg. ones with a bad signature,
+            // shouldn't hold this up forever
+            let gave_up = now.duration_since(settling.since) > RELAY_TIMEOUT;
+            if settling.progress.unresolved.is_empty() || gave_up {
+                self.cover(
+                    &settling.relay,
+                    settling.range,
+                    settling.limit,
+                    &settling.progress,
+                );
+            } else {
+                self.settling.push(settling);
+            }
+        }
+    }
+
+    /// A relay sent EOSE for one of our requests. Returns true once every
+    /// relay we sent it to is done.
+    pub fn relay_finished(&mut self, subid: &str, relay: &str) -> bool {
+        let Some(request) = self.requests.get_mut(subid) else {
+            return false;
+        };
+
+        if !request.pending.finish(relay) {
+            return false;
+        }
+
+        let (range, limit) = (request.range, request.limit);
+        let progress = request.progress.remove(relay).unwrap_or_default();
+        let done = request.pending.is_done();
+        if done {
+            self.requests.remove(subid);
+        }
+
+        if progress.unresolved.is_empty() {
+            self.cover(relay, range, limit, &progress);
+        } else {
+            self.settling.push(Settling {
+                relay: relay.to_owned(),
+                range,
+                limit,
+                progress,
+                since: Instant::now(),
+            });
+        }
+
+        done
+    }
+
+    fn cover(&mut self, relay: &str, range: TimeRange, limit: u64, progress: &RelayProgress) {
+        let since = if progress.received < limit {
+            Some(range.since)
+        } else {
+            // there might be more notes than what we got
+            progress.oldest
+        };
+
+        if let Some(since) = since {
+            let covered = TimeRange::new(since, range.until);
+            debug!("{relay} covers {:?}", covered);
+            self.relays
+                .entry(relay.to_owned())
+                .or_default()
+                .add(covered);
+        }
+    }
+
+    /// Stop waiting on relays that disconnected or went quiet. They don't
+    /// cover anything, and we close gap requests on the ones that timed
+    /// out. Call this regularly.
+    pub fn update(&mut self, pool: &mut RelayPool, subs: &mut Subscriptions) {
+        let now = Instant::now();
+        self.requests.retain(|subid, request| {
+            for relay in request.pending.expire(pool, now) {
+                request.progress.remove(&relay);
+                if !request.is_live() {
+                    debug!("gap request on {relay} timed out");
+                    pool.send_to(&ClientMessage::close(subid.clone()), &relay);
+                }
+            }
+
+            // disconnected relays are done with it too
+            request
+                .progress
+                .retain(|relay, _| request.pending.is_waiting_on(relay));
+
+            if !request.pending.is_done() {
+                return true;
+            }
+
+            if !request.is_live() {
+                subs.subs.remove(subid);
+            }
+            false
+        });
+    }
+
+    fn is_loading(&self, range: &TimeRange) -> bool {
+        self.requests
+            .values()
+            .any(|req| req.range.overlaps(range) && !req.is_live())
+    }
+
+    /// The missing ranges between the notes of a timeline. We only look
+    /// below ranges relays have covered, above those we simply don't know.
+    pub fn gaps(&self, notes: &[NoteRef]) -> Vec<Gap> {
+        let mut covered = self.local.clone();
+        for range in self.relays.values().flat_map(|r| r.0.iter()) {
+            covered.add(*range);
+        }
+
+        let mut gaps: Vec<Gap> = Vec::new();
+        for range in &covered.0 {
+            // notes are newest first
+            let below = notes.partition_point(|n| n.created_at >= range.since);
+            let Some(older) = notes.get(below) else {
+                continue;
+            };
+
+            // nothing newer than the gap, so nothing to show it under
+            if below == 0 {
+                continue;
+            }
+
+            let range = TimeRange::new(older.created_at, range.since);
+            if covered
+                .containing(older.created_at)
+                .is_some_and(|r| r.until >= range.until)
+            {
+                continue;
+            }
+
+            gaps.push(Gap {
+                range,
+                loading: self.is_loading(&range),
+            });
+        }
+
+        gaps
+    }
+
+    /// Ask for the notes in a gap. Gaps that scroll into view are only
+    /// filled on their own once, after that it takes a click.
+    pub fn request_fill(&mut self, range: TimeRange, clicked: bool) {
+        if self.is_loading(&range) {
+            return;
+        }
+
+        if !clicked && !self.auto_filled.insert(range) {
+            return;
+        }
+
+        self.fill = Some(range);
+    }
+
+    pub fn take_fill(&mut self) -> Option<TimeRange> {
+        self.fill.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostrdb::NoteKey;
+
+    fn notes(times: &[u64]) -> Vec<NoteRef> {
+        times
+            .iter()
+            .enumerate()
+            .map(|(i, created_at)| NoteRef {
+                key: NoteKey::new(i as u64 + 1),
+                created_at: *created_at,
+            })
+            .collect()
+    }
+
+    const RELAY: &str = "wss://relay.damus.io";
+
+    /// Note ids that carry their own `created_at`, in place of nostrdb
+    fn note_id(created_at: u64) -> NoteId {
+        let mut id = [0u8; 32];
+        id[..8].copy_from_slice(&created_at.to_le_bytes());
+        NoteId::new(id)
+    }
+
+    fn created_at_of(id: &NoteId) -> Option<u64> {
+        Some(u64::from_le_bytes(id.bytes()[..8].try_into().ok()?))
+    }
+
+    /// A note that made it into nostrdb right away
+    fn receive(coverage: &mut Coverage, subid: &str, created_at: u64) {
+        coverage.note_received(subid, RELAY, note_id(created_at));
+        coverage.resolve(created_at_of);
+    }
+
+    #[test]
+    fn test_ranges_merge() {
+        let mut ranges = Ranges::default();
+        ranges.add(TimeRange::new(10, 20));
+        ranges.add(TimeRange::new(30, 40));
+        assert_eq!(ranges.0.len(), 2);
+
+        ranges.add(TimeRange::new(21, 29));
+        assert_eq!(ranges.0, vec![TimeRange::new(10, 40)]);
+
+        ranges.cap(25);
+        assert_eq!(ranges.0, vec![TimeRange::new(10, 25)]);
+    }
+
+    #[test]
+    fn test_gap_when_relay_hits_limit() {
+        let mut coverage = Coverage::default();
+        // what we had in nostrdb from last time
+        coverage.cover_local(100, 200);
+
+        // relay sent as many notes as we asked for, all newer than ours
+        coverage.track("initial", RELAY, TimeRange::new(140, u64::MAX), 2);
+        coverage.note_received("initial", RELAY, note_id(1000));
+        coverage.note_received("initial", RELAY, note_id(900));
+        assert!(coverage.relay_finished("initial", RELAY));
+
+        // we don't know how far back it got until nostrdb has the notes
+        let timeline = notes(&[1000, 900, 200, 150, 100]);
+        assert!(coverage.gaps(&timeline).is_empty());
+        coverage.resolve(created_at_of);
+
+        let gaps = coverage.gaps(&timeline);
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].range, TimeRange::new(200, 900));
+        assert!(!gaps[0].loading);
+    }
+
+    #[test]
+    fn test_no_gap_when_relay_has_everything() {
+        let mut coverage = Coverage::default();
+        coverage.cover_local(100, 200);
+
+        coverage.track("initial", RELAY, TimeRange::new(140, u64::MAX), 10);
+        receive(&mut coverage, "initial", 1000);
+        coverage.relay_finished("initial", RELAY);
+
+        assert!(coverage.gaps(&notes(&[1000, 200, 150, 100])).is_empty());
+    }
+
+    #[test]
+    fn test_filling_a_gap() {
+        let mut coverage = Coverage::default();
+        coverage.cover_local(100, 200);
+        coverage.track("initial", RELAY, TimeRange::new(140, u64::MAX), 1);
+        receive(&mut coverage, "initial", 1000);
+        coverage.relay_finished("initial", RELAY);
+
+        let gap = TimeRange::new(200, 1000);
+        assert_eq!(coverage.gaps(&notes(&[1000, 200, 100]))[0].range, gap);
+
+        coverage.request_fill(gap, false);
+        assert_eq!(coverage.take_fill(), Some(gap));
+
+        // only filled on its own once
+        coverage.request_fill(gap, false);
+        assert_eq!(coverage.take_fill(), None);
+
+        // relay hit the limit again, so the gap shrinks
+        coverage.track("fill", RELAY, gap, 1);
+        assert!(coverage.gaps(&notes(&[1000, 200, 100]))[0].loading);
+        receive(&mut coverage, "fill", 600);
+        assert!(coverage.relay_finished("fill", RELAY));
+
+        let timeline = notes(&[1000, 600, 200, 100]);
+        assert_eq!(coverage.gaps(&timeline)[0].range, TimeRange::new(200, 600));
+
+        // and then closes
+        coverage.track("fill2", RELAY, TimeRange::new(200, 600), 1);
+        coverage.relay_finished("fill2", RELAY);
+        assert!(coverage.gaps(&timeline).is_empty());
+    }
+
+    #[test]
+    fn test_relay_restarted() {
+        let mut coverage = Coverage::default();
+        coverage.cover_local(100, 200);
+        coverage.track("initial", RELAY, TimeRange::new(150, u64::MAX), 10);
+        coverage.relay_finished("initial", RELAY);
+
+        // we were offline between 300 and the new subscription, which
+        // hit its limit
+        coverage.relay_restarted(RELAY, 300);
+        coverage.track("again", RELAY, TimeRange::new(240, u64::MAX), 1);
+        receive(&mut coverage, "again", 2000);
+        coverage.relay_finished("again", RELAY);
+
+        let gaps = coverage.gaps(&notes(&[2000, 300, 200]));
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].range, TimeRange::new(300, 2000));
+    }
+
+    #[test]
+    fn test_no_coverage_from_dead_relays() {
+        let mut coverage = Coverage::default();
+        coverage.cover_local(100, 200);
+        coverage.track("initial", RELAY, TimeRange::new(140, u64::MAX), 1);
+        receive(&mut coverage, "initial", 1000);
+        coverage.relay_finished("initial", RELAY);
+
+        let gap = TimeRange::new(200, 1000);
+        let timeline = notes(&[1000, 200, 100]);
+        coverage.track("fill", RELAY, gap, 10);
+        assert!(coverage.is_waiting_on("fill", RELAY));
+        assert!(coverage.gaps(&timeline)[0].loading);
+
+        // the relay isn't connected, so we stop waiting and the gap stays
+        let mut pool = RelayPool::new();
+        let mut subs = Subscriptions::default();
+        coverage.update(&mut pool, &mut subs);
+        assert!(!coverage.is_waiting_on("fill", RELAY));
+
+        let gaps = coverage.gaps(&timeline);
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].range, gap);
+        assert!(!gaps[0].loading);
+    }
+}
@@ -0,0 +1,387 @@
+//! Columns for arbitrary [NIP-01](https://github.com/nostr-protocol/nips/blob/master/01.md)
+//! filters
+
+use crate::error::Error;
+use enostr::{NoteId, Pubkey};
+use nostrdb::Filter;
+use notedeck::filter::default_limit;
+use serde_json::{Map, Value};
+use std::hash::{Hash, Hasher};
+use tokenator::{ParseError, TokenParser, TokenSerializable, TokenWriter};
+
+/// A filter the user wrote themselves. The title isn't part of what
+/// makes two filters the same, so that renaming a column doesn't
+/// resubscribe it.
+#[derive(Debug, Clone, Default)]
+pub struct GenericFilter {
+    pub title: String,
+    pub kinds: Vec<u64>,
+    pub authors: Vec<Pubkey>,
+    /// Single letter tags, like `t` for hashtags
+    pub tags: Vec<(char, Vec<String>)>,
+    pub search: Option<String>,
+    pub since: Option<u64>,
+}
+
+impl PartialEq for GenericFilter {
+    fn eq(&self, other: &Self) -> bool {
+        self.kinds == other.kinds
+            && self.authors == other.authors
+            && self.tags == other.tags
+            && self.search == other.search
+            && self.since == other.since
+    }
+}
+
+impl Eq for GenericFilter {}
+
+impl Hash for GenericFilter {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.kinds.hash(state);
+        self.authors.hash(state);
+        self.tags.hash(state);
+        self.search.hash(state);
+        self.since.hash(state);
+    }
+}
+
+impl GenericFilter {
+    /// A filter without any of these would match every note
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+            && self.authors.is_empty()
+            && self.tags.is_empty()
+            && self.search.is_none()
+    }
+
+    pub fn filter(&self) -> Filter {
+        let mut filter = Filter::new();
+
+        if !self.kinds.is_empty() {
+            filter = filter.kinds(self.kinds.iter().copied());
+        }
+
+        if !self.authors.is_empty() {
+            filter = filter.authors(self.authors.iter().map(|pk| pk.bytes()));
+        }
+
+        for (tag, values) in &self.tags {
+            // nostrdb stores 64 character hex tag values, like the pubkeys
+            // in `p` tags and note ids in `e` tags, as ids rather than
+            // strings, so those only match id filters
+            let pubkeys: Option<Vec<Pubkey>> = (*tag == 'p')
+                .then(|| values.iter().map(|v| Pubkey::from_hex(v).ok()).collect())
+                .flatten();
+            let notes: Option<Vec<NoteId>> = (*tag == 'e')
+                .then(|| values.iter().map(|v| NoteId::from_hex(v).ok()).collect())
+                .flatten();
+
+            filter = match (pubkeys, notes) {
+                (Some(pubkeys), _) => filter.pubkeys(pubkeys.iter().map(|pk| pk.bytes())),
+                (_, Some(notes)) => filter.events(notes.iter().map(|id| id.bytes())),
+                _ => filter.tags(values.iter().map(String::as_str), *tag),
+            };
+        }
+
+        if let Some(search) = &self.search {
+            filter = filter.search(search);
+        }
+
+        if let Some(since) = self.since {
+            filter = filter.since(since);
+        }
+
+        filter.limit(default_limit()).build()
+    }
+
+    /// The NIP-01 filter, without the title
+    pub fn to_json(&self) -> String {
+        let mut obj = Map::new();
+
+        if !self.kinds.is_empty() {
+            obj.insert("kinds".to_owned(), self.kinds.clone().into());
+        }
+
+        if !self.authors.is_empty() {
+            let authors: Vec<String> = self.authors.iter().map(|pk| pk.hex()).collect();
+            obj.insert("authors".to_owned(), authors.into());
+        }
+
+        for (tag, values) in &self.tags {
+            obj.insert(format!("#{tag}"), values.clone().into());
+        }
+
+        if let Some(search) = &self.search {
+            obj.insert("search".to_owned(), search.clone().into());
+        }
+
+        if let Some(since) = self.since {
+            obj.insert("since".to_owned(), since.into());
+        }
+
+        Value::Object(obj).to_string()
+    }
+
+    /// Read a NIP-01 filter. We don't support `ids` or `until`, since a
+    /// column is meant to keep getting new notes.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let invalid = |field: &str| Error::Generic(format!("invalid filter field '{field}'"));
+
+        let value = serde_json::from_str(json).map_err(|e| Error::Generic(e.to_string()))?;
+        let Value::Object(obj) = value else {
+            return Err(Error::Generic("filters are json objects".to_owned()));
+        };
+
+        let mut filter = GenericFilter::default();
+        for (key, value) in obj {
+            match key.as_str() {
+                "kinds" => {
+                    filter.kinds = serde_json::from_value(value).map_err(|_| invalid(&key))?;
+                }
+                "authors" => {
+                    let authors: Vec<String> =
+                        serde_json::from_value(value).map_err(|_| invalid(&key))?;
+                    filter.authors = authors
+                        .iter()
+                        .map(|pk| Pubkey::from_hex(pk))
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| invalid(&key))?;
+                }
+                "search" => {
+                    filter.search = Some(value.as_str().ok_or_else(|| invalid(&key))?.to_owned());
+                }
+                "since" => {
+                    filter.since = Some(value.as_u64().ok_or_else(|| invalid(&key))?);
+                }
+                // we pick our own limit
+                "limit" => {}
+                _ => {
+                    let Some(tag) = key.strip_prefix('#').and_then(single_letter) else {
+                        return Err(Error::Generic(format!("unsupported filter field '{key}'")));
+                    };
+                    let values = serde_json::from_value(value).map_err(|_| invalid(&key))?;
+                    filter.tags.push((tag, values));
+                }
+            }
+        }
+
+        Ok(filter)
+    }
+
+    /// Build a filter from what the user typed in the add column form
+    ///
+    /// - `kinds`: numbers, separated by commas or spaces
+    /// - `authors`: npubs or hex pubkeys
+    /// - `tags`: `t=nostr p=npub1...`, one letter tag name per value
+    /// - `since`: a unix timestamp, or how long ago like `12h` or `7d`
+    pub fn from_form(form: &GenericFilterForm, now: u64) -> Result<Self, String> {
+        let mut filter = GenericFilter {
+            title: form.title.trim().to_owned(),
+            ..Default::default()
+        };
+
+        for kind in split_list(&form.kinds) {
+            let kind = kind
+                .parse()
+                .map_err(|_| format!("'{kind}' is not a kind number"))?;
+            filter.kinds.push(kind);
+        }
+
+        for author in split_list(&form.authors) {
+            let pk = Pubkey::parse(author).map_err(|_| format!("'{author}' is not a pubkey"))?;
+            filter.authors.push(pk);
+        }
+
+        for tag in split_list(&form.tags) {
+            let Some((name, value)) = tag.split_once('=') else {
+                return Err(format!("'{tag}' should look like t=nostr"));
+            };
+
+            let Some(name) = single_letter(name.trim_start_matches('#')) else {
+                return Err(format!("'{name}' is not a single letter tag"));
+            };
+
+            // pubkey tags are matched by their hex form
+            let value = if name == 'p' {
+                Pubkey::parse(value)
+                    .map_err(|_| format!("'{value}' is not a pubkey"))?
+                    .hex()
+            } else {
+                value.to_owned()
+            };
+
+            match filter.tags.iter_mut().find(|(t, _)| *t == name) {
+                Some((_, values)) => values.push(value),
+                None => filter.tags.push((name, vec![value])),
+            }
+        }
+
+        let search = form.search.trim();
+        if !search.is_empty() {
+            filter.search = Some(search.to_owned());
+        }
+
+        let since = form.since.trim();
+        if !since.is_empty() {
+            filter.since = Some(parse_since(since, now)?);
+        }
+
+        if filter.is_empty() {
+            return Err("Add at least one kind, author, tag or search".to_owned());
+        }
+
+        Ok(filter)
+    }
+}
+
+/// The text fields of the add column form
+#[derive(Debug, Clone, Default)]
+pub struct GenericFilterForm {
+    pub title: String,
+    pub kinds: String,
+    pub authors: String,
+    pub tags: String,
+    pub search: String,
+    pub since: String,
+}
+
+fn single_letter(name: &str) -> Option<char> {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+fn split_list(s: &str) -> impl Iterator<Item = &str> {
+    s.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|item| !item.is_empty())
+}
+
+fn parse_since(since: &str, now: u64) -> Result<u64, String> {
+    let invalid = || format!("'{since}' should be a timestamp, or like 12h or 7d");
+
+    let (amount, unit) = match since.char_indices().last() {
+        Some((i, 'h')) => (&since[..i], 60 * 60),
+        Some((i, 'd')) => (&since[..i], 60 * 60 * 24),
+        Some((i, 'w')) => (&since[..i], 60 * 60 * 24 * 7),
+        _ => return since.parse().map_err(|_| invalid()),
+    };
+
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    Ok(now.saturating_sub(amount * unit))
+}
+
+impl TokenSerializable for GenericFilter {
+    fn serialize_tokens(&self, writer: &mut TokenWriter) {
+        // both are free text and could contain our delimiter
+        writer.write_token(&hex::encode(self.to_json()));
+        writer.write_token(&hex::encode(&self.title));
+    }
+
+    fn parse_from_tokens<'a>(parser: &mut TokenParser<'a>) -> Result<Self, ParseError<'a>> {
+        parser.parse_all(|p| {
+            let json = parse_hex_string(p)?;
+            let title = parse_hex_string(p)?;
+            let mut filter =
+                GenericFilter::from_json(&json).map_err(|_| ParseError::DecodeFailed)?;
+            filter.title = title;
+            Ok(filter)
+        })
+    }
+}
+
+fn parse_hex_string<'a>(parser: &mut TokenParser<'a>) -> Result<String, ParseError<'a>> {
+    let bytes = hex::decode(parser.pull_token()?).map_err(|_| ParseError::HexDecodeFailed)?;
+    String::from_utf8(bytes).map_err(|_| ParseError::DecodeFailed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostrdb::{Config, IngestMetadata, Ndb, Transaction};
+    use tempfile::TempDir;
+
+    const REPLY: &str = r#"{"id":"991a9af01ca6581c4f65cbe5c5ee4a214c690313679edb7bf053ad62b7db16e1","pubkey":"94bdd61bdc97db14423926d663d33c6ab6dd5e59d38ba26bf14ffb8b40f00a18","created_at":1743191138,"kind":1,"tags":[["e","0303030303030303030303030303030303030303030303030303030303030303"]],"content":"reply","sig":"affc668d2c77eef4baf67a7a226957361d64a286a74653337ef30c46e2454d8171f5542dc8ca4393ab217404b5ef0d098218166dd9f42112cf5ddf0afae6fefd"}"#;
+
+    #[test]
+    fn test_from_form() {
+        let pk = Pubkey::new([1; 32]);
+        let form = GenericFilterForm {
+            title: " Art ".to_owned(),
+            kinds: "1, 20".to_owned(),
+            authors: pk.hex(),
+            tags: "t=art #t=photography".to_owned(),
+            search: String::new(),
+            since: "2d".to_owned(),
+        };
+
+        let filter = GenericFilter::from_form(&form, 1_000_000).unwrap();
+        assert_eq!(filter.title, "Art");
+        assert_eq!(filter.kinds, vec![1, 20]);
+        assert_eq!(filter.authors, vec![pk]);
+        assert_eq!(
+            filter.tags,
+            vec![('t', vec!["art".to_owned(), "photography".to_owned()])]
+        );
+        assert_eq!(filter.since, Some(1_000_000 - 2 * 24 * 60 * 60));
+
+        assert!(GenericFilter::from_form(&GenericFilterForm::default(), 0).is_err());
+        let bad_kind = GenericFilterForm {
+            kinds: "note".to_owned(),
+            ..Default::default()
+        };
+        assert!(GenericFilter::from_form(&bad_kind, 0).is_err());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let json = r##"{"kinds":[30023],"#t":["nostr"],"search":"relays","limit":10}"##;
+        let filter = GenericFilter::from_json(json).unwrap();
+        assert_eq!(filter.kinds, vec![30023]);
+        assert_eq!(filter.tags, vec![('t', vec!["nostr".to_owned()])]);
+        assert_eq!(filter.search.as_deref(), Some("relays"));
+        assert_eq!(GenericFilter::from_json(&filter.to_json()).unwrap(), filter);
+
+        assert!(GenericFilter::from_json(r#"{"ids":["00"]}"#).is_err());
+    }
+
+    #[test]
+    fn test_tokens_keep_title() {
+        let filter = GenericFilter {
+            title: "notes: mine".to_owned(),
+            authors: vec![Pubkey::new([2; 32])],
+            ..Default::default()
+        };
+
+        let mut writer = TokenWriter::default();
+        filter.serialize_tokens(&mut writer);
+        let data = writer.str().split(":").collect::<Vec<&str>>();
+        let mut parser = TokenParser::new(&data);
+        let parsed = GenericFilter::parse_from_tokens(&mut parser).unwrap();
+
+        assert_eq!(parsed, filter);
+        assert_eq!(parsed.title, filter.title);
+    }
+
+    #[tokio::test]
+    async fn test_event_tags_match_locally() {
+        let tmp_dir = TempDir::new().unwrap();
+        let ndb = Ndb::new(tmp_dir.path().to_str().unwrap(), &Config::new()).unwrap();
+
+        let filter = GenericFilter::from_json(&format!(
+            r##"{{"kinds":[1],"#e":["{}"]}}"##,
+            hex::encode([3; 32])
+        ))
+        .unwrap()
+        .filter();
+        let sub_id = ndb.subscribe(&[filter.clone()]).unwrap();
+        let ev = format!(r#"["EVENT", "random_string", {REPLY}]"#);
+        assert!(ndb.process_event_with(&ev, IngestMetadata::new()).is_ok());
+        ndb.wait_for_notes(sub_id, 1).await.unwrap();
+
+        let txn = Transaction::new(&ndb).unwrap();
+        let results = ndb.query(&txn, &[filter], 10).unwrap();
+        assert_eq!(results.len(), 1);
+    }
+}
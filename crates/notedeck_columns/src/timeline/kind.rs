@@ -1,19 +1,21 @@
 use crate::{
-    error::Error,
-    search::SearchQuery,
-    timeline::{Timeline, TimelineTab},
+    search::{RemoteSearch, SearchQuery},
+    timeline::{GenericFilter, Timeline, TimelineTab},
 };
-use enostr::{Filter, NoteId, Pubkey};
-use nostrdb::{Ndb, Transaction};
+use enostr::{Filter, NoteId, PoolRelay, Pubkey};
+use nostrdb::{Ndb, Note, Transaction};
 use notedeck::{
     filter::{self, default_limit},
+    lists::{self, KIND_BOOKMARKS, KIND_BOOKMARK_SET, KIND_FOLLOW_SET},
+    poll::KIND_POLL,
     FilterError, FilterState, NoteCache, RootIdError, RootNoteIdBuf,
 };
 use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 use std::{borrow::Cow, fmt::Display};
 use tokenator::{ParseError, TokenParser, TokenSerializable, TokenWriter};
-use tracing::{error, warn};
+use tracing::error;
+use url::Url;
 
 #[derive(Clone, Hash, Copy, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PubkeySource {
@@ -22,15 +24,197 @@ pub enum PubkeySource {
     DeckAuthor,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Hash, Eq)]
+/// A list a column gets its notes from
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
 pub enum ListKind {
     Contact(Pubkey),
+
+    /// A NIP-51 follow set, by its `d` tag
+    FollowSet(Pubkey, String),
+
+    /// NIP-51 bookmarks
+    Bookmarks(Pubkey),
+
+    /// A NIP-51 bookmark set, by its `d` tag
+    BookmarkSet(Pubkey, String),
 }
 
 impl ListKind {
     pub fn pubkey(&self) -> Option<&Pubkey> {
+        Some(self.author())
+    }
+
+    fn author(&self) -> &Pubkey {
+        match self {
+            Self::Contact(pk)
+            | Self::FollowSet(pk, _)
+            | Self::Bookmarks(pk)
+            | Self::BookmarkSet(pk, _) => pk,
+        }
+    }
+
+    /// The kind of the list note
+    pub fn note_kind(&self) -> u32 {
+        match self {
+            Self::Contact(_) => 3,
+            Self::FollowSet(_, _) => KIND_FOLLOW_SET,
+            Self::Bookmarks(_) => KIND_BOOKMARKS,
+            Self::BookmarkSet(_, _) => KIND_BOOKMARK_SET,
+        }
+    }
+
+    pub fn identifier(&self) -> Option<&str> {
         match self {
-            Self::Contact(pk) => Some(pk),
+            Self::FollowSet(_, d) | Self::BookmarkSet(_, d) => Some(d),
+            Self::Contact(_) | Self::Bookmarks(_) => None,
+        }
+    }
+
+    /// Bookmark lists are lists of notes, the others are lists of people
+    pub fn is_bookmarks(&self) -> bool {
+        matches!(self, Self::Bookmarks(_) | Self::BookmarkSet(_, _))
+    }
+
+    /// Which list a list note is, if it's one we can make a column out of
+    pub fn from_list_note(note: &Note) -> Option<Self> {
+        let pk = Pubkey::new(*note.pubkey());
+        let d = || lists::list_identifier(note).map(str::to_owned);
+        match note.kind() {
+            3 => Some(Self::Contact(pk)),
+            KIND_FOLLOW_SET => Some(Self::FollowSet(pk, d()?)),
+            KIND_BOOKMARKS => Some(Self::Bookmarks(pk)),
+            KIND_BOOKMARK_SET => Some(Self::BookmarkSet(pk, d()?)),
+            _ => None,
+        }
+    }
+
+    /// Finds the list note itself
+    pub fn list_filter(&self) -> Filter {
+        let filter = Filter::new()
+            .authors([self.author().bytes()])
+            .kinds([self.note_kind() as u64]);
+
+        match self.identifier() {
+            Some(d) => filter.tags([d], 'd'),
+            None => filter,
+        }
+        .limit(1)
+        .build()
+    }
+
+    /// The notes a column of this list shows
+    pub fn feed_filter(&self, list: &Note) -> Result<Vec<Filter>, notedeck::Error> {
+        let with_hashtags = false;
+        match self {
+            Self::Contact(pk) => {
+                Ok(
+                    filter::filter_from_tags(list, Some(pk.bytes()), with_hashtags)?
+                        .into_follow_filter(),
+                )
+            }
+
+            Self::FollowSet(_, _) => {
+                Ok(filter::filter_from_tags(list, None, with_hashtags)?.into_follow_filter())
+            }
+
+            Self::Bookmarks(_) | Self::BookmarkSet(_, _) => {
+                let ids = lists::list_note_ids(list);
+                if ids.is_empty() {
+                    return Err(notedeck::Error::empty_contact_list());
+                }
+
+                Ok(vec![Filter::new()
+                    .ids(ids.iter().copied())
+                    .limit(ids.len() as u64)
+                    .build()])
+            }
+        }
+    }
+
+    /// The last note of each person on the list. For bookmarks, that's
+    /// the people whose notes were bookmarked.
+    pub fn last_per_pubkey_filter(
+        &self,
+        list: &Note,
+        txn: &Transaction,
+        ndb: &Ndb,
+    ) -> Result<Vec<Filter>, notedeck::Error> {
+        let kind = 1;
+        let notes_per_pk = 1;
+        if !self.is_bookmarks() {
+            return filter::last_n_per_pubkey_from_tags(list, kind, notes_per_pk);
+        }
+
+        let mut authors: Vec<[u8; 32]> = lists::list_pubkeys(list).into_iter().copied().collect();
+        for id in lists::list_note_ids(list) {
+            if let Ok(note) = ndb.get_note_by_id(txn, id) {
+                if !authors.contains(note.pubkey()) {
+                    authors.push(*note.pubkey());
+                }
+            }
+        }
+
+        if authors.is_empty() {
+            return Err(notedeck::Error::empty_contact_list());
+        }
+
+        // TODO: fix arbitrary MAX_FILTER limit in nostrdb
+        Ok(authors
+            .iter()
+            .take(15)
+            .map(|author| {
+                Filter::new()
+                    .authors([author])
+                    .kinds([kind])
+                    .limit(notes_per_pk)
+                    .build()
+            })
+            .collect())
+    }
+
+    /// The list note is fetched from relays first if we don't have it
+    pub fn filter_state(&self, txn: &Transaction, ndb: &Ndb) -> FilterState {
+        self.list_filter_state(txn, ndb, |list| self.feed_filter(list))
+    }
+
+    pub fn last_per_pubkey_filter_state(&self, txn: &Transaction, ndb: &Ndb) -> FilterState {
+        self.list_filter_state(txn, ndb, |list| self.last_per_pubkey_filter(list, txn, ndb))
+    }
+
+    fn list_filter_state(
+        &self,
+        txn: &Transaction,
+        ndb: &Ndb,
+        make_filter: impl FnOnce(&Note) -> Result<Vec<Filter>, notedeck::Error>,
+    ) -> FilterState {
+        let list_filter = self.list_filter();
+
+        let results = ndb
+            .query(txn, &[list_filter.clone()], 1)
+            .expect("list query failed?");
+
+        let Some(list) = results.first() else {
+            return FilterState::needs_remote(vec![list_filter]);
+        };
+
+        match make_filter(&list.note) {
+            Err(notedeck::Error::Filter(FilterError::EmptyContactList)) => {
+                FilterState::needs_remote(vec![list_filter])
+            }
+            Err(err) => {
+                error!("Error getting list filter state: {err}");
+                FilterState::Broken(FilterError::EmptyContactList)
+            }
+            Ok(filter) => FilterState::ready(filter),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Contact(_) => "Contacts",
+            Self::FollowSet(_, _) => "Follow Set",
+            Self::Bookmarks(_) => "Bookmarks",
+            Self::BookmarkSet(_, _) => "Bookmark Set",
         }
     }
 }
@@ -84,6 +268,11 @@ impl TokenSerializable for PubkeySource {
     }
 }
 
+const CONTACT_TOKEN: &str = "contact";
+const FOLLOW_SET_TOKEN: &str = "follow_set";
+const BOOKMARKS_TOKEN: &str = "bookmarks";
+const BOOKMARK_SET_TOKEN: &str = "bookmark_set";
+
 impl ListKind {
     pub fn contact_list(pk: Pubkey) -> Self {
         ListKind::Contact(pk)
@@ -93,38 +282,67 @@ impl ListKind {
         parser: &mut TokenParser<'a>,
         deck_author: &Pubkey,
     ) -> Result<Self, ParseError<'a>> {
-        parser.parse_all(|p| {
-            p.parse_token("contact")?;
+        // the alt parsers can't capture the deck author, so we try them
+        // one at a time
+        let contact = parser.parse_all(|p| {
+            p.parse_token(CONTACT_TOKEN)?;
             let pk_src = PubkeySource::parse_from_tokens(p)?;
             Ok(ListKind::Contact(*pk_src.as_pubkey(deck_author)))
-        })
+        });
+        if contact.is_ok() {
+            return contact;
+        }
 
-        /* here for u when you need more things to parse
-        TokenParser::alt(
-            parser,
-            &[|p| {
-                p.parse_all(|p| {
-                    p.parse_token("contact")?;
-                    let pk_src = PubkeySource::parse_from_tokens(p)?;
-                    Ok(ListKind::Contact(pk_src))
-                });
-            },|p| {
-                // more cases...
-            }],
-        )
-        */
+        let bookmarks = parser.parse_all(|p| {
+            p.parse_token(BOOKMARKS_TOKEN)?;
+            let pk_src = PubkeySource::parse_from_tokens(p)?;
+            Ok(ListKind::Bookmarks(*pk_src.as_pubkey(deck_author)))
+        });
+        if bookmarks.is_ok() {
+            return bookmarks;
+        }
+
+        parser.parse_all(|p| {
+            let set = p.parse_any_token(&[FOLLOW_SET_TOKEN, BOOKMARK_SET_TOKEN])?;
+            let pk = *PubkeySource::parse_from_tokens(p)?.as_pubkey(deck_author);
+            let d = parse_identifier(p)?;
+            Ok(if set == FOLLOW_SET_TOKEN {
+                ListKind::FollowSet(pk, d)
+            } else {
+                ListKind::BookmarkSet(pk, d)
+            })
+        })
     }
 
     pub fn serialize_tokens(&self, writer: &mut TokenWriter) {
         match self {
             ListKind::Contact(pk) => {
-                writer.write_token("contact");
+                writer.write_token(CONTACT_TOKEN);
+                PubkeySource::pubkey(*pk).serialize_tokens(writer);
+            }
+            ListKind::Bookmarks(pk) => {
+                writer.write_token(BOOKMARKS_TOKEN);
                 PubkeySource::pubkey(*pk).serialize_tokens(writer);
             }
+            ListKind::FollowSet(pk, d) | ListKind::BookmarkSet(pk, d) => {
+                writer.write_token(if matches!(self, ListKind::FollowSet(_, _)) {
+                    FOLLOW_SET_TOKEN
+                } else {
+                    BOOKMARK_SET_TOKEN
+                });
+                PubkeySource::pubkey(*pk).serialize_tokens(writer);
+                // d tags are free text and could contain our delimiter
+                writer.write_token(&hex::encode(d));
+            }
         }
     }
 }
 
+fn parse_identifier<'a>(parser: &mut TokenParser<'a>) -> Result<String, ParseError<'a>> {
+    let bytes = hex::decode(parser.pull_token()?).map_err(|_| ParseError::HexDecodeFailed)?;
+    String::from_utf8(bytes).map_err(|_| ParseError::DecodeFailed)
+}
+
 /// Thread selection hashing is done in a specific way. For TimelineCache
 /// lookups, we want to only let the root_id influence thread selection.
 /// This way Thread TimelineKinds always map to the same cached timeline
@@ -213,8 +431,11 @@ pub enum TimelineKind {
 
     Universe,
 
-    /// Generic filter, references a hash of a filter
-    Generic(u64),
+    /// A filter the user made
+    Generic(GenericFilter),
+
+    /// Everything on one relay, by its canonical url
+    Relay(String),
 
     Hashtag(String),
 }
@@ -223,7 +444,7 @@ const NOTIFS_TOKEN_DEPRECATED: &str = "notifs";
 const NOTIFS_TOKEN: &str = "notifications";
 
 /// Hardcoded algo timelines
-#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Hash, Clone, PartialEq, Eq)]
 pub enum AlgoTimeline {
     /// LastPerPubkey: a special nostr query that fetches the last N
     /// notes for each pubkey on the list
@@ -260,12 +481,13 @@ impl AlgoTimeline {
 impl Display for TimelineKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            TimelineKind::List(ListKind::Contact(_src)) => f.write_str("Contacts"),
+            TimelineKind::List(list_kind) => f.write_str(list_kind.name()),
             TimelineKind::Algo(AlgoTimeline::LastPerPubkey(_lk)) => f.write_str("Last Notes"),
             TimelineKind::Generic(_) => f.write_str("Timeline"),
             TimelineKind::Notifications(_) => f.write_str("Notifications"),
             TimelineKind::Profile(_) => f.write_str("Profile"),
             TimelineKind::Universe => f.write_str("Universe"),
+            TimelineKind::Relay(_) => f.write_str("Relay"),
             TimelineKind::Hashtag(_) => f.write_str("Hashtag"),
             TimelineKind::Thread(_) => f.write_str("Thread"),
             TimelineKind::Search(_) => f.write_str("Search"),
@@ -281,6 +503,7 @@ impl TimelineKind {
             TimelineKind::Notifications(pk) => Some(pk),
             TimelineKind::Profile(pk) => Some(pk),
             TimelineKind::Universe => None,
+            TimelineKind::Relay(_) => None,
             TimelineKind::Generic(_) => None,
             TimelineKind::Hashtag(_ht) => None,
             TimelineKind::Thread(_ht) => None,
@@ -297,6 +520,7 @@ impl TimelineKind {
             TimelineKind::Notifications(_pk_src) => true,
             TimelineKind::Profile(_pk_src) => true,
             TimelineKind::Universe => true,
+            TimelineKind::Relay(_) => true,
             TimelineKind::Generic(_) => true,
             TimelineKind::Hashtag(_ht) => true,
             TimelineKind::Thread(_ht) => true,
@@ -304,6 +528,49 @@ impl TimelineKind {
         }
     }
 
+    /// Search timelines only query relays that advertise NIP-50 support,
+    /// so they shouldn't get the usual remote subscription on every relay
+    pub fn should_subscribe_remotely(&self) -> bool {
+        !matches!(self, TimelineKind::Search(_))
+    }
+
+    /// Relay feeds only subscribe on their own relay
+    pub fn relay_url(&self) -> Option<&str> {
+        match self {
+            TimelineKind::Relay(url) => Some(url),
+            _ => None,
+        }
+    }
+
+    /// Whether our remote subscription goes to every relay in the pool
+    pub fn should_subscribe_everywhere(&self) -> bool {
+        self.should_subscribe_remotely() && self.relay_url().is_none()
+    }
+
+    /// Whether our requests for this timeline go to a relay. Relay feeds
+    /// only use their own relay, other timelines skip the relays only a
+    /// relay feed is there for.
+    pub fn uses_relay(&self, relay: &PoolRelay) -> bool {
+        match self.relay_url() {
+            Some(url) => relay.url() == url,
+            None => !relay.is_exclusive(),
+        }
+    }
+
+    /// Whether we send this timeline's filters to a relay
+    pub fn should_subscribe_on(&self, relay: &PoolRelay) -> bool {
+        self.should_subscribe_remotely() && self.uses_relay(relay)
+    }
+
+    /// The filters we send to relays. Relay feeds match notes locally by
+    /// where we got them from, which relays don't know about.
+    pub fn remote_filters(&self, filters: &[Filter]) -> Vec<Filter> {
+        match self {
+            TimelineKind::Relay(_) => universe_filter(),
+            _ => filters.to_vec(),
+        }
+    }
+
     // NOTE!!: if you just added a TimelineKind enum, make sure to update
     //         the parser below as well
     pub fn serialize_tokens(&self, writer: &mut TokenWriter) {
@@ -329,9 +596,14 @@ impl TimelineKind {
             TimelineKind::Universe => {
                 writer.write_token("universe");
             }
-            TimelineKind::Generic(_usize) => {
-                // TODO: lookup filter and then serialize
+            TimelineKind::Relay(url) => {
+                writer.write_token("relay");
+                // urls contain our delimiter
+                writer.write_token(&hex::encode(url));
+            }
+            TimelineKind::Generic(filter) => {
                 writer.write_token("generic");
+                filter.serialize_tokens(writer);
             }
             TimelineKind::Hashtag(ht) => {
                 writer.write_token("hashtag");
@@ -388,10 +660,13 @@ impl TimelineKind {
                     p.parse_token("universe")?;
                     Ok(TimelineKind::Universe)
                 },
+                |p| {
+                    p.parse_token("relay")?;
+                    Ok(TimelineKind::Relay(parse_identifier(p)?))
+                },
                 |p| {
                     p.parse_token("generic")?;
-                    // TODO: generic filter serialization
-                    Ok(TimelineKind::Generic(0))
+                    Ok(TimelineKind::Generic(GenericFilter::parse_from_tokens(p)?))
                 },
                 |p| {
                     p.parse_token("hashtag")?;
@@ -406,6 +681,27 @@ impl TimelineKind {
         )
     }
 
+    /// The filter for this timeline, once we've fetched the list it
+    /// needs from relays
+    pub fn filter_from_list(
+        &self,
+        list: &Note,
+        txn: &Transaction,
+        ndb: &Ndb,
+    ) -> Result<Vec<Filter>, notedeck::Error> {
+        match self {
+            TimelineKind::List(list_kind) => list_kind.feed_filter(list),
+            TimelineKind::Algo(AlgoTimeline::LastPerPubkey(list_kind)) => {
+                list_kind.last_per_pubkey_filter(list, txn, ndb)
+            }
+            _ => {
+                let with_hashtags = false;
+                let add_pk = self.pubkey().map(|pk| pk.bytes());
+                Ok(filter::filter_from_tags(list, add_pk, with_hashtags)?.into_follow_filter())
+            }
+        }
+    }
+
     pub fn last_per_pubkey(list_kind: ListKind) -> Self {
         TimelineKind::Algo(AlgoTimeline::LastPerPubkey(list_kind))
     }
@@ -430,6 +726,24 @@ impl TimelineKind {
         TimelineKind::Thread(selected_note)
     }
 
+    /// A relay feed from what the user typed. The scheme is optional,
+    /// we assume `wss://`.
+    pub fn relay(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let url = if input.contains("://") {
+            Url::parse(input)
+        } else {
+            Url::parse(&format!("wss://{input}"))
+        }
+        .ok()?;
+
+        if !matches!(url.scheme(), "ws" | "wss") || url.host_str().is_none() {
+            return None;
+        }
+
+        Some(TimelineKind::Relay(url.to_string()))
+    }
+
     pub fn is_notifications(&self) -> bool {
         matches!(self, TimelineKind::Notifications(_))
     }
@@ -445,9 +759,9 @@ impl TimelineKind {
 
             TimelineKind::Universe => FilterState::ready(universe_filter()),
 
-            TimelineKind::List(list_k) => match list_k {
-                ListKind::Contact(pubkey) => contact_filter_state(txn, ndb, pubkey),
-            },
+            TimelineKind::Relay(url) => FilterState::ready(relay_filter(url)),
+
+            TimelineKind::List(list_k) => list_k.filter_state(txn, ndb),
 
             // TODO: still need to update this to fetch likes, zaps, etc
             TimelineKind::Notifications(pubkey) => FilterState::ready(vec![Filter::new()
@@ -465,16 +779,12 @@ impl TimelineKind {
                     .build()])
             }
 
-            TimelineKind::Algo(algo_timeline) => match algo_timeline {
-                AlgoTimeline::LastPerPubkey(list_k) => match list_k {
-                    ListKind::Contact(pubkey) => last_per_pubkey_filter_state(ndb, pubkey),
-                },
-            },
-
-            TimelineKind::Generic(_) => {
-                todo!("implement generic filter lookups")
+            TimelineKind::Algo(AlgoTimeline::LastPerPubkey(list_k)) => {
+                list_k.last_per_pubkey_filter_state(txn, ndb)
             }
 
+            TimelineKind::Generic(filter) => FilterState::ready(vec![filter.filter()]),
+
             TimelineKind::Thread(selection) => FilterState::ready(vec![
                 nostrdb::Filter::new()
                     .kinds([1])
@@ -488,7 +798,7 @@ impl TimelineKind {
 
             TimelineKind::Profile(pk) => FilterState::ready(vec![Filter::new()
                 .authors([pk.bytes()])
-                .kinds([1])
+                .kinds([1, KIND_POLL as u64])
                 .limit(default_limit())
                 .build()]),
         }
@@ -498,11 +808,11 @@ impl TimelineKind {
         match self {
             TimelineKind::Search(s) => {
                 let filter = FilterState::ready(search_filter(&s));
-                Some(Timeline::new(
-                    TimelineKind::Search(s),
-                    filter,
-                    TimelineTab::full_tabs(),
-                ))
+                let remote_filter = s.filter().limit(filter::default_remote_limit()).build();
+                let mut timeline =
+                    Timeline::new(TimelineKind::Search(s), filter, TimelineTab::full_tabs());
+                timeline.remote_search = Some(RemoteSearch::new(remote_filter));
+                Some(timeline)
             }
 
             TimelineKind::Universe => Some(Timeline::new(
@@ -511,58 +821,39 @@ impl TimelineKind {
                 TimelineTab::no_replies(),
             )),
 
-            TimelineKind::Thread(root_id) => Some(Timeline::thread(root_id)),
-
-            TimelineKind::Generic(_filter_id) => {
-                warn!("you can't convert a TimelineKind::Generic to a Timeline");
-                // TODO: you actually can! just need to look up the filter id
-                None
+            TimelineKind::Relay(url) => {
+                let filter_state = FilterState::ready(relay_filter(&url));
+                Some(Timeline::new(
+                    TimelineKind::Relay(url),
+                    filter_state,
+                    TimelineTab::no_replies(),
+                ))
             }
 
-            TimelineKind::Algo(AlgoTimeline::LastPerPubkey(ListKind::Contact(pk))) => {
-                let contact_filter = Filter::new()
-                    .authors([pk.bytes()])
-                    .kinds([3])
-                    .limit(1)
-                    .build();
-
-                let results = ndb
-                    .query(txn, &[contact_filter.clone()], 1)
-                    .expect("contact query failed?");
-
-                let kind_fn = TimelineKind::last_per_pubkey;
-                let tabs = TimelineTab::only_notes_and_replies();
-
-                if results.is_empty() {
-                    return Some(Timeline::new(
-                        kind_fn(ListKind::contact_list(pk)),
-                        FilterState::needs_remote(vec![contact_filter.clone()]),
-                        tabs,
-                    ));
-                }
+            TimelineKind::Thread(root_id) => Some(Timeline::thread(root_id)),
 
-                let list_kind = ListKind::contact_list(pk);
+            TimelineKind::Generic(filter) => {
+                let filter_state = FilterState::ready(vec![filter.filter()]);
+                Some(Timeline::new(
+                    TimelineKind::Generic(filter),
+                    filter_state,
+                    TimelineTab::only_notes_and_replies(),
+                ))
+            }
 
-                match Timeline::last_per_pubkey(&results[0].note, &list_kind) {
-                    Err(Error::App(notedeck::Error::Filter(FilterError::EmptyContactList))) => {
-                        Some(Timeline::new(
-                            kind_fn(list_kind),
-                            FilterState::needs_remote(vec![contact_filter]),
-                            tabs,
-                        ))
-                    }
-                    Err(e) => {
-                        error!("Unexpected error: {e}");
-                        None
-                    }
-                    Ok(tl) => Some(tl),
-                }
+            TimelineKind::Algo(AlgoTimeline::LastPerPubkey(list_kind)) => {
+                let filter_state = list_kind.last_per_pubkey_filter_state(txn, ndb);
+                Some(Timeline::new(
+                    TimelineKind::last_per_pubkey(list_kind),
+                    filter_state,
+                    TimelineTab::only_notes_and_replies(),
+                ))
             }
 
             TimelineKind::Profile(pk) => {
                 let filter = Filter::new()
                     .authors([pk.bytes()])
-                    .kinds([1])
+                    .kinds([1, KIND_POLL as u64])
                     .limit(default_limit())
                     .build();
 
@@ -589,30 +880,53 @@ impl TimelineKind {
 
             TimelineKind::Hashtag(hashtag) => Some(Timeline::hashtag(hashtag)),
 
-            TimelineKind::List(ListKind::Contact(pk)) => Some(Timeline::new(
-                TimelineKind::contact_list(pk),
-                contact_filter_state(txn, ndb, &pk),
-                TimelineTab::full_tabs(),
-            )),
+            TimelineKind::List(list_kind) => {
+                let filter_state = list_kind.filter_state(txn, ndb);
+                // bookmarks are whatever was saved, replies included
+                let tabs = if list_kind.is_bookmarks() {
+                    TimelineTab::only_notes_and_replies()
+                } else {
+                    TimelineTab::full_tabs()
+                };
+                Some(Timeline::new(
+                    TimelineKind::List(list_kind),
+                    filter_state,
+                    tabs,
+                ))
+            }
         }
     }
 
     pub fn to_title(&self) -> ColumnTitle<'_> {
         match self {
             TimelineKind::Search(query) => {
-                ColumnTitle::formatted(format!("Search \"{}\"", query.search))
+                ColumnTitle::formatted(format!("Search \"{}\"", query.to_query_string()))
             }
             TimelineKind::List(list_kind) => match list_kind {
                 ListKind::Contact(_pubkey_source) => ColumnTitle::simple("Contacts"),
+                ListKind::Bookmarks(_pubkey_source) => ColumnTitle::simple("Bookmarks"),
+                ListKind::FollowSet(_, _) | ListKind::BookmarkSet(_, _) => {
+                    ColumnTitle::needs_db(self)
+                }
             },
             TimelineKind::Algo(AlgoTimeline::LastPerPubkey(list_kind)) => match list_kind {
                 ListKind::Contact(_pubkey_source) => ColumnTitle::simple("Contacts (last notes)"),
+                ListKind::Bookmarks(_pubkey_source) => {
+                    ColumnTitle::simple("Bookmarks (last notes)")
+                }
+                ListKind::FollowSet(_, _) | ListKind::BookmarkSet(_, _) => {
+                    ColumnTitle::needs_db(self)
+                }
             },
             TimelineKind::Notifications(_pubkey_source) => ColumnTitle::simple("Notifications"),
             TimelineKind::Profile(_pubkey_source) => ColumnTitle::needs_db(self),
             TimelineKind::Thread(_root_id) => ColumnTitle::simple("Thread"),
             TimelineKind::Universe => ColumnTitle::simple("Universe"),
-            TimelineKind::Generic(_) => ColumnTitle::simple("Custom"),
+            TimelineKind::Relay(url) => ColumnTitle::formatted(relay_name(url).to_owned()),
+            TimelineKind::Generic(filter) if filter.title.is_empty() => {
+                ColumnTitle::simple("Custom")
+            }
+            TimelineKind::Generic(filter) => ColumnTitle::formatted(filter.title.clone()),
             TimelineKind::Hashtag(hashtag) => ColumnTitle::formatted(hashtag.to_string()),
         }
     }
@@ -637,6 +951,14 @@ impl<'a> TitleNeedsDb<'a> {
                 .map(|p| notedeck::name::get_display_name(Some(p)).name());
 
             m_name.unwrap_or("Profile")
+        } else if let TimelineKind::List(list_kind)
+        | TimelineKind::Algo(AlgoTimeline::LastPerPubkey(list_kind)) = self.kind
+        {
+            ndb.query(txn, &[list_kind.list_filter()], 1)
+                .ok()
+                .and_then(|results| results.into_iter().next())
+                .and_then(|qr| lists::list_title(&qr.note))
+                .unwrap_or(list_kind.name())
         } else {
             "Unknown"
         }
@@ -665,68 +987,74 @@ impl<'a> ColumnTitle<'a> {
     }
 }
 
-fn contact_filter_state(txn: &Transaction, ndb: &Ndb, pk: &Pubkey) -> FilterState {
-    let contact_filter = Filter::new()
-        .authors([pk.bytes()])
-        .kinds([3])
-        .limit(1)
-        .build();
-
-    let results = ndb
-        .query(txn, &[contact_filter.clone()], 1)
-        .expect("contact query failed?");
+fn search_filter(s: &SearchQuery) -> Vec<Filter> {
+    vec![s.local_filter().limit(default_limit()).build()]
+}
 
-    if results.is_empty() {
-        FilterState::needs_remote(vec![contact_filter.clone()])
-    } else {
-        let with_hashtags = false;
-        match filter::filter_from_tags(&results[0].note, Some(pk.bytes()), with_hashtags) {
-            Err(notedeck::Error::Filter(FilterError::EmptyContactList)) => {
-                FilterState::needs_remote(vec![contact_filter])
-            }
-            Err(err) => {
-                error!("Error getting contact filter state: {err}");
-                FilterState::Broken(FilterError::EmptyContactList)
-            }
-            Ok(filter) => FilterState::ready(filter.into_follow_filter()),
-        }
-    }
+fn universe_filter() -> Vec<Filter> {
+    vec![Filter::new()
+        .kinds([1, KIND_POLL as u64])
+        .limit(default_limit())
+        .build()]
 }
 
-fn last_per_pubkey_filter_state(ndb: &Ndb, pk: &Pubkey) -> FilterState {
-    let contact_filter = Filter::new()
-        .authors([pk.bytes()])
-        .kinds([3])
-        .limit(1)
-        .build();
+/// Only the notes we got from this relay
+fn relay_filter(relay: &str) -> Vec<Filter> {
+    vec![Filter::new()
+        .kinds([1, KIND_POLL as u64])
+        .relays([relay])
+        .limit(default_limit())
+        .build()]
+}
 
-    let txn = Transaction::new(ndb).expect("txn");
-    let results = ndb
-        .query(&txn, &[contact_filter.clone()], 1)
-        .expect("contact query failed?");
+/// The relay's host, without the scheme or trailing slash
+pub fn relay_name(url: &str) -> &str {
+    let name = url.split_once("://").map_or(url, |(_scheme, rest)| rest);
+    name.trim_end_matches('/')
+}
 
-    if results.is_empty() {
-        FilterState::needs_remote(vec![contact_filter])
-    } else {
-        let kind = 1;
-        let notes_per_pk = 1;
-        match filter::last_n_per_pubkey_from_tags(&results[0].note, kind, notes_per_pk) {
-            Err(notedeck::Error::Filter(FilterError::EmptyContactList)) => {
-                FilterState::needs_remote(vec![contact_filter])
-            }
-            Err(err) => {
-                error!("Error getting contact filter state: {err}");
-                FilterState::Broken(FilterError::EmptyContactList)
-            }
-            Ok(filter) => FilterState::ready(filter),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_kind_round_trip() {
+        let pk = Pubkey::new([4; 32]);
+        let kinds = [
+            TimelineKind::List(ListKind::Contact(pk)),
+            TimelineKind::List(ListKind::Bookmarks(pk)),
+            TimelineKind::List(ListKind::FollowSet(pk, "nostr devs: rust".to_owned())),
+            TimelineKind::last_per_pubkey(ListKind::BookmarkSet(pk, "reading".to_owned())),
+            TimelineKind::Generic(GenericFilter {
+                title: "Long form: nostr".to_owned(),
+                kinds: vec![30023],
+                tags: vec![('t', vec!["nostr".to_owned()])],
+                ..Default::default()
+            }),
+            TimelineKind::Relay("wss://relay.damus.io/".to_owned()),
+        ];
+
+        for kind in kinds {
+            let mut writer = TokenWriter::default();
+            kind.serialize_tokens(&mut writer);
+            let data = writer.str().split(":").collect::<Vec<&str>>();
+            let mut parser = TokenParser::new(&data);
+
+            assert_eq!(TimelineKind::parse(&mut parser, &pk).unwrap(), kind);
         }
     }
-}
-
-fn search_filter(s: &SearchQuery) -> Vec<Filter> {
-    vec![s.filter().limit(default_limit()).build()]
-}
 
-fn universe_filter() -> Vec<Filter> {
-    vec![Filter::new().kinds([1]).limit(default_limit()).build()]
+    #[test]
+    fn test_relay_from_input() {
+        let relay = TimelineKind::relay(" relay.damus.io ").unwrap();
+        assert_eq!(
+            relay,
+            TimelineKind::Relay("wss://relay.damus.io/".to_owned())
+        );
+        assert_eq!(relay.relay_url().map(relay_name), Some("relay.damus.io"));
+
+        assert!(TimelineKind::relay("ws://localhost:8080").is_some());
+        assert!(TimelineKind::relay("https://relay.damus.io").is_none());
+        assert!(TimelineKind::relay("").is_none());
+    }
 }
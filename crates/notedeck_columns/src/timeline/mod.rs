@@ -1,8 +1,8 @@
 use crate::{
     error::Error,
     multi_subscriber::MultiSubscriber,
+    search::RemoteSearch,
     subscriptions::{self, SubKind, Subscriptions},
-    timeline::kind::ListKind,
     Result,
 };
 
@@ -11,20 +11,32 @@ use notedeck::{
 };
 
 use egui_virtual_list::VirtualList;
-use enostr::{PoolRelay, Pubkey, RelayPool};
+use enostr::{ClientMessage, NoteId, PoolRelay, Pubkey, RelayPool, RelayStatus};
 use nostrdb::{Filter, Ndb, Note, NoteKey, Transaction};
 use std::cell::RefCell;
 use std::rc::Rc;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 
 use tracing::{debug, error, info, warn};
 
+pub mod backfill;
 pub mod cache;
+pub mod gap;
+pub mod generic;
 pub mod kind;
 pub mod route;
+pub mod thread;
 
+pub use backfill::Backfill;
 pub use cache::TimelineCache;
+pub use gap::{Coverage, Gap, TimeRange};
+pub use generic::{GenericFilter, GenericFilterForm};
 pub use kind::{ColumnTitle, PubkeySource, ThreadSelection, TimelineKind};
+pub use thread::{ThreadLayout, ThreadRow, ThreadRowKind, ThreadTree};
+
+/// How long we wait for a note we asked relays for to show up in nostrdb
+const REQUESTED_TTL: Duration = Duration::from_secs(60);
 
 //#[derive(Debug, Hash, Clone, Eq, PartialEq)]
 //pub type TimelineId = TimelineKind;
@@ -132,7 +144,7 @@ impl TimelineTab {
         }
     }
 
-    fn insert(&mut self, new_refs: &[NoteRef], reversed: bool) {
+    pub fn insert(&mut self, new_refs: &[NoteRef], reversed: bool) {
         if new_refs.is_empty() {
             return;
         }
@@ -155,6 +167,10 @@ impl TimelineTab {
                     );
                     list.reset();
                 }
+                // the virtual list picks up items added at the end on its own
+                MergeKind::BackInsert => {
+                    debug!("inserting {} older notes at end", new_refs.len());
+                }
                 MergeKind::FrontInsert => {
                     // only run this logic if we're reverse-chronological
                     // reversed in this case means chronological, since the
@@ -200,6 +216,22 @@ pub struct Timeline {
     pub pending_notes: Vec<NoteRef>,
 
     pub subscription: Option<MultiSubscriber>,
+
+    /// Search timelines also ask NIP-50 relays for results
+    pub remote_search: Option<RemoteSearch>,
+
+    /// Loading notes older than the ones we have
+    pub backfill: Backfill,
+
+    /// The time ranges we know we have every note for
+    pub coverage: Coverage,
+
+    /// How thread timelines are laid out around the selected note
+    pub thread_layout: ThreadLayout,
+
+    /// Notes relays sent for backfill and gap requests, and when. These go
+    /// straight into the timeline instead of waiting in `pending_notes`.
+    requested: HashMap<NoteId, Instant>,
 }
 
 impl Timeline {
@@ -234,18 +266,6 @@ impl Timeline {
         )
     }
 
-    pub fn last_per_pubkey(list: &Note, list_kind: &ListKind) -> Result<Self> {
-        let kind = 1;
-        let notes_per_pk = 1;
-        let filter = filter::last_n_per_pubkey_from_tags(list, kind, notes_per_pk)?;
-
-        Ok(Timeline::new(
-            TimelineKind::last_per_pubkey(*list_kind),
-            FilterState::ready(filter),
-            TimelineTab::only_notes_and_replies(),
-        ))
-    }
-
     pub fn hashtag(hashtag: String) -> Self {
         let hashtag = hashtag.to_lowercase();
         let htag: &str = &hashtag;
@@ -278,6 +298,11 @@ impl Timeline {
             selected_view: 0,
             pending_notes: Vec::new(),
             subscription: None,
+            remote_search: None,
+            backfill: Backfill::default(),
+            coverage: Coverage::default(),
+            thread_layout: ThreadLayout::default(),
+            requested: HashMap::new(),
         }
     }
 
@@ -326,6 +351,11 @@ impl Timeline {
             filters
         };
 
+        if let (Some(newest), Some(oldest)) = (notes.first(), notes.last()) {
+            self.coverage
+                .cover_local(oldest.created_at, newest.created_at);
+        }
+
         for note_ref in notes {
             for (view, filter) in filters.iter().enumerate() {
                 if let Ok(note) = ndb.get_note_by_key(txn, note_ref.key) {
@@ -402,6 +432,182 @@ impl Timeline {
         Ok(())
     }
 
+    /// Insert relay search results that have been ingested by nostrdb.
+    /// Notes we already found locally are skipped.
+    pub fn poll_remote_search(
+        &mut self,
+        ndb: &Ndb,
+        txn: &Transaction,
+        unknown_ids: &mut UnknownIds,
+        note_cache: &mut NoteCache,
+    ) -> Result<()> {
+        let Some(search) = &mut self.remote_search else {
+            return Ok(());
+        };
+
+        let found = search.poll_results(ndb, txn);
+        if found.is_empty() {
+            return Ok(());
+        }
+
+        let notes = self.all_or_any_notes();
+        let new_refs: Vec<NoteRef> = found
+            .into_iter()
+            .filter(|nr| !notes.contains(nr) && !self.pending_notes.contains(nr))
+            .collect();
+
+        if let Some(search) = &mut self.remote_search {
+            search.mark_remote(&new_refs);
+        }
+
+        let new_keys: Vec<NoteKey> = new_refs.iter().map(|nr| nr.key).collect();
+        self.insert(&new_keys, ndb, txn, unknown_ids, note_cache, false)
+    }
+
+    /// Threads are loaded whole, search paging and algo feeds work
+    /// differently
+    pub fn can_backfill(&self) -> bool {
+        self.kind.should_subscribe_locally()
+            && !matches!(self.kind, TimelineKind::Search(_) | TimelineKind::Thread(_))
+    }
+
+    /// Load notes older than the oldest one we have, if the user asked for
+    /// them. We check nostrdb first, and only ask relays when we don't have
+    /// enough locally.
+    pub fn backfill(
+        &mut self,
+        ndb: &Ndb,
+        txn: &Transaction,
+        unknown_ids: &mut UnknownIds,
+        note_cache: &mut NoteCache,
+        pool: &mut RelayPool,
+        subs: &mut Subscriptions,
+    ) -> Result<()> {
+        if !self.backfill.take_request() || !self.can_backfill() {
+            return Ok(());
+        }
+
+        let Some(filters) = self.filter.get_any_ready().cloned() else {
+            return Ok(());
+        };
+
+        let Some(oldest) = self.all_or_any_notes().last().copied() else {
+            return Ok(());
+        };
+
+        let until = backfill::until_filters(&filters, oldest.created_at);
+        let limit = until.iter().filter_map(|f| f.limit()).max().unwrap_or(0);
+        let found: Vec<NoteRef> = match ndb.query(txn, &until, limit as i32) {
+            Ok(results) => results
+                .into_iter()
+                .map(NoteRef::from_query_result)
+                .collect(),
+            Err(err) => {
+                error!("backfill query failed: {err}");
+                vec![]
+            }
+        };
+
+        // `until` is inclusive, so we get our oldest notes back
+        let notes = self.all_or_any_notes();
+        let new_keys: Vec<NoteKey> = found
+            .iter()
+            .filter(|nr| !notes.contains(nr))
+            .map(|nr| nr.key)
+            .collect();
+
+        debug!(
+            "backfilled {} older notes for {:?} from nostrdb",
+            new_keys.len(),
+            self.kind
+        );
+
+        let have_enough = new_keys.len() as u64 >= limit;
+        self.insert(&new_keys, ndb, txn, unknown_ids, note_cache, false)?;
+        self.cover_bottom(oldest.created_at);
+
+        if have_enough || !self.kind.should_subscribe_remotely() {
+            return Ok(());
+        }
+
+        let until = self
+            .all_or_any_notes()
+            .last()
+            .map(|nr| nr.created_at)
+            .unwrap_or(oldest.created_at);
+        let kind = self.kind.clone();
+        let filters = kind.remote_filters(&filters);
+        self.backfill
+            .send_remote(&kind, &filters, until, pool, subs);
+
+        Ok(())
+    }
+
+    /// The notes at the bottom of a timeline are loaded in order, so we
+    /// treat everything below what used to be the oldest note as covered
+    fn cover_bottom(&mut self, prev_oldest: u64) {
+        if let Some(oldest) = self.all_or_any_notes().last().map(|nr| nr.created_at) {
+            self.coverage.cover_local(oldest, prev_oldest);
+        }
+    }
+
+    /// The time ranges between the notes of the current view that we
+    /// haven't fetched
+    pub fn gaps(&self) -> Vec<Gap> {
+        self.coverage.gaps(&self.current_view().notes)
+    }
+
+    /// A relay sent a note we asked for with a backfill or gap request
+    pub fn note_requested(&mut self, id: NoteId) {
+        self.requested.insert(id, Instant::now());
+    }
+
+    /// Ask relays for the notes in a gap, if one was requested
+    pub fn fill_gap(&mut self, pool: &mut RelayPool, subs: &mut Subscriptions) {
+        let Some(range) = self.coverage.take_fill() else {
+            return;
+        };
+
+        let Some(filters) = self.filter.get_any_ready() else {
+            return;
+        };
+
+        let filters = self.kind.remote_filters(filters);
+        let filters: Vec<Filter> = backfill::until_filters(&filters, range.until)
+            .into_iter()
+            .map(|f| f.since_mut(range.since))
+            .collect();
+        let limit = filters
+            .iter()
+            .filter_map(|f| f.limit())
+            .min()
+            .unwrap_or(filter::default_remote_limit());
+
+        let relays: Vec<String> = pool
+            .relays
+            .iter()
+            .filter(|r| matches!(r.status(), RelayStatus::Connected))
+            .filter(|r| self.kind.uses_relay(r))
+            .map(|r| r.url().to_owned())
+            .filter(|url| url != "multicast")
+            .collect();
+
+        if relays.is_empty() {
+            return;
+        }
+
+        debug!("filling gap {:?} in {:?}", range, self.kind);
+
+        let subid = subscriptions::new_sub_id();
+        let msg = ClientMessage::req(subid.clone(), filters);
+        for relay in relays {
+            pool.send_to(&msg, &relay);
+            self.coverage.track(&subid, &relay, range, limit);
+        }
+
+        subs.subs.insert(subid, SubKind::FillGap(self.kind.clone()));
+    }
+
     /// Adds newly polled notes to the `pending_notes` list.
     /// Returns true if new notes were added.
     pub fn poll_notes_into_pending(
@@ -431,6 +637,7 @@ impl Timeline {
 
         // Fetch NoteRefs for the new NoteKeys and add to pending_notes
         let mut new_refs: Vec<NoteRef> = Vec::with_capacity(new_note_ids.len());
+        let mut requested: Vec<NoteKey> = Vec::new();
         for key in new_note_ids {
             let note = match ndb.get_note_by_key(txn, key) {
                 Ok(note) => note,
@@ -446,6 +653,13 @@ impl Timeline {
             // Ensure that unknown ids are captured (needed for profile info etc.)
             UnknownIds::update_from_note(txn, ndb, unknown_ids, note_cache, &note);
 
+            // backfill results and notes that fill in gaps are older than
+            // what we show, so they go straight into the timeline
+            if self.requested.remove(&NoteId::new(*note.id())).is_some() {
+                requested.push(key);
+                continue;
+            }
+
             let created_at = note.created_at();
             new_refs.push(NoteRef {
                 key,
@@ -453,6 +667,18 @@ impl Timeline {
             });
         }
 
+        let now = Instant::now();
+        self.requested
+            .retain(|_, at| now.duration_since(*at) < REQUESTED_TTL);
+
+        if !requested.is_empty() {
+            let oldest = self.all_or_any_notes().last().map(|nr| nr.created_at);
+            self.insert(&requested, ndb, txn, unknown_ids, note_cache, false)?;
+            if let Some(oldest) = oldest {
+                self.cover_bottom(oldest);
+            }
+        }
+
         // Add to pending, ensuring no duplicates and maintaining order (newest first)
         // We assume poll_for_notes returns newest first.
         let mut existing_pending_keys: HashSet<_> = self.pending_notes.iter().map(|nr| nr.key).collect();
@@ -532,6 +758,9 @@ impl Timeline {
 
 pub enum MergeKind {
     FrontInsert,
+
+    /// Every new item is older than what we had, eg. when backfilling
+    BackInsert,
     Spliced,
 }
 
@@ -541,6 +770,12 @@ pub fn merge_sorted_vecs<T: Ord + Copy>(vec1: &[T], vec2: &[T]) -> (Vec<T>, Merg
     let mut j = 0;
     let mut result: Option<MergeKind> = None;
 
+    if let (Some(last), Some(first)) = (vec1.last(), vec2.first()) {
+        if last <= first {
+            result = Some(MergeKind::BackInsert);
+        }
+    }
+
     while i < vec1.len() && j < vec2.len() {
         if vec1[i] <= vec2[j] {
             if result.is_none() && j < vec2.len() {
@@ -644,8 +879,16 @@ pub fn send_initial_timeline_filter(
         }
 
         FilterState::Ready(filter) => {
-            let filter = filter.to_owned();
-            let new_filters = filter.into_iter().map(|f| {
+            if !timeline.kind.should_subscribe_on(relay) {
+                // these timelines send their own remote requests, or
+                // only subscribe on one relay
+                return;
+            }
+
+            let filter = timeline.kind.remote_filters(filter);
+            let mut since: Option<u64> = None;
+            let mut limit = filter::default_remote_limit();
+            let new_filters: Vec<Filter> = filter.into_iter().map(|f| {
                 // limit the size of remote filters
                 let default_limit = filter::default_remote_limit();
                 let mut lim = f.limit().unwrap_or(default_limit);
@@ -654,6 +897,7 @@ pub fn send_initial_timeline_filter(
                     lim = default_limit;
                     filter = filter.limit_mut(lim);
                 }
+                limit = limit.min(lim);
 
                 let notes = timeline.all_or_any_notes();
 
@@ -665,6 +909,7 @@ pub fn send_initial_timeline_filter(
                 // older notes
                 if can_since_optimize && filter::should_since_optimize(lim, notes.len()) {
                     filter = filter::since_optimize_filter(filter, notes);
+                    since = filter.since();
                 } else {
                     warn!("Skipping since optimization for {:?}: number of local notes is less than limit, attempting to backfill.", &timeline.kind);
                 }
@@ -674,7 +919,21 @@ pub fn send_initial_timeline_filter(
 
             //let sub_id = damus.gen_subid(&SubKind::Initial);
             let sub_id = subscriptions::new_sub_id();
-            subs.subs.insert(sub_id.clone(), SubKind::Initial);
+            subs.subs
+                .insert(sub_id.clone(), SubKind::Initial(timeline.kind.clone()));
+
+            // what this relay told us before stops here, we might have
+            // missed notes while we weren't subscribed
+            let anchor = timeline
+                .all_or_any_notes()
+                .first()
+                .map(|nr| nr.created_at)
+                .unwrap_or(0);
+            timeline.coverage.relay_restarted(relay.url(), anchor);
+
+            // the subscription stays open, so it covers everything newer
+            let range = TimeRange::new(since.unwrap_or(0), u64::MAX);
+            timeline.coverage.track(&sub_id, relay.url(), range, limit);
 
             if let Err(err) = relay.subscribe(sub_id, new_filters) {
                 error!("error subscribing: {err}");
@@ -723,10 +982,12 @@ fn setup_initial_timeline(
         let local_sub = ndb.subscribe(filters)?;
         match &mut timeline.subscription {
             None => {
-                timeline.subscription = Some(MultiSubscriber::with_initial_local_sub(
-                    local_sub,
-                    filters.to_vec(),
-                ));
+                let mut msub =
+                    MultiSubscriber::with_initial_local_sub(local_sub, filters.to_vec());
+                if !timeline.kind.should_subscribe_everywhere() {
+                    msub = msub.local_only();
+                }
+                timeline.subscription = Some(msub);
             }
 
             Some(msub) => {
@@ -823,16 +1084,13 @@ pub fn is_timeline_ready(
     info!("notes found for contact timeline after GotRemote!");
 
     let note_key = res[0];
-    let with_hashtags = false;
 
     let filter = {
         let txn = Transaction::new(ndb).expect("txn");
         let note = ndb.get_note_by_key(&txn, note_key).expect("note");
-        let add_pk = timeline.kind.pubkey().map(|pk| pk.bytes());
-        filter::filter_from_tags(&note, add_pk, with_hashtags).map(|f| f.into_follow_filter())
+        timeline.kind.filter_from_list(&note, &txn, ndb)
     };
 
-    // TODO: into_follow_filter is hardcoded to contact lists, let's generalize
     match filter {
         Err(notedeck::Error::Filter(e)) => {
             error!("got broken when building filter {e}");
@@ -865,3 +1123,23 @@ pub fn is_timeline_ready(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_back_insert() {
+        let (merged, kind) = merge_sorted_vecs(&[1, 2, 3], &[3, 4, 5]);
+        assert!(matches!(kind, MergeKind::BackInsert));
+        assert_eq!(merged, vec![1, 2, 3, 3, 4, 5]);
+
+        let (merged, kind) = merge_sorted_vecs(&[3, 4], &[1, 2]);
+        assert!(matches!(kind, MergeKind::FrontInsert));
+        assert_eq!(merged, vec![1, 2, 3, 4]);
+
+        let (merged, kind) = merge_sorted_vecs(&[1, 3], &[2]);
+        assert!(matches!(kind, MergeKind::Spliced));
+        assert_eq!(merged, vec![1, 2, 3]);
+    }
+}
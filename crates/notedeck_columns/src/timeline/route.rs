@@ -1,18 +1,26 @@
 use crate::{
+    followers::Followers,
     nav::RenderNavAction,
     profile::ProfileAction,
-    timeline::{TimelineCache, TimelineKind},
+    timeline::{kind::ListKind, TimelineCache, TimelineKind},
     ui::{self, ProfileView},
 };
 
-use enostr::Pubkey;
-use notedeck::{Accounts, MuteFun, NoteContext, UnknownIds};
+use enostr::{FullKeypair, Pubkey};
+use nostrdb::{Ndb, Transaction};
+use notedeck::{
+    lists::{self, KIND_BOOKMARKS},
+    Accounts, Bookmarks, MuteFun, NoteContext, UnknownIds,
+};
 use notedeck_ui::NoteOptions;
+use std::sync::Arc;
+use tracing::error;
 
 #[allow(clippy::too_many_arguments)]
 pub fn render_timeline_route(
     unknown_ids: &mut UnknownIds,
     timeline_cache: &mut TimelineCache,
+    followers: &mut Followers,
     accounts: &mut Accounts,
     kind: &TimelineKind,
     col: usize,
@@ -21,16 +29,33 @@ pub fn render_timeline_route(
     ui: &mut egui::Ui,
     note_context: &mut NoteContext,
 ) -> Option<RenderNavAction> {
-    if kind == &TimelineKind::Universe {
+    if matches!(kind, TimelineKind::Universe | TimelineKind::Relay(_)) {
         note_options.set_hide_media(true);
     }
 
     match kind {
+        TimelineKind::List(ListKind::Bookmarks(pubkey)) => {
+            let bookmarks = user_bookmarks(accounts, note_context.ndb, pubkey);
+            let note_action = ui::BookmarksView::new(
+                &bookmarks,
+                unknown_ids,
+                note_options,
+                &accounts.mutefun(),
+                note_context,
+                &accounts.get_selected_account().map(|a| (&a.key).into()),
+            )
+            .id_source(egui::Id::new(("bookmarks", col)))
+            .ui(ui);
+
+            note_action.map(RenderNavAction::NoteAction)
+        }
+
         TimelineKind::List(_)
         | TimelineKind::Search(_)
         | TimelineKind::Algo(_)
         | TimelineKind::Notifications(_)
         | TimelineKind::Universe
+        | TimelineKind::Relay(_)
         | TimelineKind::Hashtag(_)
         | TimelineKind::Generic(_) => {
             let note_action = ui::TimelineView::new(
@@ -53,6 +78,7 @@ pub fn render_timeline_route(
                     pubkey,
                     accounts,
                     timeline_cache,
+                    followers,
                     unknown_ids,
                     col,
                     ui,
@@ -92,11 +118,27 @@ pub fn render_timeline_route(
     }
 }
 
+/// Our own bookmarks include the private ones, for anyone else we show
+/// what they made public
+fn user_bookmarks(accounts: &Accounts, ndb: &Ndb, pubkey: &Pubkey) -> Arc<Bookmarks> {
+    if accounts.selected_account_pubkey_bytes() == Some(pubkey.bytes()) {
+        return accounts.bookmarks();
+    }
+
+    let txn = Transaction::new(ndb).expect("txn");
+    let bookmarks = lists::query_lists(ndb, &txn, pubkey.bytes(), &[KIND_BOOKMARKS])
+        .first()
+        .map(|note| Bookmarks::from_note(note, None))
+        .unwrap_or_default();
+    Arc::new(bookmarks)
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn render_profile_route(
     pubkey: &Pubkey,
     accounts: &Accounts,
     timeline_cache: &mut TimelineCache,
+    followers: &mut Followers,
     unknown_ids: &mut UnknownIds,
     col: usize,
     ui: &mut egui::Ui,
@@ -109,6 +151,7 @@ pub fn render_profile_route(
         accounts,
         col,
         timeline_cache,
+        followers,
         note_options,
         unknown_ids,
         is_muted,
@@ -124,12 +167,26 @@ pub fn render_profile_route(
             ui::profile::ProfileViewAction::Note(note_action) => {
                 Some(RenderNavAction::NoteAction(note_action))
             }
+            ui::profile::ProfileViewAction::Follow(target) => selected_full_kp(accounts)
+                .map(|kp| RenderNavAction::ProfileAction(ProfileAction::Follow(kp, target))),
+            ui::profile::ProfileViewAction::Unfollow(target) => selected_full_kp(accounts)
+                .map(|kp| RenderNavAction::ProfileAction(ProfileAction::Unfollow(kp, target))),
         }
     } else {
         None
     }
 }
 
+fn selected_full_kp(accounts: &Accounts) -> Option<FullKeypair> {
+    let kp = accounts
+        .selected_account_pubkey()
+        .and_then(|pk| accounts.get_full(pk.bytes()));
+    if kp.is_none() {
+        error!("can't change follows without a full keypair");
+    }
+    kp.map(|kp| kp.to_full())
+}
+
 #[cfg(test)]
 mod tests {
     use enostr::NoteId;
@@ -0,0 +1,389 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use egui_virtual_list::VirtualList;
+use enostr::NoteId;
+use nostrdb::{Ndb, Note, NoteKey, Transaction};
+use notedeck::{NoteCache, NoteRef};
+
+/// Guard against reply chains that loop back on themselves
+const MAX_ANCESTORS: usize = 100;
+
+/// A note in a thread, along with the note it replies to
+#[derive(Debug, Clone)]
+pub struct ThreadNode {
+    pub id: NoteId,
+    pub key: NoteKey,
+    pub created_at: u64,
+
+    /// The note this one replies to, from its NIP-10 `e` tags. Both
+    /// marked and legacy positional tags are understood.
+    pub parent: Option<NoteId>,
+}
+
+impl ThreadNode {
+    pub fn from_note(note_cache: &mut NoteCache, note: &Note) -> Option<Self> {
+        let key = note.key()?;
+        let parent = note_cache
+            .cached_note_or_insert(key, note)
+            .reply
+            .borrow(note.tags())
+            .reply()
+            .map(|reply| NoteId::new(*reply.id));
+
+        Some(Self {
+            id: NoteId::new(*note.id()),
+            key,
+            created_at: note.created_at(),
+            parent,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ThreadRowKind {
+    /// A note above the selected note in its reply chain
+    Ancestor,
+    Selected,
+    Reply,
+}
+
+/// A note as it is laid out in the thread view
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ThreadRow {
+    pub id: NoteId,
+    pub key: NoteKey,
+    pub kind: ThreadRowKind,
+
+    /// How far below the selected note a reply is. Direct replies are at
+    /// depth 1, the selected note and its ancestors are at depth 0.
+    pub depth: usize,
+
+    /// Replies below this note that are collapsed because the branch
+    /// got too deep
+    pub hidden_replies: usize,
+}
+
+/// The reply structure of a thread
+#[derive(Debug, Default)]
+pub struct ThreadTree {
+    nodes: HashMap<NoteId, ThreadNode>,
+    children: HashMap<NoteId, Vec<NoteId>>,
+}
+
+impl ThreadTree {
+    pub fn new(nodes: impl IntoIterator<Item = ThreadNode>) -> Self {
+        let mut tree = ThreadTree::default();
+
+        for node in nodes {
+            tree.nodes.entry(node.id).or_insert(node);
+        }
+
+        for node in tree.nodes.values() {
+            if let Some(parent) = node.parent {
+                tree.children.entry(parent).or_default().push(node.id);
+            }
+        }
+
+        // replies are shown oldest first, like a conversation
+        let nodes = &tree.nodes;
+        for children in tree.children.values_mut() {
+            children.sort_by_key(|id| (nodes[id].created_at, *id.bytes()));
+        }
+
+        tree
+    }
+
+    /// Build the tree from the notes of a thread timeline. The ancestors
+    /// of the selected note are looked up in nostrdb, since they may be
+    /// missing from the timeline, eg. when they don't tag the root.
+    pub fn from_notes(
+        ndb: &Ndb,
+        note_cache: &mut NoteCache,
+        txn: &Transaction,
+        notes: &[NoteRef],
+        selected: &NoteId,
+    ) -> Self {
+        let mut nodes: Vec<ThreadNode> = notes
+            .iter()
+            .filter_map(|nr| ndb.get_note_by_key(txn, nr.key).ok())
+            .filter_map(|note| ThreadNode::from_note(note_cache, &note))
+            .collect();
+
+        let mut next = Some(*selected);
+        for _ in 0..MAX_ANCESTORS {
+            let Some(id) = next else {
+                break;
+            };
+
+            let Some(node) = ndb
+                .get_note_by_id(txn, id.bytes())
+                .ok()
+                .and_then(|note| ThreadNode::from_note(note_cache, &note))
+            else {
+                break;
+            };
+
+            next = node.parent;
+            nodes.push(node);
+        }
+
+        ThreadTree::new(nodes)
+    }
+
+    /// The replies to this note, oldest first
+    pub fn replies(&self, id: &NoteId) -> &[NoteId] {
+        self.children.get(id).map(|c| c.as_slice()).unwrap_or(&[])
+    }
+
+    /// The reply chain leading up to this note, starting at the root. We
+    /// stop early at notes we don't have.
+    pub fn ancestors(&self, id: &NoteId) -> Vec<&ThreadNode> {
+        let mut ancestors = Vec::new();
+        let mut seen = HashSet::from([*id]);
+
+        let mut next = self.nodes.get(id).and_then(|n| n.parent);
+        while let Some(parent) = next {
+            if !seen.insert(parent) {
+                break;
+            }
+
+            let Some(node) = self.nodes.get(&parent) else {
+                break;
+            };
+
+            ancestors.push(node);
+            next = node.parent;
+        }
+
+        ancestors.reverse();
+        ancestors
+    }
+
+    /// Every reply below this note, however deep
+    pub fn count_descendants(&self, id: &NoteId) -> usize {
+        let mut seen = HashSet::from([*id]);
+        let mut stack: Vec<&NoteId> = self.replies(id).iter().collect();
+
+        while let Some(reply) = stack.pop() {
+            if seen.insert(*reply) {
+                stack.extend(self.replies(reply));
+            }
+        }
+
+        seen.len() - 1
+    }
+
+    /// Lay out the thread around the selected note: its ancestors, the
+    /// note itself, and the replies below it, depth first. Branches
+    /// deeper than `max_depth` are collapsed into their last shown note.
+    pub fn rows(&self, selected: &NoteId, max_depth: usize) -> Vec<ThreadRow> {
+        let Some(selected_node) = self.nodes.get(selected) else {
+            return Vec::new();
+        };
+
+        let mut rows: Vec<ThreadRow> = self
+            .ancestors(selected)
+            .into_iter()
+            .map(|node| ThreadRow {
+                id: node.id,
+                key: node.key,
+                kind: ThreadRowKind::Ancestor,
+                depth: 0,
+                hidden_replies: 0,
+            })
+            .collect();
+
+        rows.push(ThreadRow {
+            id: *selected,
+            key: selected_node.key,
+            kind: ThreadRowKind::Selected,
+            depth: 0,
+            hidden_replies: if max_depth == 0 {
+                self.count_descendants(selected)
+            } else {
+                0
+            },
+        });
+
+        if max_depth == 0 {
+            return rows;
+        }
+
+        let mut seen = HashSet::from([*selected]);
+        let mut stack: Vec<(&NoteId, usize)> = self
+            .replies(selected)
+            .iter()
+            .rev()
+            .map(|id| (id, 1))
+            .collect();
+
+        while let Some((id, depth)) = stack.pop() {
+            if !seen.insert(*id) {
+                continue;
+            }
+
+            let node = &self.nodes[id];
+            let collapsed = depth >= max_depth;
+
+            rows.push(ThreadRow {
+                id: *id,
+                key: node.key,
+                kind: ThreadRowKind::Reply,
+                depth,
+                hidden_replies: if collapsed {
+                    self.count_descendants(id)
+                } else {
+                    0
+                },
+            });
+
+            if !collapsed {
+                stack.extend(self.replies(id).iter().rev().map(|id| (id, depth + 1)));
+            }
+        }
+
+        rows
+    }
+}
+
+/// The rows of a thread view. Laying them out looks up every note in the
+/// thread, so we only do it again when the thread's notes or the selected
+/// note change.
+#[derive(Debug, Default)]
+pub struct ThreadLayout {
+    /// The selected note and the number of notes the rows were built from
+    built_from: Option<(NoteId, usize)>,
+
+    rows: Rc<[ThreadRow]>,
+
+    /// Replies below the selected note, which is where threads get long
+    pub list: Rc<RefCell<VirtualList>>,
+}
+
+impl ThreadLayout {
+    pub fn rows(
+        &mut self,
+        ndb: &Ndb,
+        note_cache: &mut NoteCache,
+        txn: &Transaction,
+        notes: &[NoteRef],
+        selected: &NoteId,
+        max_depth: usize,
+    ) -> Rc<[ThreadRow]> {
+        let built_from = Some((*selected, notes.len()));
+
+        // keep trying until we have the selected note
+        if self.built_from != built_from || self.rows.is_empty() {
+            let tree = ThreadTree::from_notes(ndb, note_cache, txn, notes, selected);
+            self.rows = tree.rows(selected, max_depth).into();
+            self.built_from = built_from;
+            self.list.borrow_mut().reset();
+        }
+
+        self.rows.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(n: u8) -> NoteId {
+        NoteId::new([n; 32])
+    }
+
+    fn node(n: u8, parent: Option<u8>, created_at: u64) -> ThreadNode {
+        ThreadNode {
+            id: id(n),
+            key: NoteKey::new(n as u64),
+            created_at,
+            parent: parent.map(id),
+        }
+    }
+
+    /// 1
+    /// ├── 2
+    /// │   ├── 4
+    /// │   │   └── 6
+    /// │   │       └── 7
+    /// │   └── 5
+    /// └── 3
+    fn test_tree() -> ThreadTree {
+        ThreadTree::new(vec![
+            node(5, Some(2), 50),
+            node(1, None, 10),
+            node(3, Some(1), 30),
+            node(2, Some(1), 20),
+            node(4, Some(2), 40),
+            node(6, Some(4), 60),
+            node(7, Some(6), 70),
+        ])
+    }
+
+    fn layout(rows: &[ThreadRow]) -> Vec<(u8, usize)> {
+        rows.iter().map(|r| (r.id.bytes()[0], r.depth)).collect()
+    }
+
+    #[test]
+    fn test_replies_are_oldest_first() {
+        let tree = test_tree();
+        assert_eq!(tree.replies(&id(1)), &[id(2), id(3)]);
+        assert_eq!(tree.replies(&id(2)), &[id(4), id(5)]);
+        assert!(tree.replies(&id(7)).is_empty());
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let tree = test_tree();
+        let ancestors: Vec<u8> = tree
+            .ancestors(&id(6))
+            .iter()
+            .map(|n| n.id.bytes()[0])
+            .collect();
+        assert_eq!(ancestors, vec![1, 2, 4]);
+        assert!(tree.ancestors(&id(1)).is_empty());
+    }
+
+    #[test]
+    fn test_rows_around_selected() {
+        let tree = test_tree();
+        let rows = tree.rows(&id(2), 10);
+
+        assert_eq!(
+            layout(&rows),
+            vec![(1, 0), (2, 0), (4, 1), (6, 2), (7, 3), (5, 1)]
+        );
+        assert_eq!(rows[0].kind, ThreadRowKind::Ancestor);
+        assert_eq!(rows[1].kind, ThreadRowKind::Selected);
+        assert!(rows[2..].iter().all(|r| r.kind == ThreadRowKind::Reply));
+    }
+
+    #[test]
+    fn test_deep_branches_collapse() {
+        let tree = test_tree();
+        let rows = tree.rows(&id(1), 2);
+
+        assert_eq!(layout(&rows), vec![(1, 0), (2, 1), (4, 2), (5, 2), (3, 1)]);
+        let collapsed = rows.iter().find(|r| r.id == id(4)).expect("row");
+        assert_eq!(collapsed.hidden_replies, 2);
+    }
+
+    #[test]
+    fn test_missing_parent_and_cycles() {
+        // 2 replies to a note we don't have, 3 and 4 reply to each other
+        let tree = ThreadTree::new(vec![
+            node(2, Some(9), 20),
+            node(3, Some(4), 30),
+            node(4, Some(3), 40),
+        ]);
+
+        assert!(tree.ancestors(&id(2)).is_empty());
+        assert_eq!(layout(&tree.rows(&id(2), 5)), vec![(2, 0)]);
+
+        let rows = tree.rows(&id(3), 5);
+        assert_eq!(layout(&rows), vec![(4, 0), (3, 0), (4, 1)]);
+        assert_eq!(tree.count_descendants(&id(3)), 1);
+    }
+}
@@ -1,22 +1,29 @@
 use core::f32;
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use egui::{
     pos2, vec2, Align, Color32, FontId, Id, ImageSource, Margin, Pos2, Rect, RichText, Separator,
     Ui, Vec2, Widget,
 };
-use enostr::Pubkey;
-use nostrdb::{Ndb, Transaction};
+use enostr::{Pubkey, RelayPool, RelayStatus};
+use nostrdb::{Ndb, Note, Transaction};
 use tracing::error;
 
 use crate::{
     login_manager::AcquireKeyState,
     route::Route,
-    timeline::{kind::ListKind, PubkeySource, TimelineKind},
+    timeline::{kind::ListKind, GenericFilter, GenericFilterForm, PubkeySource, TimelineKind},
     Damus,
 };
 
-use notedeck::{AppContext, Images, NotedeckTextStyle, UserAccount};
+use notedeck::{
+    lists::{self, KIND_BOOKMARKS, KIND_BOOKMARK_SET, KIND_FOLLOW_SET},
+    AppContext, Images, NotedeckTextStyle, UserAccount,
+};
 use notedeck_ui::anim::ICON_EXPANSION_MULTIPLE;
 use tokenator::{ParseError, TokenParser, TokenSerializable, TokenWriter};
 
@@ -31,6 +38,10 @@ pub enum AddColumnResponse {
     Algo(AlgoOption),
     UndecidedIndividual,
     ExternalIndividual,
+    UndecidedList,
+    UndecidedGeneric,
+    UndecidedRelay,
+    Drafts,
 }
 
 pub enum NotificationColumnType {
@@ -61,6 +72,11 @@ enum AddColumnOption {
     UndecidedIndividual,
     ExternalIndividual,
     Individual(PubkeySource),
+    UndecidedList,
+    List(ListKind),
+    UndecidedGeneric,
+    UndecidedRelay,
+    Drafts,
 }
 
 #[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
@@ -79,6 +95,9 @@ pub enum AddColumnRoute {
     Algo(AddAlgoRoute),
     UndecidedIndividual,
     ExternalIndividual,
+    Lists,
+    Generic,
+    Relay,
 }
 
 // Parser for the common case without any payloads
@@ -104,6 +123,9 @@ impl AddColumnRoute {
             Self::UndecidedIndividual => &["column", "individual_selection"],
             Self::ExternalIndividual => &["column", "external_individual_selection"],
             Self::Hashtag => &["column", "hashtag"],
+            Self::Lists => &["column", "list_selection"],
+            Self::Generic => &["column", "generic"],
+            Self::Relay => &["column", "relay"],
             Self::Algo(AddAlgoRoute::Base) => &["column", "algo_selection"],
             Self::Algo(AddAlgoRoute::LastPerPubkey) => {
                 &["column", "algo_selection", "last_per_pubkey"]
@@ -131,6 +153,9 @@ impl TokenSerializable for AddColumnRoute {
                 |p| parse_column_route(p, AddColumnRoute::UndecidedIndividual),
                 |p| parse_column_route(p, AddColumnRoute::ExternalIndividual),
                 |p| parse_column_route(p, AddColumnRoute::Hashtag),
+                |p| parse_column_route(p, AddColumnRoute::Lists),
+                |p| parse_column_route(p, AddColumnRoute::Generic),
+                |p| parse_column_route(p, AddColumnRoute::Relay),
                 |p| parse_column_route(p, AddColumnRoute::Algo(AddAlgoRoute::Base)),
                 |p| parse_column_route(p, AddColumnRoute::Algo(AddAlgoRoute::LastPerPubkey)),
             ],
@@ -157,6 +182,13 @@ impl AddColumnOption {
             AddColumnOption::Individual(pubkey_source) => AddColumnResponse::Timeline(
                 TimelineKind::profile(*pubkey_source.as_pubkey(&cur_account.key.pubkey)),
             ),
+            AddColumnOption::UndecidedList => AddColumnResponse::UndecidedList,
+            AddColumnOption::List(list_kind) => {
+                AddColumnResponse::Timeline(TimelineKind::List(list_kind))
+            }
+            AddColumnOption::UndecidedGeneric => AddColumnResponse::UndecidedGeneric,
+            AddColumnOption::UndecidedRelay => AddColumnResponse::UndecidedRelay,
+            AddColumnOption::Drafts => AddColumnResponse::Drafts,
         }
     }
 }
@@ -224,26 +256,95 @@ impl<'a> AddColumnView<'a> {
         deck_author: Pubkey,
     ) -> Option<AddColumnResponse> {
         let algo_option = ColumnOptionData {
-            title: "Contact List",
-            description: "Source the last note for each user in your contact list",
+            title: "Contact List".into(),
+            description: "Source the last note for each user in your contact list".into(),
             icon: egui::include_image!("../../../../assets/icons/home_icon_dark_4x.png"),
             option: AddColumnOption::Algo(AlgoOption::LastPerPubkey(Decision::Decided(
                 ListKind::contact_list(deck_author),
             ))),
         };
 
-        let option = algo_option.option.clone();
-        if self.column_option_ui(ui, algo_option).clicked() {
-            self.cur_account.map(|acct| option.take_as_response(acct))
-        } else {
-            None
+        let mut selected_option: Option<AddColumnResponse> = None;
+        let mut options = vec![algo_option];
+        options.extend(self.list_options(&deck_author, |list_kind| {
+            AddColumnOption::Algo(AlgoOption::LastPerPubkey(Decision::Decided(list_kind)))
+        }));
+
+        for column_option_data in options {
+            let option = column_option_data.option.clone();
+            if self.column_option_ui(ui, column_option_data).clicked() {
+                selected_option = self.cur_account.map(|acct| option.take_as_response(acct));
+            }
+
+            ui.add(Separator::default().spacing(0.0));
         }
+
+        selected_option
+    }
+
+    fn lists_ui(&mut self, ui: &mut Ui, deck_author: Pubkey) -> Option<AddColumnResponse> {
+        let options = self.list_options(&deck_author, AddColumnOption::List);
+        if options.is_empty() {
+            padding(16.0, ui, |ui| {
+                ui.label(
+                    RichText::new("We haven't found any of your lists yet")
+                        .text_style(NotedeckTextStyle::Body.text_style())
+                        .weak(),
+                );
+            });
+            return None;
+        }
+
+        let mut selected_option: Option<AddColumnResponse> = None;
+        for column_option_data in options {
+            let option = column_option_data.option.clone();
+            if self.column_option_ui(ui, column_option_data).clicked() {
+                selected_option = self.cur_account.map(|acct| option.take_as_response(acct));
+            }
+
+            ui.add(Separator::default().spacing(0.0));
+        }
+
+        selected_option
+    }
+
+    /// The deck author's NIP-51 lists that we have locally
+    fn list_options(
+        &self,
+        deck_author: &Pubkey,
+        to_option: impl Fn(ListKind) -> AddColumnOption,
+    ) -> Vec<ColumnOptionData> {
+        let txn = Transaction::new(self.ndb).expect("txn");
+        lists::query_lists(
+            self.ndb,
+            &txn,
+            deck_author.bytes(),
+            &[KIND_FOLLOW_SET, KIND_BOOKMARKS, KIND_BOOKMARK_SET],
+        )
+        .iter()
+        .filter_map(|note| {
+            let list_kind = ListKind::from_list_note(note)?;
+            Some(ColumnOptionData {
+                title: lists::list_title(note)
+                    .unwrap_or(list_kind.name())
+                    .to_owned()
+                    .into(),
+                description: list_description(note, &list_kind).into(),
+                icon: if list_kind.is_bookmarks() {
+                    egui::include_image!("../../../../assets/icons/select_icon_3x.png")
+                } else {
+                    egui::include_image!("../../../../assets/icons/profile_icon_4x.png")
+                },
+                option: to_option(list_kind),
+            })
+        })
+        .collect()
     }
 
     fn algo_ui(&mut self, ui: &mut Ui) -> Option<AddColumnResponse> {
         let algo_option = ColumnOptionData {
-            title: "Last Note per User",
-            description: "Show the last note for each user from a list",
+            title: "Last Note per User".into(),
+            description: "Show the last note for each user from a list".into(),
             icon: egui::include_image!("../../../../assets/icons/algo.png"),
             option: AddColumnOption::Algo(AlgoOption::LastPerPubkey(Decision::Undecided)),
         };
@@ -388,7 +489,11 @@ impl<'a> AddColumnView<'a> {
             title_font_max_size + inter_text_padding + desc_font_max_size + (2.0 * height_padding)
         };
 
-        let helper = AnimationHelper::new(ui, data.title, vec2(max_width, max_height));
+        let helper = AnimationHelper::new(
+            ui,
+            (&data.title, &data.description),
+            vec2(max_width, max_height),
+        );
         let animation_rect = helper.get_animation_rect();
 
         let cur_icon_width = helper.scale_1d_pos(min_icon_width);
@@ -447,8 +552,8 @@ impl<'a> AddColumnView<'a> {
     fn get_base_options(&self) -> Vec<ColumnOptionData> {
         let mut vec = Vec::new();
         vec.push(ColumnOptionData {
-            title: "Universe",
-            description: "See the whole nostr universe",
+            title: "Universe".into(),
+            description: "See the whole nostr universe".into(),
             icon: egui::include_image!("../../../../assets/icons/universe_icon_dark_4x.png"),
             option: AddColumnOption::Universe,
         });
@@ -461,36 +566,75 @@ impl<'a> AddColumnView<'a> {
             };
 
             vec.push(ColumnOptionData {
-                title: "Contacts",
-                description: "See notes from your contacts",
+                title: "Contacts".into(),
+                description: "See notes from your contacts".into(),
                 icon: egui::include_image!("../../../../assets/icons/home_icon_dark_4x.png"),
                 option: AddColumnOption::Contacts(source),
             });
         }
+        if self.cur_account.is_some() {
+            vec.push(ColumnOptionData {
+                title: "Lists".into(),
+                description: "See notes from your follow sets and bookmarks".into(),
+                icon: egui::include_image!("../../../../assets/icons/select_icon_3x.png"),
+                option: AddColumnOption::UndecidedList,
+            });
+        }
+        if let Some(acc) = self.cur_account {
+            vec.push(ColumnOptionData {
+                title: "Bookmarks".into(),
+                description: "Notes you saved for later".into(),
+                icon: egui::include_image!("../../../../assets/icons/select_icon_3x.png"),
+                option: AddColumnOption::List(ListKind::Bookmarks(acc.key.pubkey)),
+            });
+        }
         vec.push(ColumnOptionData {
-            title: "Notifications",
-            description: "Stay up to date with notifications and mentions",
+            title: "Notifications".into(),
+            description: "Stay up to date with notifications and mentions".into(),
             icon: egui::include_image!("../../../../assets/icons/notifications_icon_dark_4x.png"),
             option: AddColumnOption::UndecidedNotification,
         });
         vec.push(ColumnOptionData {
-            title: "Hashtag",
-            description: "Stay up to date with a certain hashtag",
+            title: "Hashtag".into(),
+            description: "Stay up to date with a certain hashtag".into(),
             icon: egui::include_image!("../../../../assets/icons/hashtag_icon_4x.png"),
             option: AddColumnOption::UndecidedHashtag,
         });
         vec.push(ColumnOptionData {
-            title: "Individual",
-            description: "Stay up to date with someone's notes & replies",
+            title: "Relay".into(),
+            description: "Browse everything on one relay".into(),
+            icon: egui::include_image!("../../../../assets/icons/add_relay_icon_4x.png"),
+            option: AddColumnOption::UndecidedRelay,
+        });
+        vec.push(ColumnOptionData {
+            title: "Individual".into(),
+            description: "Stay up to date with someone's notes & replies".into(),
             icon: egui::include_image!("../../../../assets/icons/profile_icon_4x.png"),
             option: AddColumnOption::UndecidedIndividual,
         });
         vec.push(ColumnOptionData {
-            title: "Algo",
-            description: "Algorithmic feeds to aid in note discovery",
+            title: "Algo".into(),
+            description: "Algorithmic feeds to aid in note discovery".into(),
             icon: egui::include_image!("../../../../assets/icons/algo.png"),
             option: AddColumnOption::Algo(AlgoOption::LastPerPubkey(Decision::Undecided)),
         });
+        vec.push(ColumnOptionData {
+            title: "Custom".into(),
+            description: "Any kinds, authors, tags or search you like".into(),
+            icon: egui::include_image!("../../../../assets/icons/select_icon_3x.png"),
+            option: AddColumnOption::UndecidedGeneric,
+        });
+        if self
+            .cur_account
+            .is_some_and(|acc| acc.key.secret_key.is_some())
+        {
+            vec.push(ColumnOptionData {
+                title: "Drafts".into(),
+                description: "Notes you started writing but haven't sent".into(),
+                icon: egui::include_image!("../../../../assets/icons/edit_icon_4x_dark.png"),
+                option: AddColumnOption::Drafts,
+            });
+        }
 
         vec
     }
@@ -506,8 +650,8 @@ impl<'a> AddColumnView<'a> {
             };
 
             vec.push(ColumnOptionData {
-                title: "Your Notifications",
-                description: "Stay up to date with your notifications and mentions",
+                title: "Your Notifications".into(),
+                description: "Stay up to date with your notifications and mentions".into(),
                 icon: egui::include_image!(
                     "../../../../assets/icons/notifications_icon_dark_4x.png"
                 ),
@@ -516,8 +660,8 @@ impl<'a> AddColumnView<'a> {
         }
 
         vec.push(ColumnOptionData {
-            title: "Someone else's Notifications",
-            description: "Stay up to date with someone else's notifications and mentions",
+            title: "Someone else's Notifications".into(),
+            description: "Stay up to date with someone else's notifications and mentions".into(),
             icon: egui::include_image!("../../../../assets/icons/notifications_icon_dark_4x.png"),
             option: AddColumnOption::ExternalNotification,
         });
@@ -536,16 +680,16 @@ impl<'a> AddColumnView<'a> {
             };
 
             vec.push(ColumnOptionData {
-                title: "Your Notes",
-                description: "Keep track of your notes & replies",
+                title: "Your Notes".into(),
+                description: "Keep track of your notes & replies".into(),
                 icon: egui::include_image!("../../../../assets/icons/profile_icon_4x.png"),
                 option: AddColumnOption::Individual(source),
             });
         }
 
         vec.push(ColumnOptionData {
-            title: "Someone else's Notes",
-            description: "Stay up to date with someone else's notes & replies",
+            title: "Someone else's Notes".into(),
+            description: "Stay up to date with someone else's notes & replies".into(),
             icon: egui::include_image!("../../../../assets/icons/profile_icon_4x.png"),
             option: AddColumnOption::ExternalIndividual,
         });
@@ -554,6 +698,44 @@ impl<'a> AddColumnView<'a> {
     }
 }
 
+fn list_description(note: &Note, list_kind: &ListKind) -> String {
+    let (count, one, many) = if list_kind.is_bookmarks() {
+        (lists::list_note_ids(note).len(), "note", "notes")
+    } else {
+        (lists::list_pubkeys(note).len(), "person", "people")
+    };
+
+    let what = if count == 1 { one } else { many };
+    format!("{}, {count} {what}", list_kind.name())
+}
+
+/// Ask the relays for the deck author's lists, once they're connected
+fn subscribe_lists(ctx: &egui::Context, pool: &mut RelayPool, deck_author: &Pubkey) {
+    let id = Id::new(("lists_subscribed", deck_author));
+    if ctx.data(|d| d.get_temp::<bool>(id).unwrap_or(false)) {
+        return;
+    }
+
+    if !pool
+        .relays
+        .iter()
+        .any(|relay| matches!(relay.status(), RelayStatus::Connected))
+    {
+        return;
+    }
+
+    let filter = nostrdb::Filter::new()
+        .authors([deck_author.bytes()])
+        .kinds([
+            KIND_FOLLOW_SET as u64,
+            KIND_BOOKMARKS as u64,
+            KIND_BOOKMARK_SET as u64,
+        ])
+        .build();
+    pool.subscribe(format!("lists-{}", deck_author.hex()), vec![filter]);
+    ctx.data_mut(|d| d.insert_temp(id, true));
+}
+
 fn find_user_button() -> impl Widget {
     styled_button("Find User", notedeck_ui::colors::PINK)
 }
@@ -584,8 +766,8 @@ pub(crate) fn sized_button(text: &str) -> impl Widget + '_ {
 */
 
 struct ColumnOptionData {
-    title: &'static str,
-    description: &'static str,
+    title: Cow<'static, str>,
+    description: Cow<'static, str>,
     icon: ImageSource<'static>,
     option: AddColumnOption,
 }
@@ -597,6 +779,15 @@ pub fn render_add_column_routes(
     col: usize,
     route: &AddColumnRoute,
 ) {
+    if matches!(
+        route,
+        AddColumnRoute::Lists | AddColumnRoute::Algo(AddAlgoRoute::LastPerPubkey)
+    ) {
+        if let Some(acc) = ctx.accounts.get_selected_account() {
+            subscribe_lists(ui.ctx(), ctx.pool, &acc.key.pubkey);
+        }
+    }
+
     let mut add_column_view = AddColumnView::new(
         &mut app.view_state.id_state_map,
         ctx.ndb,
@@ -618,8 +809,17 @@ pub fn render_add_column_routes(
         AddColumnRoute::UndecidedNotification => add_column_view.notifications_ui(ui),
         AddColumnRoute::ExternalNotification => add_column_view.external_notification_ui(ui),
         AddColumnRoute::Hashtag => hashtag_ui(ui, &mut app.view_state.id_string_map),
+        AddColumnRoute::Generic => generic_filter_ui(ui, &mut app.view_state.id_string_map),
+        AddColumnRoute::Relay => relay_ui(ui, &mut app.view_state.id_string_map),
         AddColumnRoute::UndecidedIndividual => add_column_view.individual_ui(ui),
         AddColumnRoute::ExternalIndividual => add_column_view.external_individual_ui(ui),
+        AddColumnRoute::Lists => {
+            if let Some(deck_author) = ctx.accounts.get_selected_account() {
+                add_column_view.lists_ui(ui, deck_author.key.pubkey)
+            } else {
+                None
+            }
+        }
     };
 
     if let Some(resp) = resp {
@@ -671,7 +871,8 @@ pub fn render_add_column_routes(
                 AlgoOption::LastPerPubkey(Decision::Decided(list_kind)) => {
                     let maybe_timeline = {
                         let txn = Transaction::new(ctx.ndb).unwrap();
-                        TimelineKind::last_per_pubkey(list_kind).into_timeline(&txn, ctx.ndb)
+                        TimelineKind::last_per_pubkey(list_kind.clone())
+                            .into_timeline(&txn, ctx.ndb)
                     };
 
                     if let Some(mut timeline) = maybe_timeline {
@@ -731,6 +932,24 @@ pub fn render_add_column_routes(
                         AddColumnRoute::UndecidedIndividual,
                     ));
             }
+            AddColumnResponse::UndecidedList => {
+                app.columns_mut(ctx.accounts)
+                    .column_mut(col)
+                    .router_mut()
+                    .route_to(crate::route::Route::AddColumn(AddColumnRoute::Lists));
+            }
+            AddColumnResponse::UndecidedGeneric => {
+                app.columns_mut(ctx.accounts)
+                    .column_mut(col)
+                    .router_mut()
+                    .route_to(crate::route::Route::AddColumn(AddColumnRoute::Generic));
+            }
+            AddColumnResponse::UndecidedRelay => {
+                app.columns_mut(ctx.accounts)
+                    .column_mut(col)
+                    .router_mut()
+                    .route_to(crate::route::Route::AddColumn(AddColumnRoute::Relay));
+            }
             AddColumnResponse::ExternalIndividual => {
                 app.columns_mut(ctx.accounts)
                     .column_mut(col)
@@ -739,6 +958,12 @@ pub fn render_add_column_routes(
                         AddColumnRoute::ExternalIndividual,
                     ));
             }
+            AddColumnResponse::Drafts => {
+                app.columns_mut(ctx.accounts)
+                    .column_mut(col)
+                    .router_mut()
+                    .route_to_replaced(Route::Drafts);
+            }
         };
     }
 }
@@ -778,6 +1003,120 @@ pub fn hashtag_ui(
     .inner
 }
 
+pub fn relay_ui(ui: &mut Ui, id_string_map: &mut HashMap<Id, String>) -> Option<AddColumnResponse> {
+    padding(16.0, ui, |ui| {
+        let id = ui.id().with("relay_feed");
+        let text_buffer = id_string_map.entry(id).or_default();
+        let kind = TimelineKind::relay(text_buffer);
+
+        let text_edit = egui::TextEdit::singleline(text_buffer)
+            .hint_text(
+                RichText::new("Enter the relay here, like wss://relay.damus.io")
+                    .text_style(NotedeckTextStyle::Body.text_style()),
+            )
+            .vertical_align(Align::Center)
+            .desired_width(f32::INFINITY)
+            .min_size(Vec2::new(0.0, 40.0))
+            .margin(Margin::same(12));
+        ui.add(text_edit);
+
+        ui.add_space(8.0);
+        let clicked = ui
+            .add_enabled_ui(kind.is_some(), |ui| {
+                ui.add_sized(egui::vec2(50.0, 40.0), add_column_button())
+            })
+            .inner
+            .clicked();
+
+        if clicked {
+            id_string_map.remove(&id);
+            kind.map(AddColumnResponse::Timeline)
+        } else {
+            None
+        }
+    })
+    .inner
+}
+
+/// The form fields, and the hints that explain what they take
+const GENERIC_FILTER_FIELDS: [(&str, &str); 6] = [
+    ("title", "Column title"),
+    ("kinds", "Kinds, like 1, 30023"),
+    ("authors", "Authors, npubs or hex"),
+    ("tags", "Tags, like t=nostr p=npub1..."),
+    ("search", "Search"),
+    ("since", "Since, like 12h, 7d or a timestamp"),
+];
+
+pub fn generic_filter_ui(
+    ui: &mut Ui,
+    id_string_map: &mut HashMap<Id, String>,
+) -> Option<AddColumnResponse> {
+    padding(16.0, ui, |ui| {
+        let id = ui.id().with("generic_filter");
+
+        for (field, hint) in GENERIC_FILTER_FIELDS {
+            let text_buffer = id_string_map.entry(id.with(field)).or_default();
+            let text_edit = egui::TextEdit::singleline(text_buffer)
+                .hint_text(RichText::new(hint).text_style(NotedeckTextStyle::Body.text_style()))
+                .vertical_align(Align::Center)
+                .desired_width(f32::INFINITY)
+                .min_size(Vec2::new(0.0, 40.0))
+                .margin(Margin::same(12));
+            ui.add(text_edit);
+            ui.add_space(8.0);
+        }
+
+        let error_id = id.with("error");
+        if let Some(error) = id_string_map.get(&error_id) {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+            ui.add_space(8.0);
+        }
+
+        if !ui
+            .add_sized(egui::vec2(50.0, 40.0), add_column_button())
+            .clicked()
+        {
+            return None;
+        }
+
+        let field = |name: &str| {
+            id_string_map
+                .get(&id.with(name))
+                .cloned()
+                .unwrap_or_default()
+        };
+        let form = GenericFilterForm {
+            title: field("title"),
+            kinds: field("kinds"),
+            authors: field("authors"),
+            tags: field("tags"),
+            search: field("search"),
+            since: field("since"),
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        match GenericFilter::from_form(&form, now) {
+            Ok(filter) => {
+                for (field, _) in GENERIC_FILTER_FIELDS {
+                    id_string_map.remove(&id.with(field));
+                }
+                id_string_map.remove(&error_id);
+                Some(AddColumnResponse::Timeline(TimelineKind::Generic(filter)))
+            }
+            Err(err) => {
+                id_string_map.insert(error_id, err);
+                None
+            }
+        }
+    })
+    .inner
+}
+
 fn sanitize_hashtag(raw_hashtag: &str) -> String {
     raw_hashtag.replace("#", "")
 }
@@ -802,6 +1141,39 @@ mod tests {
             assert_eq!(token_writer.str(), data_str);
         }
 
+        {
+            let data_str = "column:list_selection";
+            let data = &data_str.split(":").collect::<Vec<&str>>();
+            let mut token_writer = TokenWriter::default();
+            let mut parser = TokenParser::new(&data);
+            let parsed = AddColumnRoute::parse_from_tokens(&mut parser).unwrap();
+            parsed.serialize_tokens(&mut token_writer);
+            assert_eq!(AddColumnRoute::Lists, parsed);
+            assert_eq!(token_writer.str(), data_str);
+        }
+
+        {
+            let data_str = "column:generic";
+            let data = &data_str.split(":").collect::<Vec<&str>>();
+            let mut token_writer = TokenWriter::default();
+            let mut parser = TokenParser::new(&data);
+            let parsed = AddColumnRoute::parse_from_tokens(&mut parser).unwrap();
+            parsed.serialize_tokens(&mut token_writer);
+            assert_eq!(AddColumnRoute::Generic, parsed);
+            assert_eq!(token_writer.str(), data_str);
+        }
+
+        {
+            let data_str = "column:relay";
+            let data = &data_str.split(":").collect::<Vec<&str>>();
+            let mut token_writer = TokenWriter::default();
+            let mut parser = TokenParser::new(&data);
+            let parsed = AddColumnRoute::parse_from_tokens(&mut parser).unwrap();
+            parsed.serialize_tokens(&mut token_writer);
+            assert_eq!(AddColumnRoute::Relay, parsed);
+            assert_eq!(token_writer.str(), data_str);
+        }
+
         {
             let data_str = "column";
             let mut token_writer = TokenWriter::default();
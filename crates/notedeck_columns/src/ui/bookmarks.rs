@@ -0,0 +1,116 @@
+use enostr::{KeypairUnowned, NoteId};
+use nostrdb::Transaction;
+use notedeck::{Bookmarks, MuteFun, NoteAction, NoteContext, UnknownIds};
+use notedeck_ui::{NoteOptions, NoteView};
+
+/// Bookmarked notes, the most recently saved first
+pub struct BookmarksView<'a, 'd> {
+    bookmarks: &'a Bookmarks,
+    unknown_ids: &'a mut UnknownIds,
+    note_options: NoteOptions,
+    id_source: egui::Id,
+    is_muted: &'a MuteFun,
+    note_context: &'a mut NoteContext<'d>,
+    cur_acc: &'a Option<KeypairUnowned<'a>>,
+}
+
+impl<'a, 'd> BookmarksView<'a, 'd> {
+    pub fn new(
+        bookmarks: &'a Bookmarks,
+        unknown_ids: &'a mut UnknownIds,
+        note_options: NoteOptions,
+        is_muted: &'a MuteFun,
+        note_context: &'a mut NoteContext<'d>,
+        cur_acc: &'a Option<KeypairUnowned<'a>>,
+    ) -> Self {
+        BookmarksView {
+            bookmarks,
+            unknown_ids,
+            note_options,
+            id_source: egui::Id::new("bookmarks_view"),
+            is_muted,
+            note_context,
+            cur_acc,
+        }
+    }
+
+    pub fn id_source(mut self, id: egui::Id) -> Self {
+        self.id_source = id;
+        self
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<NoteAction> {
+        let txn = Transaction::new(self.note_context.ndb).expect("txn");
+        self.fetch_missing(ui, &txn);
+
+        egui::ScrollArea::vertical()
+            .id_salt(self.id_source)
+            .animated(false)
+            .auto_shrink([false, false])
+            .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysVisible)
+            .show(ui, |ui| {
+                if self.bookmarks.is_empty() {
+                    notedeck_ui::padding(8.0, ui, |ui| {
+                        ui.weak("No bookmarks yet");
+                    });
+                    return None;
+                }
+
+                let mut action: Option<NoteAction> = None;
+                for bookmark in self.bookmarks.newest_first() {
+                    let Ok(note) = self.note_context.ndb.get_note_by_id(&txn, &bookmark.id) else {
+                        continue;
+                    };
+
+                    if (self.is_muted)(&note, note.id()) {
+                        continue;
+                    }
+
+                    notedeck_ui::padding(8.0, ui, |ui| {
+                        if bookmark.private {
+                            ui.add(
+                                egui::Label::new(egui::RichText::new("private").small().weak())
+                                    .selectable(false),
+                            );
+                        }
+
+                        let resp = NoteView::new(
+                            self.note_context,
+                            self.cur_acc,
+                            &note,
+                            self.note_options,
+                        )
+                        .show(ui);
+
+                        if let Some(note_action) = resp.action {
+                            action = Some(note_action);
+                        }
+                    });
+
+                    notedeck_ui::hline(ui);
+                }
+
+                action
+            })
+            .inner
+    }
+
+    /// Ask relays for the notes we don't have, once per version of the list
+    fn fetch_missing(&mut self, ui: &egui::Ui, txn: &Transaction) {
+        let fetched_id = self.id_source.with("fetched");
+        let created_at = self.bookmarks.created_at();
+        if ui.data(|d| d.get_temp::<u64>(fetched_id)) == Some(created_at) {
+            return;
+        }
+
+        for bookmark in self.bookmarks.newest_first() {
+            self.unknown_ids.add_note_id_if_missing(
+                self.note_context.ndb,
+                txn,
+                &NoteId::new(bookmark.id),
+            );
+        }
+
+        ui.data_mut(|d| d.insert_temp(fetched_id, created_at));
+    }
+}
@@ -86,6 +86,9 @@ impl<'a> NavTitle<'a> {
         if let Some(resp) = title_resp {
             match resp {
                 TitleResponse::RemoveColumn => Some(RenderNavAction::RemoveColumn),
+                TitleResponse::Rename(title) => Some(RenderNavAction::SwitchingAction(
+                    SwitchingAction::Columns(ColumnsAction::Rename(self.col_id, title)),
+                )),
                 TitleResponse::MoveColumn(to_index) => {
                     let from = self.col_id;
                     Some(RenderNavAction::SwitchingAction(SwitchingAction::Columns(
@@ -437,6 +440,7 @@ impl<'a> NavTitle<'a> {
                 }
 
                 TimelineKind::Universe
+                | TimelineKind::Relay(_)
                 | TimelineKind::Algo(_)
                 | TimelineKind::Notifications(_)
                 | TimelineKind::Generic(_)
@@ -449,6 +453,8 @@ impl<'a> NavTitle<'a> {
             Route::Quote(_) => {}
             Route::Accounts(_as) => {}
             Route::ComposeNote => {}
+            Route::Drafts => {}
+            Route::Scheduled => {}
             Route::AddColumn(_add_col_route) => {}
             Route::Support => {}
             Route::Relays => {}
@@ -478,7 +484,11 @@ impl<'a> NavTitle<'a> {
             .selectable(false)
     }
 
-    fn title_label(&self, ui: &mut egui::Ui, top: &Route) {
+    fn title_label(&self, ui: &mut egui::Ui, top: &Route) -> Option<String> {
+        if let Route::Timeline(TimelineKind::Generic(filter)) = top {
+            return self.editable_title(ui, &filter.title);
+        }
+
         let column_title = top.title();
 
         match &column_title {
@@ -492,41 +502,92 @@ impl<'a> NavTitle<'a> {
                 ui.add(Self::title_label_value(title));
             }
         };
+
+        None
     }
 
-    fn title(&mut self, ui: &mut egui::Ui, top: &Route, navigating: bool) -> Option<TitleResponse> {
-        if !navigating {
-            self.title_presentation(ui, top, 32.0);
+    /// Custom columns are named by the user, a double click lets them
+    /// rename it. Returns the new title once they're done.
+    fn editable_title(&self, ui: &mut egui::Ui, title: &str) -> Option<String> {
+        let id = ui.id().with(("rename_column", self.col_id));
+        let edit_id = id.with("edit");
+
+        let Some(mut buffer) = ui.data(|d| d.get_temp::<String>(id)) else {
+            let shown = if title.is_empty() { "Custom" } else { title };
+            let resp = ui
+                .add(Self::title_label_value(shown).sense(egui::Sense::click()))
+                .on_hover_text("Double click to rename");
+
+            if resp.double_clicked() {
+                ui.data_mut(|d| d.insert_temp(id, title.to_owned()));
+                ui.memory_mut(|m| m.request_focus(edit_id));
+            }
+            return None;
+        };
+
+        let resp = ui.add(
+            egui::TextEdit::singleline(&mut buffer)
+                .id(edit_id)
+                .desired_width(160.0),
+        );
+
+        if !resp.lost_focus() {
+            ui.data_mut(|d| d.insert_temp(id, buffer));
+            return None;
         }
 
-        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-            if navigating {
-                self.title_presentation(ui, top, 32.0);
-                None
-            } else {
-                let move_col = self.move_button_section(ui);
-                let remove_col = self.delete_button_section(ui);
-                if let Some(col) = move_col {
-                    Some(TitleResponse::MoveColumn(col))
-                } else if remove_col {
-                    Some(TitleResponse::RemoveColumn)
-                } else {
+        ui.data_mut(|d| d.remove::<String>(id));
+        if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            None
+        } else {
+            Some(buffer.trim().to_owned())
+        }
+    }
+
+    fn title(&mut self, ui: &mut egui::Ui, top: &Route, navigating: bool) -> Option<TitleResponse> {
+        let renamed = if navigating {
+            None
+        } else {
+            self.title_presentation(ui, top, 32.0)
+        };
+
+        let resp = ui
+            .with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if navigating {
+                    self.title_presentation(ui, top, 32.0);
                     None
+                } else {
+                    let move_col = self.move_button_section(ui);
+                    let remove_col = self.delete_button_section(ui);
+                    if let Some(col) = move_col {
+                        Some(TitleResponse::MoveColumn(col))
+                    } else if remove_col {
+                        Some(TitleResponse::RemoveColumn)
+                    } else {
+                        None
+                    }
                 }
-            }
-        })
-        .inner
+            })
+            .inner;
+
+        resp.or(renamed.map(TitleResponse::Rename))
     }
 
-    fn title_presentation(&mut self, ui: &mut egui::Ui, top: &Route, pfp_size: f32) {
+    fn title_presentation(
+        &mut self,
+        ui: &mut egui::Ui,
+        top: &Route,
+        pfp_size: f32,
+    ) -> Option<String> {
         self.title_pfp(ui, top, pfp_size);
-        self.title_label(ui, top);
+        self.title_label(ui, top)
     }
 }
 
 enum TitleResponse {
     RemoveColumn,
     MoveColumn(usize),
+    Rename(String),
 }
 
 fn prev<R>(xs: &[R]) -> Option<&R> {
@@ -0,0 +1,118 @@
+use egui::{RichText, ScrollArea};
+use nostrdb::{Ndb, Transaction};
+use notedeck::name::get_display_name;
+use notedeck_ui::padding;
+
+use crate::{
+    draft::{AccountDrafts, Draft},
+    ui::note::PostType,
+};
+
+/// How much of a draft we show in the list
+const PREVIEW_CHARS: usize = 140;
+
+pub enum DraftsAction {
+    Resume(PostType),
+    Delete(PostType),
+}
+
+/// Lists the unsent drafts of an account
+pub struct DraftsView<'a> {
+    ndb: &'a Ndb,
+    txn: &'a Transaction,
+    drafts: &'a AccountDrafts,
+}
+
+impl<'a> DraftsView<'a> {
+    pub fn new(ndb: &'a Ndb, txn: &'a Transaction, drafts: &'a AccountDrafts) -> Self {
+        Self { ndb, txn, drafts }
+    }
+
+    pub fn ui(&self, ui: &mut egui::Ui) -> Option<DraftsAction> {
+        let unsent = self.drafts.unsent();
+
+        if unsent.is_empty() {
+            padding(16.0, ui, |ui| {
+                ui.label(RichText::new("No drafts").weak());
+            });
+            return None;
+        }
+
+        let mut action = None;
+        ScrollArea::vertical().show(ui, |ui| {
+            for (post_type, draft) in unsent {
+                padding(8.0, ui, |ui| {
+                    if let Some(row_action) = self.draft_row(ui, &post_type, draft) {
+                        action = Some(row_action);
+                    }
+                });
+                notedeck_ui::hline(ui);
+            }
+        });
+
+        action
+    }
+
+    fn draft_row(
+        &self,
+        ui: &mut egui::Ui,
+        post_type: &PostType,
+        draft: &Draft,
+    ) -> Option<DraftsAction> {
+        let mut action = None;
+
+        ui.label(RichText::new(self.title(post_type)).strong());
+
+        let preview: String = draft
+            .buffer
+            .text_buffer
+            .chars()
+            .take(PREVIEW_CHARS)
+            .collect();
+        if preview.len() < draft.buffer.text_buffer.len() {
+            ui.label(format!("{preview}…"));
+        } else if !preview.is_empty() {
+            ui.label(preview);
+        }
+
+        let num_media = draft.uploaded_media.len();
+        if num_media > 0 {
+            let s = if num_media == 1 { "" } else { "s" };
+            ui.label(RichText::new(format!("{num_media} attachment{s}")).weak());
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("Resume").clicked() {
+                action = Some(DraftsAction::Resume(post_type.clone()));
+            }
+
+            if ui.button("Delete").clicked() {
+                action = Some(DraftsAction::Delete(post_type.clone()));
+            }
+        });
+
+        action
+    }
+
+    fn title(&self, post_type: &PostType) -> String {
+        let Some(target) = post_type.target() else {
+            return "New note".to_owned();
+        };
+
+        let author = self
+            .ndb
+            .get_note_by_id(self.txn, target.bytes())
+            .ok()
+            .map(|note| {
+                let profile = self.ndb.get_profile_by_pubkey(self.txn, note.pubkey()).ok();
+                get_display_name(profile.as_ref()).name().to_owned()
+            })
+            .unwrap_or_else(|| "unknown note".to_owned());
+
+        match post_type {
+            PostType::Reply(_) => format!("Reply to {author}"),
+            PostType::Quote(_) => format!("Quote of {author}"),
+            PostType::New => "New note".to_owned(),
+        }
+    }
+}
@@ -1,14 +1,17 @@
 pub mod account_login_view;
 pub mod accounts;
 pub mod add_column;
+pub mod bookmarks;
 pub mod column;
 pub mod configure_deck;
+pub mod drafts;
 pub mod edit_deck;
 pub mod images;
 pub mod note;
 pub mod preview;
 pub mod profile;
 pub mod relay;
+pub mod scheduled;
 pub mod search;
 pub mod search_results;
 pub mod side_panel;
@@ -19,6 +22,7 @@ pub mod wallet;
 pub mod widgets;
 
 pub use accounts::AccountsView;
+pub use bookmarks::BookmarksView;
 pub use note::{PostReplyView, PostView};
 pub use preview::{Preview, PreviewApp, PreviewConfig};
 pub use profile::ProfileView;
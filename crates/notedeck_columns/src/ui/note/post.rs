@@ -1,19 +1,22 @@
-use crate::draft::{Draft, Drafts, MentionHint};
-use crate::media_upload::{nostrbuild_nip96_upload, MediaPath};
-use crate::post::{downcast_post_buffer, MentionType, NewPost};
+use crate::draft::{Draft, Drafts, MentionHint, NoteHint, PendingUpload, PollDraft, ScheduleDraft};
+use crate::media_upload::{upload_backend, MediaPath};
+use crate::post::{
+    downcast_post_buffer, note_search_query, referenced_notes, MentionType, NewPost, RelayHints,
+};
+use crate::scheduled::{unix_now, Schedule, ScheduledPosts};
 use crate::ui::search_results::SearchResultsView;
 use crate::ui::{self, Preview, PreviewConfig};
 use crate::Result;
 
 use egui::{
-    text::{CCursorRange, LayoutJob},
+    text::{CCursor, CCursorRange, LayoutJob},
     text_edit::TextEditOutput,
     vec2,
     widgets::text_edit::TextEdit,
     Frame, Layout, Margin, Pos2, ScrollArea, Sense, TextBuffer,
 };
 use enostr::{FilledKeypair, FullKeypair, NoteId, Pubkey, RelayPool};
-use nostrdb::{Ndb, Transaction};
+use nostrdb::{Filter, Ndb, Transaction};
 use notedeck_ui::{
     gif::{handle_repaint, retrieve_latest_texture},
     images::render_images,
@@ -21,9 +24,16 @@ use notedeck_ui::{
     NoteOptions, ProfilePic,
 };
 
-use notedeck::{name::get_display_name, supported_mime_hosted_at_url, NoteAction, NoteContext};
+use notedeck::{
+    name::get_display_name, poll::PollType, supported_media_hosted_at_url, MediaKind, NoteAction,
+    NoteContext,
+};
+use std::path::PathBuf;
 use tracing::error;
 
+/// The largest we show attached media in the compose box
+const MEDIA_PREVIEW_SIZE: f32 = 300.0;
+
 pub struct PostView<'a, 'd> {
     note_context: &'a mut NoteContext<'d>,
     draft: &'a mut Draft,
@@ -32,15 +42,28 @@ pub struct PostView<'a, 'd> {
     id_source: Option<egui::Id>,
     inner_rect: egui::Rect,
     note_options: NoteOptions,
+
+    /// The poster's kind 10063 media servers
+    media_servers: &'a [String],
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum PostType {
     New,
     Quote(NoteId),
     Reply(NoteId),
 }
 
+impl PostType {
+    /// The note we're replying to or quoting
+    pub fn target(&self) -> Option<&NoteId> {
+        match self {
+            PostType::New => None,
+            PostType::Quote(note_id) | PostType::Reply(note_id) => Some(note_id),
+        }
+    }
+}
+
 pub enum PostAction {
     /// The NoteAction on a note you are replying to.
     QuotedNoteAction(NoteAction),
@@ -52,11 +75,26 @@ pub enum PostAction {
 pub struct NewPostAction {
     post_type: PostType,
     post: NewPost,
+    schedule: Option<Schedule>,
 }
 
 impl NewPostAction {
     pub fn new(post_type: PostType, post: NewPost) -> Self {
-        NewPostAction { post_type, post }
+        NewPostAction {
+            post_type,
+            post,
+            schedule: None,
+        }
+    }
+
+    /// Queue the post to be published later instead of now. It's dated
+    /// for when it goes out.
+    pub fn schedule(mut self, schedule: Option<Schedule>) -> Self {
+        if let Some(schedule) = schedule {
+            self.post.created_at = Some(schedule.publish_at);
+        }
+        self.schedule = schedule;
+        self
     }
 
     pub fn execute(
@@ -65,6 +103,7 @@ impl NewPostAction {
         txn: &Transaction,
         pool: &mut RelayPool,
         drafts: &mut Drafts,
+        scheduled: &mut ScheduledPosts,
     ) -> Result<()> {
         let seckey = self.post.account.secret_key.to_secret_bytes();
 
@@ -82,8 +121,13 @@ impl NewPostAction {
             }
         };
 
-        pool.send(&enostr::ClientMessage::event(&note)?);
-        drafts.get_from_post_type(&self.post_type).clear();
+        match self.schedule {
+            Some(schedule) => scheduled.add(self.post.account.pubkey, schedule, &note)?,
+            None => pool.send(&enostr::ClientMessage::event(&note)?),
+        }
+        drafts
+            .account_mut(&self.post.account.pubkey)
+            .remove(&self.post_type);
 
         Ok(())
     }
@@ -113,6 +157,7 @@ impl<'a, 'd> PostView<'a, 'd> {
             post_type,
             inner_rect,
             note_options,
+            media_servers: &[],
         }
     }
 
@@ -121,6 +166,11 @@ impl<'a, 'd> PostView<'a, 'd> {
         self
     }
 
+    pub fn media_servers(mut self, media_servers: &'a [String]) -> Self {
+        self.media_servers = media_servers;
+        self
+    }
+
     fn editbox(&mut self, txn: &nostrdb::Transaction, ui: &mut egui::Ui) -> egui::Response {
         ui.spacing_mut().item_spacing.x = 12.0;
 
@@ -186,6 +236,7 @@ impl<'a, 'd> PostView<'a, 'd> {
 
         if let Some(cursor_index) = get_cursor_index(&out.state.cursor.char_range()) {
             self.show_mention_hints(txn, ui, cursor_index, &out);
+            self.show_note_hints(txn, ui, cursor_index, &out);
         }
 
         let focused = out.response.has_focus();
@@ -280,6 +331,108 @@ impl<'a, 'd> PostView<'a, 'd> {
         }
     }
 
+    /// Notes matching the `~` search at the cursor, to reference one
+    fn show_note_hints(
+        &mut self,
+        txn: &Transaction,
+        ui: &mut egui::Ui,
+        cursor_index: usize,
+        textedit_output: &TextEditOutput,
+    ) {
+        let Some((range, query)) = note_search_query(&self.draft.buffer.text_buffer, cursor_index)
+        else {
+            self.draft.cur_note_hint = None;
+            return;
+        };
+
+        if self
+            .draft
+            .cur_note_hint
+            .as_ref()
+            .is_none_or(|hint| hint.query != query)
+        {
+            let filter = Filter::new().kinds([1]).search(query).limit(10).build();
+            let results = self
+                .note_context
+                .ndb
+                .query(txn, &[filter], 10)
+                .map(|results| results.into_iter().map(|qr| qr.note_key).collect())
+                .unwrap_or_default();
+
+            self.draft.cur_note_hint = Some(NoteHint {
+                query: query.to_owned(),
+                results,
+            });
+        }
+
+        let Some(hint) = &mut self.draft.cur_note_hint else {
+            return;
+        };
+
+        if hint.results.is_empty() {
+            return;
+        }
+
+        if ui.ctx().input(|i| i.key_pressed(egui::Key::Escape)) {
+            // stay closed until the search changes
+            hint.results.clear();
+            return;
+        }
+
+        let pos = calculate_mention_hints_pos(textedit_output, range.start);
+        let width = self.inner_rect.width();
+        let mut selected = None;
+        egui::Area::new(ui.id().with("note_hints"))
+            .order(egui::Order::Foreground)
+            .fixed_pos(Pos2::new(self.inner_rect.left(), pos.y))
+            .show(ui.ctx(), |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(width - 16.0);
+                    for key in &hint.results {
+                        let Ok(note) = self.note_context.ndb.get_note_by_key(txn, *key) else {
+                            continue;
+                        };
+
+                        let profile = self
+                            .note_context
+                            .ndb
+                            .get_profile_by_pubkey(txn, note.pubkey())
+                            .ok();
+                        let content: String = note.content().chars().take(80).collect();
+                        let text = format!(
+                            "{}: {}",
+                            get_display_name(profile.as_ref()).name(),
+                            content.replace('\n', " ")
+                        );
+
+                        if ui
+                            .add(egui::Button::new(text).frame(false).truncate())
+                            .clicked()
+                        {
+                            selected = Some(NoteId::new(*note.id()));
+                        }
+                    }
+                });
+            });
+
+        let Some(bech) = selected.and_then(|id| id.to_bech()) else {
+            return;
+        };
+
+        let reference = format!("nostr:{bech} ");
+        self.draft.buffer.delete_char_range(range.clone());
+        self.draft.buffer.insert_text(&reference, range.start);
+        self.draft.cur_note_hint = None;
+
+        // put the cursor after the reference
+        let cursor = CCursor::new(range.start + reference.chars().count());
+        let text_id = textedit_output.response.id;
+        if let Some(mut state) = TextEdit::load_state(ui.ctx(), text_id) {
+            state.cursor.set_char_range(Some(CCursorRange::one(cursor)));
+            state.store(ui.ctx(), text_id);
+        }
+    }
+
     fn focused(&self, ui: &egui::Ui) -> bool {
         ui.ctx()
             .data(|d| d.get_temp::<bool>(self.id()).unwrap_or(false))
@@ -299,7 +452,8 @@ impl<'a, 'd> PostView<'a, 'd> {
 
     pub fn ui(&mut self, txn: &Transaction, ui: &mut egui::Ui) -> PostResponse {
         let focused = self.focused(ui);
-        let stroke = if focused {
+        let file_hovering = ui.input(|i| !i.raw.hovered_files.is_empty());
+        let stroke = if focused || file_hovering {
             ui.visuals().selection.stroke
         } else {
             ui.visuals().noninteractive().bg_stroke
@@ -321,12 +475,16 @@ impl<'a, 'd> PostView<'a, 'd> {
             });
         }
 
-        frame
-            .show(ui, |ui| ui.vertical(|ui| self.input_ui(txn, ui)).inner)
-            .inner
+        let resp = frame.show(ui, |ui| ui.vertical(|ui| self.input_ui(txn, ui)).inner);
+        self.handle_dropped_files(ui, resp.response.rect);
+
+        resp.inner
     }
 
     fn input_ui(&mut self, txn: &Transaction, ui: &mut egui::Ui) -> PostResponse {
+        #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+        self.handle_pasted_image(ui);
+
         let edit_response = ui.horizontal(|ui| self.editbox(txn, ui)).inner;
 
         let note_response = if let PostType::Quote(id) = self.post_type {
@@ -357,6 +515,8 @@ impl<'a, 'd> PostView<'a, 'd> {
             None
         };
 
+        let reference_response = self.show_note_references(txn, ui);
+
         Frame::new()
             .inner_margin(Margin::symmetric(0, 8))
             .show(ui, |ui| {
@@ -368,13 +528,18 @@ impl<'a, 'd> PostView<'a, 'd> {
                 });
             });
 
-        self.transfer_uploads(ui);
+        self.transfer_uploads();
         self.show_upload_errors(ui);
+        self.show_content_warning_reason(ui);
+        self.show_poll(ui);
+        self.show_schedule(ui);
 
-        let post_action = ui.horizontal(|ui| self.input_buttons(ui)).inner;
+        let post_action = ui.horizontal(|ui| self.input_buttons(txn, ui)).inner;
 
         let action = note_response
-            .and_then(|nr| nr.action.map(PostAction::QuotedNoteAction))
+            .and_then(|nr| nr.action)
+            .or(reference_response)
+            .map(PostAction::QuotedNoteAction)
             .or(post_action.map(PostAction::NewPostAction));
 
         PostResponse {
@@ -383,31 +548,105 @@ impl<'a, 'd> PostView<'a, 'd> {
         }
     }
 
-    fn input_buttons(&mut self, ui: &mut egui::Ui) -> Option<NewPostAction> {
+    /// Previews of the notes referenced in the text
+    fn show_note_references(&mut self, txn: &Transaction, ui: &mut egui::Ui) -> Option<NoteAction> {
+        let quoting = match &self.post_type {
+            PostType::Quote(id) => Some(*id),
+            _ => None,
+        };
+
+        let mut action = None;
+        for id in referenced_notes(&self.draft.buffer.text_buffer) {
+            if Some(id) == quoting {
+                continue;
+            }
+
+            let avail_size = ui.available_size_before_wrap();
+            let resp = ui
+                .with_layout(Layout::left_to_right(egui::Align::TOP), |ui| {
+                    ui.vertical(|ui| {
+                        ui.set_max_width(avail_size.x * 0.8);
+                        render_note_preview(
+                            ui,
+                            self.note_context,
+                            &Some(self.poster.into()),
+                            txn,
+                            id.bytes(),
+                            nostrdb::NoteKey::new(0),
+                            self.note_options,
+                        )
+                    })
+                    .inner
+                })
+                .inner;
+
+            action = action.or(resp.action);
+        }
+
+        action
+    }
+
+    fn input_buttons(&mut self, txn: &Transaction, ui: &mut egui::Ui) -> Option<NewPostAction> {
         ui.with_layout(egui::Layout::left_to_right(egui::Align::BOTTOM), |ui| {
             self.show_upload_media_button(ui);
+            self.show_content_warning_toggle(ui);
+            if self.post_type == PostType::New {
+                self.show_poll_toggle(ui);
+            }
+            self.show_schedule_toggle(ui);
         });
 
+        // a scheduled post needs a time we can use
+        let schedule = self
+            .draft
+            .schedule
+            .as_ref()
+            .map(|draft| draft.schedule(unix_now()));
+        let now = unix_now();
+        let poll = self.draft.poll.as_ref().map(|draft| draft.to_poll(now));
+        let can_post = !self.draft.buffer.is_empty()
+            && schedule.is_none_or(|s| s.is_some())
+            && poll.as_ref().is_none_or(|p| p.is_some());
+        let schedule = schedule.flatten();
+        let mut poll = poll.flatten();
+
         ui.with_layout(egui::Layout::right_to_left(egui::Align::BOTTOM), |ui| {
+            let label = if schedule.is_some() {
+                "Schedule"
+            } else {
+                "Post now"
+            };
             let post_button_clicked = ui
-                .add_sized([91.0, 32.0], post_button(!self.draft.buffer.is_empty()))
+                .add_sized([91.0, 32.0], post_button(label, can_post))
                 .clicked();
 
             let shortcut_pressed = ui.input(|i| {
                 (i.modifiers.ctrl || i.modifiers.command) && i.key_pressed(egui::Key::Enter)
             });
 
-            if post_button_clicked
-                || (!self.draft.buffer.is_empty() && shortcut_pressed && self.focused(ui))
-            {
-                let output = self.draft.buffer.output();
+            if post_button_clicked || (can_post && shortcut_pressed && self.focused(ui)) {
+                let mut hints = RelayHints::for_post(
+                    self.note_context.ndb,
+                    txn,
+                    &self.post_type,
+                    &self.draft.buffer.output().mentions,
+                    &referenced_notes(&self.draft.buffer.text_buffer),
+                );
+                if poll.is_some() {
+                    // responses go to our relays
+                    hints.add_pubkey(self.note_context.ndb, txn, self.poster.pubkey.bytes());
+                }
+                let output = self.draft.buffer.output_with_hints(&hints);
                 let new_post = NewPost::new(
                     output.text,
                     self.poster.to_full(),
                     self.draft.uploaded_media.clone(),
                     output.mentions,
-                );
-                Some(NewPostAction::new(self.post_type.clone(), new_post))
+                )
+                .content_warning(self.draft.content_warning.clone())
+                .poll(poll.take())
+                .relay_hints(hints);
+                Some(NewPostAction::new(self.post_type.clone(), new_post).schedule(schedule))
             } else {
                 None
             }
@@ -417,16 +656,59 @@ impl<'a, 'd> PostView<'a, 'd> {
 
     fn show_media(&mut self, ui: &mut egui::Ui) {
         let mut to_remove = Vec::new();
-        for (i, media) in self.draft.uploaded_media.iter().enumerate() {
+        for (i, media) in self.draft.uploaded_media.iter_mut().enumerate() {
             let (width, height) = if let Some(dims) = media.dimensions {
                 (dims.0, dims.1)
             } else {
                 (300, 300)
             };
 
-            if let Some(cache_type) =
-                supported_mime_hosted_at_url(&mut self.note_context.img_cache.urls, &media.url)
-            {
+            let Some(kind) =
+                supported_media_hosted_at_url(&mut self.note_context.img_cache.urls, &media.url)
+            else {
+                self.draft
+                    .upload_errors
+                    .push("Uploaded media is not supported.".to_owned());
+                error!("Unsupported mime type at url: {}", &media.url);
+                continue;
+            };
+
+            ui.vertical(|ui| {
+                ui.set_max_width(MEDIA_PREVIEW_SIZE);
+
+                let cache_type = match kind {
+                    MediaKind::Image(cache_type) => cache_type,
+                    MediaKind::Video | MediaKind::Audio => {
+                        let id = ui.id().with(("upload_preview", &media.url));
+                        let ctx = ui.ctx().clone();
+                        let removed = if kind == MediaKind::Video
+                            && notedeck_ui::video::can_play_video(&ctx, &media.url)
+                        {
+                            let resp = notedeck_ui::video::video_player(
+                                ui,
+                                id,
+                                &media.url,
+                                MEDIA_PREVIEW_SIZE,
+                                false,
+                            );
+                            show_remove_upload_button(ui, remove_button_rect(resp.rect)).clicked()
+                        } else if kind == MediaKind::Audio
+                            && notedeck_ui::video::can_play_audio(&ctx, &media.url)
+                        {
+                            notedeck_ui::video::audio_player(ui, id, &media.url);
+                            ui.button("Remove").clicked()
+                        } else {
+                            ui.hyperlink(&media.url);
+                            ui.button("Remove").clicked()
+                        };
+
+                        if removed {
+                            to_remove.push(i);
+                        }
+                        return;
+                    }
+                };
+
                 render_images(
                     ui,
                     self.note_context.img_cache,
@@ -442,7 +724,7 @@ impl<'a, 'd> PostView<'a, 'd> {
                     },
                     |ui, url, renderable_media, gifs| {
                         let media_size = vec2(width as f32, height as f32);
-                        let max_size = vec2(300.0, 300.0);
+                        let max_size = vec2(MEDIA_PREVIEW_SIZE, MEDIA_PREVIEW_SIZE);
                         let size = if media_size.x > max_size.x || media_size.y > max_size.y {
                             max_size
                         } else {
@@ -459,29 +741,62 @@ impl<'a, 'd> PostView<'a, 'd> {
                                 .corner_radius(12.0),
                         );
 
-                        let remove_button_rect = {
-                            let top_left = img_resp.rect.left_top();
-                            let spacing = 13.0;
-                            let center = Pos2::new(top_left.x + spacing, top_left.y + spacing);
-                            egui::Rect::from_center_size(center, egui::vec2(26.0, 26.0))
-                        };
-                        if show_remove_upload_button(ui, remove_button_rect).clicked() {
+                        if show_remove_upload_button(ui, remove_button_rect(img_resp.rect))
+                            .clicked()
+                        {
                             to_remove.push(i);
                         }
                         ui.advance_cursor_after_rect(img_resp.rect);
                     },
                 );
-            } else {
-                self.draft
-                    .upload_errors
-                    .push("Uploaded media is not supported.".to_owned());
-                error!("Unsupported mime type at url: {}", &media.url);
-            }
+
+                let alt = media.alt.get_or_insert_with(String::new);
+                ui.add(
+                    TextEdit::singleline(alt)
+                        .hint_text("Alt text")
+                        .desired_width(MEDIA_PREVIEW_SIZE),
+                );
+            });
         }
         to_remove.reverse();
         for i in to_remove {
             self.draft.uploaded_media.remove(i);
         }
+
+        let mut cancelled = Vec::new();
+        for (i, upload) in self.draft.uploading_media.iter().enumerate() {
+            if show_pending_upload(ui, upload) {
+                cancelled.push(i);
+            }
+        }
+        cancelled.reverse();
+        for i in cancelled {
+            // the request keeps going, we just won't use what it returns
+            self.draft.uploading_media.remove(i);
+        }
+    }
+
+    fn upload(&mut self, media_path: MediaPath) {
+        let local_path = media_path.full_path().to_path_buf();
+        let promise = upload_backend(self.media_servers)
+            .upload(self.poster.secret_key.secret_bytes(), media_path);
+
+        self.draft.uploading_media.push(PendingUpload {
+            local_path: Some(local_path),
+            promise,
+        });
+    }
+
+    fn upload_files(&mut self, files: impl IntoIterator<Item = PathBuf>) {
+        for file in files {
+            match MediaPath::new(file) {
+                Ok(media_path) => self.upload(media_path),
+                Err(e) => {
+                    error!("{e}");
+                    self.draft.upload_errors.push(e.to_string());
+                }
+            }
+        }
     }
 
     fn show_upload_media_button(&mut self, ui: &mut egui::Ui) {
@@ -489,30 +804,231 @@ impl<'a, 'd> PostView<'a, 'd> {
             #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
             {
                 if let Some(files) = rfd::FileDialog::new().pick_files() {
-                    for file in files {
-                        match MediaPath::new(file) {
-                            Ok(media_path) => {
-                                let promise = nostrbuild_nip96_upload(
-                                    self.poster.secret_key.secret_bytes(),
-                                    media_path,
-                                );
-                                self.draft.uploading_media.push(promise);
-                            }
-                            Err(e) => {
-                                error!("{e}");
-                                self.draft.upload_errors.push(e.to_string());
-                            }
-                        }
-                    }
+                    self.upload_files(files);
                 }
             }
         }
     }
 
-    fn transfer_uploads(&mut self, ui: &mut egui::Ui) {
+    fn show_content_warning_toggle(&mut self, ui: &mut egui::Ui) {
+        let enabled = self.draft.content_warning.is_some();
+        let resp = ui
+            .add_sized([32.0, 32.0], egui::Button::new("CW").selected(enabled))
+            .on_hover_text("Content warning");
+
+        if resp.clicked() {
+            self.draft.content_warning = if enabled { None } else { Some(String::new()) };
+        }
+    }
+
+    fn show_content_warning_reason(&mut self, ui: &mut egui::Ui) {
+        let Some(reason) = &mut self.draft.content_warning else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("⚠ Content warning");
+            ui.add(
+                egui::TextEdit::singleline(reason)
+                    .hint_text("Reason (optional)")
+                    .desired_width(f32::INFINITY),
+            );
+        });
+    }
+
+    fn show_poll_toggle(&mut self, ui: &mut egui::Ui) {
+        let enabled = self.draft.poll.is_some();
+        let resp = ui
+            .add_sized([32.0, 32.0], egui::Button::new("☰").selected(enabled))
+            .on_hover_text("Poll");
+
+        if resp.clicked() {
+            self.draft.poll = if enabled {
+                None
+            } else {
+                Some(PollDraft::new(unix_now() + 86_400))
+            };
+        }
+    }
+
+    fn show_poll(&mut self, ui: &mut egui::Ui) {
+        let Some(poll) = &mut self.draft.poll else {
+            return;
+        };
+
+        let mut to_remove = None;
+        let can_remove = poll.options.len() > 2;
+        for (i, option) in poll.options.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(option)
+                        .hint_text(format!("Option {}", i + 1))
+                        .desired_width(ui.available_width() - 32.0),
+                );
+
+                if can_remove && ui.small_button("✕").clicked() {
+                    to_remove = Some(i);
+                }
+            });
+        }
+
+        if let Some(i) = to_remove {
+            poll.options.remove(i);
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            if ui.button("Add option").clicked() {
+                poll.options.push(String::new());
+            }
+
+            ui.radio_value(&mut poll.poll_type, PollType::SingleChoice, "Single choice");
+            ui.radio_value(
+                &mut poll.poll_type,
+                PollType::MultipleChoice,
+                "Multiple choice",
+            );
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Ends");
+            ui.add(
+                egui::TextEdit::singleline(&mut poll.ends_at)
+                    .hint_text("never")
+                    .desired_width(130.0),
+            );
+            ui.label("UTC");
+
+            if poll.ends_at(unix_now()).is_none() {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    "Pick a time in the future, or leave it empty",
+                );
+            }
+        });
+    }
+
+    fn show_schedule_toggle(&mut self, ui: &mut egui::Ui) {
+        let enabled = self.draft.schedule.is_some();
+        let resp = ui
+            .add_sized([32.0, 32.0], egui::Button::new("⏰").selected(enabled))
+            .on_hover_text("Schedule for…");
+
+        if resp.clicked() {
+            self.draft.schedule = if enabled {
+                None
+            } else {
+                Some(ScheduleDraft::new(unix_now() + 3600))
+            };
+        }
+    }
+
+    fn show_schedule(&mut self, ui: &mut egui::Ui) {
+        let Some(schedule) = &mut self.draft.schedule else {
+            return;
+        };
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("⏰ Schedule for");
+            ui.add(
+                egui::TextEdit::singleline(&mut schedule.time)
+                    .hint_text("YYYY-MM-DD HH:MM")
+                    .desired_width(130.0),
+            );
+            ui.label("UTC");
+
+            match schedule.schedule(unix_now()) {
+                Some(s) => {
+                    ui.weak(notedeck::time_ago_since(s.publish_at));
+                }
+                None => {
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        "Pick a time in the future, like 2025-01-31 18:30",
+                    );
+                }
+            }
+
+            ui.checkbox(&mut schedule.sign_at_send, "Sign when it's sent")
+                .on_hover_text(
+                    "Otherwise the note is signed now, with the time it's scheduled for",
+                );
+        });
+    }
+
+    /// Files dropped onto the compose box get uploaded
+    fn handle_dropped_files(&mut self, ui: &mut egui::Ui, rect: egui::Rect) {
+        let dropped = ui.input(|i| i.raw.dropped_files.clone());
+        if dropped.is_empty() {
+            return;
+        }
+
+        // there might be more than one compose box open
+        if !ui.rect_contains_pointer(rect) && !self.focused(ui) {
+            return;
+        }
+
+        let mut files = Vec::new();
+        for file in dropped {
+            match file.path {
+                Some(path) => files.push(path),
+                None => self
+                    .draft
+                    .upload_errors
+                    .push(format!("Could not read dropped file {}", file.name)),
+            }
+        }
+        self.upload_files(files);
+    }
+
+    /// Images pasted from the clipboard get uploaded. Text pastes are
+    /// handled by the text box.
+    #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+    fn handle_pasted_image(&mut self, ui: &mut egui::Ui) {
+        let pasted = ui.input(|i| {
+            i.events.iter().any(|e| {
+                matches!(
+                    e,
+                    egui::Event::Key {
+                        key: egui::Key::V,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } if modifiers.command
+                )
+            }) && !i.events.iter().any(|e| matches!(e, egui::Event::Paste(_)))
+        });
+
+        if !pasted || !self.focused(ui) {
+            return;
+        }
+
+        let image = match arboard::Clipboard::new().and_then(|mut c| c.get_image()) {
+            Ok(image) => image,
+            // nothing we can upload in there
+            Err(_) => return,
+        };
+
+        let image = crate::media_upload::PastedImage {
+            width: image.width,
+            height: image.height,
+            rgba: image.bytes.into_owned(),
+        };
+        let promise = crate::media_upload::upload_pasted_image(
+            self.media_servers.to_vec(),
+            self.poster.secret_key.secret_bytes(),
+            image,
+        );
+
+        self.draft.uploading_media.push(PendingUpload {
+            local_path: None,
+            promise,
+        });
+    }
+
+    fn transfer_uploads(&mut self) {
         let mut indexes_to_remove = Vec::new();
-        for (i, promise) in self.draft.uploading_media.iter().enumerate() {
-            match promise.ready() {
+        for (i, upload) in self.draft.uploading_media.iter().enumerate() {
+            match upload.promise.ready() {
                 Some(Ok(media)) => {
                     self.draft.uploaded_media.push(media.clone());
                     indexes_to_remove.push(i);
@@ -520,10 +1036,9 @@ impl<'a, 'd> PostView<'a, 'd> {
                 Some(Err(e)) => {
                     self.draft.upload_errors.push(e.to_string());
                     error!("{e}");
+                    indexes_to_remove.push(i);
                 }
-                None => {
-                    ui.spinner();
-                }
+                None => {}
             }
         }
 
@@ -556,9 +1071,9 @@ impl<'a, 'd> PostView<'a, 'd> {
     }
 }
 
-fn post_button(interactive: bool) -> impl egui::Widget {
+fn post_button(label: &str, interactive: bool) -> impl egui::Widget + '_ {
     move |ui: &mut egui::Ui| {
-        let button = egui::Button::new("Post now");
+        let button = egui::Button::new(label);
         if interactive {
             ui.add(button)
         } else {
@@ -605,6 +1120,39 @@ fn media_upload_button() -> impl egui::Widget {
     }
 }
 
+/// Where the remove button goes on a media preview
+fn remove_button_rect(img_rect: egui::Rect) -> egui::Rect {
+    let top_left = img_rect.left_top();
+    let spacing = 13.0;
+    let center = Pos2::new(top_left.x + spacing, top_left.y + spacing);
+    egui::Rect::from_center_size(center, egui::vec2(26.0, 26.0))
+}
+
+/// The local file dimmed behind a spinner. Returns true if the upload
+/// should be cancelled.
+fn show_pending_upload(ui: &mut egui::Ui, upload: &PendingUpload) -> bool {
+    let size = vec2(MEDIA_PREVIEW_SIZE, MEDIA_PREVIEW_SIZE);
+    let img_resp = match &upload.local_path {
+        Some(path) => {
+            let uri = format!("file://{}", path.display());
+            ui.add(egui::Image::new(uri).max_size(size).corner_radius(12.0))
+        }
+        None => ui.allocate_response(size, egui::Sense::hover()),
+    };
+
+    ui.painter()
+        .rect_filled(img_resp.rect, 12.0, egui::Color32::from_black_alpha(140));
+    ui.put(
+        egui::Rect::from_center_size(img_resp.rect.center(), vec2(24.0, 24.0)),
+        egui::Spinner::new(),
+    )
+    .on_hover_text("Uploading…");
+
+    let cancel = show_remove_upload_button(ui, remove_button_rect(img_resp.rect)).clicked();
+    ui.advance_cursor_after_rect(img_resp.rect);
+    cancel
+}
+
 fn show_remove_upload_button(ui: &mut egui::Ui, desired_rect: egui::Rect) -> egui::Response {
     let resp = ui.allocate_rect(desired_rect, egui::Sense::click());
     let size = 24.0;
@@ -732,12 +1280,16 @@ mod preview {
     impl App for PostPreview {
         fn update(&mut self, app: &mut AppContext<'_>, ui: &mut egui::Ui) {
             let txn = Transaction::new(app.ndb).expect("txn");
+            let bookmarks = app.accounts.bookmarks();
             let mut note_context = NoteContext {
                 ndb: app.ndb,
                 img_cache: app.img_cache,
                 note_cache: app.note_cache,
                 zaps: app.zaps,
+                nip05: app.nip05,
+                polls: app.polls,
                 pool: app.pool,
+                bookmarks: &bookmarks,
             };
 
             PostView::new(
@@ -16,6 +16,7 @@ pub struct QuoteRepostView<'a, 'd> {
     id_source: Option<egui::Id>,
     inner_rect: egui::Rect,
     note_options: NoteOptions,
+    media_servers: &'a [String],
 }
 
 impl<'a, 'd> QuoteRepostView<'a, 'd> {
@@ -37,9 +38,15 @@ impl<'a, 'd> QuoteRepostView<'a, 'd> {
             id_source,
             inner_rect,
             note_options,
+            media_servers: &[],
         }
     }
 
+    pub fn media_servers(mut self, media_servers: &'a [String]) -> Self {
+        self.media_servers = media_servers;
+        self
+    }
+
     pub fn show(&mut self, ui: &mut egui::Ui) -> PostResponse {
         let id = self.id();
         let quoting_note_id = self.quoting_note.id();
@@ -53,6 +60,7 @@ impl<'a, 'd> QuoteRepostView<'a, 'd> {
             self.note_options,
         )
         .id_source(id)
+        .media_servers(self.media_servers)
         .ui(self.quoting_note.txn().unwrap(), ui);
         post_resp
     }
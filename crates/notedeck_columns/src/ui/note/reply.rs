@@ -16,6 +16,7 @@ pub struct PostReplyView<'a, 'd> {
     id_source: Option<egui::Id>,
     inner_rect: egui::Rect,
     note_options: NoteOptions,
+    media_servers: &'a [String],
 }
 
 impl<'a, 'd> PostReplyView<'a, 'd> {
@@ -37,9 +38,15 @@ impl<'a, 'd> PostReplyView<'a, 'd> {
             id_source,
             inner_rect,
             note_options,
+            media_servers: &[],
         }
     }
 
+    pub fn media_servers(mut self, media_servers: &'a [String]) -> Self {
+        self.media_servers = media_servers;
+        self
+    }
+
     pub fn id_source(mut self, id: egui::Id) -> Self {
         self.id_source = Some(id);
         self
@@ -93,6 +100,7 @@ impl<'a, 'd> PostReplyView<'a, 'd> {
                     self.note_options,
                 )
                 .id_source(id)
+                .media_servers(self.media_servers)
                 .ui(self.note.txn().unwrap(), ui)
             };
 
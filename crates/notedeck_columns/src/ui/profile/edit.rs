@@ -1,24 +1,51 @@
 use core::f32;
+use std::path::Path;
 
-use crate::profile_state::ProfileState;
-use egui::{vec2, Button, CornerRadius, Layout, Margin, RichText, ScrollArea, TextEdit};
+use crate::{
+    media_upload::{crop_and_save, upload_backend, Crop},
+    profile_state::{ProfileImage, ProfileImageEdit, ProfileState, ProfileUpload},
+};
+use egui::{vec2, Button, CornerRadius, Layout, Margin, RichText, ScrollArea, Sense, TextEdit};
 use notedeck::{profile::unwrap_profile_url, Images, NotedeckTextStyle};
 use notedeck_ui::{profile::banner, ProfilePic};
+use tracing::error;
 
 pub struct EditProfileView<'a> {
     state: &'a mut ProfileState,
     img_cache: &'a mut Images,
+    /// Signs uploads, we can't upload without it
+    upload_key: Option<[u8; 32]>,
+    media_servers: &'a [String],
 }
 
 impl<'a> EditProfileView<'a> {
     pub fn new(state: &'a mut ProfileState, img_cache: &'a mut Images) -> Self {
-        Self { state, img_cache }
+        Self {
+            state,
+            img_cache,
+            upload_key: None,
+            media_servers: &[],
+        }
+    }
+
+    /// Lets the picture and banner be uploaded
+    pub fn uploader(mut self, seckey: [u8; 32], media_servers: &'a [String]) -> Self {
+        self.upload_key = Some(seckey);
+        self.media_servers = media_servers;
+        self
     }
 
     // return true to save
     pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        self.transfer_uploads();
+
         ScrollArea::vertical()
             .show(ui, |ui| {
+                if self.state.image_edit.is_some() {
+                    notedeck_ui::padding(24.0, ui, |ui| self.crop_ui(ui));
+                    return false;
+                }
+
                 banner(ui, Some(&self.state.banner), 188.0);
 
                 let padding = 24.0;
@@ -31,8 +58,13 @@ impl<'a> EditProfileView<'a> {
                 let mut save = false;
                 notedeck_ui::padding(padding, ui, |ui| {
                     ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                        // saving now would drop what's being uploaded
+                        let uploading = !self.state.uploads.is_empty();
                         if ui
-                            .add(button("Save changes", 119.0).fill(notedeck_ui::colors::PINK))
+                            .add_enabled(
+                                !uploading,
+                                button("Save changes", 119.0).fill(notedeck_ui::colors::PINK),
+                            )
                             .clicked()
                         {
                             save = true;
@@ -65,6 +97,10 @@ impl<'a> EditProfileView<'a> {
                 .border(ProfilePic::border_stroke(ui)),
         );
 
+        if let Some(err) = &self.state.upload_error {
+            ui.colored_label(ui.visuals().error_fg_color, err);
+        }
+
         in_frame(ui, |ui| {
             ui.add(label("Display name"));
             ui.add(singleline_textedit(&mut self.state.display_name));
@@ -76,18 +112,19 @@ impl<'a> EditProfileView<'a> {
         });
 
         in_frame(ui, |ui| {
-            ui.add(label("Profile picture"));
-            ui.add(multiline_textedit(&mut self.state.picture));
+            ui.add(label("Pronouns"));
+            ui.add(singleline_textedit(&mut self.state.pronouns));
         });
 
-        in_frame(ui, |ui| {
-            ui.add(label("Banner"));
-            ui.add(multiline_textedit(&mut self.state.banner));
-        });
+        self.image_field(ui, ProfileImage::Picture);
+        self.image_field(ui, ProfileImage::Banner);
 
         in_frame(ui, |ui| {
             ui.add(label("About"));
             ui.add(multiline_textedit(&mut self.state.about));
+            if !self.state.about.trim().is_empty() {
+                self.about_preview(ui);
+            }
         });
 
         in_frame(ui, |ui| {
@@ -100,6 +137,11 @@ impl<'a> EditProfileView<'a> {
             ui.add(multiline_textedit(&mut self.state.lud16));
         });
 
+        in_frame(ui, |ui| {
+            ui.add(label("LNURL (lud06)"));
+            ui.add(multiline_textedit(&mut self.state.lud06));
+        });
+
         in_frame(ui, |ui| {
             ui.add(label("Nostr address (NIP-05 identity)"));
             ui.add(singleline_textedit(&mut self.state.nip05));
@@ -127,7 +169,233 @@ impl<'a> EditProfileView<'a> {
                 }
             }
         });
+
+        in_frame(ui, |ui| {
+            ui.checkbox(&mut self.state.bot, "This account is a bot")
+                .on_hover_text("It posts automatically, without a person behind it");
+        });
+    }
+
+    /// How the profile will look with the new about
+    fn about_preview(&self, ui: &mut egui::Ui) {
+        egui::Frame::new()
+            .fill(ui.visuals().faint_bg_color)
+            .corner_radius(8.0)
+            .inner_margin(Margin::same(12))
+            .show(ui, |ui| {
+                ui.spacing_mut().item_spacing = egui::vec2(0.0, 4.0);
+                ui.set_width(ui.available_width());
+
+                let name = if self.state.display_name.is_empty() {
+                    &self.state.name
+                } else {
+                    &self.state.display_name
+                };
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(RichText::new(name).strong());
+                    if !self.state.pronouns.is_empty() {
+                        ui.label(RichText::new(format!(" ({})", self.state.pronouns)).weak());
+                    }
+                });
+                ui.label(&self.state.about);
+                for extra in [&self.state.website, &self.state.lud16] {
+                    if !extra.trim().is_empty() {
+                        ui.label(RichText::new(extra.trim()).weak());
+                    }
+                }
+            });
+    }
+
+    fn image_field(&mut self, ui: &mut egui::Ui, image: ProfileImage) {
+        let uploading = self
+            .state
+            .uploads
+            .iter()
+            .any(|upload| upload.image == image);
+        let can_upload = self.upload_key.is_some();
+
+        let mut pick = false;
+        in_frame(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(label(match image {
+                    ProfileImage::Picture => "Profile picture",
+                    ProfileImage::Banner => "Banner",
+                }));
+
+                ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                    if uploading {
+                        ui.spinner();
+                        ui.label(RichText::new("Uploading…").weak());
+                    } else if can_upload && ui.button("Upload").clicked() {
+                        pick = true;
+                    }
+                });
+            });
+
+            let url = match image {
+                ProfileImage::Picture => &mut self.state.picture,
+                ProfileImage::Banner => &mut self.state.banner,
+            };
+            ui.add(multiline_textedit(url));
+        });
+
+        if pick {
+            self.pick_image(ui.ctx(), image);
+        }
+    }
+
+    fn pick_image(&mut self, ctx: &egui::Context, image: ProfileImage) {
+        #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+        {
+            let Some(path) = rfd::FileDialog::new()
+                .add_filter("Images", &["png", "jpg", "jpeg", "webp", "gif"])
+                .pick_file()
+            else {
+                return;
+            };
+
+            match load_crop_texture(ctx, &path) {
+                Ok(texture) => {
+                    self.state.upload_error = None;
+                    self.state.image_edit = Some(ProfileImageEdit {
+                        image,
+                        path,
+                        texture,
+                        crop: Crop::new(image.aspect()),
+                    });
+                }
+                Err(err) => {
+                    error!("{err}");
+                    self.state.upload_error = Some(err);
+                }
+            }
+        }
+
+        #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+        {
+            let _ = (ctx, image);
+        }
+    }
+
+    fn crop_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(edit) = &mut self.state.image_edit else {
+            return;
+        };
+
+        ui.spacing_mut().item_spacing = egui::vec2(0.0, 12.0);
+        ui.add(label(match edit.image {
+            ProfileImage::Picture => "Crop your profile picture",
+            ProfileImage::Banner => "Crop your banner",
+        }));
+
+        let tex_size = edit.texture.size_vec2();
+        let crop = edit.crop.rect(tex_size.x, tex_size.y);
+        let uv = egui::Rect::from_min_max(
+            (crop.min.to_vec2() / tex_size).to_pos2(),
+            (crop.max.to_vec2() / tex_size).to_pos2(),
+        );
+
+        let width = ui.available_width().min(400.0);
+        let size = vec2(width, width / edit.crop.aspect);
+        let resp = ui.add(
+            egui::Image::new(&edit.texture)
+                .uv(uv)
+                .fit_to_exact_size(size)
+                .sense(Sense::drag()),
+        );
+
+        if edit.image == ProfileImage::Picture {
+            // avatars are shown round
+            ui.painter().circle_stroke(
+                resp.rect.center(),
+                resp.rect.width() / 2.0,
+                egui::Stroke::new(2.0, ui.visuals().selection.stroke.color),
+            );
+        }
+
+        if resp.dragged() {
+            // dragging the image moves it under the crop
+            let image_px_per_point = crop.width() / size.x;
+            let delta = resp.drag_delta() * image_px_per_point / tex_size;
+            edit.crop.center = (edit.crop.center - delta).clamp(vec2(0.0, 0.0), vec2(1.0, 1.0));
+        }
+        resp.on_hover_cursor(egui::CursorIcon::Grab);
+
+        ui.add(egui::Slider::new(&mut edit.crop.zoom, 1.0..=4.0).text("Zoom"));
+
+        let mut done = false;
+        let mut upload = false;
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 8.0;
+            if ui.add(button("Use image", 100.0)).clicked() {
+                upload = true;
+            }
+            if ui.add(button("Cancel", 80.0)).clicked() {
+                done = true;
+            }
+        });
+
+        if upload {
+            self.upload_cropped();
+        } else if done {
+            self.state.image_edit = None;
+        }
+    }
+
+    fn upload_cropped(&mut self) {
+        let Some(edit) = self.state.image_edit.take() else {
+            return;
+        };
+        let Some(seckey) = self.upload_key else {
+            return;
+        };
+
+        match crop_and_save(&edit.path, &edit.crop, edit.image.max_width()) {
+            Ok(media_path) => {
+                let promise = upload_backend(self.media_servers).upload(seckey, media_path);
+                self.state.uploads.push(ProfileUpload {
+                    image: edit.image,
+                    promise,
+                });
+            }
+            Err(err) => {
+                error!("{err}");
+                self.state.upload_error = Some(err.to_string());
+            }
+        }
     }
+
+    /// Finished uploads fill in their url
+    fn transfer_uploads(&mut self) {
+        let state = &mut *self.state;
+        state.uploads.retain(|upload| match upload.promise.ready() {
+            None => true,
+            Some(Ok(media)) => {
+                let url = media.url.clone();
+                match upload.image {
+                    ProfileImage::Picture => state.picture = url,
+                    ProfileImage::Banner => state.banner = url,
+                }
+                false
+            }
+            Some(Err(err)) => {
+                error!("{err}");
+                state.upload_error = Some(err.to_string());
+                false
+            }
+        });
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+fn load_crop_texture(ctx: &egui::Context, path: &Path) -> Result<egui::TextureHandle, String> {
+    let img = image::open(path)
+        .map_err(|e| format!("could not open {}: {e}", path.display()))?
+        .to_rgba8();
+    let size = [img.width() as usize, img.height() as usize];
+    let color = egui::ColorImage::from_rgba_unmultiplied(size, img.as_raw());
+
+    Ok(ctx.load_texture("profile-image-crop", color, Default::default()))
 }
 
 fn label(text: &str) -> impl egui::Widget + '_ {
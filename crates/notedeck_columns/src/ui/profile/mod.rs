@@ -1,12 +1,16 @@
 pub mod edit;
 
 pub use edit::EditProfileView;
+use std::collections::HashSet;
+
 use egui::{vec2, Color32, CornerRadius, Layout, Rect, RichText, ScrollArea, Sense, Stroke};
 use enostr::Pubkey;
 use nostrdb::{ProfileRecord, Transaction};
 use tracing::error;
 
 use crate::{
+    followers::Followers,
+    profile::{contact_pubkeys, get_contact_list},
     timeline::{TimelineCache, TimelineKind},
     ui::timeline::{tabs_ui, TimelineTabView},
 };
@@ -16,14 +20,19 @@ use notedeck::{
 };
 use notedeck_ui::{
     profile::{about_section_widget, banner, display_name_widget},
-    NoteOptions, ProfilePic,
+    NoteOptions, ProfilePic, ProfilePreview,
 };
 
+/// How many people we show in the following and followers lists before
+/// asking for more
+const FOLLOWS_PAGE: usize = 50;
+
 pub struct ProfileView<'a, 'd> {
     pubkey: &'a Pubkey,
     accounts: &'a Accounts,
     col_id: usize,
     timeline_cache: &'a mut TimelineCache,
+    followers: &'a mut Followers,
     note_options: NoteOptions,
     unknown_ids: &'a mut UnknownIds,
     is_muted: &'a MuteFun,
@@ -33,6 +42,23 @@ pub struct ProfileView<'a, 'd> {
 pub enum ProfileViewAction {
     EditProfile,
     Note(NoteAction),
+    Follow(Pubkey),
+    Unfollow(Pubkey),
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+enum ProfileTab {
+    #[default]
+    Notes,
+    Following,
+    Followers,
+}
+
+/// Who the profile follows and who follows them
+struct SocialGraph {
+    following: Vec<Pubkey>,
+    followers: Vec<Pubkey>,
+    follows_you: bool,
 }
 
 impl<'a, 'd> ProfileView<'a, 'd> {
@@ -42,6 +68,7 @@ impl<'a, 'd> ProfileView<'a, 'd> {
         accounts: &'a Accounts,
         col_id: usize,
         timeline_cache: &'a mut TimelineCache,
+        followers: &'a mut Followers,
         note_options: NoteOptions,
         unknown_ids: &'a mut UnknownIds,
         is_muted: &'a MuteFun,
@@ -52,6 +79,7 @@ impl<'a, 'd> ProfileView<'a, 'd> {
             accounts,
             col_id,
             timeline_cache,
+            followers,
             note_options,
             unknown_ids,
             is_muted,
@@ -61,70 +89,228 @@ impl<'a, 'd> ProfileView<'a, 'd> {
 
     pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<ProfileViewAction> {
         let scroll_id = egui::Id::new(("profile_scroll", self.col_id, self.pubkey));
+        let tab_id = egui::Id::new(("profile_tab", self.col_id, self.pubkey));
+        let mut tab: ProfileTab = ui.ctx().data(|d| d.get_temp(tab_id)).unwrap_or_default();
 
-        ScrollArea::vertical()
+        let action = ScrollArea::vertical()
             .id_salt(scroll_id)
             .show(ui, |ui| {
                 let mut action = None;
                 let txn = Transaction::new(self.note_context.ndb).expect("txn");
+                let graph = self.social_graph(ui, &txn);
+
                 if let Ok(profile) = self
                     .note_context
                     .ndb
                     .get_profile_by_pubkey(&txn, self.pubkey.bytes())
                 {
-                    if self.profile_body(ui, profile) {
+                    if self.profile_body(ui, profile, &graph, &mut tab) {
                         action = Some(ProfileViewAction::EditProfile);
                     }
                 }
 
-                let kind = TimelineKind::Profile(*self.pubkey);
-                let profile_timeline_opt = self
-                    .timeline_cache
-                    .timelines
-                    .get_mut(&kind);
-
-                if let Some(profile_timeline) = profile_timeline_opt {
-                    // poll timeline to add notes *before* getting the immutable reference for the view
-                    if let Err(e) = profile_timeline.poll_notes_into_pending(
-                        self.note_context.ndb,
-                        &txn,
-                        self.unknown_ids,
-                        self.note_context.note_cache,
-                    ) {
-                        error!("Profile::poll_notes_into_pending: {e}");
+                ui.horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 16.0;
+                    ui.add_space(12.0);
+                    for (label, this) in [
+                        ("Notes", ProfileTab::Notes),
+                        ("Following", ProfileTab::Following),
+                        ("Followers", ProfileTab::Followers),
+                    ] {
+                        if ui.selectable_label(tab == this, label).clicked() {
+                            tab = this;
+                        }
                     }
+                });
 
-                    // Now we can use the (implicitly reborrowed) timeline for the view
-                    profile_timeline.selected_view =
-                        tabs_ui(ui, profile_timeline.selected_view, &profile_timeline.views);
-
-                    if let Some(note_action) = TimelineTabView::new(
-                        profile_timeline.current_view(),
-                        false, // reversed
-                        self.note_options,
-                        &txn,
-                        self.is_muted,
-                        self.note_context,
-                        &self
-                            .accounts
-                            .get_selected_account()
-                            .map(|a| (&a.key).into()),
-                    )
-                    .show(ui)
-                    {
-                        action = Some(ProfileViewAction::Note(note_action));
+                match tab {
+                    ProfileTab::Notes => {
+                        if let Some(note_action) = self.notes_ui(ui, &txn) {
+                            action = Some(ProfileViewAction::Note(note_action));
+                        }
+                    }
+                    ProfileTab::Following => {
+                        if let Some(follows_action) = self.follows_ui(ui, &txn, &graph.following) {
+                            action = Some(follows_action);
+                        }
+                    }
+                    ProfileTab::Followers => {
+                        if let Some(follows_action) = self.follows_ui(ui, &txn, &graph.followers) {
+                            action = Some(follows_action);
+                        }
                     }
-                } else {
-                    // Handle case where timeline doesn't exist yet (maybe show loading?)
-                    ui.label("Loading profile timeline...");
                 }
 
                 action
             })
-            .inner
+            .inner;
+
+        ui.ctx().data_mut(|d| d.insert_temp(tab_id, tab));
+        action
+    }
+
+    fn notes_ui(&mut self, ui: &mut egui::Ui, txn: &Transaction) -> Option<NoteAction> {
+        let kind = TimelineKind::Profile(*self.pubkey);
+        let profile_timeline_opt = self
+            .timeline_cache
+            .timelines
+            .get_mut(&kind);
+
+        let Some(profile_timeline) = profile_timeline_opt else {
+            // Handle case where timeline doesn't exist yet (maybe show loading?)
+            ui.label("Loading profile timeline...");
+            return None;
+        };
+
+        // poll timeline to add notes *before* getting the immutable reference for the view
+        if let Err(e) = profile_timeline.poll_notes_into_pending(
+            self.note_context.ndb,
+            txn,
+            self.unknown_ids,
+            self.note_context.note_cache,
+        ) {
+            error!("Profile::poll_notes_into_pending: {e}");
+        }
+
+        // Now we can use the (implicitly reborrowed) timeline for the view
+        profile_timeline.selected_view =
+            tabs_ui(ui, profile_timeline.selected_view, &profile_timeline.views);
+
+        TimelineTabView::new(
+            profile_timeline.current_view(),
+            false, // reversed
+            self.note_options,
+            txn,
+            self.is_muted,
+            self.note_context,
+            &self
+                .accounts
+                .get_selected_account()
+                .map(|a| (&a.key).into()),
+        )
+        .show(ui)
+    }
+
+    fn social_graph(&mut self, ui: &egui::Ui, txn: &Transaction) -> SocialGraph {
+        let ndb = self.note_context.ndb;
+        let contact_list = get_contact_list(ndb, txn, self.pubkey);
+        let following_set = contact_list
+            .as_ref()
+            .map(contact_pubkeys)
+            .unwrap_or_default();
+
+        let follows_you = self
+            .accounts
+            .selected_account_pubkey()
+            .is_some_and(|me| me != self.pubkey && following_set.contains(me));
+
+        let mut following: Vec<Pubkey> = following_set.into_iter().collect();
+        following.sort();
+
+        let followers = self.followers.get(ndb, ui.ctx(), self.pubkey).to_vec();
+
+        SocialGraph {
+            following,
+            followers,
+            follows_you,
+        }
+    }
+
+    fn follows_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        txn: &Transaction,
+        pubkeys: &[Pubkey],
+    ) -> Option<ProfileViewAction> {
+        let shown_id = egui::Id::new(("profile_follows_shown", self.col_id, self.pubkey));
+        let mut shown: usize = ui
+            .ctx()
+            .data(|d| d.get_temp(shown_id))
+            .unwrap_or(FOLLOWS_PAGE);
+
+        let me = self
+            .accounts
+            .selected_account_pubkey()
+            .filter(|me| self.accounts.contains_full_kp(me));
+        let my_follows: HashSet<Pubkey> = me
+            .and_then(|me| get_contact_list(self.note_context.ndb, txn, me))
+            .as_ref()
+            .map(contact_pubkeys)
+            .unwrap_or_default();
+
+        if pubkeys.is_empty() {
+            notedeck_ui::padding(12.0, ui, |ui| {
+                ui.label(RichText::new("Nobody yet").weak());
+            });
+            return None;
+        }
+
+        let mut action = None;
+        for pubkey in pubkeys.iter().take(shown) {
+            ui.separator();
+
+            let resp = if let Ok(profile) = self
+                .note_context
+                .ndb
+                .get_profile_by_pubkey(txn, pubkey.bytes())
+            {
+                let nip05_status = self
+                    .note_context
+                    .nip05
+                    .profile_status(pubkey.bytes(), Some(&profile));
+                ui.add(
+                    ProfilePreview::new(&profile, self.note_context.img_cache)
+                        .nip05_status(nip05_status),
+                )
+            } else {
+                self.unknown_ids
+                    .add_pubkey_if_missing(self.note_context.ndb, txn, pubkey);
+                notedeck_ui::padding(12.0, ui, |ui| {
+                    ui.label(pubkey.to_bech().unwrap_or_else(|| pubkey.hex()))
+                })
+                .response
+            };
+
+            if resp
+                .interact(Sense::click())
+                .on_hover_cursor(egui::CursorIcon::PointingHand)
+                .clicked()
+            {
+                action = Some(ProfileViewAction::Note(NoteAction::Profile(*pubkey)));
+            }
+
+            if me.is_some_and(|me| me != pubkey) {
+                notedeck_ui::padding(12.0, ui, |ui| {
+                    if my_follows.contains(pubkey) {
+                        if ui.button("Unfollow").clicked() {
+                            action = Some(ProfileViewAction::Unfollow(*pubkey));
+                        }
+                    } else if ui.button("Follow").clicked() {
+                        action = Some(ProfileViewAction::Follow(*pubkey));
+                    }
+                });
+            }
+        }
+
+        if pubkeys.len() > shown {
+            notedeck_ui::padding(12.0, ui, |ui| {
+                if ui.button("Show more").clicked() {
+                    shown += FOLLOWS_PAGE;
+                }
+            });
+        }
+        ui.ctx().data_mut(|d| d.insert_temp(shown_id, shown));
+
+        action
     }
 
-    fn profile_body(&mut self, ui: &mut egui::Ui, profile: ProfileRecord<'_>) -> bool {
+    fn profile_body(
+        &mut self,
+        ui: &mut egui::Ui,
+        profile: ProfileRecord<'_>,
+        graph: &SocialGraph,
+        tab: &mut ProfileTab,
+    ) -> bool {
         let mut action = false;
         ui.vertical(|ui| {
             banner(
@@ -173,11 +359,21 @@ impl<'a, 'd> ProfileView<'a, 'd> {
 
                 ui.add_space(18.0);
 
+                let nip05_status = self
+                    .note_context
+                    .nip05
+                    .profile_status(self.pubkey.bytes(), Some(&profile));
                 ui.add(display_name_widget(
                     &get_display_name(Some(&profile)),
+                    nip05_status,
                     false,
                 ));
 
+                if graph.follows_you {
+                    ui.add_space(4.0);
+                    ui.add(follows_you_badge());
+                }
+
                 ui.add_space(8.0);
 
                 ui.add(about_section_widget(&profile));
@@ -201,6 +397,23 @@ impl<'a, 'd> ProfileView<'a, 'd> {
                         handle_lud16(ui, lud16);
                     }
                 });
+
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    ui.spacing_mut().item_spacing.x = 12.0;
+                    if ui
+                        .add(follow_count(graph.following.len(), "Following"))
+                        .clicked()
+                    {
+                        *tab = ProfileTab::Following;
+                    }
+                    if ui
+                        .add(follow_count(graph.followers.len(), "Followers"))
+                        .clicked()
+                    {
+                        *tab = ProfileTab::Followers;
+                    }
+                });
             });
         });
 
@@ -208,6 +421,36 @@ impl<'a, 'd> ProfileView<'a, 'd> {
     }
 }
 
+fn follow_count(count: usize, label: &str) -> impl egui::Widget + '_ {
+    move |ui: &mut egui::Ui| -> egui::Response {
+        ui.horizontal(|ui| {
+            ui.spacing_mut().item_spacing.x = 4.0;
+            ui.label(RichText::new(count.to_string()).strong());
+            ui.label(RichText::new(label).weak());
+        })
+        .response
+        .interact(Sense::click())
+        .on_hover_cursor(egui::CursorIcon::PointingHand)
+    }
+}
+
+fn follows_you_badge() -> impl egui::Widget {
+    |ui: &mut egui::Ui| -> egui::Response {
+        egui::Frame::new()
+            .fill(ui.visuals().widgets.inactive.bg_fill)
+            .corner_radius(CornerRadius::same(4))
+            .inner_margin(egui::Margin::symmetric(6, 2))
+            .show(ui, |ui| {
+                ui.label(
+                    RichText::new("Follows you")
+                        .size(11.0)
+                        .color(ui.visuals().weak_text_color()),
+                );
+            })
+            .response
+    }
+}
+
 fn handle_link(ui: &mut egui::Ui, website_url: &str) {
     ui.image(egui::include_image!(
         "../../../../../assets/icons/links_4x.png"
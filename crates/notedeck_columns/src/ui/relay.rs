@@ -20,6 +20,33 @@ pub struct RelayView<'a> {
 
 impl View for RelayView<'_> {
     fn ui(&mut self, ui: &mut egui::Ui) {
+        self.show(ui);
+    }
+}
+
+/// What the user picked on one of the relays in the list
+enum RelayAction {
+    Remove(String),
+    Browse(String),
+}
+
+impl<'a> RelayView<'a> {
+    pub fn new(
+        accounts: &'a mut Accounts,
+        manager: RelayPoolManager<'a>,
+        id_string_map: &'a mut HashMap<Id, String>,
+    ) -> Self {
+        RelayView {
+            accounts,
+            manager,
+            id_string_map,
+        }
+    }
+
+    /// Show the relays and return one the user wants to browse in a
+    /// relay feed column
+    pub fn show(&mut self, ui: &mut egui::Ui) -> Option<String> {
+        let mut relay_to_browse = None;
         Frame::new()
             .inner_margin(Margin::symmetric(10, 0))
             .show(ui, |ui| {
@@ -40,9 +67,13 @@ impl View for RelayView<'_> {
                     .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::AlwaysHidden)
                     .auto_shrink([false; 2])
                     .show(ui, |ui| {
-                        if let Some(relay_to_remove) = self.show_relays(ui) {
-                            self.accounts
-                                .remove_advertised_relay(&relay_to_remove, self.manager.pool);
+                        match self.show_relays(ui) {
+                            Some(RelayAction::Remove(relay_to_remove)) => {
+                                self.accounts
+                                    .remove_advertised_relay(&relay_to_remove, self.manager.pool);
+                            }
+                            Some(RelayAction::Browse(relay)) => relay_to_browse = Some(relay),
+                            None => {}
                         }
                         ui.add_space(8.0);
                         if let Some(relay_to_add) = self.show_add_relay_ui(ui) {
@@ -51,29 +82,17 @@ impl View for RelayView<'_> {
                         }
                     });
             });
-    }
-}
 
-impl<'a> RelayView<'a> {
-    pub fn new(
-        accounts: &'a mut Accounts,
-        manager: RelayPoolManager<'a>,
-        id_string_map: &'a mut HashMap<Id, String>,
-    ) -> Self {
-        RelayView {
-            accounts,
-            manager,
-            id_string_map,
-        }
+        relay_to_browse
     }
 
     pub fn panel(&mut self, ui: &mut egui::Ui) {
         egui::CentralPanel::default().show(ui.ctx(), |ui| self.ui(ui));
     }
 
-    /// Show the current relays and return a relay the user selected to delete
-    fn show_relays(&'a self, ui: &mut Ui) -> Option<String> {
-        let mut relay_to_remove = None;
+    /// Show the current relays and return what the user picked on one
+    fn show_relays(&'a self, ui: &mut Ui) -> Option<RelayAction> {
+        let mut action = None;
         for (index, relay_info) in self.manager.get_relay_infos().iter().enumerate() {
             ui.add_space(8.0);
             ui.vertical_centered_justified(|ui| {
@@ -89,7 +108,8 @@ impl<'a> RelayView<'a> {
                                         .id_salt(index)
                                         .max_width(
                                             ui.max_rect().width()
-                                                - get_right_side_width(relay_info.status),
+                                                - get_right_side_width(relay_info.status)
+                                                - BROWSE_BUTTON_WIDTH,
                                         ) // TODO: refactor to dynamically check the size of the 'right to left' portion and set the max width to be the screen width minus padding minus 'right to left' width
                                         .show(ui, |ui| {
                                             ui.label(
@@ -111,16 +131,26 @@ impl<'a> RelayView<'a> {
 
                         ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                             if ui.add(delete_button(ui.visuals().dark_mode)).clicked() {
-                                relay_to_remove = Some(relay_info.relay_url.to_string());
+                                action =
+                                    Some(RelayAction::Remove(relay_info.relay_url.to_string()));
                             };
 
+                            if ui
+                                .add(browse_button())
+                                .on_hover_text("Add a column with everything on this relay")
+                                .clicked()
+                            {
+                                action =
+                                    Some(RelayAction::Browse(relay_info.relay_url.to_string()));
+                            }
+
                             show_connection_status(ui, relay_info.status);
                         });
                     });
                 });
             });
         }
-        relay_to_remove
+        action
     }
 
     const RELAY_PREFILL: &'static str = "wss://";
@@ -207,6 +237,12 @@ fn get_right_side_width(status: RelayStatus) -> f32 {
     }
 }
 
+const BROWSE_BUTTON_WIDTH: f32 = 60.0;
+
+fn browse_button() -> Button<'static> {
+    Button::new(RichText::new("Browse").size(13.0).color(PINK)).frame(false)
+}
+
 fn delete_button(_dark_mode: bool) -> egui::Button<'static> {
     /*
     let img_data = if dark_mode {
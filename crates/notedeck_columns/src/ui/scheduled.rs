@@ -0,0 +1,187 @@
+use egui::{RichText, ScrollArea};
+use enostr::Pubkey;
+use notedeck::time_ago_since;
+use notedeck_ui::padding;
+
+use crate::scheduled::{format_utc, parse_utc, unix_now, ScheduledPost, ScheduledPosts};
+
+/// How much of a post we show in the list
+const PREVIEW_CHARS: usize = 140;
+
+pub enum ScheduledAction {
+    Reschedule {
+        id: u64,
+        publish_at: u64,
+        content: String,
+    },
+    SendNow(u64),
+    Cancel(u64),
+}
+
+/// The post being edited, kept in egui's temp data while the fields
+/// are open
+#[derive(Clone)]
+struct ScheduledEdit {
+    id: u64,
+    content: String,
+    time: String,
+}
+
+/// Lists the queued posts of an account
+pub struct ScheduledView<'a> {
+    scheduled: &'a ScheduledPosts,
+    pubkey: &'a Pubkey,
+}
+
+impl<'a> ScheduledView<'a> {
+    pub fn new(scheduled: &'a ScheduledPosts, pubkey: &'a Pubkey) -> Self {
+        Self { scheduled, pubkey }
+    }
+
+    pub fn ui(&self, ui: &mut egui::Ui) -> Option<ScheduledAction> {
+        let mut posts = self.scheduled.for_account(self.pubkey).peekable();
+
+        if posts.peek().is_none() {
+            padding(16.0, ui, |ui| {
+                ui.label(RichText::new("No scheduled posts").weak());
+            });
+            return None;
+        }
+
+        let edit_id = ui.id().with(("scheduled_edit", self.pubkey));
+        let mut edit: Option<ScheduledEdit> = ui.ctx().data(|d| d.get_temp(edit_id));
+
+        let mut action = None;
+        ScrollArea::vertical().show(ui, |ui| {
+            for post in posts {
+                padding(8.0, ui, |ui| {
+                    let row_action = match &mut edit {
+                        Some(editing) if editing.id == post.id => {
+                            let (row_action, done) = edit_row(ui, editing);
+                            if done {
+                                edit = None;
+                            }
+                            row_action
+                        }
+                        _ => post_row(ui, post, &mut edit),
+                    };
+
+                    if let Some(row_action) = row_action {
+                        action = Some(row_action);
+                    }
+                });
+                notedeck_ui::hline(ui);
+            }
+        });
+
+        ui.ctx().data_mut(|d| match edit {
+            Some(edit) => d.insert_temp(edit_id, edit),
+            None => d.remove::<ScheduledEdit>(edit_id),
+        });
+
+        action
+    }
+}
+
+fn post_row(
+    ui: &mut egui::Ui,
+    post: &ScheduledPost,
+    edit: &mut Option<ScheduledEdit>,
+) -> Option<ScheduledAction> {
+    let mut action = None;
+
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(format!("{} UTC", format_utc(post.publish_at))).strong());
+        ui.label(RichText::new(time_ago_since(post.publish_at)).weak());
+    });
+
+    let content = post.content();
+    let preview: String = content.chars().take(PREVIEW_CHARS).collect();
+    if preview.len() < content.len() {
+        ui.label(format!("{preview}…"));
+    } else {
+        ui.label(preview);
+    }
+
+    if post.sending {
+        ui.label(RichText::new("Sent, waiting for a relay to accept it").weak());
+    } else if post.sign_at_send {
+        ui.label(RichText::new("Signed when it's sent").weak());
+    }
+
+    ui.horizontal(|ui| {
+        if post.sending {
+            if ui.button("Cancel").clicked() {
+                action = Some(ScheduledAction::Cancel(post.id));
+            }
+            return;
+        }
+
+        if ui.button("Edit").clicked() {
+            *edit = Some(ScheduledEdit {
+                id: post.id,
+                content,
+                time: format_utc(post.publish_at),
+            });
+        }
+
+        if ui.button("Send now").clicked() {
+            action = Some(ScheduledAction::SendNow(post.id));
+        }
+
+        if ui.button("Cancel").clicked() {
+            action = Some(ScheduledAction::Cancel(post.id));
+        }
+    });
+
+    action
+}
+
+/// Returns the action, and whether we're done editing
+fn edit_row(ui: &mut egui::Ui, edit: &mut ScheduledEdit) -> (Option<ScheduledAction>, bool) {
+    ui.add(
+        egui::TextEdit::multiline(&mut edit.content)
+            .desired_width(f32::INFINITY)
+            .desired_rows(3),
+    );
+
+    let publish_at = parse_utc(&edit.time).filter(|publish_at| *publish_at > unix_now());
+    ui.horizontal_wrapped(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut edit.time)
+                .hint_text("YYYY-MM-DD HH:MM")
+                .desired_width(130.0),
+        );
+        ui.label("UTC");
+
+        match publish_at {
+            Some(publish_at) => ui.weak(time_ago_since(publish_at)),
+            None => ui.colored_label(ui.visuals().error_fg_color, "Pick a time in the future"),
+        };
+    });
+
+    let mut action = None;
+    let mut done = false;
+    ui.horizontal(|ui| {
+        let can_save = publish_at.is_some() && !edit.content.trim().is_empty();
+        if ui
+            .add_enabled(can_save, egui::Button::new("Save"))
+            .clicked()
+        {
+            if let Some(publish_at) = publish_at {
+                action = Some(ScheduledAction::Reschedule {
+                    id: edit.id,
+                    publish_at,
+                    content: edit.content.clone(),
+                });
+                done = true;
+            }
+        }
+
+        if ui.button("Discard changes").clicked() {
+            done = true;
+        }
+    });
+
+    (action, done)
+}
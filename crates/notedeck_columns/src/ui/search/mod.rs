@@ -1,17 +1,44 @@
 use egui::{vec2, Align, Color32, CornerRadius, RichText, Stroke, TextEdit};
-use enostr::KeypairUnowned;
+use enostr::{KeypairUnowned, Pubkey};
 
-use crate::ui::timeline::TimelineTabView;
+use crate::{
+    profile,
+    relay_info::RelayInfoCache,
+    search::{PersonResult, SearchQuery, SearchTarget},
+    subscriptions::Subscriptions,
+    ui::timeline::TimelineTabView,
+};
 use egui_winit::clipboard::Clipboard;
-use nostrdb::{Filter, Transaction};
-use notedeck::{MuteFun, NoteAction, NoteContext, NoteRef};
-use notedeck_ui::{icons::search_icon, padding, NoteOptions};
-use std::time::{Duration, Instant};
-use tracing::{error, info, warn};
+use nostrdb::{ProfileRecord, Transaction};
+use notedeck::{
+    filter, name::get_display_name, profile::get_profile_url, Images, MuteFun, Nip05Status,
+    NoteAction, NoteContext, NoteRef,
+};
+use notedeck_ui::{icons::search_icon, padding, profile::nip05_badge, NoteOptions, ProfilePic};
+use tracing::info;
 
 mod state;
 
-pub use state::{FocusState, SearchQueryState, SearchState};
+pub use state::{FocusState, SearchQueryState, SearchState, SearchTab};
+
+/// Something the user did in the search view
+pub enum SearchAction {
+    Note(NoteAction),
+
+    /// Follow this person with the selected account
+    Follow(Pubkey),
+
+    /// Add a column with this person's notes
+    AddColumn(Pubkey),
+}
+
+/// What the search view needs to send its search to relays
+pub struct SearchRelays<'a> {
+    /// The search view's id, so that relay results find their way back
+    pub id: egui::Id,
+    pub relay_info: &'a RelayInfoCache,
+    pub subscriptions: &'a mut Subscriptions,
+}
 
 pub struct SearchView<'a, 'd> {
     query: &'a mut SearchQueryState,
@@ -20,6 +47,7 @@ pub struct SearchView<'a, 'd> {
     is_muted: &'a MuteFun,
     note_context: &'a mut NoteContext<'d>,
     cur_acc: &'a Option<KeypairUnowned<'a>>,
+    relays: SearchRelays<'a>,
 }
 
 impl<'a, 'd> SearchView<'a, 'd> {
@@ -30,6 +58,7 @@ impl<'a, 'd> SearchView<'a, 'd> {
         query: &'a mut SearchQueryState,
         note_context: &'a mut NoteContext<'d>,
         cur_acc: &'a Option<KeypairUnowned<'a>>,
+        relays: SearchRelays<'a>,
     ) -> Self {
         Self {
             txn,
@@ -38,10 +67,11 @@ impl<'a, 'd> SearchView<'a, 'd> {
             note_options,
             note_context,
             cur_acc,
+            relays,
         }
     }
 
-    pub fn show(&mut self, ui: &mut egui::Ui, clipboard: &mut Clipboard) -> Option<NoteAction> {
+    pub fn show(&mut self, ui: &mut egui::Ui, clipboard: &mut Clipboard) -> Option<SearchAction> {
         padding(8.0, ui, |ui| self.show_impl(ui, clipboard)).inner
     }
 
@@ -49,96 +79,323 @@ impl<'a, 'd> SearchView<'a, 'd> {
         &mut self,
         ui: &mut egui::Ui,
         clipboard: &mut Clipboard,
-    ) -> Option<NoteAction> {
+    ) -> Option<SearchAction> {
         ui.spacing_mut().item_spacing = egui::vec2(0.0, 12.0);
 
         if search_box(self.query, ui, clipboard) {
             self.execute_search(ui.ctx());
         }
 
+        self.poll_local_search();
+        self.poll_remote_search(ui.ctx());
+        if self.query.people.poll() || self.query.people.is_verifying() {
+            let ndb = self.note_context.ndb;
+            let txn = self.txn;
+            let nip05 = &mut *self.note_context.nip05;
+            self.query.people.rank_verified(|pubkey| {
+                let profile = ndb.get_profile_by_pubkey(txn, pubkey.bytes()).ok();
+                nip05.profile_status(pubkey.bytes(), profile.as_ref())
+            });
+        }
+
         match self.query.state {
             SearchState::New | SearchState::Navigating => None,
 
             SearchState::Searched | SearchState::Typing => {
-                if self.query.state == SearchState::Typing {
-                    ui.label(format!("Searching for '{}'", &self.query.string));
-                } else {
-                    ui.label(format!(
-                        "Got {} results for '{}'",
-                        self.query.notes.notes.len(),
-                        &self.query.string
-                    ));
+                if let Some(err) = &self.query.error {
+                    ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                    return None;
                 }
 
-                egui::ScrollArea::vertical()
-                    .show(ui, |ui| {
-                        let reversed = false;
-                        TimelineTabView::new(
-                            &self.query.notes,
-                            reversed,
-                            self.note_options,
-                            self.txn,
-                            self.is_muted,
-                            self.note_context,
-                            self.cur_acc,
-                        )
-                        .show(ui)
-                    })
-                    .inner
+                search_tabs(ui, &mut self.query.tab);
+
+                match self.query.tab {
+                    SearchTab::Notes => self.notes_ui(ui).map(SearchAction::Note),
+                    SearchTab::People => self.people_ui(ui),
+                }
             }
         }
     }
 
+    fn notes_ui(&mut self, ui: &mut egui::Ui) -> Option<NoteAction> {
+        if self.query.state == SearchState::Typing {
+            ui.label(format!("Searching for '{}'", &self.query.string));
+        } else if self.query.local.is_searching() {
+            ui.horizontal(|ui| {
+                ui.add(egui::Spinner::new().size(12.0));
+                ui.label(format!(
+                    "Searching for '{}', {} results so far",
+                    &self.query.string,
+                    self.query.notes.notes.len()
+                ));
+            });
+        } else {
+            let total = self.query.notes.notes.len();
+            let remote = self.query.remote.remote_keys.len();
+            ui.label(format!(
+                "Got {} results for '{}' ({} local, {} from relays)",
+                total,
+                &self.query.string,
+                total.saturating_sub(remote),
+                remote
+            ));
+        }
+
+        if self.query.remote.is_searching() {
+            ui.horizontal(|ui| {
+                ui.add(egui::Spinner::new().size(12.0));
+                ui.label(
+                    RichText::new(format!(
+                        "Searching {} relays...",
+                        self.query.remote.num_relays()
+                    ))
+                    .weak(),
+                );
+            });
+        }
+
+        egui::ScrollArea::vertical()
+            .show(ui, |ui| {
+                let reversed = false;
+                TimelineTabView::new(
+                    &self.query.notes,
+                    reversed,
+                    self.note_options,
+                    self.txn,
+                    self.is_muted,
+                    self.note_context,
+                    self.cur_acc,
+                )
+                .remote_keys(Some(&self.query.remote.remote_keys))
+                .show(ui)
+            })
+            .inner
+    }
+
+    fn people_ui(&mut self, ui: &mut egui::Ui) -> Option<SearchAction> {
+        if self.query.state == SearchState::Typing || self.query.people.is_searching() {
+            ui.horizontal(|ui| {
+                ui.add(egui::Spinner::new().size(12.0));
+                ui.label(format!("Searching people for '{}'", &self.query.string));
+            });
+            return None;
+        }
+
+        if self.query.people.results.is_empty() {
+            ui.label(format!("No people found for '{}'", &self.query.string));
+            return None;
+        }
+
+        let mut action = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(8.0, 8.0);
+
+            for person in &self.query.people.results {
+                let profile = self
+                    .note_context
+                    .ndb
+                    .get_profile_by_pubkey(self.txn, person.pubkey.bytes())
+                    .ok();
+
+                let nip05_status = self
+                    .note_context
+                    .nip05
+                    .profile_status(person.pubkey.bytes(), profile.as_ref());
+
+                if let Some(person_action) = person_row(
+                    ui,
+                    self.note_context.img_cache,
+                    person,
+                    profile.as_ref(),
+                    nip05_status,
+                ) {
+                    action = Some(person_action);
+                }
+
+                ui.separator();
+            }
+        });
+
+        action
+    }
+
     fn execute_search(&mut self, ctx: &egui::Context) {
         if self.query.string.is_empty() {
+            self.query
+                .remote
+                .stop(self.note_context.pool, self.relays.subscriptions);
+            self.query.people.clear();
             return;
         }
 
-        let max_results = 500;
-        let filter = Filter::new()
-            .search(&self.query.string)
-            .kinds([1])
-            .limit(max_results)
+        let ndb = self.note_context.ndb;
+        let txn = self.txn;
+        let search = match SearchQuery::parse(&self.query.string, |name| {
+            let results = ndb.search_profile(txn, name, 1).ok()?;
+            results.first().map(|pk| Pubkey::new(**pk))
+        }) {
+            Ok(search) => search,
+            Err(err) => {
+                self.query.error = Some(err);
+                self.query.local.cancel();
+                self.query.people.clear();
+                self.query
+                    .remote
+                    .stop(self.note_context.pool, self.relays.subscriptions);
+                return;
+            }
+        };
+        self.query.error = None;
+
+        // ask search relays too. local results show up right away, relay
+        // results get merged in as they come in
+        let remote_filter = search
+            .filter()
+            .limit(filter::default_remote_limit())
             .build();
+        self.query.remote.start(
+            remote_filter,
+            self.note_context.pool,
+            self.relays.subscriptions,
+        );
+
+        self.query.notes.notes.clear();
+        self.query.notes.list.borrow_mut().reset();
 
-        // TODO: execute in thread
-
-        let before = Instant::now();
-        let qrs = self
-            .note_context
-            .ndb
-            .query(self.txn, &[filter], max_results as i32);
-        let after = Instant::now();
-        let duration = after - before;
-
-        if duration > Duration::from_millis(20) {
-            warn!(
-                "query took {:?}... let's update this to use a thread!",
-                after - before
-            );
+        // operators don't mean anything for profiles, so people are
+        // searched with just the free text
+        if search.search.is_empty() {
+            self.query.people.clear();
+        } else {
+            let follows = self
+                .cur_acc
+                .as_ref()
+                .and_then(|acc| profile::get_contact_list(ndb, txn, acc.pubkey))
+                .map(|contacts| profile::contact_pubkeys(&contacts))
+                .unwrap_or_default();
+            self.query
+                .people
+                .start(ndb, ctx, search.search.clone(), follows);
         }
 
-        match qrs {
-            Ok(qrs) => {
-                info!(
-                    "queried '{}' and got {} results",
-                    self.query.string,
-                    qrs.len()
-                );
+        let max_results = 500;
+        info!("searching for '{}'", search.to_query_string());
+        self.query
+            .local
+            .start(self.note_context.ndb, ctx, max_results, move |until| {
+                // page through results with an until bound, keeping any
+                // until: from the query
+                let mut search = search.clone();
+                if let Some(until) = until {
+                    search.until = Some(search.until.map_or(until, |u| u.min(until)));
+                }
+                search.local_filter().build()
+            });
+    }
 
-                let note_refs = qrs.into_iter().map(NoteRef::from_query_result).collect();
-                self.query.notes.notes = note_refs;
-                self.query.notes.list.borrow_mut().reset();
-                ctx.request_repaint();
-            }
+    /// Merge in any pages of local results our search worker has found
+    fn poll_local_search(&mut self) {
+        let found = self.query.local.poll();
+        if found.is_empty() {
+            return;
+        }
 
-            Err(err) => {
-                error!("fulltext query failed: {err}")
-            }
+        let new_refs: Vec<NoteRef> = found
+            .into_iter()
+            .filter(|nr| !self.query.notes.notes.contains(nr))
+            .collect();
+
+        self.query.notes.insert(&new_refs, false);
+    }
+
+    /// Send our search to any newly discovered search relays and merge
+    /// in relay results that have been ingested
+    fn poll_remote_search(&mut self, ctx: &egui::Context) {
+        self.query.remote.update(
+            &SearchTarget::View(self.relays.id),
+            self.relays.relay_info,
+            self.note_context.pool,
+            self.relays.subscriptions,
+        );
+
+        let found = self
+            .query
+            .remote
+            .poll_results(self.note_context.ndb, self.txn);
+        if found.is_empty() {
+            return;
         }
+
+        let new_refs: Vec<NoteRef> = found
+            .into_iter()
+            .filter(|nr| !self.query.notes.notes.contains(nr))
+            .collect();
+
+        self.query.remote.mark_remote(&new_refs);
+        self.query.notes.insert(&new_refs, false);
+        ctx.request_repaint();
     }
 }
 
+fn search_tabs(ui: &mut egui::Ui, tab: &mut SearchTab) {
+    ui.horizontal(|ui| {
+        ui.spacing_mut().item_spacing = egui::vec2(16.0, 0.0);
+        ui.selectable_value(tab, SearchTab::Notes, "Notes");
+        ui.selectable_value(tab, SearchTab::People, "People");
+    });
+}
+
+fn person_row(
+    ui: &mut egui::Ui,
+    img_cache: &mut Images,
+    person: &PersonResult,
+    profile: Option<&ProfileRecord<'_>>,
+    nip05_status: Nip05Status,
+) -> Option<SearchAction> {
+    let mut action = None;
+
+    ui.horizontal(|ui| {
+        let pfp = ui
+            .add(ProfilePic::new(img_cache, get_profile_url(profile)).size(40.0))
+            .interact(egui::Sense::click())
+            .on_hover_cursor(egui::CursorIcon::PointingHand);
+        if pfp.clicked() {
+            action = Some(SearchAction::Note(NoteAction::Profile(person.pubkey)));
+        }
+
+        let name = get_display_name(profile);
+        ui.vertical(|ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(0.0, 2.0);
+            ui.label(RichText::new(name.name()).strong());
+            if let Some(nip05) = name.nip05 {
+                ui.horizontal(|ui| {
+                    nip05_badge(ui, nip05_status);
+                    ui.label(RichText::new(nip05).weak().small());
+                });
+            }
+            if person.followed {
+                ui.label(RichText::new("Following").weak().small());
+            }
+        });
+
+        ui.with_layout(egui::Layout::right_to_left(Align::Center), |ui| {
+            if ui.button("Add column").clicked() {
+                action = Some(SearchAction::AddColumn(person.pubkey));
+            }
+
+            if !person.followed && ui.button("Follow").clicked() {
+                action = Some(SearchAction::Follow(person.pubkey));
+            }
+
+            if ui.button("Open").clicked() {
+                action = Some(SearchAction::Note(NoteAction::Profile(person.pubkey)));
+            }
+        });
+    });
+
+    action
+}
+
 fn search_box(query: &mut SearchQueryState, ui: &mut egui::Ui, clipboard: &mut Clipboard) -> bool {
     ui.horizontal(|ui| {
         // Container for search input and icon
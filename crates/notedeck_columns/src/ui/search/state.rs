@@ -1,4 +1,7 @@
-use crate::timeline::TimelineTab;
+use crate::{
+    search::{LocalSearch, PeopleSearch, RemoteSearch, SearchSyntaxError},
+    timeline::TimelineTab,
+};
 use notedeck::debouncer::Debouncer;
 use std::time::Duration;
 
@@ -10,6 +13,14 @@ pub enum SearchState {
     New,
 }
 
+/// Which kind of results the search view is showing
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum SearchTab {
+    #[default]
+    Notes,
+    People,
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum FocusState {
     /// Get ready to focus
@@ -40,8 +51,25 @@ pub struct SearchQueryState {
     /// When was the input updated? We use this to debounce searches
     pub debouncer: Debouncer,
 
+    /// Set when the query couldn't be parsed, eg. a `from:` with an
+    /// unknown name
+    pub error: Option<SearchSyntaxError>,
+
+    /// Are we showing notes or people?
+    pub tab: SearchTab,
+
     /// The search results
     pub notes: TimelineTab,
+
+    /// Profiles matching the query, for the people tab
+    pub people: PeopleSearch,
+
+    /// Our nostrdb query, running in the background
+    pub local: LocalSearch,
+
+    /// Our search request to NIP-50 relays. Its results get merged into
+    /// `notes` as they arrive
+    pub remote: RemoteSearch,
 }
 
 impl Default for SearchQueryState {
@@ -55,7 +83,12 @@ impl SearchQueryState {
         Self {
             string: "".to_string(),
             state: SearchState::New,
+            error: None,
+            tab: SearchTab::default(),
             notes: TimelineTab::default(),
+            people: PeopleSearch::default(),
+            local: LocalSearch::default(),
+            remote: RemoteSearch::default(),
             focus_state: FocusState::Navigating,
             debouncer: Debouncer::new(Duration::from_millis(200)),
         }
@@ -69,6 +102,9 @@ impl SearchQueryState {
     /// the searched flag, enabling us to search again. This should be
     /// called when the search box changes
     pub fn mark_updated(&mut self) {
+        // the results of the old query aren't interesting anymore
+        self.local.cancel();
+        self.error = None;
         self.state = SearchState::Typing;
         self.debouncer.bounce();
     }
@@ -1,5 +1,5 @@
 use egui::{vec2, Button, Label, Layout, RichText};
-use notedeck::{NamedFontFamily, NotedeckTextStyle};
+use notedeck::{Images, MediaCache, NamedFontFamily, NotedeckTextStyle};
 use notedeck_ui::{colors::PINK, padding};
 use tracing::error;
 
@@ -7,11 +7,12 @@ use crate::support::Support;
 
 pub struct SupportView<'a> {
     support: &'a mut Support,
+    images: &'a mut Images,
 }
 
 impl<'a> SupportView<'a> {
-    pub fn new(support: &'a mut Support) -> Self {
-        Self { support }
+    pub fn new(support: &'a mut Support, images: &'a mut Images) -> Self {
+        Self { support, images }
     }
 
     pub fn show(&mut self, ui: &mut egui::Ui) {
@@ -69,12 +70,72 @@ impl<'a> SupportView<'a> {
                         .color(egui::Color32::RED),
                 );
             }
+            ui.add_space(8.0);
+            self.storage(ui);
+            ui.add_space(8.0);
+
             ui.label(format!("Notedeck {}", env!("CARGO_PKG_VERSION")));
             ui.label(format!("Commit hash: {}", env!("GIT_COMMIT_HASH")));
         });
     }
 }
 
+impl SupportView<'_> {
+    fn storage(&mut self, ui: &mut egui::Ui) {
+        ui.label(RichText::new("Storage").text_style(NotedeckTextStyle::Heading3.text_style()));
+        padding(8.0, ui, |ui| {
+            cache_usage(ui, "Images", &self.images.static_imgs);
+            cache_usage(ui, "GIFs", &self.images.gifs);
+            ui.label(format!(
+                "Link types: {} remembered",
+                self.images.urls.cache.len()
+            ));
+
+            let font_size = notedeck::fonts::get_font_size(ui.ctx(), &NotedeckTextStyle::Body);
+            let clear_button = Button::new(RichText::new("Clear cache").size(font_size))
+                .fill(PINK)
+                .min_size(vec2(120.0, 40.0));
+            if ui
+                .add(clear_button)
+                .on_hover_text(
+                    "Delete cached images and GIFs. They will be downloaded again when needed.",
+                )
+                .clicked()
+            {
+                self.images.clear_cache();
+            }
+        });
+    }
+}
+
+fn cache_usage(ui: &mut egui::Ui, name: &str, cache: &MediaCache) {
+    let disk = match cache.disk_usage() {
+        Some(bytes) => format_bytes(bytes),
+        None => "…".to_owned(),
+    };
+    ui.label(format!(
+        "{name}: {disk} of {} on disk, {} in memory",
+        format_bytes(cache.limits().disk_bytes),
+        format_bytes(cache.texture_usage() as u64),
+    ));
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
 fn open_email_button(font_size: f32, size: egui::Vec2) -> impl egui::Widget {
     Button::new(RichText::new("Open Email").size(font_size))
         .fill(PINK)
@@ -1,11 +1,20 @@
-use enostr::KeypairUnowned;
+use enostr::{KeypairUnowned, NoteId};
 use nostrdb::Transaction;
 use notedeck::{MuteFun, NoteAction, NoteContext, RootNoteId, UnknownIds};
-use notedeck_ui::NoteOptions;
-use tracing::error;
+use notedeck_ui::{NoteOptions, NoteView};
+use tracing::{error, warn};
 
-use crate::timeline::{ThreadSelection, TimelineCache, TimelineKind};
-use crate::ui::timeline::TimelineTabView;
+use crate::timeline::{ThreadRow, ThreadRowKind, ThreadSelection, TimelineCache, TimelineKind};
+
+/// Replies deeper than this below the selected note are collapsed
+const MAX_REPLY_DEPTH: usize = 6;
+
+/// Past this depth we stop indenting, so that deep branches stay readable
+/// in narrow columns
+const MAX_INDENT_DEPTH: usize = 4;
+
+/// How far each level of replies is indented
+const INDENT: f32 = 14.0;
 
 pub struct ThreadView<'a, 'd> {
     timeline_cache: &'a mut TimelineCache,
@@ -71,37 +80,181 @@ impl<'a, 'd> ThreadView<'a, 'd> {
 
                 let kind = TimelineKind::Thread(ThreadSelection::from_root_id(root_id.to_owned()));
 
-                // Get mutable reference ONCE
-                let thread_timeline_opt = self.timeline_cache.timelines.get_mut(&kind);
-
-                if let Some(thread_timeline) = thread_timeline_opt {
-                    // poll timeline to add notes
-                    if let Err(err) = thread_timeline.poll_notes_into_pending(
-                        self.note_context.ndb,
-                        &txn,
-                        self.unknown_ids,
-                        self.note_context.note_cache,
-                    ) {
-                        error!("ThreadView::poll_notes_into_pending: {err}");
-                    }
-
-                    // Use the same mutable reference (reborrowed implicitly) for the view
-                    TimelineTabView::new(
-                        thread_timeline.current_view(),
-                        true, // reversed for threads
-                        self.note_options,
-                        &txn,
-                        self.is_muted,
-                        self.note_context,
-                        self.cur_acc,
-                    )
-                    .show(ui)
-                } else {
+                let Some(thread_timeline) = self.timeline_cache.timelines.get_mut(&kind) else {
                     // Handle case where timeline doesn't exist yet (maybe show loading?)
                     ui.label("Loading thread timeline...");
-                    None
+                    return None;
+                };
+
+                // poll timeline to add notes
+                if let Err(err) = thread_timeline.poll_notes_into_pending(
+                    self.note_context.ndb,
+                    &txn,
+                    self.unknown_ids,
+                    self.note_context.note_cache,
+                ) {
+                    error!("ThreadView::poll_notes_into_pending: {err}");
+                }
+
+                // replies show up as they come in, there's no new notes
+                // button in threads
+                if let Err(err) = thread_timeline.apply_pending_notes(
+                    self.note_context.ndb,
+                    &txn,
+                    self.unknown_ids,
+                    self.note_context.note_cache,
+                ) {
+                    error!("ThreadView::apply_pending_notes: {err}");
+                }
+
+                let selected = NoteId::new(*self.selected_note_id);
+                let rows = thread_timeline.thread_layout.rows(
+                    self.note_context.ndb,
+                    self.note_context.note_cache,
+                    &txn,
+                    &thread_timeline.views[thread_timeline.selected_view].notes,
+                    &selected,
+                    MAX_REPLY_DEPTH,
+                );
+                let list = thread_timeline.thread_layout.list.clone();
+
+                if rows.is_empty() {
+                    ui.label("Loading thread...");
+                    return None;
+                }
+
+                ui.spacing_mut().item_spacing.y = 0.0;
+
+                // the selected note and its ancestors are always laid out,
+                // so that we can scroll to the selected note
+                let split = rows
+                    .iter()
+                    .position(|row| row.kind == ThreadRowKind::Selected)
+                    .map_or(0, |i| i + 1);
+                let (above, replies) = rows.split_at(split);
+
+                let mut action: Option<NoteAction> = None;
+                for row in above {
+                    if let Some(row_action) = self.row_ui(ui, &txn, root_id.bytes(), row) {
+                        action = Some(row_action);
+                    }
                 }
+
+                list.borrow_mut()
+                    .ui_custom_layout(ui, replies.len(), |ui, index| {
+                        if let Some(row_action) =
+                            self.row_ui(ui, &txn, root_id.bytes(), &replies[index])
+                        {
+                            action = Some(row_action);
+                        }
+
+                        1
+                    });
+
+                action
             })
             .inner
     }
+
+    fn row_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        txn: &Transaction,
+        root_id: &[u8; 32],
+        row: &ThreadRow,
+    ) -> Option<NoteAction> {
+        let Ok(note) = self.note_context.ndb.get_note_by_key(txn, row.key) else {
+            warn!("failed to query note {:?}", row.key);
+            return None;
+        };
+
+        if (self.is_muted)(&note, root_id) {
+            return None;
+        }
+
+        let mut action: Option<NoteAction> = None;
+        let indent = row.depth.min(MAX_INDENT_DEPTH) as f32 * INDENT;
+        let is_selected = row.kind == ThreadRowKind::Selected;
+
+        let resp = ui.horizontal(|ui| {
+            ui.add_space(indent);
+
+            let frame = if is_selected {
+                egui::Frame::new().fill(ui.visuals().selection.bg_fill.gamma_multiply(0.2))
+            } else {
+                egui::Frame::new()
+            };
+
+            frame.show(ui, |ui| {
+                ui.vertical(|ui| {
+                    notedeck_ui::padding(8.0, ui, |ui| {
+                        let resp = NoteView::new(
+                            self.note_context,
+                            self.cur_acc,
+                            &note,
+                            self.note_options,
+                        )
+                        .show(ui);
+
+                        if let Some(note_action) = resp.action {
+                            action = Some(note_action);
+                        }
+
+                        if row.hidden_replies > 0 {
+                            let text = if row.hidden_replies == 1 {
+                                "Show 1 more reply".to_owned()
+                            } else {
+                                format!("Show {} more replies", row.hidden_replies)
+                            };
+
+                            // continue the conversation with this note selected
+                            if ui.link(text).clicked() {
+                                action = Some(NoteAction::Note(row.id));
+                            }
+                        }
+                    });
+                });
+            });
+        });
+
+        let rect = resp.response.rect;
+        let stroke = ui.visuals().widgets.noninteractive.bg_stroke;
+
+        // connect replies to their parents
+        for depth in 1..=row.depth.min(MAX_INDENT_DEPTH) {
+            let x = rect.left() + (depth as f32 - 0.5) * INDENT;
+            ui.painter().vline(x, rect.y_range(), stroke);
+        }
+
+        // and ancestors to the note below them, down from their pfp
+        if row.kind == ThreadRowKind::Ancestor {
+            let pfp_size = self.note_options.pfp_size() as f32;
+            let x = rect.left() + 8.0 + pfp_size / 2.0;
+            let top = (rect.top() + 8.0 + pfp_size).min(rect.bottom());
+            ui.painter()
+                .vline(x, egui::Rangef::new(top, rect.bottom()), stroke);
+        }
+
+        if is_selected {
+            self.scroll_to_selected_once(ui, &resp.response, row);
+        }
+
+        if row.kind != ThreadRowKind::Ancestor {
+            notedeck_ui::hline(ui);
+        }
+
+        action
+    }
+
+    /// Bring the selected note into view when it has ancestors above it,
+    /// but only the first time we show it so that we don't fight the user
+    fn scroll_to_selected_once(&self, ui: &egui::Ui, resp: &egui::Response, row: &ThreadRow) {
+        let scrolled_id = self.id_source.with("scrolled_to");
+        let scrolled_to = ui.data(|d| d.get_temp::<NoteId>(scrolled_id));
+
+        if scrolled_to != Some(row.id) {
+            resp.scroll_to_me(Some(egui::Align::TOP));
+            ui.data_mut(|d| d.insert_temp(scrolled_id, row.id));
+        }
+    }
 }
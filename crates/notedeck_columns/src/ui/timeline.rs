@@ -2,17 +2,25 @@ use egui::containers::scroll_area::ScrollBarVisibility;
 use egui::{vec2, Direction, Layout, Pos2, Stroke};
 use egui_tabs::TabColor;
 use enostr::KeypairUnowned;
-use nostrdb::Transaction;
+use nostrdb::{NoteKey, Transaction};
+use std::collections::HashSet;
 use std::f32::consts::PI;
 use tracing::{error, warn};
 
-use crate::timeline::{TimelineCache, TimelineKind, TimelineTab, ViewFilter};
-use notedeck::{note::root_note_id_from_selected_id, MuteFun, NoteAction, NoteContext, UnknownIds};
+use crate::timeline::{
+    Backfill, Gap, TimeRange, TimelineCache, TimelineKind, TimelineTab, ViewFilter,
+};
+use notedeck::{
+    note::root_note_id_from_selected_id, MuteFun, NoteAction, NoteContext, NoteRef, UnknownIds,
+};
 use notedeck_ui::{
     anim::{AnimationHelper, ICON_EXPANSION_MULTIPLE},
     show_pointer, NoteOptions, NoteView,
 };
 
+/// How close to the bottom of a timeline we start loading older notes
+const BACKFILL_THRESHOLD: f32 = 400.0;
+
 pub struct TimelineView<'a, 'd> {
     timeline_id: &'a TimelineKind,
     timeline_cache: &'a mut TimelineCache,
@@ -161,7 +169,8 @@ fn timeline_ui(
 
         let txn = Transaction::new(note_context.ndb).expect("failed to create txn");
 
-        TimelineTabView::new(
+        let gaps = timeline.gaps();
+        let mut view = TimelineTabView::new(
             timeline.current_view(),
             reversed,
             note_options,
@@ -170,9 +179,41 @@ fn timeline_ui(
             note_context,
             cur_acc,
         )
-        .show(ui)
+        .remote_keys(timeline.remote_search.as_ref().map(|s| &s.remote_keys))
+        .gaps(&gaps);
+        let action = view.show(ui);
+        let gap_fill = view.gap_fill;
+
+        if timeline.can_backfill() {
+            backfill_footer(ui, &timeline.backfill);
+        }
+
+        Some((action, gap_fill))
     });
 
+    let (action, gap_fill) = scroll_output.inner.unwrap_or_default();
+    if let Some((range, clicked)) = gap_fill {
+        if let Some(timeline) = timeline_cache.timelines.get_mut(timeline_id) {
+            timeline.coverage.request_fill(range, clicked);
+        }
+    }
+
+    // load older notes once we get close to the bottom
+    let state = &scroll_output.state;
+    let bottom = state.offset.y + scroll_output.inner_rect.height();
+    if bottom >= scroll_output.content_size.y - BACKFILL_THRESHOLD {
+        if let Some(timeline) = timeline_cache.timelines.get_mut(timeline_id) {
+            if timeline.can_backfill()
+                && !timeline.current_view().notes.is_empty()
+                && !timeline.backfill.is_loading()
+                && !timeline.backfill.is_exhausted()
+            {
+                timeline.backfill.request();
+                ui.ctx().request_repaint();
+            }
+        }
+    }
+
     let at_top_after_scroll = scroll_output.state.offset.y == 0.0;
     let cur_show_top_button = ui.ctx().data(|d| d.get_temp::<bool>(show_top_button_id));
 
@@ -186,7 +227,58 @@ fn timeline_ui(
             .data_mut(|d| d.insert_temp(show_top_button_id, true));
     }
 
-    scroll_output.inner.or(note_action)
+    action.or(note_action)
+}
+
+/// The gap that belongs right below the note at `ind`, if any
+fn gap_below<'a>(gaps: &'a [Gap], notes: &[NoteRef], ind: usize) -> Option<&'a Gap> {
+    let note = notes.get(ind)?;
+    let older = notes.get(ind + 1)?;
+
+    gaps.iter()
+        .find(|gap| note.created_at >= gap.range.until && older.created_at <= gap.range.since)
+}
+
+/// Marks notes we never fetched between two notes in a timeline. Returns
+/// true when clicked.
+fn gap_row(ui: &mut egui::Ui, gap: &Gap) -> bool {
+    let mut clicked = false;
+
+    ui.vertical_centered(|ui| {
+        ui.add_space(6.0);
+        if gap.loading {
+            ui.horizontal(|ui| {
+                ui.add(egui::Spinner::new());
+                ui.label(egui::RichText::new("Loading missing notes…").weak());
+            });
+        } else {
+            clicked = ui
+                .link("Load missing notes")
+                .on_hover_text("Notes between these two were never fetched")
+                .clicked();
+        }
+        ui.add_space(6.0);
+    });
+    notedeck_ui::hline(ui);
+
+    clicked
+}
+
+/// Shows whether we're loading older notes at the bottom of a timeline
+fn backfill_footer(ui: &mut egui::Ui, backfill: &Backfill) {
+    if backfill.is_loading() {
+        ui.horizontal(|ui| {
+            ui.add_space(8.0);
+            ui.add(egui::Spinner::new());
+            ui.label(egui::RichText::new("Loading older notes…").weak());
+        });
+    } else if backfill.is_exhausted() {
+        ui.vertical_centered(|ui| {
+            ui.add_space(8.0);
+            ui.label(egui::RichText::new("No older notes").weak());
+            ui.add_space(8.0);
+        });
+    }
 }
 
 fn goto_top_button(center: Pos2) -> impl egui::Widget {
@@ -340,6 +432,11 @@ pub struct TimelineTabView<'a, 'd> {
     is_muted: &'a MuteFun,
     note_context: &'a mut NoteContext<'d>,
     cur_acc: &'a Option<KeypairUnowned<'a>>,
+    remote_keys: Option<&'a HashSet<NoteKey>>,
+    gaps: &'a [Gap],
+
+    /// A gap that was clicked, or scrolled into view
+    pub gap_fill: Option<(TimeRange, bool)>,
 }
 
 impl<'a, 'd> TimelineTabView<'a, 'd> {
@@ -361,9 +458,25 @@ impl<'a, 'd> TimelineTabView<'a, 'd> {
             is_muted,
             note_context,
             cur_acc,
+            remote_keys: None,
+            gaps: &[],
+            gap_fill: None,
         }
     }
 
+    /// Notes that came from a relay search rather than our local db.
+    /// These get a small label so you can tell them apart.
+    pub fn remote_keys(mut self, remote_keys: Option<&'a HashSet<NoteKey>>) -> Self {
+        self.remote_keys = remote_keys;
+        self
+    }
+
+    /// Missing time ranges to mark between notes
+    pub fn gaps(mut self, gaps: &'a [Gap]) -> Self {
+        self.gaps = gaps;
+        self
+    }
+
     pub fn show(&mut self, ui: &mut egui::Ui) -> Option<NoteAction> {
         let mut action: Option<NoteAction> = None;
         let len = self.tab.notes.len();
@@ -406,7 +519,20 @@ impl<'a, 'd> TimelineTabView<'a, 'd> {
                 };
 
                 if !muted {
+                    let from_relay = self
+                        .remote_keys
+                        .is_some_and(|keys| keys.contains(&note_key));
+
                     notedeck_ui::padding(8.0, ui, |ui| {
+                        if from_relay {
+                            ui.add(
+                                egui::Label::new(
+                                    egui::RichText::new("from relay search").small().weak(),
+                                )
+                                .selectable(false),
+                            );
+                        }
+
                         let resp = NoteView::new(
                             self.note_context,
                             self.cur_acc,
@@ -423,6 +549,15 @@ impl<'a, 'd> TimelineTabView<'a, 'd> {
                     notedeck_ui::hline(ui);
                 }
 
+                if !self.reversed {
+                    if let Some(gap) = gap_below(self.gaps, &self.tab.notes, ind) {
+                        let clicked = gap_row(ui, gap);
+                        if clicked || !gap.loading {
+                            self.gap_fill = Some((gap.range, clicked));
+                        }
+                    }
+                }
+
                 1
             });
 
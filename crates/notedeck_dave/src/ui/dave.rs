@@ -172,12 +172,16 @@ impl<'a> DaveUi<'a> {
 
     /// The ai has asked us to render some notes, so we do that here
     fn present_notes_ui(ctx: &mut AppContext, call: &PresentNotesCall, ui: &mut egui::Ui) {
+        let bookmarks = ctx.accounts.bookmarks();
         let mut note_context = NoteContext {
             ndb: ctx.ndb,
             img_cache: ctx.img_cache,
             note_cache: ctx.note_cache,
             zaps: ctx.zaps,
+            nip05: ctx.nip05,
+            polls: ctx.polls,
             pool: ctx.pool,
+            bookmarks: &bookmarks,
         };
 
         let txn = Transaction::new(note_context.ndb).unwrap();
@@ -3,8 +3,8 @@ use image::codecs::gif::GifDecoder;
 use image::imageops::FilterType;
 use image::{AnimationDecoder, DynamicImage, FlatSamples, Frame};
 use notedeck::{
-    Animation, GifStateMap, ImageFrame, Images, MediaCache, MediaCacheType, TextureFrame,
-    TexturedImage,
+    Animation, GifStateMap, ImageFrame, Images, Imeta, MediaCache, MediaCacheType, PlaceholderMap,
+    TextureFrame, TexturedImage,
 };
 use poll_promise::Promise;
 use std::collections::VecDeque;
@@ -338,6 +338,92 @@ pub fn fetch_binary_from_disk(path: PathBuf) -> Result<Vec<u8>, notedeck::Error>
     std::fs::read(path).map_err(|e| notedeck::Error::Generic(e.to_string()))
 }
 
+/// Placeholders are decoded at most this big and stretched
+const PLACEHOLDER_SIZE: f32 = 32.0;
+
+/// How big media described by `imeta` is when shown at most `max_height`
+/// tall, so we can make room for it before it loads. Small media isn't
+/// scaled up.
+pub fn media_display_size(imeta: &Imeta, max_height: f32) -> Option<egui::Vec2> {
+    if let Some((w, h)) = imeta.dimensions.filter(|(w, h)| *w > 0 && *h > 0) {
+        let (w, h) = (w as f32, h as f32);
+        let scale = (max_height / h).min(1.0);
+        return Some(egui::vec2(w * scale, h * scale));
+    }
+
+    let ratio = imeta.aspect_ratio()?;
+    Some(egui::vec2(max_height * ratio, max_height))
+}
+
+/// The blurry version of media from its imeta thumbhash or blurhash
+pub fn placeholder_texture(
+    ctx: &egui::Context,
+    placeholders: &mut PlaceholderMap,
+    imeta: &Imeta,
+) -> Option<egui::TextureHandle> {
+    let key = imeta.thumbhash.as_ref().or(imeta.blurhash.as_ref())?;
+    if let Some(texture) = placeholders.get(key) {
+        return Some(texture.clone());
+    }
+
+    let image = decode_placeholder(imeta)?;
+    let texture = ctx.load_texture(format!("placeholder:{key}"), image, Default::default());
+    placeholders.insert(key.to_owned(), texture.clone());
+    Some(texture)
+}
+
+fn decode_placeholder(imeta: &Imeta) -> Option<ColorImage> {
+    if let Some((w, h, rgba)) = imeta
+        .thumbhash
+        .as_deref()
+        .and_then(notedeck::thumbhash::from_base64)
+        .and_then(|hash| notedeck::thumbhash::decode(&hash))
+    {
+        return Some(ColorImage::from_rgba_unmultiplied([w, h], &rgba));
+    }
+
+    let ratio = imeta.aspect_ratio().unwrap_or(1.0);
+    let (w, h) = if ratio > 1.0 {
+        (PLACEHOLDER_SIZE, PLACEHOLDER_SIZE / ratio)
+    } else {
+        (PLACEHOLDER_SIZE * ratio, PLACEHOLDER_SIZE)
+    };
+    let size = [(w.round() as usize).max(1), (h.round() as usize).max(1)];
+
+    let rgba = notedeck::blurhash::decode(imeta.blurhash.as_deref()?, size[0], size[1])?;
+    Some(ColorImage::from_rgba_unmultiplied(size, &rgba))
+}
+
+/// Make room for media that's still loading, blurred if we have a
+/// placeholder for it
+pub fn show_placeholder(
+    ui: &mut egui::Ui,
+    size: egui::Vec2,
+    texture: Option<&egui::TextureHandle>,
+) -> egui::Response {
+    let (rect, resp) = ui.allocate_exact_size(size, Sense::hover());
+
+    match texture {
+        Some(texture) => {
+            egui::Image::new(texture)
+                .corner_radius(5.0)
+                .paint_at(ui, rect);
+        }
+        None => {
+            ui.painter()
+                .rect_filled(rect, 5.0, ui.visuals().faint_bg_color);
+        }
+    }
+
+    let spinner_size = (size.min_elem() / 4.0).clamp(8.0, 24.0);
+    egui::Spinner::new().size(spinner_size).paint_at(
+        ui,
+        Rect::from_center_size(rect.center(), egui::vec2(spinner_size, spinner_size)),
+    );
+
+    resp
+}
+
 /// Controls type-specific handling
 #[derive(Debug, Clone, Copy)]
 pub enum ImageType {
@@ -358,6 +444,7 @@ pub fn fetch_img(
     let path = img_cache.cache_dir.join(key);
 
     if path.exists() {
+        MediaCache::touch(&path);
         fetch_img_from_disk(ctx, url, &path, cache_type)
     } else {
         fetch_img_from_net(&img_cache.cache_dir, ctx, url, imgtyp, cache_type)
@@ -457,6 +544,7 @@ fn render_media_cache(
     show_error: impl FnOnce(&mut egui::Ui, String),
     show_success: impl FnOnce(&mut egui::Ui, &str, &mut TexturedImage, &mut GifStateMap),
 ) -> egui::Response {
+    cache.mark_used(url);
     let m_cached_promise = cache.map().get(url);
 
     if m_cached_promise.is_none() {
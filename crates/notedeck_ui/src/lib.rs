@@ -8,6 +8,7 @@ pub mod mention;
 pub mod note;
 pub mod profile;
 mod username;
+pub mod video;
 pub mod widgets;
 
 pub use anim::{AnimationHelper, ImagePulseTint};
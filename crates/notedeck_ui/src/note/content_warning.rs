@@ -0,0 +1,199 @@
+use std::{collections::HashSet, sync::Arc};
+
+use egui::{Color32, RichText, Sense};
+use nostrdb::{Filter, Ndb, Note, NoteKey, Transaction};
+
+/// The reason given in a note's NIP-36 `content-warning` tag. Notes can
+/// have the tag without a reason, then this is empty.
+pub fn content_warning<'a>(note: &Note<'a>) -> Option<&'a str> {
+    note.tags()
+        .into_iter()
+        .find(|tag| tag.get_str(0) == Some("content-warning"))
+        .map(|tag| tag.get_str(1).unwrap_or("").trim())
+}
+
+fn is_revealed(ui: &egui::Ui, id: egui::Id) -> bool {
+    ui.ctx().data(|d| d.get_temp(id).unwrap_or(false))
+}
+
+fn reveal(ui: &egui::Ui, id: egui::Id) {
+    ui.ctx().data_mut(|d| d.insert_temp(id, true));
+}
+
+/// Has the content warning on this note been clicked through?
+pub fn content_revealed(ui: &egui::Ui, note_key: NoteKey) -> bool {
+    is_revealed(ui, content_warning_id(note_key))
+}
+
+fn content_warning_id(note_key: NoteKey) -> egui::Id {
+    egui::Id::new(("content_warning", note_key))
+}
+
+/// Stands in for the contents of a note with a content warning until it's
+/// shown
+pub fn content_warning_cover(ui: &mut egui::Ui, note_key: NoteKey, reason: &str) -> egui::Response {
+    egui::Frame::new()
+        .fill(ui.visuals().faint_bg_color)
+        .corner_radius(8.0)
+        .inner_margin(egui::Margin::same(8))
+        .show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            ui.horizontal_wrapped(|ui| {
+                ui.label(RichText::new("⚠ Content warning").strong());
+                if !reason.is_empty() {
+                    ui.label(RichText::new(reason).weak());
+                }
+            });
+
+            if ui.button("Show").clicked() {
+                reveal(ui, content_warning_id(note_key));
+            }
+        })
+        .response
+}
+
+/// The people the account follows, remembered until their contact list
+/// changes
+#[derive(Clone, Default)]
+struct FollowCache {
+    contact_list: Option<NoteKey>,
+    follows: Arc<HashSet<[u8; 32]>>,
+}
+
+/// Does `account` follow `author`? Everyone follows themselves.
+pub fn follows(
+    ctx: &egui::Context,
+    ndb: &Ndb,
+    txn: &Transaction,
+    account: &[u8; 32],
+    author: &[u8; 32],
+) -> bool {
+    if account == author {
+        return true;
+    }
+
+    let filter = Filter::new().authors([account]).kinds([3]).limit(1).build();
+    let Some(contact_list) = ndb
+        .query(txn, &[filter], 1)
+        .ok()
+        .and_then(|results| results.into_iter().next())
+    else {
+        return false;
+    };
+
+    let id = egui::Id::new(("follow_cache", account));
+    let cache = ctx.data(|d| d.get_temp::<FollowCache>(id));
+    let follows = match cache {
+        Some(cache) if cache.contact_list == Some(contact_list.note_key) => cache.follows,
+        _ => {
+            let follows: Arc<HashSet<[u8; 32]>> = Arc::new(
+                contact_list
+                    .note
+                    .tags()
+                    .into_iter()
+                    .filter(|tag| tag.count() >= 2 && tag.get_str(0) == Some("p"))
+                    .filter_map(|tag| tag.get_id(1).copied())
+                    .collect(),
+            );
+            ctx.data_mut(|d| {
+                d.insert_temp(
+                    id,
+                    FollowCache {
+                        contact_list: Some(contact_list.note_key),
+                        follows: follows.clone(),
+                    },
+                )
+            });
+            follows
+        }
+    };
+
+    follows.contains(author)
+}
+
+/// Has the blurred media in this carousel been clicked through?
+pub fn media_revealed(ui: &egui::Ui, carousel_id: egui::Id) -> bool {
+    is_revealed(ui, carousel_id.with("revealed"))
+}
+
+/// Covers media from people we don't follow, blurred if we have a
+/// placeholder for it, until it's clicked
+pub fn sensitive_media_cover(
+    ui: &mut egui::Ui,
+    carousel_id: egui::Id,
+    size: egui::Vec2,
+    placeholder: Option<&egui::TextureHandle>,
+) -> egui::Response {
+    let (rect, resp) = ui.allocate_exact_size(size, Sense::click());
+
+    match placeholder {
+        Some(texture) => {
+            egui::Image::new(texture)
+                .corner_radius(5.0)
+                .paint_at(ui, rect);
+        }
+        None => {
+            ui.painter()
+                .rect_filled(rect, 5.0, ui.visuals().faint_bg_color);
+        }
+    }
+
+    let label_rect = rect.shrink(8.0);
+    let galley = ui.painter().layout(
+        "Media from someone you don't follow. Click to show.".to_owned(),
+        egui::FontId::proportional(13.0),
+        Color32::WHITE,
+        label_rect.width(),
+    );
+    let text_rect = egui::Align2::CENTER_CENTER
+        .anchor_size(rect.center(), galley.size() + egui::vec2(16.0, 8.0));
+    ui.painter()
+        .rect_filled(text_rect, 6.0, Color32::from_black_alpha(160));
+    ui.painter()
+        .galley(text_rect.min + egui::vec2(8.0, 4.0), galley, Color32::WHITE);
+
+    if resp.hovered() {
+        crate::show_pointer(ui);
+    }
+
+    if resp.clicked() {
+        reveal(ui, carousel_id.with("revealed"));
+    }
+
+    resp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostrdb::NoteBuilder;
+
+    #[test]
+    fn test_content_warning_tag() {
+        let note = NoteBuilder::new()
+            .content("spoilers")
+            .start_tag()
+            .tag_str("content-warning")
+            .tag_str("movie ending")
+            .sign(&[1; 32])
+            .build()
+            .unwrap();
+        assert_eq!(content_warning(&note), Some("movie ending"));
+
+        let note = NoteBuilder::new()
+            .content("spoilers")
+            .start_tag()
+            .tag_str("content-warning")
+            .sign(&[1; 32])
+            .build()
+            .unwrap();
+        assert_eq!(content_warning(&note), Some(""));
+
+        let note = NoteBuilder::new()
+            .content("hi")
+            .sign(&[1; 32])
+            .build()
+            .unwrap();
+        assert_eq!(content_warning(&note), None);
+    }
+}
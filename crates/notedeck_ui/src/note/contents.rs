@@ -1,15 +1,24 @@
 use crate::{
     gif::{handle_repaint, retrieve_latest_texture},
-    images::{render_images, ImageType},
-    note::{NoteAction, NoteOptions, NoteResponse, NoteView},
+    images::{media_display_size, placeholder_texture, render_images, show_placeholder, ImageType},
+    note::{
+        content_warning::{self, content_warning},
+        poll, NoteAction, NoteOptions, NoteResponse, NoteView,
+    },
+    video,
 };
 
+use std::collections::HashMap;
+
 use egui::{Button, Color32, Hyperlink, Image, Response, RichText, Sense, Window};
 use enostr::KeypairUnowned;
 use nostrdb::{BlockType, Mention, Note, NoteKey, Transaction};
 use tracing::warn;
 
-use notedeck::{supported_mime_hosted_at_url, Images, MediaCacheType, NoteContext};
+use notedeck::{
+    note_imeta, poll::KIND_POLL, supported_media_hosted_at_url, Images, Imeta, MediaCacheType,
+    MediaKind, NoteContext,
+};
 
 pub struct NoteContents<'a, 'd> {
     note_context: &'a mut NoteContext<'d>,
@@ -74,7 +83,7 @@ pub fn render_note_preview(
 ) -> NoteResponse {
     let note = if let Ok(note) = note_context.ndb.get_note_by_id(txn, id) {
         // TODO: support other preview kinds
-        if note.kind() == 1 {
+        if note.kind() == 1 || note.kind() == KIND_POLL {
             note
         } else {
             return NoteResponse::new(ui.colored_label(
@@ -112,8 +121,16 @@ pub fn render_note_contents(
     options: NoteOptions,
 ) -> NoteResponse {
     let note_key = note.key().expect("todo: implement non-db notes");
+
+    if let Some(reason) = content_warning(note) {
+        if !content_warning::content_revealed(ui, note_key) {
+            return NoteResponse::new(content_warning::content_warning_cover(ui, note_key, reason));
+        }
+    }
+
     let selectable = options.has_selectable_text();
-    let mut images: Vec<(String, MediaCacheType)> = vec![];
+    let mut media: Vec<(String, MediaKind)> = vec![];
+    let mut audio: Vec<String> = vec![];
     let mut note_action: Option<NoteAction> = None;
     let mut inline_note: Option<(&[u8; 32], &str)> = None;
     let hide_media = options.has_hide_media();
@@ -190,15 +207,22 @@ pub fn render_note_contents(
                 }
 
                 BlockType::Url => {
+                    let ctx = ui.ctx().clone();
                     let mut found_supported = || -> bool {
                         let url = block.as_str();
-                        if let Some(cache_type) =
-                            supported_mime_hosted_at_url(&mut note_context.img_cache.urls, url)
-                        {
-                            images.push((url.to_string(), cache_type));
-                            true
-                        } else {
-                            false
+                        match supported_media_hosted_at_url(&mut note_context.img_cache.urls, url) {
+                            // nothing to play it with, leave it a link
+                            Some(MediaKind::Video) if !video::can_play_video(&ctx, url) => false,
+                            Some(MediaKind::Audio) if !video::can_play_audio(&ctx, url) => false,
+                            Some(MediaKind::Audio) => {
+                                audio.push(url.to_string());
+                                true
+                            }
+                            Some(kind) => {
+                                media.push((url.to_string(), kind));
+                                true
+                            }
+                            None => false,
                         }
                     };
                     if hide_media || !found_supported() {
@@ -232,20 +256,59 @@ pub fn render_note_contents(
         }
     });
 
+    let poll_action = if note.kind() == KIND_POLL {
+        poll::poll_ui(ui, note_context, cur_acc, txn, note)
+    } else {
+        None
+    };
+
     let preview_note_action = if let Some((id, _block_str)) = inline_note {
         render_note_preview(ui, note_context, cur_acc, txn, id, note_key, options).action
     } else {
         None
     };
 
-    if !images.is_empty() && !options.has_textmode() {
+    if !media.is_empty() && !options.has_textmode() {
         ui.add_space(2.0);
         let carousel_id = egui::Id::new(("carousel", note.key().expect("expected tx note")));
-        image_carousel(ui, note_context.img_cache, images, carousel_id);
+        let imetas = note_imeta(note);
+        let blurred = options.has_blur_media()
+            && !content_warning::media_revealed(ui, carousel_id)
+            && !cur_acc.as_ref().is_some_and(|acc| {
+                content_warning::follows(
+                    ui.ctx(),
+                    note_context.ndb,
+                    txn,
+                    acc.pubkey.bytes(),
+                    note.pubkey(),
+                )
+            });
+
+        if blurred {
+            blurred_media(ui, note_context.img_cache, &media, &imetas, carousel_id);
+        } else {
+            image_carousel(
+                ui,
+                note_context.img_cache,
+                media,
+                &imetas,
+                carousel_id,
+                options.has_autoplay_media(),
+            );
+        }
         ui.add_space(2.0);
     }
 
-    let note_action = preview_note_action.or(note_action);
+    if !audio.is_empty() && !options.has_textmode() {
+        let audio_id = egui::Id::new(("audio", note_key));
+        for url in audio {
+            ui.add_space(2.0);
+            video::audio_player(ui, audio_id.with(&url), &url);
+        }
+        ui.add_space(2.0);
+    }
+
+    let note_action = preview_note_action.or(poll_action).or(note_action);
 
     NoteResponse::new(response.response).with_action(note_action)
 }
@@ -268,11 +331,33 @@ fn rot13(input: &str) -> String {
         .collect()
 }
 
+/// The first piece of media stands in for the rest, blurred
+fn blurred_media(
+    ui: &mut egui::Ui,
+    img_cache: &mut Images,
+    media: &[(String, MediaKind)],
+    imetas: &HashMap<String, Imeta>,
+    carousel_id: egui::Id,
+) {
+    let height = 360.0;
+    let imeta = media.first().and_then(|(url, _)| imetas.get(url));
+    let size = imeta
+        .and_then(|imeta| media_display_size(imeta, height))
+        .unwrap_or(egui::vec2(ui.available_width(), height / 2.0));
+    let size = egui::vec2(size.x.min(ui.available_width()), size.y);
+    let placeholder =
+        imeta.and_then(|imeta| placeholder_texture(ui.ctx(), &mut img_cache.placeholders, imeta));
+
+    content_warning::sensitive_media_cover(ui, carousel_id, size, placeholder.as_ref());
+}
+
 fn image_carousel(
     ui: &mut egui::Ui,
     img_cache: &mut Images,
-    images: Vec<(String, MediaCacheType)>,
+    media: Vec<(String, MediaKind)>,
+    imetas: &HashMap<String, Imeta>,
     carousel_id: egui::Id,
+    autoplay: bool,
 ) {
     // let's make sure everything is within our area
 
@@ -290,24 +375,59 @@ fn image_carousel(
         ui.ctx().memory(|mem| {
             mem.data
                 .get_temp::<(String, MediaCacheType)>(carousel_id.with("current_image"))
-                .unwrap_or_else(|| (images[0].0.clone(), images[0].1.clone()))
+                .or_else(|| {
+                    media.iter().find_map(|(url, kind)| match kind {
+                        MediaKind::Image(cache_type) => Some((url.clone(), cache_type.clone())),
+                        _ => None,
+                    })
+                })
         })
     });
+    let current_image = current_image.flatten();
+    let show_popup = current_image.is_some();
 
     ui.add_sized([width, height], |ui: &mut egui::Ui| {
         egui::ScrollArea::horizontal()
             .id_salt(carousel_id)
             .show(ui, |ui| {
                 ui.horizontal(|ui| {
-                    for (image, cache_type) in images {
+                    for (image, kind) in media {
+                        let cache_type = match kind {
+                            MediaKind::Image(cache_type) => cache_type,
+                            MediaKind::Video => {
+                                video::video_player(
+                                    ui,
+                                    carousel_id.with(&image),
+                                    &image,
+                                    height,
+                                    autoplay,
+                                );
+                                continue;
+                            }
+                            MediaKind::Audio => continue,
+                        };
+
+                        // make room for it before it loads so the
+                        // timeline doesn't jump around
+                        let imeta = imetas.get(&image);
+                        let size = imeta.and_then(|imeta| media_display_size(imeta, height));
+                        let placeholder = imeta.and_then(|imeta| {
+                            placeholder_texture(ui.ctx(), &mut img_cache.placeholders, imeta)
+                        });
+
                         render_images(
                             ui,
                             img_cache,
                             &image,
                             ImageType::Content,
                             cache_type.clone(),
-                            |ui| {
-                                ui.allocate_space(egui::vec2(spinsz, spinsz));
+                            |ui| match size {
+                                Some(size) => {
+                                    show_placeholder(ui, size, placeholder.as_ref());
+                                }
+                                None => {
+                                    ui.allocate_space(egui::vec2(spinsz, spinsz));
+                                }
                             },
                             |ui, _| {
                                 ui.allocate_space(egui::vec2(spinsz, spinsz));
@@ -317,15 +437,12 @@ fn image_carousel(
                                     ui,
                                     retrieve_latest_texture(&image, gifs, renderable_media),
                                 );
-                                let img_resp = ui.add(
-                                    Button::image(
-                                        Image::new(texture)
-                                            .max_height(height)
-                                            .corner_radius(5.0)
-                                            .fit_to_original_size(1.0),
-                                    )
-                                    .frame(false),
-                                );
+                                let img = Image::new(texture).corner_radius(5.0);
+                                let img = match size {
+                                    Some(size) => img.fit_to_exact_size(size),
+                                    None => img.max_height(height).fit_to_original_size(1.0),
+                                };
+                                let img_resp = ui.add(Button::image(img).frame(false));
 
                                 if img_resp.clicked() {
                                     ui.ctx().memory_mut(|mem| {
@@ -350,7 +467,7 @@ fn image_carousel(
     if show_popup {
         let current_image = current_image
             .as_ref()
-            .expect("the image was actually clicked");
+            .expect("the popup only opens with an image");
         let image = current_image.clone().0;
         let cache_type = current_image.clone().1;
 
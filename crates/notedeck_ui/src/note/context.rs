@@ -1,6 +1,6 @@
 use egui::{Rect, Vec2};
 use nostrdb::NoteKey;
-use notedeck::{BroadcastContext, NoteContextSelection};
+use notedeck::{BookmarkContext, Bookmarks, BroadcastContext, NoteContextSelection};
 
 pub struct NoteContextButton {
     put_at: Option<Rect>,
@@ -102,10 +102,13 @@ impl NoteContextButton {
         response
     }
 
+    /// `bookmarks` is None when the account can't save bookmarks
     #[profiling::function]
     pub fn menu(
         ui: &mut egui::Ui,
         button_response: egui::Response,
+        note_id: &[u8; 32],
+        bookmarks: Option<&Bookmarks>,
     ) -> Option<NoteContextSelection> {
         let mut context_selection: Option<NoteContextSelection> = None;
 
@@ -127,6 +130,32 @@ impl NoteContextButton {
                 context_selection = Some(NoteContextSelection::CopyNoteJSON);
                 ui.close_menu();
             }
+            if let Some(bookmarks) = bookmarks {
+                if bookmarks.contains(note_id) {
+                    if ui.button("Remove bookmark").clicked() {
+                        context_selection =
+                            Some(NoteContextSelection::Bookmark(BookmarkContext::Remove));
+                        ui.close_menu();
+                    }
+                } else {
+                    if ui.button("Bookmark").clicked() {
+                        context_selection =
+                            Some(NoteContextSelection::Bookmark(BookmarkContext::Public));
+                        ui.close_menu();
+                    }
+                    let private = ui
+                        .add_enabled(
+                            bookmarks.is_unlocked(),
+                            egui::Button::new("Bookmark privately"),
+                        )
+                        .on_disabled_hover_text("Your private bookmarks couldn't be decrypted");
+                    if private.clicked() {
+                        context_selection =
+                            Some(NoteContextSelection::Bookmark(BookmarkContext::Private));
+                        ui.close_menu();
+                    }
+                }
+            }
             if ui.button("Broadcast").clicked() {
                 context_selection = Some(NoteContextSelection::Broadcast(
                     BroadcastContext::Everywhere,
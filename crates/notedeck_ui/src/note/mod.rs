@@ -1,11 +1,14 @@
+pub mod content_warning;
 pub mod contents;
 pub mod context;
 pub mod options;
+pub mod poll;
 pub mod reply_description;
 
 use crate::{
-    profile::name::one_line_display_name_widget, widgets::x_button, ImagePulseTint, ProfilePic,
-    ProfilePreview, Username,
+    profile::{name::one_line_display_name_widget, nip05_badge},
+    widgets::x_button,
+    ImagePulseTint, ProfilePic, ProfilePreview, Username,
 };
 
 pub use contents::{render_note_contents, render_note_preview, NoteContents};
@@ -20,8 +23,8 @@ use nostrdb::{Ndb, Note, NoteKey, Transaction};
 use notedeck::{
     name::get_display_name,
     note::{NoteAction, NoteContext, ZapAction},
-    AnyZapState, CachedNote, ContextSelection, NoteCache, NoteZapTarget, NoteZapTargetOwned,
-    NotedeckTextStyle, ZapTarget, Zaps,
+    AnyZapState, CachedNote, ContextSelection, Nip05Status, NoteCache, NoteZapTarget,
+    NoteZapTargetOwned, NotedeckTextStyle, ZapTarget, Zaps,
 };
 
 pub struct NoteView<'a, 'd> {
@@ -246,16 +249,21 @@ impl<'a, 'd> NoteView<'a, 'd> {
                     anim_speed,
                 );
 
+                let nip05_status = self
+                    .note_context
+                    .nip05
+                    .profile_status(self.note.pubkey(), profile.as_ref().ok());
+
                 ui.put(
                     rect,
                     ProfilePic::new(self.note_context.img_cache, pic).size(size),
                 )
                 .on_hover_ui_at_pointer(|ui| {
                     ui.set_max_width(300.0);
-                    ui.add(ProfilePreview::new(
-                        profile.as_ref().unwrap(),
-                        self.note_context.img_cache,
-                    ));
+                    ui.add(
+                        ProfilePreview::new(profile.as_ref().unwrap(), self.note_context.img_cache)
+                            .nip05_status(nip05_status),
+                    );
                 });
 
                 if resp.hovered() || resp.clicked() {
@@ -289,6 +297,11 @@ impl<'a, 'd> NoteView<'a, 'd> {
                 .ndb
                 .get_profile_by_pubkey(txn, self.note.pubkey());
 
+            let nip05_status = self
+                .note_context
+                .nip05
+                .profile_status(self.note.pubkey(), profile.as_ref().ok());
+
             let style = NotedeckTextStyle::Small;
             ui.horizontal(|ui| {
                 ui.vertical(|ui| {
@@ -304,7 +317,10 @@ impl<'a, 'd> NoteView<'a, 'd> {
                 if let Ok(rec) = &profile {
                     resp.on_hover_ui_at_pointer(|ui| {
                         ui.set_max_width(300.0);
-                        ui.add(ProfilePreview::new(rec, self.note_context.img_cache));
+                        ui.add(
+                            ProfilePreview::new(rec, self.note_context.img_cache)
+                                .nip05_status(nip05_status),
+                        );
                     });
                 }
                 let color = ui.style().visuals.noninteractive().fg_stroke.color;
@@ -347,12 +363,14 @@ impl<'a, 'd> NoteView<'a, 'd> {
         note_cache: &mut NoteCache,
         note: &Note,
         profile: &Result<nostrdb::ProfileRecord<'_>, nostrdb::Error>,
+        nip05_status: Nip05Status,
     ) {
         let note_key = note.key().unwrap();
 
         ui.horizontal(|ui| {
             ui.spacing_mut().item_spacing.x = 2.0;
             ui.add(Username::new(profile.as_ref().ok(), note.pubkey()).abbreviated(20));
+            nip05_badge(ui, nip05_status);
 
             let cached_note = note_cache.cached_note_or_insert_mut(note_key, note);
             render_reltime(ui, cached_note, true);
@@ -371,6 +389,10 @@ impl<'a, 'd> NoteView<'a, 'd> {
             .note_context
             .ndb
             .get_profile_by_pubkey(txn, self.note.pubkey());
+        let nip05_status = self
+            .note_context
+            .nip05
+            .profile_status(self.note.pubkey(), profile.as_ref().ok());
         let maybe_hitbox = maybe_note_hitbox(ui, hitbox_id);
 
         // wide design
@@ -392,6 +414,7 @@ impl<'a, 'd> NoteView<'a, 'd> {
                                         self.note_context.note_cache,
                                         self.note,
                                         &profile,
+                                        nip05_status,
                                     );
                                 })
                                 .response
@@ -459,7 +482,13 @@ impl<'a, 'd> NoteView<'a, 'd> {
                 };
 
                 ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
-                    NoteView::note_header(ui, self.note_context.note_cache, self.note, &profile);
+                    NoteView::note_header(
+                        ui,
+                        self.note_context.note_cache,
+                        self.note,
+                        &profile,
+                        nip05_status,
+                    );
                     ui.horizontal(|ui| {
                         ui.spacing_mut().item_spacing.x = 2.0;
 
@@ -526,8 +555,17 @@ impl<'a, 'd> NoteView<'a, 'd> {
                 Rect::from_min_size(min, egui::vec2(size, size))
             };
 
+            // we can only bookmark as an account we can sign for
+            let bookmarks = self
+                .cur_acc
+                .as_ref()
+                .filter(|acc| acc.secret_key.is_some())
+                .map(|_| self.note_context.bookmarks);
+
             let resp = ui.add(NoteContextButton::new(note_key).place_at(context_pos));
-            if let Some(action) = NoteContextButton::menu(ui, resp.clone()) {
+            if let Some(action) =
+                NoteContextButton::menu(ui, resp.clone(), self.note.id(), bookmarks)
+            {
                 note_action = Some(NoteAction::Context(ContextSelection { note_key, action }));
             }
         }
@@ -21,6 +21,12 @@ bitflags! {
 
         /// Whether the current note is a preview
         const is_preview      = 0b0000010000000000;
+
+        /// Start videos as soon as they're on screen, muted
+        const autoplay_media  = 0b0000100000000000;
+
+        /// Blur media from people we don't follow until it's clicked
+        const blur_media      = 0b0001000000000000;
     }
 }
 
@@ -60,6 +66,8 @@ impl NoteOptions {
     create_bit_methods!(set_hide_media, has_hide_media, hide_media);
     create_bit_methods!(set_scramble_text, has_scramble_text, scramble_text);
     create_bit_methods!(set_is_preview, has_is_preview, is_preview);
+    create_bit_methods!(set_autoplay_media, has_autoplay_media, autoplay_media);
+    create_bit_methods!(set_blur_media, has_blur_media, blur_media);
 
     pub fn new(is_universe_timeline: bool) -> Self {
         let mut options = NoteOptions::default();
@@ -0,0 +1,160 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use egui::{Align2, FontId, RichText, Sense};
+use enostr::{KeypairUnowned, NoteId};
+use nostrdb::{Note, Transaction};
+use notedeck::{
+    poll::{Poll, PollResults, PollType},
+    time_ago_since, NoteAction, NoteContext, PollVote,
+};
+
+const OPTION_HEIGHT: f32 = 28.0;
+
+/// The options of a NIP-88 poll, as buttons until we've voted or it
+/// ended, then as bars with the results
+pub fn poll_ui(
+    ui: &mut egui::Ui,
+    note_context: &mut NoteContext,
+    cur_acc: &Option<KeypairUnowned>,
+    txn: &Transaction,
+    note: &Note,
+) -> Option<NoteAction> {
+    let poll = Poll::from_note(note)?;
+
+    let me = cur_acc.as_ref().map(|acc| acc.pubkey.bytes());
+    let results = note_context
+        .polls
+        .results(note_context.ndb, txn, &poll, note.id(), me);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let can_vote = cur_acc.as_ref().is_some_and(|acc| acc.secret_key.is_some());
+    let ended = poll.has_ended(now);
+
+    let mut action = None;
+    ui.add_space(4.0);
+    ui.vertical(|ui| {
+        if ended || !results.my_choices.is_empty() || !can_vote {
+            show_results(ui, &poll, &results);
+        } else {
+            action = show_voting(ui, &poll, note.id());
+        }
+
+        let s = if results.voters == 1 { "" } else { "s" };
+        let status = match poll.ends_at {
+            _ if ended => "Ended".to_owned(),
+            Some(ends_at) => format!(
+                "Ends in {}",
+                time_ago_since(ends_at).trim_start_matches('+')
+            ),
+            None => "No end date".to_owned(),
+        };
+        ui.label(RichText::new(format!("{} vote{s} · {status}", results.voters)).weak());
+    });
+    ui.add_space(4.0);
+
+    action
+}
+
+fn show_voting(ui: &mut egui::Ui, poll: &Poll, poll_id: &[u8; 32]) -> Option<NoteAction> {
+    let width = ui.available_width();
+    let vote = |choices| {
+        Some(NoteAction::Vote(PollVote {
+            poll: NoteId::new(*poll_id),
+            choices,
+        }))
+    };
+
+    if poll.poll_type == PollType::SingleChoice {
+        let mut action = None;
+        for option in &poll.options {
+            let button = egui::Button::new(&option.label).truncate();
+            if ui.add_sized([width, OPTION_HEIGHT], button).clicked() {
+                action = vote(vec![option.id.clone()]);
+            }
+        }
+        return action;
+    }
+
+    let id = egui::Id::new(("poll_choices", poll_id));
+    let mut chosen: Vec<String> = ui.ctx().data(|d| d.get_temp(id)).unwrap_or_default();
+
+    for option in &poll.options {
+        let mut checked = chosen.contains(&option.id);
+        if ui.checkbox(&mut checked, &option.label).changed() {
+            if checked {
+                chosen.push(option.id.clone());
+            } else {
+                chosen.retain(|choice| *choice != option.id);
+            }
+        }
+    }
+
+    let mut action = None;
+    if ui
+        .add_enabled(!chosen.is_empty(), egui::Button::new("Vote"))
+        .clicked()
+    {
+        action = vote(std::mem::take(&mut chosen));
+    }
+
+    ui.ctx().data_mut(|d| d.insert_temp(id, chosen));
+    action
+}
+
+fn show_results(ui: &mut egui::Ui, poll: &Poll, results: &PollResults) {
+    let width = ui.available_width();
+    let most = poll
+        .options
+        .iter()
+        .map(|option| results.votes(&option.id))
+        .max()
+        .unwrap_or(0);
+
+    for option in &poll.options {
+        let votes = results.votes(&option.id);
+        let fraction = if results.voters == 0 {
+            0.0
+        } else {
+            votes as f32 / results.voters as f32
+        };
+
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(width, OPTION_HEIGHT), Sense::hover());
+        let painter = ui.painter();
+        painter.rect_filled(rect, 4.0, ui.visuals().faint_bg_color);
+
+        let mut bar = rect;
+        bar.set_width(rect.width() * fraction);
+        let fill = if votes > 0 && votes == most {
+            ui.visuals().selection.bg_fill
+        } else {
+            ui.visuals().widgets.inactive.bg_fill
+        };
+        painter.rect_filled(bar, 4.0, fill);
+
+        let mine = results.my_choices.contains(&option.id);
+        let label = if mine {
+            format!("✔ {}", option.label)
+        } else {
+            option.label.clone()
+        };
+        let text_color = ui.visuals().text_color();
+        let font = FontId::proportional(14.0);
+        painter.text(
+            rect.left_center() + egui::vec2(8.0, 0.0),
+            Align2::LEFT_CENTER,
+            label,
+            font.clone(),
+            text_color,
+        );
+        painter.text(
+            rect.right_center() - egui::vec2(8.0, 0.0),
+            Align2::RIGHT_CENTER,
+            format!("{:.0}%", fraction * 100.0),
+            font,
+            text_color,
+        );
+    }
+}
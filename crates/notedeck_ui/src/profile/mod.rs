@@ -8,10 +8,11 @@ pub use picture::ProfilePic;
 pub use preview::ProfilePreview;
 
 use egui::{load::TexturePoll, Label, RichText};
-use notedeck::{NostrName, NotedeckTextStyle};
+use notedeck::{Nip05Status, NostrName, NotedeckTextStyle};
 
 pub fn display_name_widget<'a>(
     name: &'a NostrName<'a>,
+    nip05_status: Nip05Status,
     add_placeholder_space: bool,
 ) -> impl egui::Widget + 'a {
     move |ui: &mut egui::Ui| -> egui::Response {
@@ -38,12 +39,13 @@ pub fn display_name_widget<'a>(
                 });
 
                 let nip05_resp = name.nip05.map(|nip05| {
-                    ui.image(egui::include_image!(
-                        "../../../../assets/icons/verified_4x.png"
-                    ));
-                    ui.add(Label::new(
-                        RichText::new(nip05).size(16.0).color(crate::colors::TEAL),
-                    ))
+                    nip05_badge(ui, nip05_status);
+                    let color = if nip05_status == Nip05Status::Verified {
+                        crate::colors::TEAL
+                    } else {
+                        crate::colors::MID_GRAY
+                    };
+                    ui.add(Label::new(RichText::new(nip05).size(16.0).color(color)))
                 });
 
                 (username_resp, nip05_resp)
@@ -67,6 +69,27 @@ pub fn display_name_widget<'a>(
     }
 }
 
+/// A checkmark for verified NIP-05 identifiers, a warning for ones that
+/// don't match, and nothing while we're still checking or couldn't check
+pub fn nip05_badge(ui: &mut egui::Ui, status: Nip05Status) -> Option<egui::Response> {
+    match status {
+        Nip05Status::Unknown | Nip05Status::Failed => None,
+        Nip05Status::Verified => Some(
+            ui.add(
+                egui::Image::new(egui::include_image!(
+                    "../../../../assets/icons/verified_4x.png"
+                ))
+                .max_height(16.0),
+            )
+            .on_hover_text("NIP-05 verified"),
+        ),
+        Nip05Status::Invalid => Some(
+            ui.label(RichText::new("⚠").color(ui.visuals().warn_fg_color))
+                .on_hover_text("NIP-05 identifier does not match this profile"),
+        ),
+    }
+}
+
 pub fn about_section_widget<'a, 'b>(profile: &'b ProfileRecord<'a>) -> impl egui::Widget + 'b
 where
     'b: 'a,
@@ -3,7 +3,9 @@ use egui::{Frame, Label, RichText};
 use egui_extras::Size;
 use nostrdb::ProfileRecord;
 
-use notedeck::{name::get_display_name, profile::get_profile_url, Images, NotedeckTextStyle};
+use notedeck::{
+    name::get_display_name, profile::get_profile_url, Images, Nip05Status, NotedeckTextStyle,
+};
 
 use super::{about_section_widget, banner, display_name_widget};
 
@@ -11,6 +13,7 @@ pub struct ProfilePreview<'a, 'cache> {
     profile: &'a ProfileRecord<'a>,
     cache: &'cache mut Images,
     banner_height: Size,
+    nip05_status: Nip05Status,
 }
 
 impl<'a, 'cache> ProfilePreview<'a, 'cache> {
@@ -20,9 +23,15 @@ impl<'a, 'cache> ProfilePreview<'a, 'cache> {
             profile,
             cache,
             banner_height,
+            nip05_status: Nip05Status::Unknown,
         }
     }
 
+    pub fn nip05_status(mut self, nip05_status: Nip05Status) -> Self {
+        self.nip05_status = nip05_status;
+        self
+    }
+
     pub fn banner_height(&mut self, size: Size) {
         self.banner_height = size;
     }
@@ -44,6 +53,7 @@ impl<'a, 'cache> ProfilePreview<'a, 'cache> {
             );
             ui.add(display_name_widget(
                 &get_display_name(Some(self.profile)),
+                self.nip05_status,
                 false,
             ));
             ui.add(about_section_widget(self.profile));
@@ -92,7 +102,11 @@ impl egui::Widget for SimpleProfilePreview<'_, '_> {
             .show(ui, |ui| {
                 ui.add(ProfilePic::new(self.cache, get_profile_url(self.profile)).size(48.0));
                 ui.vertical(|ui| {
-                    ui.add(display_name_widget(&get_display_name(self.profile), true));
+                    ui.add(display_name_widget(
+                        &get_display_name(self.profile),
+                        Nip05Status::Unknown,
+                        true,
+                    ));
                     if !self.is_nsec {
                         ui.add(
                             Label::new(
@@ -0,0 +1,1047 @@
+use std::{
+    collections::HashMap,
+    io::Read,
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Once, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use egui::{
+    vec2, Align, Color32, ColorImage, Layout, Rect, RichText, Sense, TextureHandle, TextureOptions,
+    UiBuilder, Window,
+};
+use poll_promise::Promise;
+use tracing::{error, info};
+
+/// Frames are decoded at most this big, that's plenty for a column
+const MAX_FRAME_SIZE: u32 = 640;
+
+/// Players that haven't been on screen for this long are paused
+const PAUSE_HIDDEN_AFTER: Duration = Duration::from_secs(1);
+
+/// and forgotten after this long
+const FORGET_HIDDEN_AFTER: Duration = Duration::from_secs(60);
+
+const CONTROLS_HEIGHT: f32 = 32.0;
+
+/// The only protocols ffmpeg may use to fetch what we give it, so a note
+/// can't point it at local files or playlists that pull in other urls
+const PROTOCOL_WHITELIST: &str = "https,tls,tcp";
+
+/// Until we know a video's size we assume the usual shape
+const DEFAULT_ASPECT_RATIO: f32 = 16.0 / 9.0;
+
+/// What ffprobe told us about a video or audio url
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MediaInfo {
+    pub width: u32,
+    pub height: u32,
+
+    /// Live streams don't have one
+    pub duration: Option<f64>,
+    pub has_video: bool,
+    pub has_audio: bool,
+}
+
+impl MediaInfo {
+    fn from_ffprobe(json: &[u8]) -> Result<Self, String> {
+        let value: serde_json::Value =
+            serde_json::from_slice(json).map_err(|e| format!("bad ffprobe output: {e}"))?;
+
+        let mut info = MediaInfo {
+            width: 0,
+            height: 0,
+            duration: value["format"]["duration"]
+                .as_str()
+                .and_then(|d| d.parse::<f64>().ok())
+                .filter(|d| *d > 0.0),
+            has_video: false,
+            has_audio: false,
+        };
+
+        for stream in value["streams"].as_array().into_iter().flatten() {
+            match stream["codec_type"].as_str() {
+                Some("video") if !info.has_video => {
+                    info.has_video = true;
+                    info.width = stream["width"].as_u64().unwrap_or(0) as u32;
+                    info.height = stream["height"].as_u64().unwrap_or(0) as u32;
+
+                    // phones record sideways and say so in the metadata,
+                    // ffmpeg rotates the frames for us
+                    let rotated = stream["side_data_list"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|side_data| side_data["rotation"].as_i64())
+                        .any(|rotation| rotation.abs() % 180 == 90);
+                    if rotated {
+                        std::mem::swap(&mut info.width, &mut info.height);
+                    }
+                }
+                Some("audio") => info.has_audio = true,
+                _ => {}
+            }
+        }
+
+        if info.has_video && (info.width == 0 || info.height == 0) {
+            return Err("video has no size".to_owned());
+        }
+
+        Ok(info)
+    }
+
+    /// The size we decode frames at, even numbers since most pixel
+    /// formats need it
+    fn frame_size(&self) -> [usize; 2] {
+        let scale = (MAX_FRAME_SIZE as f32 / self.width.max(self.height) as f32).min(1.0);
+        let even = |x: u32| ((x as f32 * scale) as usize / 2 * 2).max(2);
+        [even(self.width), even(self.height)]
+    }
+
+    fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+}
+
+fn command(program: &str) -> Command {
+    #[allow(unused_mut)]
+    let mut cmd = Command::new(program);
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // don't flash a console window
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    cmd
+}
+
+fn spawn_error(program: &str, err: std::io::Error) -> String {
+    if err.kind() == std::io::ErrorKind::NotFound {
+        format!("{program} is needed to play media")
+    } else {
+        format!("could not run {program}: {err}")
+    }
+}
+
+/// Playing media runs the ffmpeg tools installed on the system, which
+/// most installs don't have. It's opt-in: the `ffmpeg` feature builds it
+/// in, and they turn it on in the settings.
+pub const FFMPEG_SUPPORTED: bool = cfg!(feature = "ffmpeg");
+
+static FFMPEG_ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_ffmpeg_enabled(enabled: bool) {
+    FFMPEG_ENABLED.store(enabled && FFMPEG_SUPPORTED, Ordering::Relaxed);
+}
+
+pub fn is_ffmpeg_enabled() -> bool {
+    FFMPEG_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Which of the ffmpeg tools are installed
+#[derive(Debug, Clone, Copy, Default)]
+struct Tools {
+    ffmpeg: bool,
+    ffprobe: bool,
+    ffplay: bool,
+}
+
+static TOOLS: OnceLock<Tools> = OnceLock::new();
+static FIND_TOOLS: Once = Once::new();
+
+/// We look for the tools once, in the background, None until we know
+fn find_tools(ctx: &egui::Context) -> Option<Tools> {
+    FIND_TOOLS.call_once(|| {
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let found = |program: &str| {
+                command(program)
+                    .arg("-version")
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status()
+                    .is_ok_and(|status| status.success())
+            };
+
+            let tools = Tools {
+                ffmpeg: found("ffmpeg"),
+                ffprobe: found("ffprobe"),
+                ffplay: found("ffplay"),
+            };
+            info!("media tools: {tools:?}");
+
+            let _ = TOOLS.set(tools);
+            ctx.request_repaint();
+        });
+    });
+
+    TOOLS.get().copied()
+}
+
+/// Until we know which tools are there, or if playing media is off, we
+/// act like they aren't there
+fn tools(ctx: &egui::Context) -> Tools {
+    if !is_ffmpeg_enabled() {
+        return Tools::default();
+    }
+
+    find_tools(ctx).unwrap_or_default()
+}
+
+/// What we found, for the settings
+pub fn ffmpeg_status(ctx: &egui::Context) -> &'static str {
+    match find_tools(ctx) {
+        None => "Looking for ffmpeg...",
+        Some(Tools {
+            ffmpeg: true,
+            ffprobe: true,
+            ffplay: true,
+        }) => "Found ffmpeg, ffprobe and ffplay",
+        Some(Tools {
+            ffmpeg: true,
+            ffprobe: true,
+            ffplay: false,
+        }) => "Found ffmpeg and ffprobe, sound also needs ffplay",
+        Some(_) => "ffmpeg and ffprobe aren't installed, media shows as links",
+    }
+}
+
+/// We only hand https urls to ffmpeg
+fn is_playable_url(url: &str) -> bool {
+    url.starts_with("https://")
+}
+
+/// Whether we can play this video. Show a link when we can't.
+pub fn can_play_video(ctx: &egui::Context, url: &str) -> bool {
+    let tools = tools(ctx);
+    tools.ffmpeg && tools.ffprobe && is_playable_url(url)
+}
+
+/// Sound also needs ffplay
+pub fn can_play_audio(ctx: &egui::Context, url: &str) -> bool {
+    can_play_video(ctx, url) && tools(ctx).ffplay
+}
+
+fn probe(url: &str) -> Result<MediaInfo, String> {
+    let output = command("ffprobe")
+        .args(["-v", "error", "-print_format", "json"])
+        .args(["-protocol_whitelist", PROTOCOL_WHITELIST])
+        .args(["-show_format", "-show_streams", url])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| spawn_error("ffprobe", e))?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+    }
+
+    MediaInfo::from_ffprobe(&output.stdout)
+}
+
+/// Find out what's at the url
+fn load_info(ctx: egui::Context, url: String) -> Promise<Result<MediaInfo, String>> {
+    let (sender, promise) = Promise::new();
+
+    std::thread::spawn(move || {
+        let result = probe(&url);
+        if let Err(e) = &result {
+            error!("could not load {url}: {e}");
+        }
+
+        sender.send(result);
+        ctx.request_repaint();
+    });
+
+    promise
+}
+
+/// What a [`Decoder`] should do
+#[derive(Debug, Clone, Copy)]
+struct Decode {
+    /// Where to start, in seconds
+    from: f64,
+
+    /// Decode video frames at this size
+    video: Option<[usize; 2]>,
+
+    /// Play the sound
+    audio: bool,
+
+    /// Just the frame at `from`, eg. after seeking while paused
+    single_frame: bool,
+}
+
+/// ffmpeg reading the url once, paced at the media's own speed. Video
+/// frames come out of stdout as raw rgba, and the sound comes out of
+/// stderr as wav, which we feed to ffplay. Since both come from the same
+/// input they stay in step.
+fn decode_command(url: &str, decode: &Decode) -> Command {
+    let mut cmd = command("ffmpeg");
+    cmd.args(["-v", "quiet", "-nostats", "-nostdin"])
+        .args(["-protocol_whitelist", PROTOCOL_WHITELIST]);
+    if !decode.single_frame {
+        cmd.arg("-re");
+    }
+    cmd.args(["-ss", &format!("{:.3}", decode.from), "-i", url]);
+
+    if let Some(size) = decode.video {
+        cmd.args(["-map", "0:v:0", "-sn"])
+            .args(["-vf", &format!("scale={}:{}", size[0], size[1])])
+            .args(["-pix_fmt", "rgba", "-f", "rawvideo"]);
+        if decode.single_frame {
+            cmd.args(["-frames:v", "1"]);
+        }
+        cmd.arg("pipe:1");
+    }
+
+    if decode.audio {
+        cmd.args(["-map", "0:a:0", "-f", "wav", "pipe:2"]);
+    }
+
+    cmd.stdin(Stdio::null())
+        .stdout(if decode.video.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stderr(if decode.audio {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        });
+    cmd
+}
+
+/// ffplay playing the sound ffmpeg pipes into it
+fn audio_command() -> Command {
+    let mut cmd = command("ffplay");
+    cmd.args(["-nodisp", "-autoexit", "-loglevel", "quiet"])
+        .args(["-protocol_whitelist", "pipe"])
+        .args(["-f", "wav", "-i", "pipe:0"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    cmd
+}
+
+/// A running ffmpeg, and ffplay if there's sound, that keeps the latest
+/// decoded frame around
+struct Decoder {
+    ffmpeg: Child,
+    ffplay: Option<Child>,
+    frame: Arc<Mutex<Option<ColorImage>>>,
+    video_done: Arc<AtomicBool>,
+    has_video: bool,
+}
+
+impl Decoder {
+    fn spawn(ctx: &egui::Context, url: &str, decode: Decode) -> Result<Self, String> {
+        let mut ffmpeg = decode_command(url, &decode)
+            .spawn()
+            .map_err(|e| spawn_error("ffmpeg", e))?;
+
+        let mut ffplay = None;
+        if let Some(mut sound) = ffmpeg.stderr.take() {
+            match audio_command().spawn() {
+                Ok(mut child) => {
+                    if let Some(mut speaker) = child.stdin.take() {
+                        std::thread::spawn(move || {
+                            // ends when either side goes away
+                            let _ = std::io::copy(&mut sound, &mut speaker);
+                        });
+                    }
+                    ffplay = Some(child);
+                }
+                Err(e) => {
+                    let _ = ffmpeg.kill();
+                    let _ = ffmpeg.wait();
+                    return Err(spawn_error("ffplay", e));
+                }
+            }
+        }
+
+        let frame = Arc::new(Mutex::new(None));
+        let video_done = Arc::new(AtomicBool::new(false));
+        if let (Some(size), Some(mut stdout)) = (decode.video, ffmpeg.stdout.take()) {
+            let ctx = ctx.clone();
+            let frame_out = frame.clone();
+            let done_out = video_done.clone();
+            std::thread::spawn(move || {
+                let mut buf = vec![0u8; size[0] * size[1] * 4];
+                while stdout.read_exact(&mut buf).is_ok() {
+                    let image = ColorImage::from_rgba_unmultiplied(size, &buf);
+                    if let Ok(mut frame) = frame_out.lock() {
+                        *frame = Some(image);
+                    }
+                    ctx.request_repaint();
+                }
+
+                done_out.store(true, Ordering::Relaxed);
+                ctx.request_repaint();
+            });
+        }
+
+        Ok(Self {
+            ffmpeg,
+            ffplay,
+            frame,
+            video_done,
+            has_video: decode.video.is_some(),
+        })
+    }
+
+    fn take_frame(&self) -> Option<ColorImage> {
+        self.frame.lock().ok()?.take()
+    }
+
+    /// The video ran out of frames, or the sound finished playing
+    fn is_done(&mut self) -> bool {
+        if self.has_video {
+            return self.video_done.load(Ordering::Relaxed);
+        }
+
+        let child = self.ffplay.as_mut().unwrap_or(&mut self.ffmpeg);
+        !matches!(child.try_wait(), Ok(None))
+    }
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        for child in std::iter::once(&mut self.ffmpeg).chain(self.ffplay.as_mut()) {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PlayState {
+    /// Not played yet
+    Stopped,
+    Playing {
+        from: f64,
+        started: Instant,
+    },
+    Paused(f64),
+    Ended,
+}
+
+struct MediaPlayer {
+    url: String,
+
+    /// Nothing runs until they press play, or autoplay is on
+    probed: Option<Promise<Result<MediaInfo, String>>>,
+
+    /// Play, with or without video, once the probe finishes
+    play_when_probed: Option<bool>,
+    texture: Option<TextureHandle>,
+    state: PlayState,
+    decoder: Option<Decoder>,
+    muted: bool,
+    fullscreen: bool,
+
+    /// They paused it, so we don't autoplay it again
+    user_paused: bool,
+
+    /// Where the seek bar is being dragged to
+    scrub: Option<f64>,
+    error: Option<String>,
+    last_shown: Instant,
+}
+
+impl MediaPlayer {
+    fn new(url: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+            probed: None,
+            play_when_probed: None,
+            texture: None,
+            state: PlayState::Stopped,
+            decoder: None,
+            muted: false,
+            fullscreen: false,
+            user_paused: false,
+            scrub: None,
+            error: None,
+            last_shown: Instant::now(),
+        }
+    }
+
+    fn probed(&self) -> Option<&Result<MediaInfo, String>> {
+        self.probed.as_ref()?.ready()
+    }
+
+    fn info(&self) -> Option<MediaInfo> {
+        self.probed()?.as_ref().ok().copied()
+    }
+
+    fn is_loading(&self) -> bool {
+        self.probed.is_some() && self.probed().is_none()
+    }
+
+    fn is_playing(&self) -> bool {
+        matches!(self.state, PlayState::Playing { .. })
+    }
+
+    fn position(&self) -> f64 {
+        let pos = match self.state {
+            PlayState::Stopped => 0.0,
+            PlayState::Playing { from, started } => from + started.elapsed().as_secs_f64(),
+            PlayState::Paused(at) => at,
+            PlayState::Ended => self.info().and_then(|i| i.duration).unwrap_or(0.0),
+        };
+
+        match self.info().and_then(|i| i.duration) {
+            Some(duration) => pos.min(duration),
+            None => pos,
+        }
+    }
+
+    fn play(&mut self, ctx: &egui::Context, with_video: bool) {
+        let Some(info) = self.info() else {
+            if self.probed.is_none() {
+                self.probed = Some(load_info(ctx.clone(), self.url.clone()));
+            }
+            self.play_when_probed = Some(with_video);
+            return;
+        };
+
+        let from = match self.state {
+            PlayState::Paused(at) => at,
+            PlayState::Playing { .. } => return,
+            PlayState::Stopped | PlayState::Ended => 0.0,
+        };
+
+        self.start_at(ctx, &info, from, with_video);
+    }
+
+    fn start_at(&mut self, ctx: &egui::Context, info: &MediaInfo, from: f64, with_video: bool) {
+        self.decoder = None;
+        self.error = None;
+
+        let decode = Decode {
+            from,
+            video: (with_video && info.has_video).then(|| info.frame_size()),
+            audio: info.has_audio && !self.muted,
+            single_frame: false,
+        };
+
+        // muted audio only needs the clock
+        if decode.video.is_some() || decode.audio {
+            match Decoder::spawn(ctx, &self.url, decode) {
+                Ok(decoder) => self.decoder = Some(decoder),
+                Err(e) => {
+                    self.error = Some(e);
+                    return;
+                }
+            }
+        }
+
+        self.state = PlayState::Playing {
+            from,
+            started: Instant::now(),
+        };
+    }
+
+    fn pause(&mut self) {
+        if self.is_playing() {
+            self.state = PlayState::Paused(self.position());
+        }
+        self.play_when_probed = None;
+        self.decoder = None;
+    }
+
+    fn seek(&mut self, ctx: &egui::Context, to: f64, with_video: bool) {
+        let Some(info) = self.info() else {
+            return;
+        };
+
+        if self.is_playing() {
+            self.start_at(ctx, &info, to, with_video);
+            return;
+        }
+
+        self.state = PlayState::Paused(to);
+
+        // show where we landed
+        self.decoder = None;
+        if with_video && info.has_video {
+            let decode = Decode {
+                from: to,
+                video: Some(info.frame_size()),
+                audio: false,
+                single_frame: true,
+            };
+            match Decoder::spawn(ctx, &self.url, decode) {
+                Ok(decoder) => self.decoder = Some(decoder),
+                Err(e) => self.error = Some(e),
+            }
+        }
+    }
+
+    /// The sound comes out of the same ffmpeg as the frames, so we pick
+    /// up again from here with or without it
+    fn set_muted(&mut self, ctx: &egui::Context, muted: bool, with_video: bool) {
+        self.muted = muted;
+
+        if let (true, Some(info)) = (self.is_playing(), self.info()) {
+            if info.has_audio {
+                self.start_at(ctx, &info, self.position(), with_video);
+            }
+        }
+    }
+
+    /// Pick up new frames and notice when playback finished
+    fn update(&mut self, ctx: &egui::Context) {
+        match self.probed().map(|probed| probed.is_ok()) {
+            Some(true) => {
+                if let Some(with_video) = self.play_when_probed.take() {
+                    self.play(ctx, with_video);
+                }
+            }
+            Some(false) => self.play_when_probed = None,
+            None => {}
+        }
+
+        let mut finished = false;
+        if let Some(decoder) = &mut self.decoder {
+            // check first so we don't miss the last frame
+            let done = decoder.is_done();
+            if let Some(frame) = decoder.take_frame() {
+                match &mut self.texture {
+                    Some(texture) => texture.set(frame, TextureOptions::LINEAR),
+                    None => {
+                        self.texture =
+                            Some(ctx.load_texture(&self.url, frame, TextureOptions::LINEAR))
+                    }
+                }
+            }
+            finished = done;
+        } else if let (PlayState::Playing { .. }, Some(duration)) =
+            (self.state, self.info().and_then(|i| i.duration))
+        {
+            // muted and nothing to decode, just run the clock
+            finished = self.position() >= duration;
+        }
+
+        if finished {
+            if self.is_playing() {
+                self.state = PlayState::Ended;
+            }
+            self.decoder = None;
+        }
+
+        if self.is_playing() {
+            // keep the clock moving
+            ctx.request_repaint_after(Duration::from_millis(250));
+        }
+    }
+}
+
+/// Every player that's been on screen recently
+#[derive(Default)]
+struct MediaPlayers {
+    players: HashMap<egui::Id, MediaPlayer>,
+}
+
+impl MediaPlayers {
+    fn player(&mut self, id: egui::Id, url: &str) -> &mut MediaPlayer {
+        let now = Instant::now();
+        for (player_id, player) in self.players.iter_mut() {
+            if *player_id != id && now - player.last_shown > PAUSE_HIDDEN_AFTER {
+                player.pause();
+            }
+        }
+        self.players
+            .retain(|_, player| now - player.last_shown < FORGET_HIDDEN_AFTER);
+
+        let player = self
+            .players
+            .entry(id)
+            .or_insert_with(|| MediaPlayer::new(url));
+        player.last_shown = now;
+        player
+    }
+}
+
+fn with_player<R>(
+    ctx: &egui::Context,
+    id: egui::Id,
+    url: &str,
+    f: impl FnOnce(&mut MediaPlayer) -> R,
+) -> R {
+    let players = ctx.data_mut(|d| {
+        d.get_temp_mut_or_default::<Arc<Mutex<MediaPlayers>>>(egui::Id::new("media_players"))
+            .clone()
+    });
+    let mut players = players.lock().expect("media players lock");
+    let player = players.player(id, url);
+    player.update(ctx);
+    f(player)
+}
+
+fn format_time(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
+}
+
+fn play_button(ui: &mut egui::Ui, playing: bool) -> egui::Response {
+    let icon = if playing { "⏸" } else { "▶" };
+    ui.add(egui::Button::new(RichText::new(icon).size(16.0)).frame(false))
+        .on_hover_text(if playing { "Pause" } else { "Play" })
+}
+
+/// Play/pause, seek bar, time and mute. `fullscreen` adds the fullscreen
+/// toggle.
+fn controls(ui: &mut egui::Ui, player: &mut MediaPlayer, with_video: bool, fullscreen: bool) {
+    ui.spacing_mut().item_spacing.x = 6.0;
+
+    let Some(info) = player.info() else {
+        if play_button(ui, false).clicked() {
+            player.play(ui.ctx(), with_video);
+        }
+        if player.is_loading() {
+            ui.spinner();
+        }
+        return;
+    };
+
+    if play_button(ui, player.is_playing()).clicked() {
+        if player.is_playing() {
+            player.pause();
+            player.user_paused = true;
+        } else {
+            player.play(ui.ctx(), with_video);
+        }
+    }
+
+    let position = player.scrub.unwrap_or_else(|| player.position());
+    let time = match info.duration {
+        Some(duration) => format!("{} / {}", format_time(position), format_time(duration)),
+        None => format_time(position),
+    };
+
+    ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+        if fullscreen {
+            let icon = if player.fullscreen { "🗗" } else { "⛶" };
+            if ui
+                .add(egui::Button::new(RichText::new(icon).size(16.0)).frame(false))
+                .on_hover_text("Fullscreen")
+                .clicked()
+            {
+                player.fullscreen = !player.fullscreen;
+            }
+        }
+
+        if info.has_audio && with_video {
+            let icon = if player.muted { "🔇" } else { "🔊" };
+            if ui
+                .add(egui::Button::new(RichText::new(icon).size(16.0)).frame(false))
+                .on_hover_text(if player.muted { "Unmute" } else { "Mute" })
+                .clicked()
+            {
+                player.set_muted(ui.ctx(), !player.muted, with_video);
+            }
+        }
+
+        ui.label(RichText::new(time).small());
+
+        if let Some(duration) = info.duration {
+            let mut pos = position;
+            ui.spacing_mut().slider_width = ui.available_width();
+            let resp = ui.add(egui::Slider::new(&mut pos, 0.0..=duration).show_value(false));
+
+            if resp.dragged() {
+                player.scrub = Some(pos);
+            } else if resp.drag_stopped() || resp.changed() {
+                player.scrub = None;
+                player.seek(ui.ctx(), pos, with_video);
+            }
+        }
+    });
+}
+
+/// The current frame, with a play button over it when it isn't playing.
+/// Clicking it plays or pauses.
+fn video_frame(ui: &mut egui::Ui, player: &mut MediaPlayer, rect: Rect) -> egui::Response {
+    let resp = ui.interact(rect, ui.id().with(("video", &player.url)), Sense::click());
+
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 5.0, Color32::BLACK);
+    if let Some(texture) = &player.texture {
+        let uv = Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+        painter.image(texture.id(), rect, uv, Color32::WHITE);
+    }
+
+    if player.is_loading() {
+        let size = (rect.height() / 4.0).clamp(16.0, 32.0);
+        ui.put(
+            Rect::from_center_size(rect.center(), vec2(size, size)),
+            egui::Spinner::new(),
+        );
+    } else if !player.is_playing() {
+        let radius = (rect.height() / 8.0).clamp(16.0, 32.0);
+        painter.circle_filled(rect.center(), radius, Color32::from_black_alpha(160));
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            "▶",
+            egui::FontId::proportional(radius),
+            Color32::WHITE,
+        );
+    }
+
+    if resp.clicked() {
+        if player.is_playing() {
+            player.pause();
+            player.user_paused = true;
+        } else {
+            player.play(ui.ctx(), true);
+        }
+    }
+
+    if resp.hovered() {
+        crate::show_pointer(ui);
+    }
+
+    resp
+}
+
+/// Controls along the bottom of a video
+fn overlay_controls(ui: &mut egui::Ui, player: &mut MediaPlayer, rect: Rect) {
+    let bar = Rect::from_min_max(
+        egui::pos2(rect.left(), rect.bottom() - CONTROLS_HEIGHT),
+        rect.right_bottom(),
+    );
+    ui.painter()
+        .rect_filled(bar, 0.0, Color32::from_black_alpha(160));
+
+    let mut bar_ui = ui.new_child(
+        UiBuilder::new()
+            .max_rect(bar.shrink2(vec2(8.0, 0.0)))
+            .layout(Layout::left_to_right(Align::Center)),
+    );
+    bar_ui.visuals_mut().override_text_color = Some(Color32::WHITE);
+    controls(&mut bar_ui, player, true, true);
+}
+
+fn error_box(ui: &mut egui::Ui, url: &str, err: &str) -> egui::Response {
+    ui.vertical(|ui| {
+        ui.label(RichText::new(format!("Can't play this: {err}")).weak());
+        ui.hyperlink(url);
+    })
+    .response
+}
+
+/// A video in a note: a play button, then the video itself with seek,
+/// mute and fullscreen controls. Nothing is fetched until it's played.
+/// Videos only start on their own if `autoplay` is set, and then muted.
+pub fn video_player(
+    ui: &mut egui::Ui,
+    id: egui::Id,
+    url: &str,
+    max_height: f32,
+    autoplay: bool,
+) -> egui::Response {
+    let ctx = ui.ctx().clone();
+    with_player(&ctx, id, url, |player| {
+        let info = match player.probed() {
+            Some(Err(e)) => return error_box(ui, url, e),
+            Some(Ok(info)) => Some(*info),
+            None => None,
+        };
+
+        if info.is_some_and(|info| !info.has_video) {
+            return audio_controls(ui, player);
+        }
+
+        if autoplay
+            && !player.user_paused
+            && !player.is_playing()
+            && player.play_when_probed.is_none()
+            && player.state != PlayState::Ended
+        {
+            if player.state == PlayState::Stopped {
+                player.muted = true;
+            }
+            player.play(ui.ctx(), true);
+        }
+
+        let aspect_ratio = info.map_or(DEFAULT_ASPECT_RATIO, |info| info.aspect_ratio());
+        let height = max_height.min(ui.available_width() / aspect_ratio);
+        let size = vec2(height * aspect_ratio, height);
+        let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+
+        let resp = video_frame(ui, player, rect);
+        let Some(info) = info else {
+            return resp;
+        };
+
+        if resp.hovered() || !player.is_playing() || player.scrub.is_some() {
+            overlay_controls(ui, player, rect);
+        }
+
+        if let Some(err) = &player.error {
+            ui.painter().text(
+                rect.left_top() + vec2(8.0, 8.0),
+                egui::Align2::LEFT_TOP,
+                err,
+                egui::FontId::proportional(12.0),
+                Color32::WHITE,
+            );
+        }
+
+        if player.fullscreen {
+            show_fullscreen(ui.ctx(), player, &info);
+        }
+
+        resp
+    })
+}
+
+fn show_fullscreen(ctx: &egui::Context, player: &mut MediaPlayer, info: &MediaInfo) {
+    if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+        player.fullscreen = false;
+        return;
+    }
+
+    let screen = ctx.screen_rect();
+    Window::new("video_fullscreen")
+        .id(egui::Id::new(("video_fullscreen", &player.url)))
+        .title_bar(false)
+        .fixed_size(screen.size())
+        .fixed_pos(screen.min)
+        .frame(egui::Frame::NONE)
+        .show(ctx, |ui| {
+            ui.painter().rect_filled(screen, 0.0, Color32::BLACK);
+
+            let area = Rect::from_min_max(
+                screen.min,
+                egui::pos2(screen.right(), screen.bottom() - CONTROLS_HEIGHT),
+            );
+            let scale = (area.width() / info.width as f32).min(area.height() / info.height as f32);
+            let rect = Rect::from_center_size(
+                area.center(),
+                vec2(info.width as f32 * scale, info.height as f32 * scale),
+            );
+
+            video_frame(ui, player, rect);
+            overlay_controls(ui, player, screen);
+        });
+}
+
+/// A compact player for audio links
+pub fn audio_player(ui: &mut egui::Ui, id: egui::Id, url: &str) -> egui::Response {
+    let ctx = ui.ctx().clone();
+    with_player(&ctx, id, url, |player| match player.probed() {
+        Some(Err(e)) => error_box(ui, url, e),
+        _ => audio_controls(ui, player),
+    })
+}
+
+fn audio_controls(ui: &mut egui::Ui, player: &mut MediaPlayer) -> egui::Response {
+    let width = ui.available_width().min(400.0);
+    let resp = egui::Frame::new()
+        .fill(ui.visuals().faint_bg_color)
+        .corner_radius(8.0)
+        .inner_margin(egui::Margin::symmetric(8, 4))
+        .show(ui, |ui| {
+            ui.set_width(width);
+            ui.horizontal(|ui| controls(ui, player, false, false));
+        })
+        .response;
+
+    if let Some(err) = &player.error {
+        ui.label(RichText::new(err).weak());
+    }
+
+    resp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffprobe_info() {
+        let json = br#"{
+            "streams": [
+                {"codec_type": "video", "width": 1920, "height": 1080,
+                 "side_data_list": [{"rotation": -90}]},
+                {"codec_type": "audio"}
+            ],
+            "format": {"duration": "12.500000"}
+        }"#;
+
+        let info = MediaInfo::from_ffprobe(json).unwrap();
+        assert_eq!((info.width, info.height), (1080, 1920));
+        assert_eq!(info.duration, Some(12.5));
+        assert!(info.has_video && info.has_audio);
+        assert_eq!(info.frame_size(), [360, 640]);
+
+        let live = br#"{"streams": [{"codec_type": "audio"}], "format": {}}"#;
+        let info = MediaInfo::from_ffprobe(live).unwrap();
+        assert!(!info.has_video);
+        assert_eq!(info.duration, None);
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(5.4), "0:05");
+        assert_eq!(format_time(65.0), "1:05");
+        assert_eq!(format_time(3725.0), "1:02:05");
+    }
+
+    #[test]
+    fn test_decode_command_whitelists_protocols() {
+        let decode = Decode {
+            from: 1.5,
+            video: Some([640, 360]),
+            audio: true,
+            single_frame: false,
+        };
+        let cmd = decode_command("https://example.com/a.mp4", &decode);
+        let args: Vec<_> = cmd.get_args().map(|a| a.to_string_lossy()).collect();
+
+        let whitelist = args
+            .iter()
+            .position(|a| a == "-protocol_whitelist")
+            .unwrap();
+        let input = args.iter().position(|a| a == "-i").unwrap();
+        assert!(whitelist < input);
+        assert_eq!(args[whitelist + 1], PROTOCOL_WHITELIST);
+        assert!(args.iter().any(|a| a == "pipe:1"));
+        assert!(args.iter().any(|a| a == "pipe:2"));
+    }
+
+    #[test]
+    fn test_only_https_is_playable() {
+        assert!(is_playable_url("https://example.com/a.mp4"));
+        assert!(!is_playable_url("http://example.com/a.mp4"));
+        assert!(!is_playable_url("file:///etc/passwd"));
+    }
+
+    #[test]
+    fn test_ffmpeg_is_opt_in() {
+        let ctx = egui::Context::default();
+        assert!(!is_ffmpeg_enabled());
+        assert!(!can_play_video(&ctx, "https://example.com/a.mp4"));
+
+        // only builds with the feature can turn it on
+        set_ffmpeg_enabled(true);
+        assert_eq!(is_ffmpeg_enabled(), FFMPEG_SUPPORTED);
+        set_ffmpeg_enabled(false);
+    }
+}